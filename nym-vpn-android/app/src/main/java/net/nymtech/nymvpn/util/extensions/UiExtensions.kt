@@ -69,6 +69,7 @@ fun ErrorStateReason.toUserMessage(context: Context): String {
 		ErrorStateReason.INVALID_EXIT_GATEWAY_COUNTRY -> "Exit country not available. Select a different country."
 		ErrorStateReason.BAD_BANDWIDTH_INCREASE -> "Bad bandwidth increase."
 		ErrorStateReason.DUPLICATE_TUN_FD -> "Duplicate tunnel"
+		ErrorStateReason.CONNECTION_ATTEMPTS_EXHAUSTED -> "Unable to connect. Please try again later."
 	}
 }
 
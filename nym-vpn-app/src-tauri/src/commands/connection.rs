@@ -87,6 +87,7 @@ pub async fn connect(
                     two_letter_iso_country_code: country.code.clone(),
                     latitude: None,
                     longitude: None,
+                    city: None,
                 })),
             }
         }
@@ -100,6 +101,7 @@ pub async fn connect(
                     two_letter_iso_country_code: FASTEST_NODE_LOCATION.code.clone(),
                     latitude: None,
                     longitude: None,
+                    city: None,
                 })),
             }
         }
@@ -113,6 +115,7 @@ pub async fn connect(
                     two_letter_iso_country_code: country.code.clone(),
                     latitude: None,
                     longitude: None,
+                    city: None,
                 })),
             }
         }
@@ -126,6 +129,7 @@ pub async fn connect(
                     two_letter_iso_country_code: FASTEST_NODE_LOCATION.code.clone(),
                     latitude: None,
                     longitude: None,
+                    city: None,
                 })),
             }
         }
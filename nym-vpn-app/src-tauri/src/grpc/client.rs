@@ -9,10 +9,10 @@ use nym_vpn_proto::{
     get_device_identity_response::Id as DeviceIdRes, health_check_response::ServingStatus,
     health_client::HealthClient, is_account_stored_response::Resp as IsAccountStoredResp,
     nym_vpnd_client::NymVpndClient, ConnectRequest, ConnectionStatus, DisconnectRequest, Dns,
-    Empty, EntryNode, ExitNode, ForgetAccountRequest, GatewayType, GetAccountIdentityRequest,
-    GetAccountLinksRequest, GetDeviceIdentityRequest, GetFeatureFlagsRequest,
-    GetSystemMessagesRequest, HealthCheckRequest, InfoRequest, InfoResponse,
-    IsAccountStoredRequest, IsReadyToConnectRequest, ListCountriesRequest, Location,
+    Empty, EntryNode, ExitNode, ForgetAccountRequest, GatewayRanking, GatewayType,
+    GetAccountIdentityRequest, GetAccountLinksRequest, GetDeviceIdentityRequest,
+    GetFeatureFlagsRequest, GetSystemMessagesRequest, HealthCheckRequest, InfoRequest,
+    InfoResponse, IsAccountStoredRequest, IsReadyToConnectRequest, ListCountriesRequest, Location,
     SetNetworkRequest, StatusRequest, StatusResponse, StoreAccountRequest, UserAgent,
 };
 use parity_tokio_ipc::Endpoint as IpcEndpoint;
@@ -366,10 +366,16 @@ impl GrpcClient {
             disable_background_cover_traffic: false,
             enable_credentials_mode: self.credentials_mode,
             dns,
+            encrypted_dns: None,
             user_agent: Some(self.user_agent.clone()),
             min_mixnode_performance: None,
             min_gateway_mixnet_performance: None,
             min_gateway_vpn_performance: None,
+            allow_lan: false,
+            gateway_ranking: GatewayRanking::Unspecified.into(),
+            sticky_gateways_secs: None,
+            retry_policy: None,
+            connect_timeouts: None,
         });
         let response = vpnd
             .vpn_connect(request)
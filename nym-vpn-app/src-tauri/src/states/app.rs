@@ -96,6 +96,9 @@ impl From<ConnectionStatus> for ConnectionState {
             // this variant means "Not connected, but with an error"
             // so it should be treated as disconnected
             ConnectionStatus::ConnectionFailed => ConnectionState::Disconnected,
+            // the daemon keeps the tunnel blocked and resumes connecting once
+            // the network is back
+            ConnectionStatus::Offline => ConnectionState::Connecting,
         }
     }
 }
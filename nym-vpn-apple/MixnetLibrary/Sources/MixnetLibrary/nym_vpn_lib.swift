@@ -4259,6 +4259,10 @@ public enum ErrorStateReason {
      * Failure to duplicate tunnel file descriptor.
      */
     case duplicateTunFd
+    /**
+     * Connecting failed as many times in a row as the retry policy allows.
+     */
+    case connectionAttemptsExhausted
     /**
      * Program errors that must not happen.
      */
@@ -4293,7 +4297,9 @@ public struct FfiConverterTypeErrorStateReason: FfiConverterRustBuffer {
         
         case 10: return .duplicateTunFd
         
-        case 11: return .`internal`
+        case 11: return .connectionAttemptsExhausted
+        
+        case 12: return .`internal`
         
         default: throw UniffiInternalError.unexpectedEnumCase
         }
@@ -4343,9 +4349,13 @@ public struct FfiConverterTypeErrorStateReason: FfiConverterRustBuffer {
             writeInt(&buf, Int32(10))
         
         
-        case .`internal`:
+        case .connectionAttemptsExhausted:
             writeInt(&buf, Int32(11))
         
+        
+        case .`internal`:
+            writeInt(&buf, Int32(12))
+        
         }
     }
 }
@@ -5454,6 +5464,7 @@ public enum TunnelState {
     )
     case error(ErrorStateReason
     )
+    case offline
 }
 
 
@@ -5478,6 +5489,8 @@ public struct FfiConverterTypeTunnelState: FfiConverterRustBuffer {
         case 5: return .error(try FfiConverterTypeErrorStateReason.read(from: &buf)
         )
         
+        case 6: return .offline
+        
         default: throw UniffiInternalError.unexpectedEnumCase
         }
     }
@@ -5509,6 +5522,10 @@ public struct FfiConverterTypeTunnelState: FfiConverterRustBuffer {
             writeInt(&buf, Int32(5))
             FfiConverterTypeErrorStateReason.write(v1, into: &buf)
             
+        
+        case .offline:
+            writeInt(&buf, Int32(6))
+        
         }
     }
 }
@@ -98,6 +98,7 @@ actor TunnelActor {
     }
 
     /// Wait until the tunnel state shifted into either connected, disconnected or error state.
+    /// The offline state keeps waiting, as the tunnel resumes connecting once the network is back.
     func waitUntilStarted() async throws {
         var stateStream = $tunnelState.values.makeAsyncIterator()
 
@@ -107,7 +108,7 @@ actor TunnelActor {
                 return
             case let .error(errorStateReason):
                 throw ErrorReason(with: errorStateReason).nsError
-            case .disconnecting, .none, .connecting:
+            case .disconnecting, .none, .connecting, .offline:
                 break
             }
         }
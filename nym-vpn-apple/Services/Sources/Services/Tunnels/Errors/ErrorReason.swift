@@ -14,6 +14,7 @@ public enum ErrorReason: LocalizedError {
     case invalidExitGatewayCountry
     case badBandwidthIncrease
     case duplicateTunFd
+    case connectionAttemptsExhausted
     case unknown
 
     public static let domain = "ErrorHandler.ErrorReason"
@@ -42,6 +43,8 @@ public enum ErrorReason: LocalizedError {
             self = .badBandwidthIncrease
         case .duplicateTunFd:
             self = .duplicateTunFd
+        case .connectionAttemptsExhausted:
+            self = .connectionAttemptsExhausted
         }
     }
 
@@ -68,6 +71,10 @@ public enum ErrorReason: LocalizedError {
             self = .invalidExitGatewayCountry
         case 9:
             self = .badBandwidthIncrease
+        case 10:
+            self = .duplicateTunFd
+        case 11:
+            self = .connectionAttemptsExhausted
         default:
             self = .unknown
         }
@@ -114,8 +121,10 @@ extension ErrorReason {
             9
         case .duplicateTunFd:
             10
-        default:
+        case .connectionAttemptsExhausted:
             11
+        default:
+            12
         }
     }
 }
@@ -145,6 +154,8 @@ extension ErrorReason {
             "errorReason.badBandwidthIncrease".localizedString
         case .duplicateTunFd:
             "errorReason.duplicateTunFd".localizedString
+        case .connectionAttemptsExhausted:
+            "errorReason.connectionAttemptsExhausted".localizedString
         case .unknown:
             "errorReason.unknown".localizedString
         }
@@ -5,5 +5,7 @@
 mod error;
 pub use error::*;
 
+pub mod fs;
+
 #[cfg(target_os = "linux")]
 pub mod linux;
@@ -37,6 +37,7 @@ const NM_IP6_CONFIG: &str = "org.freedesktop.NetworkManager.IP6Config";
 const DEVICE_READY_TIMEOUT: Duration = Duration::from_secs(15);
 const RC_MANAGEMENT_MODE_KEY: &str = "RcManager";
 const DNS_MODE_KEY: &str = "Mode";
+const DNS_CONFIGURATION_KEY: &str = "Configuration";
 const DNS_FIRST_PRIORITY: i32 = -2147483647;
 
 const NM_DEVICE_STATE_IP_CHECK: u32 = 80;
@@ -117,6 +118,13 @@ pub type VariantMap = HashMap<String, VariantRefArg>;
 // settings are a{sa{sv}}
 pub type DeviceConfig = HashMap<String, VariantMap>;
 
+/// DNS servers applied by NetworkManager, either for a device or globally.
+#[derive(Debug, Clone)]
+pub struct DnsConfigurationEntry {
+    pub interface: Option<String>,
+    pub nameservers: Vec<IpAddr>,
+}
+
 /// Implements functionality to control NetworkManager over DBus.
 pub struct NetworkManager {
     connection: Arc<SyncConnection>,
@@ -439,6 +447,37 @@ impl NetworkManager {
         Ok(())
     }
 
+    /// Returns the DNS configuration currently applied by NetworkManager.
+    pub fn get_dns_configuration(&self) -> Result<Vec<DnsConfigurationEntry>> {
+        let entries: Vec<VariantMap> = self
+            .as_dns_manager()
+            .get(NM_DNS_MANAGER, DNS_CONFIGURATION_KEY)?;
+
+        Ok(entries
+            .into_iter()
+            .map(|entry| {
+                let interface = entry
+                    .get("interface")
+                    .and_then(|interface| interface.0.as_str())
+                    .map(str::to_owned);
+                let nameservers = entry
+                    .get("nameservers")
+                    .and_then(|nameservers| nameservers.0.as_iter())
+                    .map(|nameservers| {
+                        nameservers
+                            .filter_map(|nameserver| nameserver.as_str()?.parse().ok())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                DnsConfigurationEntry {
+                    interface,
+                    nameservers,
+                }
+            })
+            .collect())
+    }
+
     fn as_manager(&'_ self) -> Proxy<'_, &SyncConnection> {
         Proxy::new(NM_BUS, NM_MANAGER_PATH, RPC_TIMEOUT, &*self.connection)
     }
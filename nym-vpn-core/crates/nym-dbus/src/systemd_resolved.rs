@@ -292,6 +292,26 @@ impl SystemdResolved {
         self.set_link_dns_domains(&link_object_path, domains)
     }
 
+    /// Returns the DNS servers of all links, along with the global ones, which have an interface
+    /// index of 0.
+    pub fn get_all_dns_servers(&self) -> Result<Vec<DnsServer>> {
+        let servers: Vec<(i32, i32, Vec<u8>)> = self
+            .as_manager_object()
+            .get(MANAGER_INTERFACE, DNS_SERVERS)
+            .map_err(Error::DBusRpcError)?;
+
+        Ok(servers
+            .into_iter()
+            .filter_map(|(iface_index, address_family, address)| {
+                Some(DnsServer {
+                    iface_index,
+                    address_family,
+                    address: ip_from_bytes(&address)?,
+                })
+            })
+            .collect())
+    }
+
     fn fetch_link(&self, interface_index: u32) -> Result<dbus::Path<'static>> {
         self.as_manager_object()
             .method_call(
@@ -564,18 +584,30 @@ impl AsyncHandle {
             .map_err(Error::AsyncTaskError)?
     }
 
-    pub async fn set_domains(
-        &self,
-        interface_index: u32,
-        domains: &[(&'static str, bool)],
-    ) -> Result<()> {
+    pub async fn get_domains(&self, interface_index: u32) -> Result<Vec<(String, bool)>> {
         let interface = self.dbus_interface.clone();
-        let domains = domains.to_vec();
-        tokio::task::spawn_blocking(move || interface.set_domains(interface_index, &domains))
+        tokio::task::spawn_blocking(move || interface.get_domains(interface_index))
             .await
             .map_err(Error::AsyncTaskError)?
     }
 
+    pub async fn set_domains(&self, interface_index: u32, domains: &[(&str, bool)]) -> Result<()> {
+        let interface = self.dbus_interface.clone();
+        let domains = domains
+            .iter()
+            .map(|(domain, routing_only)| (domain.to_string(), *routing_only))
+            .collect::<Vec<_>>();
+        tokio::task::spawn_blocking(move || {
+            let domains = domains
+                .iter()
+                .map(|(domain, routing_only)| (domain.as_str(), *routing_only))
+                .collect::<Vec<_>>();
+            interface.set_domains(interface_index, &domains)
+        })
+        .await
+        .map_err(Error::AsyncTaskError)?
+    }
+
     pub async fn revert_link(&self, state: DnsState) -> Result<()> {
         let mut interface = self.dbus_interface.clone();
         tokio::task::spawn_blocking(move || interface.revert_link(&state))
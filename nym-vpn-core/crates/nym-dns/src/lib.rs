@@ -2,6 +2,8 @@
 // Copyright 2024 Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
+#[cfg(target_os = "linux")]
+use std::path::PathBuf;
 use std::{fmt, net::IpAddr};
 
 #[cfg(target_os = "linux")]
@@ -16,7 +18,7 @@ mod imp;
 mod imp;
 
 #[cfg(target_os = "linux")]
-pub use imp::will_use_nm;
+pub use imp::{diagnostics, will_use_nm, will_use_systemd_resolved};
 
 #[cfg(windows)]
 #[path = "windows/mod.rs"]
@@ -75,6 +77,7 @@ impl DnsConfig {
             InnerDnsConfig::Default => ResolvedDnsConfig {
                 tunnel_config: default_tun_config.to_owned(),
                 non_tunnel_config: vec![],
+                domain_resolvers: vec![],
             },
             InnerDnsConfig::Override {
                 tunnel_config,
@@ -82,11 +85,21 @@ impl DnsConfig {
             } => ResolvedDnsConfig {
                 tunnel_config: tunnel_config.to_owned(),
                 non_tunnel_config: non_tunnel_config.to_owned(),
+                domain_resolvers: vec![],
             },
         }
     }
 }
 
+/// DNS servers resolving the names under a domain instead of the tunnel DNS servers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DomainResolver {
+    /// Domain name, without the trailing dot. Subdomains are resolved by the same servers.
+    pub domain: String,
+    /// Addresses of the servers, reached outside of the tunnel.
+    pub servers: Vec<IpAddr>,
+}
+
 /// DNS configuration with `DnsConfig::Default` resolved
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ResolvedDnsConfig {
@@ -96,6 +109,8 @@ pub struct ResolvedDnsConfig {
     /// For the most part, the tunnel state machine will not handle any of this configuration
     /// on non-tunnel interface, only allow them in the firewall.
     non_tunnel_config: Vec<IpAddr>,
+    /// Servers resolving specific domains. Only supported by systemd-resolved.
+    domain_resolvers: Vec<DomainResolver>,
 }
 
 impl fmt::Display for ResolvedDnsConfig {
@@ -104,7 +119,13 @@ impl fmt::Display for ResolvedDnsConfig {
         Self::fmt_addr_set(f, &self.tunnel_config)?;
 
         f.write_str(" Non-tunnel DNS: ")?;
-        Self::fmt_addr_set(f, &self.non_tunnel_config)
+        Self::fmt_addr_set(f, &self.non_tunnel_config)?;
+
+        for resolver in &self.domain_resolvers {
+            write!(f, " {} DNS: ", resolver.domain)?;
+            Self::fmt_addr_set(f, &resolver.servers)?;
+        }
+        Ok(())
     }
 }
 
@@ -132,6 +153,17 @@ impl ResolvedDnsConfig {
         &self.non_tunnel_config
     }
 
+    /// Use specific servers to resolve the names under the given domains.
+    pub fn with_domain_resolvers(mut self, domain_resolvers: Vec<DomainResolver>) -> Self {
+        self.domain_resolvers = domain_resolvers;
+        self
+    }
+
+    /// Servers resolving specific domains
+    pub fn domain_resolvers(&self) -> &[DomainResolver] {
+        &self.domain_resolvers
+    }
+
     /// Consume `self` and return a vector of all addresses
     pub fn addresses(self) -> impl Iterator<Item = IpAddr> {
         self.non_tunnel_config.into_iter().chain(self.tunnel_config)
@@ -145,9 +177,13 @@ pub struct DnsMonitor {
 
 impl DnsMonitor {
     /// Returns a new `DnsMonitor` that can set and monitor the system DNS.
+    ///
+    /// On Linux, the DNS configuration replaced when setting DNS is journaled at `journal_path`,
+    /// and restored when a journal left over by a previous process is found.
     pub fn new(
         #[cfg(target_os = "linux")] handle: tokio::runtime::Handle,
         #[cfg(target_os = "linux")] route_manager: RouteManagerHandle,
+        #[cfg(target_os = "linux")] journal_path: Option<PathBuf>,
     ) -> Result<Self, Error> {
         Ok(DnsMonitor {
             inner: imp::DnsMonitor::new(
@@ -155,6 +191,8 @@ impl DnsMonitor {
                 handle,
                 #[cfg(target_os = "linux")]
                 route_manager,
+                #[cfg(target_os = "linux")]
+                journal_path,
             )?,
         })
     }
@@ -187,6 +225,7 @@ trait DnsMonitorT: Sized {
     fn new(
         #[cfg(target_os = "linux")] handle: tokio::runtime::Handle,
         #[cfg(target_os = "linux")] route_manager: RouteManagerHandle,
+        #[cfg(target_os = "linux")] journal_path: Option<PathBuf>,
     ) -> Result<Self, Self::Error>;
 
     fn set(&mut self, interface: &str, servers: ResolvedDnsConfig) -> Result<(), Self::Error>;
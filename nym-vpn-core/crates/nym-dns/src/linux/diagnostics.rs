@@ -0,0 +1,645 @@
+// Copyright 2024 Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Inspection of the system resolver configuration, to check that only the tunnel DNS servers
+//! are in use while connected.
+
+use std::{
+    fs, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use nym_dbus::{
+    network_manager::NetworkManager as NetworkManagerDbus,
+    systemd_resolved::SystemdResolved as SystemdResolvedDbus,
+};
+use resolv_conf::{Config, ScopedIp};
+
+pub use super::DnsManager;
+
+const RESOLV_CONF_PATH: &str = "/etc/resolv.conf";
+
+/// Directories holding the per-interface records of resolvconf and openresolv respectively.
+const RESOLVCONF_RECORD_DIRS: [&str; 2] =
+    ["/run/resolvconf/interface", "/run/resolvconf/interfaces"];
+
+/// Max size of the responses to the probe queries.
+const MAX_RESPONSE_LEN: usize = 512;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Failed to read the DNS servers from systemd-resolved")]
+    SystemdResolved(#[source] nym_dbus::systemd_resolved::Error),
+
+    #[error("Failed to read the DNS configuration from NetworkManager")]
+    NetworkManager(#[source] nym_dbus::network_manager::Error),
+
+    #[error("Failed to read {0}")]
+    ReadResolvConf(PathBuf, #[source] io::Error),
+
+    #[error("{0} could not be parsed")]
+    ParseResolvConf(PathBuf, #[source] resolv_conf::ParseError),
+
+    #[error("Failed to list network interfaces")]
+    ListInterfaces(#[source] nix::Error),
+
+    #[error("Failed to detect how DNS is managed")]
+    DetectDnsManager(#[source] super::Error),
+}
+
+/// DNS servers configured for an interface, or globally.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceDnsServers {
+    /// Name of the interface, `None` for the global servers.
+    pub interface: Option<String>,
+    pub servers: Vec<IpAddr>,
+}
+
+/// Resolver configuration of the system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemDnsConfig {
+    pub manager: DnsManager,
+    pub interfaces: Vec<InterfaceDnsServers>,
+}
+
+/// Reads the active resolver configuration from the module used by the DNS monitor.
+pub fn inspect_system_dns() -> Result<SystemDnsConfig> {
+    let manager = DnsManager::detect().map_err(Error::DetectDnsManager)?;
+    let mut interfaces = match manager {
+        DnsManager::SystemdResolved => systemd_resolved_servers()?,
+        DnsManager::NetworkManager => network_manager_servers()?,
+        DnsManager::Resolvconf => resolvconf_servers()?,
+        DnsManager::StaticResolvConf => {
+            let mut interfaces = Vec::new();
+            for server in read_resolv_conf(Path::new(RESOLV_CONF_PATH))? {
+                push_server(&mut interfaces, None, server);
+            }
+            interfaces
+        }
+    };
+    interfaces.retain(|entry| !entry.servers.is_empty());
+
+    Ok(SystemDnsConfig {
+        manager,
+        interfaces,
+    })
+}
+
+fn systemd_resolved_servers() -> Result<Vec<InterfaceDnsServers>> {
+    let servers = SystemdResolvedDbus::new()
+        .and_then(|systemd_resolved| systemd_resolved.get_all_dns_servers())
+        .map_err(Error::SystemdResolved)?;
+    let interface_names = nix::net::if_::if_nameindex().map_err(Error::ListInterfaces)?;
+
+    let mut interfaces = Vec::new();
+    for server in servers {
+        let interface = (server.iface_index != 0).then(|| {
+            interface_names
+                .iter()
+                .find(|interface| i64::from(interface.index()) == i64::from(server.iface_index))
+                .map(|interface| interface.name().to_string_lossy().into_owned())
+                .unwrap_or_else(|| format!("#{}", server.iface_index))
+        });
+        push_server(&mut interfaces, interface, server.address);
+    }
+    Ok(interfaces)
+}
+
+fn network_manager_servers() -> Result<Vec<InterfaceDnsServers>> {
+    let entries = NetworkManagerDbus::new()
+        .and_then(|network_manager| network_manager.get_dns_configuration())
+        .map_err(Error::NetworkManager)?;
+
+    let mut interfaces = Vec::new();
+    for entry in entries {
+        let interface = entry.interface.filter(|interface| !interface.is_empty());
+        for server in entry.nameservers {
+            push_server(&mut interfaces, interface.clone(), server);
+        }
+    }
+    Ok(interfaces)
+}
+
+fn resolvconf_servers() -> Result<Vec<InterfaceDnsServers>> {
+    let mut interfaces = Vec::new();
+    for dir in RESOLVCONF_RECORD_DIRS {
+        let records = match fs::read_dir(dir) {
+            Ok(records) => records,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(Error::ReadResolvConf(PathBuf::from(dir), e)),
+        };
+
+        for record in records.filter_map(|record| record.ok()) {
+            // Records are named after their interface, e.g. `eth0.dhclient`
+            let record_name = record.file_name().to_string_lossy().into_owned();
+            let interface = record_name.split('.').next().unwrap_or_default().to_owned();
+            for server in read_resolv_conf(&record.path())? {
+                push_server(&mut interfaces, Some(interface.clone()), server);
+            }
+        }
+    }
+
+    // The generated file merges the records, only keep the servers added by other means
+    for server in read_resolv_conf(Path::new(RESOLV_CONF_PATH))? {
+        if !interfaces
+            .iter()
+            .any(|entry: &InterfaceDnsServers| entry.servers.contains(&server))
+        {
+            push_server(&mut interfaces, None, server);
+        }
+    }
+    Ok(interfaces)
+}
+
+fn read_resolv_conf(path: &Path) -> Result<Vec<IpAddr>> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| Error::ReadResolvConf(path.to_owned(), e))?;
+    let config = Config::parse(contents).map_err(|e| Error::ParseResolvConf(path.to_owned(), e))?;
+
+    Ok(config
+        .nameservers
+        .into_iter()
+        .map(|server| match server {
+            ScopedIp::V4(address) => IpAddr::V4(address),
+            ScopedIp::V6(address, _) => IpAddr::V6(address),
+        })
+        .collect())
+}
+
+fn push_server(
+    interfaces: &mut Vec<InterfaceDnsServers>,
+    interface: Option<String>,
+    server: IpAddr,
+) {
+    match interfaces
+        .iter_mut()
+        .find(|entry| entry.interface == interface)
+    {
+        Some(entry) => {
+            if !entry.servers.contains(&server) {
+                entry.servers.push(server);
+            }
+        }
+        None => interfaces.push(InterfaceDnsServers {
+            interface,
+            servers: vec![server],
+        }),
+    }
+}
+
+/// Expected DNS configuration while connected.
+#[derive(Debug, Clone)]
+pub struct DiagnoseOptions {
+    /// Addresses of the tunnel interface.
+    pub tunnel_addresses: Vec<IpAddr>,
+    /// DNS servers the system resolver should be pointed at.
+    pub tunnel_servers: Vec<IpAddr>,
+    /// DNS servers of the domain rules, reached outside of the tunnel on purpose.
+    pub domain_rule_servers: Vec<IpAddr>,
+    /// Port the DNS servers are queried on.
+    pub port: u16,
+    /// Time to wait for the answers of the DNS servers, which are queried concurrently.
+    pub timeout: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolverKind {
+    /// DNS server of the tunnel.
+    Tunnel,
+    /// DNS server of a domain rule.
+    DomainRule,
+    /// Any other DNS server, which may be sent queries outside of the tunnel.
+    Foreign,
+}
+
+/// DNS server of the system configuration, along with the outcome of a query sent to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolverStatus {
+    pub address: IpAddr,
+    /// Interface the server is configured for, `None` for the global servers.
+    pub interface: Option<String>,
+    pub kind: ResolverKind,
+    /// Interface the queries to the server are routed through.
+    pub route_interface: Option<String>,
+    /// Time the server took to answer, if it did.
+    pub response_time: Option<Duration>,
+    /// Reason the server could not be queried.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsDiagnostics {
+    pub manager: DnsManager,
+    /// Interface holding the tunnel addresses.
+    pub tunnel_interface: Option<String>,
+    pub resolvers: Vec<ResolverStatus>,
+    /// Tunnel DNS servers missing from the system configuration.
+    pub missing_servers: Vec<IpAddr>,
+    /// Interfaces other than the tunnel with DNS servers of their own.
+    pub foreign_interfaces: Vec<String>,
+}
+
+impl DnsDiagnostics {
+    /// Returns true if only the tunnel DNS servers are in use, besides the ones of the domain
+    /// rules, and they answer through the tunnel.
+    pub fn is_tunnel_dns_only(&self) -> bool {
+        self.missing_servers.is_empty()
+            && self.foreign_interfaces.is_empty()
+            && self.resolvers.iter().all(|resolver| match resolver.kind {
+                ResolverKind::Tunnel => {
+                    resolver.response_time.is_some() && self.is_routed_through_tunnel(resolver)
+                }
+                ResolverKind::DomainRule => true,
+                ResolverKind::Foreign => false,
+            })
+    }
+
+    /// Returns true if the queries to the DNS server go through the tunnel, or to a local
+    /// forwarder, whose own queries go through the tunnel.
+    pub fn is_routed_through_tunnel(&self, resolver: &ResolverStatus) -> bool {
+        resolver.address.is_loopback()
+            || (resolver.route_interface.is_some()
+                && resolver.route_interface == self.tunnel_interface)
+    }
+}
+
+/// Queries the DNS servers of the system configuration and checks them against the expected
+/// configuration.
+pub fn diagnose(config: &SystemDnsConfig, options: &DiagnoseOptions) -> DnsDiagnostics {
+    let interface_addresses = interface_addresses();
+    let tunnel_interface = interface_addresses
+        .iter()
+        .find(|(_, address)| options.tunnel_addresses.contains(address))
+        .map(|(interface, _)| interface.clone());
+
+    let kind_of = |address: &IpAddr| {
+        if options.tunnel_servers.contains(address) {
+            ResolverKind::Tunnel
+        } else if options.domain_rule_servers.contains(address) {
+            ResolverKind::DomainRule
+        } else {
+            ResolverKind::Foreign
+        }
+    };
+
+    // The servers are queried concurrently, so that the unresponsive ones only take the timeout
+    // once overall.
+    let resolvers = thread::scope(|scope| {
+        let interface_addresses = &interface_addresses;
+        let probes = config
+            .interfaces
+            .iter()
+            .flat_map(|entry| entry.servers.iter().map(move |address| (entry, *address)))
+            .map(|(entry, address)| {
+                let server = SocketAddr::new(address, options.port);
+                let probe = scope
+                    .spawn(move || probe_resolver(server, options.timeout, interface_addresses));
+                (entry, address, probe)
+            })
+            .collect::<Vec<_>>();
+
+        probes
+            .into_iter()
+            .map(|(entry, address, probe)| {
+                let probe = probe.join().unwrap_or_else(|_| Probe {
+                    route_interface: None,
+                    response_time: None,
+                    error: Some("probe panicked".to_owned()),
+                });
+                ResolverStatus {
+                    address,
+                    interface: entry.interface.clone(),
+                    kind: kind_of(&address),
+                    route_interface: probe.route_interface,
+                    response_time: probe.response_time,
+                    error: probe.error,
+                }
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let missing_servers = options
+        .tunnel_servers
+        .iter()
+        .filter(|server| {
+            !resolvers
+                .iter()
+                .any(|resolver| resolver.address == **server)
+        })
+        .copied()
+        .collect();
+
+    let mut foreign_interfaces = Vec::new();
+    for entry in &config.interfaces {
+        let Some(interface) = entry.interface.as_ref() else {
+            continue;
+        };
+        if Some(interface) != tunnel_interface.as_ref()
+            && entry
+                .servers
+                .iter()
+                .any(|server| kind_of(server) == ResolverKind::Foreign)
+            && !foreign_interfaces.contains(interface)
+        {
+            foreign_interfaces.push(interface.clone());
+        }
+    }
+
+    DnsDiagnostics {
+        manager: config.manager,
+        tunnel_interface,
+        resolvers,
+        missing_servers,
+        foreign_interfaces,
+    }
+}
+
+fn interface_addresses() -> Vec<(String, IpAddr)> {
+    match nix::ifaddrs::getifaddrs() {
+        Ok(addresses) => addresses
+            .filter_map(|interface_address| {
+                let address = interface_address.address?;
+                let address = match address.as_sockaddr_in() {
+                    Some(address) => IpAddr::V4(address.ip()),
+                    None => IpAddr::V6(address.as_sockaddr_in6()?.ip()),
+                };
+                Some((interface_address.interface_name, address))
+            })
+            .collect(),
+        Err(e) => {
+            log::error!("Failed to list interface addresses: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+struct Probe {
+    route_interface: Option<String>,
+    response_time: Option<Duration>,
+    error: Option<String>,
+}
+
+fn probe_resolver(
+    server: SocketAddr,
+    timeout: Duration,
+    interface_addresses: &[(String, IpAddr)],
+) -> Probe {
+    let socket = match connect_socket(server) {
+        Ok(socket) => socket,
+        Err(e) => {
+            return Probe {
+                route_interface: None,
+                response_time: None,
+                error: Some(e.to_string()),
+            }
+        }
+    };
+
+    // The source address is picked from the route to the server
+    let route_interface = socket.local_addr().ok().and_then(|local_addr| {
+        interface_addresses
+            .iter()
+            .find(|(_, address)| *address == local_addr.ip())
+            .map(|(interface, _)| interface.clone())
+    });
+
+    match send_query(&socket, timeout) {
+        Ok(response_time) => Probe {
+            route_interface,
+            response_time: Some(response_time),
+            error: None,
+        },
+        Err(e) => Probe {
+            route_interface,
+            response_time: None,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+fn connect_socket(server: SocketAddr) -> io::Result<UdpSocket> {
+    let bind_addr = match server {
+        SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+        SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+    };
+    let socket = UdpSocket::bind(bind_addr)?;
+    socket.connect(server)?;
+    Ok(socket)
+}
+
+/// Sends a query for the root name servers and waits for the answer.
+fn send_query(socket: &UdpSocket, timeout: Duration) -> io::Result<Duration> {
+    let id = query_id();
+    let started = Instant::now();
+    socket.send(&build_query(id))?;
+
+    let no_answer = || io::Error::new(io::ErrorKind::TimedOut, format!("no answer in {timeout:?}"));
+    let mut buf = [0u8; MAX_RESPONSE_LEN];
+    loop {
+        let remaining = timeout
+            .checked_sub(started.elapsed())
+            .filter(|remaining| !remaining.is_zero())
+            .ok_or_else(no_answer)?;
+        socket.set_read_timeout(Some(remaining))?;
+
+        let len = socket.recv(&mut buf).map_err(|e| match e.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => no_answer(),
+            _ => e,
+        })?;
+        if is_response(&buf[..len], id) {
+            return Ok(started.elapsed());
+        }
+    }
+}
+
+fn query_id() -> u16 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    (nanos as u16) ^ (std::process::id() as u16)
+}
+
+fn build_query(id: u16) -> Vec<u8> {
+    let mut query = Vec::with_capacity(17);
+    query.extend_from_slice(&id.to_be_bytes());
+    // Standard query, recursion desired
+    query.extend_from_slice(&[0x01, 0x00]);
+    // One question, no answer, authority or additional records
+    query.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+    // Root name, type NS, class IN
+    query.push(0);
+    query.extend_from_slice(&[0, 2, 0, 1]);
+    query
+}
+
+fn is_response(message: &[u8], id: u16) -> bool {
+    message.len() >= 12 && message[..2] == id.to_be_bytes() && message[2] & 0x80 != 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(500);
+
+    /// Answers all the queries it receives, standing in for the tunnel DNS server.
+    fn spawn_stand_in_resolver() -> SocketAddr {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = socket.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut buf = [0u8; MAX_RESPONSE_LEN];
+            while let Ok((len, peer)) = socket.recv_from(&mut buf) {
+                let mut response = buf[..len].to_vec();
+                response[2] |= 0x80;
+                let _ = socket.send_to(&response, peer);
+            }
+        });
+        addr
+    }
+
+    fn options(resolver: SocketAddr) -> DiagnoseOptions {
+        DiagnoseOptions {
+            tunnel_addresses: vec![resolver.ip()],
+            tunnel_servers: vec![resolver.ip()],
+            domain_rule_servers: vec![],
+            port: resolver.port(),
+            timeout: TIMEOUT,
+        }
+    }
+
+    fn config(interfaces: Vec<(Option<&str>, Vec<IpAddr>)>) -> SystemDnsConfig {
+        SystemDnsConfig {
+            manager: DnsManager::SystemdResolved,
+            interfaces: interfaces
+                .into_iter()
+                .map(|(interface, servers)| InterfaceDnsServers {
+                    interface: interface.map(str::to_owned),
+                    servers,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_tunnel_dns_only() {
+        let resolver = spawn_stand_in_resolver();
+        let config = config(vec![(Some("lo"), vec![resolver.ip()])]);
+
+        let diagnostics = diagnose(&config, &options(resolver));
+
+        assert_eq!(diagnostics.tunnel_interface.as_deref(), Some("lo"));
+        assert_eq!(diagnostics.resolvers.len(), 1);
+        assert_eq!(diagnostics.resolvers[0].kind, ResolverKind::Tunnel);
+        assert_eq!(
+            diagnostics.resolvers[0].route_interface.as_deref(),
+            Some("lo")
+        );
+        assert!(diagnostics.resolvers[0].response_time.is_some());
+        assert!(diagnostics.missing_servers.is_empty());
+        assert!(diagnostics.foreign_interfaces.is_empty());
+        assert!(diagnostics.is_tunnel_dns_only());
+    }
+
+    #[test]
+    fn test_foreign_interface_dns() {
+        let resolver = spawn_stand_in_resolver();
+        let foreign_server = IpAddr::from([127, 0, 0, 2]);
+        let config = config(vec![
+            (Some("lo"), vec![resolver.ip()]),
+            (Some("eth0"), vec![foreign_server]),
+        ]);
+
+        let diagnostics = diagnose(&config, &options(resolver));
+
+        assert_eq!(diagnostics.foreign_interfaces, vec!["eth0".to_owned()]);
+        assert_eq!(diagnostics.resolvers[1].address, foreign_server);
+        assert_eq!(diagnostics.resolvers[1].kind, ResolverKind::Foreign);
+        assert!(!diagnostics.is_tunnel_dns_only());
+    }
+
+    #[test]
+    fn test_domain_rule_dns() {
+        let resolver = spawn_stand_in_resolver();
+        let rule_server = IpAddr::from([127, 0, 0, 2]);
+        let config = config(vec![
+            (Some("lo"), vec![resolver.ip()]),
+            (Some("eth0"), vec![rule_server]),
+        ]);
+        let options = DiagnoseOptions {
+            domain_rule_servers: vec![rule_server],
+            ..options(resolver)
+        };
+
+        let diagnostics = diagnose(&config, &options);
+
+        assert_eq!(diagnostics.resolvers[1].kind, ResolverKind::DomainRule);
+        assert!(diagnostics.foreign_interfaces.is_empty());
+        assert!(diagnostics.is_tunnel_dns_only());
+    }
+
+    #[test]
+    fn test_global_foreign_dns() {
+        let resolver = spawn_stand_in_resolver();
+        let foreign_server = IpAddr::from([127, 0, 0, 2]);
+        let config = config(vec![(None, vec![foreign_server])]);
+
+        let diagnostics = diagnose(&config, &options(resolver));
+
+        assert_eq!(diagnostics.missing_servers, vec![resolver.ip()]);
+        assert!(diagnostics.foreign_interfaces.is_empty());
+        assert_eq!(diagnostics.resolvers[0].kind, ResolverKind::Foreign);
+        assert!(!diagnostics.is_tunnel_dns_only());
+    }
+
+    #[test]
+    fn test_unresponsive_tunnel_dns() {
+        // Bound so that the queries are not rejected, but never answered
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let resolver = socket.local_addr().unwrap();
+        let config = config(vec![(Some("lo"), vec![resolver.ip()])]);
+
+        let diagnostics = diagnose(&config, &options(resolver));
+
+        assert!(diagnostics.resolvers[0].response_time.is_none());
+        assert!(diagnostics.resolvers[0].error.is_some());
+        assert!(!diagnostics.is_tunnel_dns_only());
+    }
+
+    #[test]
+    fn test_query_servers_concurrently() {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let resolver = socket.local_addr().unwrap();
+        let config = config(vec![
+            (Some("lo"), vec![resolver.ip(), resolver.ip()]),
+            (None, vec![resolver.ip(), resolver.ip()]),
+        ]);
+
+        let started = Instant::now();
+        let diagnostics = diagnose(&config, &options(resolver));
+
+        assert_eq!(diagnostics.resolvers.len(), 4);
+        assert!(diagnostics
+            .resolvers
+            .iter()
+            .all(|resolver| resolver.error.is_some()));
+        assert!(started.elapsed() < 2 * TIMEOUT);
+    }
+
+    #[test]
+    fn test_ignore_unrelated_messages() {
+        let id = 0x1234;
+        let mut response = build_query(id);
+        assert!(!is_response(&response, id));
+
+        response[2] |= 0x80;
+        assert!(is_response(&response, id));
+        assert!(!is_response(&response, id + 1));
+        assert!(!is_response(&response[..11], id));
+    }
+}
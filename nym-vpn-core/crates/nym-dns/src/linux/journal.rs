@@ -0,0 +1,151 @@
+// Copyright 2024 Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Journal of the DNS configuration replaced by the DNS monitor. It is written before setting
+//! DNS and removed once DNS is reset, so that a journal found on startup means that the previous
+//! process did not get to reset DNS.
+
+use std::{
+    fs, io,
+    net::IpAddr,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Failed to read the DNS journal at {0}")]
+    Read(PathBuf, #[source] io::Error),
+
+    #[error("DNS journal at {0} could not be parsed")]
+    Parse(PathBuf, #[source] serde_json::Error),
+
+    #[error("Failed to serialize the DNS journal")]
+    Serialize(#[source] serde_json::Error),
+
+    #[error("Failed to write the DNS journal at {0}")]
+    Write(PathBuf, #[source] io::Error),
+
+    #[error("Failed to remove the DNS journal at {0}")]
+    Remove(PathBuf, #[source] io::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsJournal {
+    /// Tunnel interface the DNS servers were set on.
+    pub interface: String,
+    /// DNS servers set on the tunnel interface.
+    pub servers: Vec<IpAddr>,
+    pub original_state: OriginalDnsState,
+}
+
+/// DNS configuration as it was before being set, for each module managing DNS.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "manager", rename_all = "snake_case")]
+pub enum OriginalDnsState {
+    SystemdResolved {
+        /// Links set to resolve specific domains.
+        links: Vec<LinkDnsState>,
+    },
+    Resolvconf {
+        record_name: String,
+    },
+    StaticResolvConf {
+        resolv_conf: String,
+    },
+    NetworkManager,
+}
+
+/// DNS configuration of a systemd-resolved link.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkDnsState {
+    /// Name of the interface, as the indices are not stable across reboots.
+    pub interface: String,
+    pub servers: Vec<IpAddr>,
+    pub domains: Vec<(String, bool)>,
+}
+
+impl DnsJournal {
+    /// Reads the journal, returning `None` if there is none.
+    pub fn read(path: &Path) -> Result<Option<Self>> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(Error::Read(path.to_owned(), error)),
+        };
+        serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| Error::Parse(path.to_owned(), e))
+    }
+
+    /// Writes the journal atomically, so that a partially written journal is never left behind.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self).map_err(Error::Serialize)?;
+        nym_common::fs::write_atomically(path, contents)
+            .map_err(|e| Error::Write(path.to_owned(), e))
+    }
+
+    /// Removes the journal. This succeeds if there is none.
+    pub fn remove(path: &Path) -> Result<()> {
+        match fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(Error::Remove(path.to_owned(), error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn journal() -> DnsJournal {
+        DnsJournal {
+            interface: "nymtun0".to_owned(),
+            servers: vec!["10.64.0.1".parse().unwrap()],
+            original_state: OriginalDnsState::SystemdResolved {
+                links: vec![LinkDnsState {
+                    interface: "eth0".to_owned(),
+                    servers: vec!["192.168.1.1".parse().unwrap()],
+                    domains: vec![("lan".to_owned(), false)],
+                }],
+            },
+        }
+    }
+
+    #[test]
+    fn test_write_and_read_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dns_journal.json");
+
+        journal().write(&path).unwrap();
+
+        assert_eq!(DnsJournal::read(&path).unwrap(), Some(journal()));
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+
+    #[test]
+    fn test_remove_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dns_journal.json");
+
+        journal().write(&path).unwrap();
+        DnsJournal::remove(&path).unwrap();
+
+        assert_eq!(DnsJournal::read(&path).unwrap(), None);
+        DnsJournal::remove(&path).unwrap();
+    }
+
+    #[test]
+    fn test_read_invalid_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dns_journal.json");
+
+        fs::write(&path, "nameserver 10.64.0.1").unwrap();
+
+        assert!(matches!(DnsJournal::read(&path), Err(Error::Parse(..))));
+    }
+}
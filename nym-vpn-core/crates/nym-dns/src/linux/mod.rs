@@ -2,19 +2,29 @@
 // Copyright 2024 Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
+pub mod diagnostics;
+mod journal;
 mod network_manager;
 mod resolvconf;
 mod static_resolv_conf;
 mod systemd_resolved;
 
 use self::{
-    network_manager::NetworkManager, resolvconf::Resolvconf, static_resolv_conf::StaticResolvConf,
+    journal::{DnsJournal, OriginalDnsState},
+    network_manager::NetworkManager,
+    resolvconf::Resolvconf,
+    static_resolv_conf::StaticResolvConf,
     systemd_resolved::SystemdResolved,
 };
+use nym_common::ErrorExt;
 use nym_routing::RouteManagerHandle;
-use std::{env, fmt, net::IpAddr};
+use std::{
+    env, fmt,
+    net::IpAddr,
+    path::{Path, PathBuf},
+};
 
-use super::ResolvedDnsConfig;
+use super::{DomainResolver, ResolvedDnsConfig};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -37,6 +47,10 @@ pub enum Error {
     #[error("Error in static /etc/resolv.conf DNS monitor")]
     StaticResolvConf(#[from] static_resolv_conf::Error),
 
+    /// Error in the journal of the replaced DNS configuration
+    #[error("Error in the DNS journal")]
+    Journal(#[from] journal::Error),
+
     /// No suitable DNS monitor implementation detected
     #[error("No suitable DNS monitor implementation detected")]
     NoDnsMonitor,
@@ -46,16 +60,26 @@ pub struct DnsMonitor {
     route_manager: RouteManagerHandle,
     handle: tokio::runtime::Handle,
     inner: Option<DnsMonitorHolder>,
+    journal_path: Option<PathBuf>,
 }
 
 impl super::DnsMonitorT for DnsMonitor {
     type Error = Error;
 
-    fn new(handle: tokio::runtime::Handle, route_manager: RouteManagerHandle) -> Result<Self> {
+    fn new(
+        handle: tokio::runtime::Handle,
+        route_manager: RouteManagerHandle,
+        journal_path: Option<PathBuf>,
+    ) -> Result<Self> {
+        if let Some(journal_path) = journal_path.as_deref() {
+            restore_from_journal(journal_path);
+        }
+
         Ok(DnsMonitor {
             route_manager,
             handle,
             inner: None,
+            journal_path,
         })
     }
 
@@ -64,8 +88,33 @@ impl super::DnsMonitorT for DnsMonitor {
         self.reset()?;
         // Creating a new DNS monitor for each set, in case the system changed how it manages DNS.
         let mut inner = DnsMonitorHolder::new()?;
+        log::debug!("Managing DNS via {}", inner);
         if !servers.is_empty() {
-            inner.set(&self.handle, &self.route_manager, interface, servers)?;
+            if let Some(journal_path) = self.journal_path.as_deref() {
+                // DNS is still set without a journal, it just won't be restored after a crash
+                if let Err(error) = inner
+                    .journal(
+                        &self.handle,
+                        &self.route_manager,
+                        interface,
+                        servers,
+                        config.domain_resolvers(),
+                    )
+                    .and_then(|journal| journal.write(journal_path).map_err(Error::from))
+                {
+                    log::error!(
+                        "{}",
+                        error.display_chain_with_msg("Failed to write DNS journal")
+                    );
+                }
+            }
+            inner.set(
+                &self.handle,
+                &self.route_manager,
+                interface,
+                servers,
+                config.domain_resolvers(),
+            )?;
             self.inner = Some(inner);
         }
         Ok(())
@@ -75,10 +124,83 @@ impl super::DnsMonitorT for DnsMonitor {
         if let Some(mut inner) = self.inner.take() {
             inner.reset(&self.handle)?;
         }
+        if let Some(journal_path) = self.journal_path.as_deref() {
+            DnsJournal::remove(journal_path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Restores the DNS configuration replaced by a previous process, if it did not get to reset DNS.
+/// The journal is removed even if restoring fails, so that it is not mistaken for the original
+/// configuration when DNS is set again.
+fn restore_from_journal(journal_path: &Path) {
+    match DnsJournal::read(journal_path) {
+        Ok(Some(journal)) => {
+            log::info!("Restoring DNS configuration left over by the previous session");
+            if let Err(error) = journal.restore() {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to restore DNS from journal")
+                );
+            }
+        }
+        Ok(None) => log::debug!("No DNS journal to restore"),
+        Err(error) => log::error!("{}", error.display_chain()),
+    }
+
+    if let Err(error) = DnsJournal::remove(journal_path) {
+        log::error!("{}", error.display_chain());
+    }
+}
+
+impl DnsJournal {
+    fn restore(&self) -> Result<()> {
+        match &self.original_state {
+            OriginalDnsState::SystemdResolved { links } => {
+                systemd_resolved::restore_journal(&self.interface, links)?
+            }
+            OriginalDnsState::Resolvconf { record_name } => {
+                Resolvconf::delete_stale_record(record_name)?
+            }
+            OriginalDnsState::StaticResolvConf { resolv_conf } => {
+                static_resolv_conf::restore_journal(resolv_conf, &self.servers)?
+            }
+            OriginalDnsState::NetworkManager => (),
+        }
         Ok(())
     }
 }
 
+/// Module managing DNS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DnsManager {
+    SystemdResolved,
+    NetworkManager,
+    Resolvconf,
+    StaticResolvConf,
+}
+
+impl fmt::Display for DnsManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::SystemdResolved => "systemd-resolved",
+            Self::NetworkManager => "network manager",
+            Self::Resolvconf => "resolvconf",
+            Self::StaticResolvConf => "/etc/resolv.conf",
+        };
+        f.write_str(name)
+    }
+}
+
+impl DnsManager {
+    /// Detects the module the DNS monitor uses. The modules are only probed, without touching
+    /// the DNS configuration.
+    pub fn detect() -> Result<Self> {
+        DnsMonitorHolder::new().map(|holder| holder.manager())
+    }
+}
+
 pub enum DnsMonitorHolder {
     SystemdResolved(SystemdResolved),
     NetworkManager(NetworkManager),
@@ -88,66 +210,122 @@ pub enum DnsMonitorHolder {
 
 impl fmt::Display for DnsMonitorHolder {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        use self::DnsMonitorHolder::*;
-        let name = match self {
-            Resolvconf(..) => "resolvconf",
-            StaticResolvConf(..) => "/etc/resolv.conf",
-            SystemdResolved(..) => "systemd-resolved",
-            NetworkManager(..) => "network manager",
-        };
-        f.write_str(name)
+        fmt::Display::fmt(&self.manager(), f)
     }
 }
 
 impl DnsMonitorHolder {
+    /// Creates the module managing DNS, unless one is picked with the `NYM_DNS_MODULE`
+    /// environment variable.
     fn new() -> Result<Self> {
-        let dns_module = env::var_os("NYM_DNS_MODULE");
-
-        let manager = match dns_module.as_ref().and_then(|value| value.to_str()) {
-            Some("static-file") => DnsMonitorHolder::StaticResolvConf(StaticResolvConf::new()?),
-            Some("resolvconf") => DnsMonitorHolder::Resolvconf(Resolvconf::new()?),
-            Some("systemd") => DnsMonitorHolder::SystemdResolved(SystemdResolved::new()?),
-            Some("network-manager") => DnsMonitorHolder::NetworkManager(NetworkManager::new()?),
-            Some(_) | None => Self::with_detected_dns_manager()?,
-        };
-        log::debug!("Managing DNS via {}", manager);
-        Ok(manager)
+        match env::var_os("NYM_DNS_MODULE")
+            .as_ref()
+            .and_then(|value| value.to_str())
+        {
+            Some("static-file") => Ok(Self::StaticResolvConf(StaticResolvConf::new()?)),
+            Some("resolvconf") => Ok(Self::Resolvconf(Resolvconf::new()?)),
+            Some("systemd") => Ok(Self::SystemdResolved(SystemdResolved::new()?)),
+            Some("network-manager") => Ok(Self::NetworkManager(NetworkManager::new()?)),
+            Some(_) | None => Self::with_detected_dns_manager(),
+        }
     }
 
     fn with_detected_dns_manager() -> Result<Self> {
         SystemdResolved::new()
-            .map(DnsMonitorHolder::SystemdResolved)
+            .map(Self::SystemdResolved)
             .or_else(|err| {
                 match err {
                     systemd_resolved::Error::SystemdResolvedError(
                         systemd_resolved::SystemdDbusError::NoSystemdResolved(_),
                     ) => (),
                     other_error => {
-                        log::debug!("NetworkManager is being used because {}", other_error)
+                        log::debug!("Not using systemd-resolved because {}", other_error)
                     }
                 }
-                NetworkManager::new().map(DnsMonitorHolder::NetworkManager)
+                NetworkManager::new().map(Self::NetworkManager)
             })
-            .or_else(|_| Resolvconf::new().map(DnsMonitorHolder::Resolvconf))
-            .or_else(|_| StaticResolvConf::new().map(DnsMonitorHolder::StaticResolvConf))
+            .or_else(|_| Resolvconf::new().map(Self::Resolvconf))
+            .or_else(|_| StaticResolvConf::new().map(Self::StaticResolvConf))
             .map_err(|_| Error::NoDnsMonitor)
     }
 
+    fn manager(&self) -> DnsManager {
+        match self {
+            Self::SystemdResolved(..) => DnsManager::SystemdResolved,
+            Self::NetworkManager(..) => DnsManager::NetworkManager,
+            Self::Resolvconf(..) => DnsManager::Resolvconf,
+            Self::StaticResolvConf(..) => DnsManager::StaticResolvConf,
+        }
+    }
+
+    /// Returns the journal of the DNS configuration about to be replaced by `set`.
+    fn journal(
+        &self,
+        handle: &tokio::runtime::Handle,
+        route_manager: &RouteManagerHandle,
+        interface: &str,
+        servers: &[IpAddr],
+        domain_resolvers: &[DomainResolver],
+    ) -> Result<DnsJournal> {
+        use self::DnsMonitorHolder::*;
+        let original_state = match self {
+            Resolvconf(..) => OriginalDnsState::Resolvconf {
+                record_name: resolvconf::Resolvconf::record_name(interface),
+            },
+            StaticResolvConf(..) => OriginalDnsState::StaticResolvConf {
+                resolv_conf: static_resolv_conf::read_resolv_conf()?,
+            },
+            SystemdResolved(ref systemd_resolved) => {
+                let links = if domain_resolvers.is_empty() {
+                    Vec::new()
+                } else {
+                    handle.block_on(
+                        systemd_resolved
+                            .domain_resolver_link_states(route_manager, domain_resolvers),
+                    )?
+                };
+                OriginalDnsState::SystemdResolved { links }
+            }
+            // NetworkManager only changes the tunnel device, which is removed along with the
+            // process that created it.
+            NetworkManager(..) => OriginalDnsState::NetworkManager,
+        };
+
+        Ok(DnsJournal {
+            interface: interface.to_owned(),
+            servers: servers.to_vec(),
+            original_state,
+        })
+    }
+
     fn set(
         &mut self,
         handle: &tokio::runtime::Handle,
         route_manager: &RouteManagerHandle,
         interface: &str,
         servers: &[IpAddr],
+        domain_resolvers: &[DomainResolver],
     ) -> Result<()> {
         use self::DnsMonitorHolder::*;
+        if !domain_resolvers.is_empty() && !matches!(self, SystemdResolved(..)) {
+            log::warn!(
+                "Ignoring domain resolvers, they are not supported by {}",
+                self
+            );
+        }
         match self {
             Resolvconf(ref mut resolvconf) => resolvconf.set_dns(interface, servers)?,
             StaticResolvConf(ref mut static_resolv_conf) => {
                 static_resolv_conf.set_dns(servers.to_vec())?
             }
-            SystemdResolved(ref mut systemd_resolved) => handle
-                .block_on(systemd_resolved.set_dns(route_manager.clone(), interface, servers))?,
+            SystemdResolved(ref mut systemd_resolved) => {
+                handle.block_on(systemd_resolved.set_dns(
+                    route_manager.clone(),
+                    interface,
+                    servers,
+                    domain_resolvers,
+                ))?
+            }
             NetworkManager(ref mut network_manager) => {
                 network_manager.set_dns(interface, servers)?
             }
@@ -171,5 +349,11 @@ impl DnsMonitorHolder {
 
 /// Returns true if DnsMonitor will use NetworkManager to manage DNS.
 pub fn will_use_nm() -> bool {
-    crate::imp::SystemdResolved::new().is_err() && crate::imp::NetworkManager::new().is_ok()
+    matches!(DnsManager::detect(), Ok(DnsManager::NetworkManager))
+}
+
+/// Returns true if DnsMonitor will use systemd-resolved to manage DNS, which is the only module
+/// supporting domain resolvers.
+pub fn will_use_systemd_resolved() -> bool {
+    matches!(DnsManager::detect(), Ok(DnsManager::SystemdResolved))
 }
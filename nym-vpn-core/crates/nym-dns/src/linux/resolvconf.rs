@@ -79,8 +79,13 @@ impl Resolvconf {
             .unwrap_or_else(|_| false)
     }
 
+    /// Returns the name of the record holding the DNS servers of the interface.
+    pub fn record_name(interface: &str) -> String {
+        format!("{interface}.mullvad")
+    }
+
     pub fn set_dns(&mut self, interface: &str, servers: &[IpAddr]) -> Result<()> {
-        let record_name = format!("{interface}.mullvad");
+        let record_name = Self::record_name(interface);
         let mut record_contents = String::new();
 
         for address in servers {
@@ -110,25 +115,40 @@ impl Resolvconf {
         let mut result = Ok(());
 
         for record_name in self.record_names.drain() {
-            let output = duct::cmd!(&self.resolvconf, "-d", &record_name, "-f")
-                .stderr_capture()
-                .unchecked()
-                .run()
-                .map_err(Error::RunResolvconf)?;
-
-            if !output.status.success() {
-                log::error!(
-                    "Failed to delete 'resolvconf' record '{}':\n{}",
-                    record_name,
-                    String::from_utf8_lossy(&output.stderr)
-                );
-                result = Err(Error::DeleteRecord);
+            match Self::delete_record(&self.resolvconf, &record_name) {
+                Err(Error::DeleteRecord) => result = Err(Error::DeleteRecord),
+                other => other?,
             }
         }
 
         result
     }
 
+    /// Deletes a record added by a process that was killed before resetting DNS.
+    pub fn delete_stale_record(record_name: &str) -> Result<()> {
+        let resolvconf_path = which("resolvconf").map_err(|_| Error::NoResolvconf)?;
+        Self::delete_record(&resolvconf_path, record_name)
+    }
+
+    fn delete_record(resolvconf: &Path, record_name: &str) -> Result<()> {
+        let output = duct::cmd!(resolvconf, "-d", record_name, "-f")
+            .stderr_capture()
+            .unchecked()
+            .run()
+            .map_err(Error::RunResolvconf)?;
+
+        if !output.status.success() {
+            log::error!(
+                "Failed to delete 'resolvconf' record '{}':\n{}",
+                record_name,
+                String::from_utf8_lossy(&output.stderr)
+            );
+            return Err(Error::DeleteRecord);
+        }
+
+        Ok(())
+    }
+
     fn is_dnsmasq_running() -> bool {
         let pid = match fs::read_to_string("/var/run/dnsmasq/dnsmasq.pid") {
             Ok(pid) => pid,
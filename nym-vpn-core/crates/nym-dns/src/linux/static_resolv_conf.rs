@@ -7,7 +7,7 @@ use inotify::{Inotify, WatchMask};
 use nym_common::ErrorExt;
 use parking_lot::Mutex;
 use resolv_conf::{Config, ScopedIp};
-use std::{fs, io, net::IpAddr, sync::Arc};
+use std::{fs, io, net::IpAddr, path::Path, sync::Arc};
 use triggered::{trigger, Listener, Trigger};
 
 const RESOLV_CONF_BACKUP_PATH: &str = "/etc/resolv.conf.mullvadbackup";
@@ -86,6 +86,33 @@ impl StaticResolvConf {
     }
 }
 
+/// Returns the contents of /etc/resolv.conf before setting DNS.
+pub fn read_resolv_conf() -> Result<String> {
+    read_config().map(|config| config.to_string())
+}
+
+/// Restores /etc/resolv.conf after a process was killed before resetting DNS. The backup follows
+/// the changes made by other programs while DNS was set, so it is preferred over the journaled
+/// contents, which are only written back if /etc/resolv.conf still points at the tunnel DNS
+/// servers.
+pub fn restore_journal(resolv_conf: &str, tunnel_servers: &[IpAddr]) -> Result<()> {
+    if Path::new(RESOLV_CONF_BACKUP_PATH).exists() {
+        return restore_from_backup();
+    }
+
+    let tunnel_nameservers = tunnel_servers
+        .iter()
+        .map(|&address| ScopedIp::from(address))
+        .collect::<Vec<_>>();
+    if read_config()?.nameservers == tunnel_nameservers {
+        log::info!("Restoring DNS state from journal");
+        fs::write(RESOLV_CONF_PATH, resolv_conf)
+            .map_err(|e| Error::WriteResolvConf(RESOLV_CONF_PATH, e))?;
+    }
+
+    Ok(())
+}
+
 struct State {
     backup: Config,
     desired_dns: Vec<IpAddr>,
@@ -6,9 +6,12 @@ use nym_common::{
     linux::{iface_index, IfaceIndexLookupError},
     ErrorExt,
 };
-use nym_dbus::systemd_resolved::{AsyncHandle, SystemdResolved as DbusInterface};
+use nym_dbus::systemd_resolved::{AsyncHandle, DnsState, SystemdResolved as DbusInterface};
 use nym_routing::RouteManagerHandle;
-use std::net::IpAddr;
+use std::{collections::BTreeMap, net::IpAddr};
+
+use super::journal::LinkDnsState;
+use crate::DomainResolver;
 
 pub(crate) use nym_dbus::systemd_resolved::Error as SystemdDbusError;
 
@@ -21,11 +24,24 @@ pub enum Error {
 
     #[error("Failed to resolve interface index with error {0}")]
     InterfaceNameError(#[from] IfaceIndexLookupError),
+
+    #[error("Failed to find the route to {0}")]
+    RouteLookupError(IpAddr, #[source] nym_routing::Error),
+
+    #[error("No interface is routing traffic to {0}")]
+    NoRouteError(IpAddr),
 }
 
 pub struct SystemdResolved {
     pub dbus_interface: AsyncHandle,
     tunnel_index: u32,
+    domain_resolver_links: Vec<LinkState>,
+}
+
+/// DNS configuration of a link before it was set to resolve specific domains.
+struct LinkState {
+    dns_state: DnsState,
+    domains: Vec<(String, bool)>,
 }
 
 impl SystemdResolved {
@@ -35,6 +51,7 @@ impl SystemdResolved {
         let systemd_resolved = SystemdResolved {
             dbus_interface,
             tunnel_index: 0,
+            domain_resolver_links: Vec::new(),
         };
 
         Ok(systemd_resolved)
@@ -42,9 +59,10 @@ impl SystemdResolved {
 
     pub async fn set_dns(
         &mut self,
-        _route_manager: RouteManagerHandle,
+        route_manager: RouteManagerHandle,
         interface_name: &str,
         servers: &[IpAddr],
+        domain_resolvers: &[DomainResolver],
     ) -> Result<()> {
         let tunnel_index = iface_index(interface_name)?;
         self.tunnel_index = tunnel_index;
@@ -66,10 +84,146 @@ impl SystemdResolved {
             .set_dns(self.tunnel_index, servers.to_vec())
             .await?;
 
+        if let Err(error) = self
+            .set_domain_resolvers(&route_manager, domain_resolvers)
+            .await
+        {
+            // Restore the links that were already set rather than leaving them half set.
+            if let Err(reset_error) = self.reset().await {
+                log::error!(
+                    "{}",
+                    reset_error.display_chain_with_msg("Failed to reset DNS")
+                );
+            }
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the DNS configuration of the links the domain resolvers are reached through, before
+    /// setting the domain resolvers.
+    pub async fn domain_resolver_link_states(
+        &self,
+        route_manager: &RouteManagerHandle,
+        domain_resolvers: &[DomainResolver],
+    ) -> Result<Vec<LinkDnsState>> {
+        let links = Self::domain_resolver_links(route_manager, domain_resolvers).await?;
+
+        let mut link_states = Vec::with_capacity(links.len());
+        for interface in links.into_keys() {
+            let link_index = iface_index(&interface)?;
+            link_states.push(LinkDnsState {
+                servers: self.dbus_interface.get_dns(link_index).await?.set_servers,
+                domains: self.dbus_interface.get_domains(link_index).await?,
+                interface,
+            });
+        }
+        Ok(link_states)
+    }
+
+    /// Returns the servers and domains to set on each link, keyed by the name of the interface
+    /// the servers are reached through.
+    async fn domain_resolver_links(
+        route_manager: &RouteManagerHandle,
+        domain_resolvers: &[DomainResolver],
+    ) -> Result<BTreeMap<String, (Vec<IpAddr>, Vec<String>)>> {
+        let mut links: BTreeMap<String, (Vec<IpAddr>, Vec<String>)> = BTreeMap::new();
+        for resolver in domain_resolvers {
+            for server in &resolver.servers {
+                let route = route_manager
+                    .get_destination_route(*server, None)
+                    .await
+                    .map_err(|e| Error::RouteLookupError(*server, e))?;
+                let interface_name = route
+                    .as_ref()
+                    .and_then(|route| route.get_node().get_device())
+                    .ok_or(Error::NoRouteError(*server))?;
+
+                let (link_servers, link_domains) =
+                    links.entry(interface_name.to_owned()).or_default();
+                if !link_servers.contains(server) {
+                    link_servers.push(*server);
+                }
+                if !link_domains.contains(&resolver.domain) {
+                    link_domains.push(resolver.domain.clone());
+                }
+            }
+        }
+        Ok(links)
+    }
+
+    /// Adds the servers of the domain resolvers to the links they are reached through, along with
+    /// routing-only domains so that systemd-resolved sends them the queries for these domains.
+    /// The servers and domains already set on the links are kept.
+    async fn set_domain_resolvers(
+        &mut self,
+        route_manager: &RouteManagerHandle,
+        domain_resolvers: &[DomainResolver],
+    ) -> Result<()> {
+        let links = Self::domain_resolver_links(route_manager, domain_resolvers).await?;
+
+        for (interface, (servers, domains)) in links {
+            let link_index = iface_index(&interface)?;
+            let link_state = LinkState {
+                dns_state: self.dbus_interface.get_dns(link_index).await?,
+                domains: self.dbus_interface.get_domains(link_index).await?,
+            };
+            let (servers, domains) = merge_link_config(
+                &link_state.dns_state.set_servers,
+                &link_state.domains,
+                servers,
+                domains,
+            );
+            // Recorded before changing the link, so that it is restored even if this fails midway.
+            self.domain_resolver_links.push(link_state);
+
+            let domains = domains
+                .iter()
+                .map(|(domain, routing_only)| (domain.as_str(), *routing_only))
+                .collect::<Vec<_>>();
+            self.dbus_interface
+                .set_domains(link_index, &domains)
+                .await?;
+            self.dbus_interface.set_dns(link_index, servers).await?;
+        }
+
         Ok(())
     }
 
+    async fn reset_domain_resolvers(&mut self) {
+        for link_state in self.domain_resolver_links.drain(..) {
+            let link_index = link_state.dns_state.interface_index;
+            let domains = link_state
+                .domains
+                .iter()
+                .map(|(domain, routing_only)| (domain.as_str(), *routing_only))
+                .collect::<Vec<_>>();
+
+            if let Err(error) = self.dbus_interface.set_domains(link_index, &domains).await {
+                log::error!(
+                    "Failed to restore search domains of link {}: {}",
+                    link_index,
+                    error.display_chain()
+                );
+            }
+            if let Err(error) = self
+                .dbus_interface
+                .set_dns_state(link_state.dns_state)
+                .await
+            {
+                log::error!(
+                    "Failed to restore DNS servers of link {}: {}",
+                    link_index,
+                    error.display_chain()
+                );
+            }
+        }
+    }
+
     pub async fn reset(&mut self) -> Result<()> {
+        self.reset_domain_resolvers().await;
+
         if let Err(error) = self
             .dbus_interface
             .set_domains(self.tunnel_index, &[])
@@ -86,3 +240,142 @@ impl SystemdResolved {
         Ok(())
     }
 }
+
+/// Returns the servers and domains of a link with the ones of the domain resolvers added. The
+/// servers of the domain resolvers come first so that systemd-resolved tries them first, and the
+/// domains already set on the link are kept as they are.
+fn merge_link_config(
+    link_servers: &[IpAddr],
+    link_domains: &[(String, bool)],
+    servers: Vec<IpAddr>,
+    domains: Vec<String>,
+) -> (Vec<IpAddr>, Vec<(String, bool)>) {
+    let mut merged_servers = servers;
+    for server in link_servers {
+        if !merged_servers.contains(server) {
+            merged_servers.push(*server);
+        }
+    }
+
+    let mut merged_domains = link_domains.to_vec();
+    for domain in domains {
+        if !merged_domains
+            .iter()
+            .any(|(link_domain, _)| link_domain.eq_ignore_ascii_case(&domain))
+        {
+            merged_domains.push((domain, true));
+        }
+    }
+
+    (merged_servers, merged_domains)
+}
+
+/// Restores the links set by a process that was killed before resetting DNS.
+pub fn restore_journal(interface_name: &str, links: &[LinkDnsState]) -> Result<()> {
+    let dbus_interface = DbusInterface::new()?;
+
+    // The tunnel interface is usually removed along with the process that created it
+    if let Ok(tunnel_index) = iface_index(interface_name) {
+        if let Err(error) = dbus_interface.set_domains(tunnel_index, &[]) {
+            log::error!("Failed to set search domains: {}", error.display_chain());
+        }
+        dbus_interface.set_dns(tunnel_index, vec![])?;
+    }
+
+    for link in links {
+        let link_index = match iface_index(&link.interface) {
+            Ok(link_index) => link_index,
+            Err(error) => {
+                log::warn!(
+                    "Not restoring DNS of {}: {}",
+                    link.interface,
+                    error.display_chain()
+                );
+                continue;
+            }
+        };
+        let domains = link
+            .domains
+            .iter()
+            .map(|(domain, routing_only)| (domain.as_str(), *routing_only))
+            .collect::<Vec<_>>();
+
+        if let Err(error) = dbus_interface.set_domains(link_index, &domains) {
+            log::error!(
+                "Failed to restore search domains of {}: {}",
+                link.interface,
+                error.display_chain()
+            );
+        }
+        if let Err(error) = dbus_interface.set_dns(link_index, link.servers.clone()) {
+            log::error!(
+                "Failed to restore DNS servers of {}: {}",
+                link.interface,
+                error.display_chain()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ip(addr: &str) -> IpAddr {
+        addr.parse().unwrap()
+    }
+
+    #[test]
+    fn test_merge_link_config_keeps_link_config() {
+        let (servers, domains) = merge_link_config(
+            &[ip("192.168.1.1")],
+            &[("lan".to_owned(), false), ("~.".to_owned(), true)],
+            vec![ip("10.0.0.53")],
+            vec!["corp.example".to_owned()],
+        );
+
+        assert_eq!(servers, vec![ip("10.0.0.53"), ip("192.168.1.1")]);
+        assert_eq!(
+            domains,
+            vec![
+                ("lan".to_owned(), false),
+                ("~.".to_owned(), true),
+                ("corp.example".to_owned(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_link_config_skips_duplicates() {
+        let (servers, domains) = merge_link_config(
+            &[ip("10.0.0.53"), ip("192.168.1.1")],
+            &[("Corp.Example".to_owned(), false)],
+            vec![ip("10.0.0.53")],
+            vec!["corp.example".to_owned(), "lab.example".to_owned()],
+        );
+
+        assert_eq!(servers, vec![ip("10.0.0.53"), ip("192.168.1.1")]);
+        assert_eq!(
+            domains,
+            vec![
+                ("Corp.Example".to_owned(), false),
+                ("lab.example".to_owned(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_link_config_without_link_config() {
+        let (servers, domains) = merge_link_config(
+            &[],
+            &[],
+            vec![ip("10.0.0.53"), ip("fd00::53")],
+            vec!["corp.example".to_owned()],
+        );
+
+        assert_eq!(servers, vec![ip("10.0.0.53"), ip("fd00::53")]);
+        assert_eq!(domains, vec![("corp.example".to_owned(), true)]);
+    }
+}
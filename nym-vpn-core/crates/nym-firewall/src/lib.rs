@@ -0,0 +1,264 @@
+// Copyright 2016-2024 Mullvad VPN AB. All Rights Reserved.
+// Copyright 2024 Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Firewall used to prevent traffic from leaking outside of the tunnel.
+#![deny(missing_docs)]
+
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+};
+
+use ipnetwork::IpNetwork;
+
+#[cfg(target_os = "linux")]
+#[path = "linux.rs"]
+mod imp;
+
+#[cfg(target_os = "linux")]
+pub use self::imp::Error;
+
+/// Private IPv4 and IPv6 networks, including link-local ranges.
+pub fn allowed_lan_nets() -> Vec<IpNetwork> {
+    [
+        "10.0.0.0/8",
+        "172.16.0.0/12",
+        "192.168.0.0/16",
+        "169.254.0.0/16",
+        "fe80::/10",
+        "fc00::/7",
+    ]
+    .iter()
+    .map(|net| net.parse().expect("invalid lan network"))
+    .collect()
+}
+
+/// Multicast networks used for local discovery, such as mDNS and SSDP.
+pub fn allowed_lan_multicast_nets() -> Vec<IpNetwork> {
+    [
+        "224.0.0.0/24",
+        "239.255.255.250/32",
+        "239.255.255.251/32",
+        "255.255.255.255/32",
+        "ff01::/16",
+        "ff02::/16",
+        "ff05::/16",
+    ]
+    .iter()
+    .map(|net| net.parse().expect("invalid multicast network"))
+    .collect()
+}
+
+/// Tunnel interfaces created by the client.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TunnelInterface {
+    /// Names of the tunnel devices.
+    pub interfaces: Vec<String>,
+}
+
+/// Transport protocol of an endpoint.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum TransportProtocol {
+    /// Transmission Control Protocol
+    Tcp,
+    /// User Datagram Protocol
+    Udp,
+}
+
+impl fmt::Display for TransportProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp => f.write_str("tcp"),
+            Self::Udp => f.write_str("udp"),
+        }
+    }
+}
+
+/// Address, port and protocol of a service reached outside of the tunnel.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Endpoint {
+    /// Address and port of the service.
+    pub address: SocketAddr,
+    /// Transport protocol used to reach the service.
+    pub protocol: TransportProtocol,
+}
+
+impl Endpoint {
+    /// Creates a new endpoint.
+    pub fn new(address: impl Into<SocketAddr>, protocol: TransportProtocol) -> Self {
+        Self {
+            address: address.into(),
+            protocol,
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.address, self.protocol)
+    }
+}
+
+/// A enum that describes the firewall rules to be applied.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FirewallPolicy {
+    /// Allow traffic only to the gateway peers and, once created, through the tunnel.
+    Connecting {
+        /// Endpoints of the gateways reached outside of the tunnel.
+        peer_endpoints: Vec<Endpoint>,
+        /// Tunnel interfaces, once they have been created.
+        tunnel: Option<TunnelInterface>,
+        /// Flag setting if communication with LAN networks should be possible.
+        allow_lan: bool,
+        /// Endpoints of the directory and API services, and of the gateways whose latency is
+        /// measured, that processes running as root may reach outside of the tunnel while the
+        /// gateways are looked up and registered with.
+        api_endpoints: Vec<Endpoint>,
+        /// Servers that processes running as root, and the system resolver relaying their
+        /// requests, may query outside of the tunnel to resolve the gateway and API hosts.
+        dns_servers: Vec<IpAddr>,
+    },
+
+    /// Allow traffic only to the gateway peers and through the tunnel.
+    Connected {
+        /// Endpoints of the gateways reached outside of the tunnel.
+        peer_endpoints: Vec<Endpoint>,
+        /// Tunnel interfaces.
+        tunnel: TunnelInterface,
+        /// Flag setting if communication with LAN networks should be possible.
+        allow_lan: bool,
+        /// Servers that are allowed to respond to DNS requests sent through the tunnel.
+        dns_servers: Vec<IpAddr>,
+        /// Networks routed outside of the tunnel.
+        excluded_networks: Vec<IpNetwork>,
+    },
+
+    /// Block all network traffic in and out of the computer.
+    Blocked {
+        /// Flag setting if communication with LAN networks should be possible.
+        allow_lan: bool,
+    },
+}
+
+impl fmt::Display for FirewallPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Connecting {
+                peer_endpoints,
+                tunnel,
+                allow_lan,
+                api_endpoints,
+                dns_servers,
+            } => {
+                write!(
+                    f,
+                    "Connecting to {} over {}, {} LAN, API access to {} (DNS: {})",
+                    fmt_addrs(peer_endpoints),
+                    tunnel
+                        .as_ref()
+                        .map(|t| t.interfaces.join(", "))
+                        .unwrap_or_else(|| "no tunnel".to_owned()),
+                    if *allow_lan { "allowing" } else { "blocking" },
+                    fmt_addrs(api_endpoints),
+                    fmt_addrs(dns_servers),
+                )
+            }
+            Self::Connected {
+                peer_endpoints,
+                tunnel,
+                allow_lan,
+                dns_servers,
+                excluded_networks,
+            } => {
+                write!(
+                    f,
+                    "Connected to {} over {} (DNS: {}), {} LAN, excluding {}",
+                    fmt_addrs(peer_endpoints),
+                    tunnel.interfaces.join(", "),
+                    fmt_addrs(dns_servers),
+                    if *allow_lan { "allowing" } else { "blocking" },
+                    fmt_networks(excluded_networks),
+                )
+            }
+            Self::Blocked { allow_lan } => {
+                write!(
+                    f,
+                    "Blocked, {} LAN",
+                    if *allow_lan { "allowing" } else { "blocking" }
+                )
+            }
+        }
+    }
+}
+
+fn fmt_addrs<T: fmt::Display>(addrs: &[T]) -> String {
+    let addrs = addrs.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+    format!("{{{}}}", addrs.join(", "))
+}
+
+fn fmt_networks(networks: &[IpNetwork]) -> String {
+    let networks = networks.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    format!("{{{}}}", networks.join(", "))
+}
+
+/// Initial firewall state applied when the firewall is created.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum InitialFirewallState {
+    /// Leave the firewall untouched.
+    None,
+    /// Block all traffic until a policy is applied.
+    Blocked,
+}
+
+/// Arguments required when first initializing the firewall.
+#[derive(Debug, Clone, Copy)]
+pub struct FirewallArguments {
+    /// Initial firewall state.
+    pub initial_state: InitialFirewallState,
+    /// Flag setting if communication with LAN networks should be possible in the initial state.
+    pub allow_lan: bool,
+    /// Packets marked with this value bypass the firewall.
+    #[cfg(target_os = "linux")]
+    pub fwmark: u32,
+    /// Traffic of processes in the net_cls cgroup with this class ID is marked with `fwmark`
+    /// so that it bypasses the tunnel.
+    #[cfg(target_os = "linux")]
+    pub exclusion_classid: Option<u32>,
+    /// User ID of the system resolver service, which sends the DNS requests of processes
+    /// using the system resolver, when it runs as a dedicated user.
+    #[cfg(target_os = "linux")]
+    pub resolver_uid: Option<u32>,
+}
+
+/// Manages network security of the computer/device. Can apply and enforce firewall policies
+/// by manipulating the OS firewall and DNS settings.
+#[cfg(target_os = "linux")]
+pub struct Firewall {
+    inner: imp::Firewall,
+}
+
+#[cfg(target_os = "linux")]
+impl Firewall {
+    /// Creates a new firewall instance.
+    pub fn from_args(args: FirewallArguments) -> Result<Self, Error> {
+        Ok(Firewall {
+            inner: imp::Firewall::from_args(args)?,
+        })
+    }
+
+    /// Applies and starts enforcing the given `FirewallPolicy`. Makes sure it is being kept in
+    /// place until this method is called again with another policy, or until `reset_policy` is
+    /// called.
+    pub fn apply_policy(&mut self, policy: FirewallPolicy) -> Result<(), Error> {
+        log::info!("Applying firewall policy: {}", policy);
+        self.inner.apply_policy(policy)
+    }
+
+    /// Resets/removes any currently enforced `FirewallPolicy`. Returns the system to the same
+    /// state it had before any policy was applied through this `Firewall` instance.
+    pub fn reset_policy(&mut self) -> Result<(), Error> {
+        log::info!("Resetting firewall policy");
+        self.inner.reset_policy()
+    }
+}
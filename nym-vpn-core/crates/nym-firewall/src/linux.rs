@@ -0,0 +1,641 @@
+// Copyright 2016-2024 Mullvad VPN AB. All Rights Reserved.
+// Copyright 2024 Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{fmt::Write, io, net::IpAddr, path::PathBuf};
+
+use ipnetwork::IpNetwork;
+use which::which;
+
+use super::{Endpoint, FirewallArguments, FirewallPolicy, InitialFirewallState, TunnelInterface};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Name of the nftables table holding all rules managed by the firewall.
+const TABLE_NAME: &str = "nym-vpn";
+
+/// Priority of the filter chains. Must run before any accepting rule in other tables is final.
+const CHAIN_PRIORITY: i32 = 0;
+
+/// Priority of the chains marking excluded traffic. Must run before the filter chains.
+const MANGLE_CHAIN_PRIORITY: i32 = -150;
+
+/// Priority of the chain masquerading excluded traffic.
+const NAT_CHAIN_PRIORITY: i32 = 100;
+
+/// Errors that can happen when applying nftables rules
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    /// Unable to locate the nft binary
+    #[error("Failed to detect 'nft' program")]
+    NoNft,
+
+    /// Unable to run the nft binary
+    #[error("Failed to execute 'nft' program")]
+    RunNft(#[source] io::Error),
+
+    /// nft rejected the ruleset
+    #[error("Failed to apply nftables ruleset: {}", stderr)]
+    ApplyRuleset {
+        /// Output of the nft program
+        stderr: String,
+    },
+}
+
+pub struct Firewall {
+    nft: PathBuf,
+    fwmark: u32,
+    exclusion_classid: Option<u32>,
+    resolver_uid: Option<u32>,
+}
+
+impl Firewall {
+    pub fn from_args(args: FirewallArguments) -> Result<Self> {
+        let nft = which("nft").map_err(|_| Error::NoNft)?;
+        let mut firewall = Firewall {
+            nft,
+            fwmark: args.fwmark,
+            exclusion_classid: args.exclusion_classid,
+            resolver_uid: args.resolver_uid,
+        };
+
+        if let InitialFirewallState::Blocked = args.initial_state {
+            firewall.apply_policy(FirewallPolicy::Blocked {
+                allow_lan: args.allow_lan,
+            })?;
+        }
+
+        Ok(firewall)
+    }
+
+    pub fn apply_policy(&mut self, policy: FirewallPolicy) -> Result<()> {
+        let ruleset = RulesetBuilder::new(self.fwmark, self.exclusion_classid)
+            .resolver_uid(self.resolver_uid)
+            .build(&policy);
+        log::trace!("Applying nftables ruleset:\n{}", ruleset);
+
+        self.run_nft(ruleset)
+    }
+
+    pub fn reset_policy(&mut self) -> Result<()> {
+        // Declaring the table first makes the deletion succeed when the table does not exist.
+        self.run_nft(format!(
+            "table inet {TABLE_NAME} {{}}\ndelete table inet {TABLE_NAME}\n"
+        ))
+    }
+
+    fn run_nft(&self, ruleset: String) -> Result<()> {
+        let output = duct::cmd!(&self.nft, "-f", "-")
+            .stdin_bytes(ruleset)
+            .stderr_capture()
+            .unchecked()
+            .run()
+            .map_err(Error::RunNft)?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return Err(Error::ApplyRuleset { stderr });
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds an nftables script that atomically replaces the firewall table.
+struct RulesetBuilder {
+    fwmark: u32,
+    exclusion_classid: Option<u32>,
+    resolver_uid: Option<u32>,
+    input: Vec<String>,
+    output: Vec<String>,
+    mangle_output: Vec<String>,
+    mangle_prerouting: Vec<String>,
+    nat_postrouting: Vec<String>,
+}
+
+impl RulesetBuilder {
+    fn new(fwmark: u32, exclusion_classid: Option<u32>) -> Self {
+        Self {
+            fwmark,
+            exclusion_classid,
+            resolver_uid: None,
+            input: Vec::new(),
+            output: Vec::new(),
+            mangle_output: Vec::new(),
+            mangle_prerouting: Vec::new(),
+            nat_postrouting: Vec::new(),
+        }
+    }
+
+    fn resolver_uid(mut self, resolver_uid: Option<u32>) -> Self {
+        self.resolver_uid = resolver_uid;
+        self
+    }
+
+    fn build(mut self, policy: &FirewallPolicy) -> String {
+        self.add_loopback_rules();
+        self.add_dhcp_and_ndp_rules();
+
+        match policy {
+            FirewallPolicy::Connecting {
+                peer_endpoints,
+                tunnel,
+                allow_lan,
+                api_endpoints,
+                dns_servers,
+            } => {
+                self.add_fwmark_rules();
+                self.add_exclusion_rules();
+                self.add_peer_rules(peer_endpoints);
+                self.add_api_rules(api_endpoints);
+                // Added before the LAN rules, which drop DNS requests outside of the tunnel
+                self.add_lookup_dns_rules(dns_servers);
+                if let Some(tunnel) = tunnel {
+                    self.add_tunnel_rules(tunnel, None);
+                }
+                if *allow_lan {
+                    self.add_lan_rules();
+                }
+            }
+            FirewallPolicy::Connected {
+                peer_endpoints,
+                tunnel,
+                allow_lan,
+                dns_servers,
+                excluded_networks,
+            } => {
+                self.add_fwmark_rules();
+                self.add_exclusion_rules();
+                self.add_peer_rules(peer_endpoints);
+                self.add_tunnel_rules(tunnel, Some(dns_servers));
+                // Added after the tunnel rules so that DNS restrictions apply first
+                self.add_excluded_network_rules(excluded_networks);
+                if *allow_lan {
+                    self.add_lan_rules();
+                }
+            }
+            FirewallPolicy::Blocked { allow_lan } => {
+                if *allow_lan {
+                    self.add_lan_rules();
+                }
+            }
+        }
+
+        self.finish()
+    }
+
+    fn add_loopback_rules(&mut self) {
+        self.output.push("oifname \"lo\" accept".to_owned());
+        self.input.push("iifname \"lo\" accept".to_owned());
+        self.input
+            .push("ct state established,related accept".to_owned());
+    }
+
+    fn add_dhcp_and_ndp_rules(&mut self) {
+        // DHCPv4
+        self.output
+            .push("udp sport 68 udp dport 67 accept".to_owned());
+        self.input
+            .push("udp sport 67 udp dport 68 accept".to_owned());
+
+        // DHCPv6
+        self.output
+            .push("ip6 saddr fe80::/10 udp sport 546 udp dport 547 accept".to_owned());
+        self.input
+            .push("ip6 saddr fe80::/10 udp sport 547 udp dport 546 accept".to_owned());
+
+        // Router and neighbor discovery
+        self.output.push(
+            "ip6 hoplimit 255 icmpv6 type { nd-router-solicit, nd-neighbor-solicit, nd-neighbor-advert } accept"
+                .to_owned(),
+        );
+        self.input.push(
+            "ip6 hoplimit 255 icmpv6 type { nd-router-advert, nd-neighbor-solicit, nd-neighbor-advert, nd-redirect } accept"
+                .to_owned(),
+        );
+    }
+
+    fn add_fwmark_rules(&mut self) {
+        self.output
+            .push(format!("meta mark {:#x} accept", self.fwmark));
+    }
+
+    /// Marks traffic of excluded processes so that it is accepted by the filter chains and
+    /// routed outside of the tunnel. The route chain makes the kernel redo the routing decision
+    /// once the mark is set, and the source address is rewritten to match the new interface.
+    fn add_exclusion_rules(&mut self) {
+        let Some(classid) = self.exclusion_classid else {
+            return;
+        };
+        let fwmark = self.fwmark;
+
+        self.mangle_output.push(format!(
+            "meta cgroup {classid:#x} ct mark set {fwmark:#x} meta mark set {fwmark:#x}"
+        ));
+        self.mangle_prerouting
+            .push(format!("ct mark {fwmark:#x} meta mark set {fwmark:#x}"));
+        self.nat_postrouting
+            .push(format!("ct mark {fwmark:#x} oifname != \"lo\" masquerade"));
+    }
+
+    fn add_peer_rules(&mut self, peer_endpoints: &[Endpoint]) {
+        for peer in peer_endpoints {
+            let ip = peer.address.ip();
+            let port = peer.address.port();
+            let family = ip_family(&ip);
+            let protocol = peer.protocol;
+            self.output.push(format!(
+                "{family} daddr {ip} {protocol} dport {port} accept"
+            ));
+            self.input.push(format!(
+                "{family} saddr {ip} {protocol} sport {port} accept"
+            ));
+        }
+    }
+
+    fn add_excluded_network_rules(&mut self, excluded_networks: &[IpNetwork]) {
+        for net in excluded_networks {
+            let family = ip_network_family(net);
+            self.output.push(format!("{family} daddr {net} accept"));
+            self.input.push(format!("{family} saddr {net} accept"));
+        }
+    }
+
+    /// Only processes running as root, such as the daemon, may reach the API endpoints.
+    fn add_api_rules(&mut self, api_endpoints: &[Endpoint]) {
+        for endpoint in api_endpoints {
+            let ip = endpoint.address.ip();
+            let port = endpoint.address.port();
+            let family = ip_family(&ip);
+            let protocol = endpoint.protocol;
+            self.output.push(format!(
+                "meta skuid 0 {family} daddr {ip} {protocol} dport {port} accept"
+            ));
+        }
+    }
+
+    /// Lets processes running as root resolve hosts with the given servers before the tunnel is
+    /// up. Requests to the system resolver are sent by the resolver service, which is allowed
+    /// too when it runs as its own user.
+    fn add_lookup_dns_rules(&mut self, dns_servers: &[IpAddr]) {
+        let uids = std::iter::once(0)
+            .chain(self.resolver_uid)
+            .map(|uid| uid.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        for server in dns_servers {
+            let family = ip_family(server);
+            self.output.push(format!(
+                "meta skuid {{ {uids} }} {family} daddr {server} meta l4proto {{ tcp, udp }} th dport 53 accept"
+            ));
+        }
+    }
+
+    fn add_tunnel_rules(&mut self, tunnel: &TunnelInterface, dns_servers: Option<&[IpAddr]>) {
+        if tunnel.interfaces.is_empty() {
+            return;
+        }
+        let interfaces = tunnel
+            .interfaces
+            .iter()
+            .map(|name| format!("\"{name}\""))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        // Only allow DNS requests to the configured servers once connected.
+        if let Some(dns_servers) = dns_servers {
+            for server in dns_servers {
+                let family = ip_family(server);
+                self.output.push(format!(
+                    "oifname {{ {interfaces} }} {family} daddr {server} meta l4proto {{ tcp, udp }} th dport 53 accept"
+                ));
+            }
+            self.output.push(format!(
+                "oifname {{ {interfaces} }} meta l4proto {{ tcp, udp }} th dport 53 drop"
+            ));
+        }
+
+        self.output
+            .push(format!("oifname {{ {interfaces} }} accept"));
+        self.input
+            .push(format!("iifname {{ {interfaces} }} accept"));
+    }
+
+    fn add_lan_rules(&mut self) {
+        // DNS requests that reach this point do not go through the tunnel, so LAN resolvers must
+        // not be used to avoid leaking them.
+        self.output
+            .push("meta l4proto { tcp, udp } th dport 53 drop".to_owned());
+        for net in super::allowed_lan_nets()
+            .into_iter()
+            .chain(super::allowed_lan_multicast_nets())
+        {
+            let family = ip_network_family(&net);
+            self.output.push(format!("{family} daddr {net} accept"));
+        }
+        for net in super::allowed_lan_nets() {
+            let family = ip_network_family(&net);
+            self.input.push(format!("{family} saddr {net} accept"));
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut ruleset = String::new();
+
+        // Declaring the table first makes the deletion succeed when the table does not exist.
+        // The whole script is applied as a single transaction.
+        _ = writeln!(ruleset, "table inet {TABLE_NAME} {{}}");
+        _ = writeln!(ruleset, "delete table inet {TABLE_NAME}");
+        _ = writeln!(ruleset, "table inet {TABLE_NAME} {{");
+
+        let filter_chains = [
+            ("input", "filter", "input", CHAIN_PRIORITY, &self.input),
+            ("output", "filter", "output", CHAIN_PRIORITY, &self.output),
+        ];
+        for (chain, chain_type, hook, priority, rules) in filter_chains {
+            write_chain(
+                &mut ruleset,
+                chain,
+                chain_type,
+                hook,
+                priority,
+                "drop",
+                rules,
+            );
+        }
+
+        // Chains used to exclude traffic from the tunnel are only declared when needed.
+        let exclusion_chains = [
+            (
+                "mangle_output",
+                "route",
+                "output",
+                MANGLE_CHAIN_PRIORITY,
+                &self.mangle_output,
+            ),
+            (
+                "mangle_prerouting",
+                "filter",
+                "prerouting",
+                MANGLE_CHAIN_PRIORITY,
+                &self.mangle_prerouting,
+            ),
+            (
+                "nat_postrouting",
+                "nat",
+                "postrouting",
+                NAT_CHAIN_PRIORITY,
+                &self.nat_postrouting,
+            ),
+        ];
+        for (chain, chain_type, hook, priority, rules) in exclusion_chains {
+            if !rules.is_empty() {
+                write_chain(
+                    &mut ruleset,
+                    chain,
+                    chain_type,
+                    hook,
+                    priority,
+                    "accept",
+                    rules,
+                );
+            }
+        }
+
+        _ = writeln!(ruleset, "}}");
+
+        ruleset
+    }
+}
+
+fn write_chain(
+    ruleset: &mut String,
+    chain: &str,
+    chain_type: &str,
+    hook: &str,
+    priority: i32,
+    policy: &str,
+    rules: &[String],
+) {
+    _ = writeln!(ruleset, "    chain {chain} {{");
+    _ = writeln!(
+        ruleset,
+        "        type {chain_type} hook {hook} priority {priority}; policy {policy};"
+    );
+    for rule in rules {
+        _ = writeln!(ruleset, "        {rule}");
+    }
+    _ = writeln!(ruleset, "    }}");
+}
+
+fn ip_family(addr: &IpAddr) -> &'static str {
+    match addr {
+        IpAddr::V4(_) => "ip",
+        IpAddr::V6(_) => "ip6",
+    }
+}
+
+fn ip_network_family(net: &IpNetwork) -> &'static str {
+    match net {
+        IpNetwork::V4(_) => "ip",
+        IpNetwork::V6(_) => "ip6",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TransportProtocol;
+
+    const FWMARK: u32 = 0x14d;
+
+    fn build(policy: FirewallPolicy) -> String {
+        RulesetBuilder::new(FWMARK, None).build(&policy)
+    }
+
+    fn endpoint(address: &str, protocol: TransportProtocol) -> Endpoint {
+        Endpoint::new(address.parse::<std::net::SocketAddr>().unwrap(), protocol)
+    }
+
+    #[test]
+    fn blocked_policy_only_allows_local_traffic() {
+        let ruleset = build(FirewallPolicy::Blocked { allow_lan: false });
+
+        assert!(ruleset.starts_with("table inet nym-vpn {}\ndelete table inet nym-vpn\n"));
+        assert!(ruleset.contains("type filter hook output priority 0; policy drop;"));
+        assert!(ruleset.contains("type filter hook input priority 0; policy drop;"));
+        assert!(ruleset.contains("oifname \"lo\" accept"));
+        assert!(!ruleset.contains("meta mark"));
+        assert!(!ruleset.contains("192.168.0.0/16"));
+    }
+
+    #[test]
+    fn blocked_policy_allows_lan() {
+        let ruleset = build(FirewallPolicy::Blocked { allow_lan: true });
+
+        assert!(ruleset.contains("ip daddr 192.168.0.0/16 accept"));
+        assert!(ruleset.contains("ip saddr 10.0.0.0/8 accept"));
+        assert!(ruleset.contains("ip6 daddr fe80::/10 accept"));
+        assert!(ruleset.contains("ip daddr 224.0.0.0/24 accept"));
+    }
+
+    #[test]
+    fn lan_rules_drop_dns_outside_of_the_tunnel() {
+        let ruleset = build(FirewallPolicy::Connected {
+            peer_endpoints: vec![endpoint("1.2.3.4:51822", TransportProtocol::Udp)],
+            tunnel: TunnelInterface {
+                interfaces: vec!["nym0".to_owned()],
+            },
+            allow_lan: true,
+            dns_servers: vec!["10.64.0.1".parse().unwrap()],
+            excluded_networks: vec![],
+        });
+
+        let tunnel_accept = ruleset.find("oifname { \"nym0\" } accept").unwrap();
+        let dns_drop = ruleset
+            .find("\n        meta l4proto { tcp, udp } th dport 53 drop")
+            .unwrap();
+        let lan_accept = ruleset.find("ip daddr 192.168.0.0/16 accept").unwrap();
+
+        assert!(tunnel_accept < dns_drop);
+        assert!(dns_drop < lan_accept);
+    }
+
+    #[test]
+    fn connecting_policy_allows_peers_and_api_endpoints() {
+        let ruleset = build(FirewallPolicy::Connecting {
+            peer_endpoints: vec![
+                endpoint("1.2.3.4:9000", TransportProtocol::Tcp),
+                endpoint("[2001:db8::1]:51822", TransportProtocol::Udp),
+            ],
+            tunnel: None,
+            allow_lan: false,
+            api_endpoints: vec![endpoint("5.6.7.8:443", TransportProtocol::Tcp)],
+            dns_servers: vec![],
+        });
+
+        assert!(ruleset.contains("meta mark 0x14d accept"));
+        assert!(ruleset.contains("ip daddr 1.2.3.4 tcp dport 9000 accept"));
+        assert!(ruleset.contains("ip saddr 1.2.3.4 tcp sport 9000 accept"));
+        assert!(ruleset.contains("ip6 daddr 2001:db8::1 udp dport 51822 accept"));
+        assert!(ruleset.contains("meta skuid 0 ip daddr 5.6.7.8 tcp dport 443 accept"));
+        assert!(!ruleset.contains("ip daddr 1.2.3.4 accept"));
+        assert!(!ruleset.contains("dport 53"));
+        assert!(!ruleset.contains("oifname {"));
+    }
+
+    #[test]
+    fn connecting_policy_without_api_endpoints_blocks_root_traffic() {
+        let ruleset = build(FirewallPolicy::Connecting {
+            peer_endpoints: vec![endpoint("1.2.3.4:9000", TransportProtocol::Tcp)],
+            tunnel: None,
+            allow_lan: false,
+            api_endpoints: vec![],
+            dns_servers: vec![],
+        });
+
+        assert!(!ruleset.contains("meta skuid 0"));
+    }
+
+    #[test]
+    fn connecting_from_blocked_policy_resolves_with_cold_cache() {
+        let blocked = build(FirewallPolicy::Blocked { allow_lan: true });
+        assert!(!blocked.contains("dport 53 accept"));
+
+        // Nothing has been resolved yet, so only the DNS servers are reachable.
+        let policy = FirewallPolicy::Connecting {
+            peer_endpoints: vec![],
+            tunnel: None,
+            allow_lan: true,
+            api_endpoints: vec![],
+            dns_servers: vec![
+                "192.168.1.1".parse().unwrap(),
+                "2001:db8::53".parse().unwrap(),
+            ],
+        };
+        let ruleset = RulesetBuilder::new(FWMARK, None)
+            .resolver_uid(Some(991))
+            .build(&policy);
+
+        let dns_accept = ruleset
+            .find("meta skuid { 0, 991 } ip daddr 192.168.1.1 meta l4proto { tcp, udp } th dport 53 accept")
+            .unwrap();
+        let dns_drop = ruleset
+            .find("\n        meta l4proto { tcp, udp } th dport 53 drop")
+            .unwrap();
+
+        assert!(dns_accept < dns_drop);
+        assert!(ruleset.contains(
+            "meta skuid { 0, 991 } ip6 daddr 2001:db8::53 meta l4proto { tcp, udp } th dport 53 accept"
+        ));
+    }
+
+    #[test]
+    fn connected_policy_does_not_allow_lookup_dns() {
+        let ruleset = RulesetBuilder::new(FWMARK, None)
+            .resolver_uid(Some(991))
+            .build(&FirewallPolicy::Connected {
+                peer_endpoints: vec![endpoint("1.2.3.4:51822", TransportProtocol::Udp)],
+                tunnel: TunnelInterface {
+                    interfaces: vec!["nym0".to_owned()],
+                },
+                allow_lan: false,
+                dns_servers: vec!["10.64.0.1".parse().unwrap()],
+                excluded_networks: vec![],
+            });
+
+        assert!(!ruleset.contains("meta skuid"));
+    }
+
+    #[test]
+    fn connected_policy_restricts_dns_to_tunnel_servers() {
+        let ruleset = build(FirewallPolicy::Connected {
+            peer_endpoints: vec![endpoint("1.2.3.4:51822", TransportProtocol::Udp)],
+            tunnel: TunnelInterface {
+                interfaces: vec!["nym0".to_owned(), "nym1".to_owned()],
+            },
+            allow_lan: false,
+            dns_servers: vec!["1.1.1.1".parse().unwrap()],
+            excluded_networks: vec![],
+        });
+
+        let dns_accept = ruleset
+            .find("oifname { \"nym0\", \"nym1\" } ip daddr 1.1.1.1 meta l4proto { tcp, udp } th dport 53 accept")
+            .unwrap();
+        let dns_drop = ruleset
+            .find("oifname { \"nym0\", \"nym1\" } meta l4proto { tcp, udp } th dport 53 drop")
+            .unwrap();
+        let tunnel_accept = ruleset
+            .find("oifname { \"nym0\", \"nym1\" } accept")
+            .unwrap();
+
+        assert!(dns_accept < dns_drop);
+        assert!(dns_drop < tunnel_accept);
+        assert!(ruleset.contains("iifname { \"nym0\", \"nym1\" } accept"));
+        assert!(!ruleset.contains("meta skuid 0"));
+    }
+
+    #[test]
+    fn excluded_traffic_is_marked_while_connected() {
+        let policy = FirewallPolicy::Connected {
+            peer_endpoints: vec![endpoint("1.2.3.4:51822", TransportProtocol::Udp)],
+            tunnel: TunnelInterface {
+                interfaces: vec!["nym0".to_owned()],
+            },
+            allow_lan: false,
+            dns_servers: vec![],
+            excluded_networks: vec![],
+        };
+        let ruleset = RulesetBuilder::new(FWMARK, Some(0x4e796d)).build(&policy);
+
+        assert!(ruleset.contains("type route hook output priority -150; policy accept;"));
+        assert!(ruleset.contains("meta cgroup 0x4e796d ct mark set 0x14d meta mark set 0x14d"));
+        assert!(ruleset.contains("ct mark 0x14d meta mark set 0x14d"));
+        assert!(ruleset.contains("ct mark 0x14d oifname != \"lo\" masquerade"));
+
+        let blocked = RulesetBuilder::new(FWMARK, Some(0x4e796d))
+            .build(&FirewallPolicy::Blocked { allow_lan: false });
+        assert!(!blocked.contains("meta cgroup"));
+        assert!(!blocked.contains("chain nat_postrouting"));
+    }
+}
@@ -0,0 +1,22 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct City {
+    name: String,
+    iso_code: String,
+}
+
+impl City {
+    pub fn new(name: String, iso_code: String) -> Self {
+        Self { name, iso_code }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn iso_code(&self) -> &str {
+        &self.iso_code
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Result, Error};
+
+// Mean radius of the Earth, used to compute great-circle distances
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+// A position on the Earth, in decimal degrees.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "RawCoordinates")]
+pub struct Coordinates {
+    latitude: f64,
+    longitude: f64,
+}
+
+// Coordinates are validated when they are created, so they are never NaN.
+impl Eq for Coordinates {}
+
+#[derive(Deserialize)]
+struct RawCoordinates {
+    latitude: f64,
+    longitude: f64,
+}
+
+impl TryFrom<RawCoordinates> for Coordinates {
+    type Error = Error;
+
+    fn try_from(raw: RawCoordinates) -> Result<Self> {
+        Self::new(raw.latitude, raw.longitude)
+    }
+}
+
+impl Coordinates {
+    pub fn new(latitude: f64, longitude: f64) -> Result<Self> {
+        if (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude) {
+            Ok(Self {
+                latitude,
+                longitude,
+            })
+        } else {
+            Err(Error::InvalidCoordinates {
+                latitude,
+                longitude,
+            })
+        }
+    }
+
+    pub fn latitude(&self) -> f64 {
+        self.latitude
+    }
+
+    pub fn longitude(&self) -> f64 {
+        self.longitude
+    }
+
+    // Returns the great-circle distance to the other position, using the haversine formula.
+    pub fn distance_km(&self, other: &Coordinates) -> f64 {
+        let (lat1, lat2) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let delta_lat = lat2 - lat1;
+        let delta_lon = (other.longitude - self.longitude).to_radians();
+
+        let a = (delta_lat / 2.0).sin().powi(2)
+            + lat1.cos() * lat2.cos() * (delta_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+    }
+}
+
+impl Display for Coordinates {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.4},{:.4}", self.latitude, self.longitude)
+    }
+}
+
+// A circular area around a position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Area {
+    pub center: Coordinates,
+    pub radius_km: u32,
+}
+
+impl Area {
+    pub fn contains(&self, position: &Coordinates) -> bool {
+        self.center.distance_km(position) <= f64::from(self.radius_km)
+    }
+}
+
+impl Display for Area {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} km around {}", self.radius_km, self.center)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_out_of_range_coordinates() {
+        assert!(Coordinates::new(90.1, 0.0).is_err());
+        assert!(Coordinates::new(0.0, -180.1).is_err());
+        assert!(Coordinates::new(f64::NAN, 0.0).is_err());
+        assert!(Coordinates::new(-90.0, 180.0).is_ok());
+    }
+
+    #[test]
+    fn distance_between_cities() {
+        let berlin = Coordinates::new(52.52, 13.405).unwrap();
+        let paris = Coordinates::new(48.8566, 2.3522).unwrap();
+
+        let distance = berlin.distance_km(&paris);
+        assert!((distance - 878.0).abs() < 5.0, "distance: {distance}");
+        assert_eq!(berlin.distance_km(&berlin), 0.0);
+    }
+}
@@ -7,8 +7,11 @@ use nym_sdk::mixnet::NodeIdentity;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
 
-use super::gateway::{Gateway, GatewayList};
-use crate::{error::Result, Error};
+use super::{
+    gateway::{Gateway, GatewayList},
+    gateway_preferences::GatewayPreferences,
+};
+use crate::{error::Result, Area, Coordinates, Error, RankingStrategy, Region};
 
 // The entry point is always a gateway identity, or some other entry that can be resolved to a
 // gateway identity.
@@ -23,6 +26,14 @@ pub enum EntryPoint {
     RandomLowLatency,
     // Select an entry gateway at random.
     Random,
+    // Select a random entry gateway in a region or continent, such as "EU" or "North America".
+    Region { region: String },
+    // Select a random entry gateway in a city.
+    City { city: String },
+    // Select the entry gateway nearest to a position, such as the position of the user.
+    Nearest { position: Coordinates },
+    // Select a random entry gateway within a radius around a position.
+    Area { area: Area },
 }
 
 impl Display for EntryPoint {
@@ -32,6 +43,10 @@ impl Display for EntryPoint {
             EntryPoint::Location { location } => write!(f, "Location: {}", location),
             EntryPoint::RandomLowLatency => write!(f, "Random low latency"),
             EntryPoint::Random => write!(f, "Random"),
+            EntryPoint::Region { region } => write!(f, "Region: {}", region),
+            EntryPoint::City { city } => write!(f, "City: {}", city),
+            EntryPoint::Nearest { position } => write!(f, "Nearest to: {}", position),
+            EntryPoint::Area { area } => write!(f, "Area: {}", area),
         }
     }
 }
@@ -48,10 +63,22 @@ impl EntryPoint {
     }
 
     pub fn is_location(&self) -> bool {
-        matches!(self, EntryPoint::Location { .. })
+        matches!(
+            self,
+            EntryPoint::Location { .. }
+                | EntryPoint::Region { .. }
+                | EntryPoint::City { .. }
+                | EntryPoint::Nearest { .. }
+                | EntryPoint::Area { .. }
+        )
     }
 
-    pub async fn lookup_gateway(&self, gateways: &GatewayList) -> Result<Gateway> {
+    pub async fn lookup_gateway(
+        &self,
+        gateways: &GatewayList,
+        preferences: &GatewayPreferences,
+        ranking: &dyn RankingStrategy,
+    ) -> Result<Gateway> {
         match &self {
             EntryPoint::Gateway { identity } => {
                 debug!("Selecting gateway by identity: {}", identity);
@@ -64,23 +91,83 @@ impl EntryPoint {
             }
             EntryPoint::Location { location } => {
                 debug!("Selecting gateway by location: {}", location);
-                gateways
-                    .random_gateway_located_at(location.to_string())
-                    .ok_or_else(|| Error::NoMatchingEntryGatewayForLocation {
-                        requested_location: location.clone(),
-                        available_countries: gateways.all_iso_codes(),
-                    })
+                GatewayList::new(
+                    gateways
+                        .gateways_located_at(location.to_string())
+                        .cloned()
+                        .collect(),
+                )
+                .with_preferences(preferences)
+                .ranked_gateway(ranking)
+                .ok_or_else(|| Error::NoMatchingEntryGatewayForLocation {
+                    requested_location: location.clone(),
+                    available_countries: gateways.all_iso_codes(),
+                })
             }
             EntryPoint::RandomLowLatency => {
                 debug!("Selecting a random low latency gateway");
-                gateways.random_low_latency_gateway().await
+                gateways
+                    .with_preferences(preferences)
+                    .random_low_latency_gateway()
+                    .await
             }
             EntryPoint::Random => {
                 debug!("Selecting a random gateway");
                 gateways
-                    .random_gateway()
+                    .with_preferences(preferences)
+                    .ranked_gateway(ranking)
                     .ok_or_else(|| Error::FailedToSelectGatewayRandomly)
             }
+            EntryPoint::Region { region } => {
+                debug!("Selecting gateway by region: {}", region);
+                let requested_region =
+                    Region::lookup(region).ok_or_else(|| Error::UnknownRegion {
+                        requested_region: region.clone(),
+                        available_regions: Region::all_ids(),
+                    })?;
+                GatewayList::new(
+                    gateways
+                        .gateways_in_region(requested_region)
+                        .cloned()
+                        .collect(),
+                )
+                .with_preferences(preferences)
+                .ranked_gateway(ranking)
+                .ok_or_else(|| Error::NoMatchingEntryGatewayForLocation {
+                    requested_location: region.clone(),
+                    available_countries: gateways.all_iso_codes(),
+                })
+            }
+            EntryPoint::City { city } => {
+                debug!("Selecting gateway by city: {}", city);
+                GatewayList::new(gateways.gateways_in_city(city).cloned().collect())
+                    .with_preferences(preferences)
+                    .ranked_gateway(ranking)
+                    .ok_or_else(|| Error::NoMatchingEntryGatewayForLocation {
+                        requested_location: city.clone(),
+                        available_countries: gateways.all_iso_codes(),
+                    })
+            }
+            EntryPoint::Nearest { position } => {
+                debug!("Selecting the gateway nearest to: {}", position);
+                gateways
+                    .with_preferences(preferences)
+                    .nearest_gateway(position)
+                    .ok_or_else(|| Error::NoMatchingEntryGatewayForLocation {
+                        requested_location: position.to_string(),
+                        available_countries: gateways.all_iso_codes(),
+                    })
+            }
+            EntryPoint::Area { area } => {
+                debug!("Selecting gateway by area: {}", area);
+                GatewayList::new(gateways.gateways_in_area(area).cloned().collect())
+                    .with_preferences(preferences)
+                    .ranked_gateway(ranking)
+                    .ok_or_else(|| Error::NoMatchingEntryGatewayForLocation {
+                        requested_location: area.to_string(),
+                        available_countries: gateways.all_iso_codes(),
+                    })
+            }
         }
     }
 }
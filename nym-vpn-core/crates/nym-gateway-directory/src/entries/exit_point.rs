@@ -7,8 +7,13 @@ use nym_sdk::mixnet::{NodeIdentity, Recipient};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info};
 
-use super::gateway::{Gateway, GatewayList};
-use crate::{error::Result, Error, IpPacketRouterAddress};
+use super::{
+    gateway::{Gateway, GatewayList},
+    gateway_preferences::GatewayPreferences,
+};
+use crate::{
+    error::Result, Area, Coordinates, Error, IpPacketRouterAddress, RankingStrategy, Region,
+};
 
 // The exit point is a nym-address, but if the exit ip-packet-router is running embedded on a
 // gateway, we can refer to it by the gateway identity.
@@ -26,6 +31,20 @@ pub enum ExitPoint {
     Location { location: String },
     // Select an exit gateway at random.
     Random,
+    // Select a random exit gateway but increase probability of selecting a low latency gateway
+    // as determined by ping times.
+    RandomLowLatency,
+    // Select a low latency exit gateway in a specific location. The entry gateway is selected the
+    // same way in that location, overriding the entry point.
+    LowLatencyPair { location: String },
+    // Select a random exit gateway in a region or continent, such as "EU" or "North America".
+    Region { region: String },
+    // Select a random exit gateway in a city.
+    City { city: String },
+    // Select the exit gateway nearest to a position, such as the position of the user.
+    Nearest { position: Coordinates },
+    // Select a random exit gateway within a radius around a position.
+    Area { area: Area },
 }
 
 impl Display for ExitPoint {
@@ -35,16 +54,37 @@ impl Display for ExitPoint {
             ExitPoint::Gateway { identity } => write!(f, "Gateway: {}", identity),
             ExitPoint::Location { location } => write!(f, "Location: {}", location),
             ExitPoint::Random => write!(f, "Random"),
+            ExitPoint::RandomLowLatency => write!(f, "Random low latency"),
+            ExitPoint::LowLatencyPair { location } => {
+                write!(f, "Low latency pair in location: {}", location)
+            }
+            ExitPoint::Region { region } => write!(f, "Region: {}", region),
+            ExitPoint::City { city } => write!(f, "City: {}", city),
+            ExitPoint::Nearest { position } => write!(f, "Nearest to: {}", position),
+            ExitPoint::Area { area } => write!(f, "Area: {}", area),
         }
     }
 }
 
 impl ExitPoint {
     pub fn is_location(&self) -> bool {
-        matches!(self, ExitPoint::Location { .. })
+        matches!(
+            self,
+            ExitPoint::Location { .. }
+                | ExitPoint::LowLatencyPair { .. }
+                | ExitPoint::Region { .. }
+                | ExitPoint::City { .. }
+                | ExitPoint::Nearest { .. }
+                | ExitPoint::Area { .. }
+        )
     }
 
-    pub fn lookup_gateway(&self, gateways: &GatewayList) -> Result<Gateway> {
+    pub async fn lookup_gateway(
+        &self,
+        gateways: &GatewayList,
+        preferences: &GatewayPreferences,
+        ranking: &dyn RankingStrategy,
+    ) -> Result<Gateway> {
         match &self {
             ExitPoint::Address { address } => {
                 debug!("Selecting gateway by address: {}", address);
@@ -74,18 +114,99 @@ impl ExitPoint {
             }
             ExitPoint::Location { location } => {
                 debug!("Selecting gateway by location: {}", location);
+                GatewayList::new(
+                    gateways
+                        .gateways_located_at(location.to_string())
+                        .cloned()
+                        .collect(),
+                )
+                .with_preferences(preferences)
+                .ranked_gateway(ranking)
+                .ok_or_else(|| Error::NoMatchingExitGatewayForLocation {
+                    requested_location: location.clone(),
+                    available_countries: gateways.all_iso_codes(),
+                })
+            }
+            ExitPoint::Random => {
+                info!("Selecting a random exit gateway");
                 gateways
-                    .random_gateway_located_at(location.to_string())
-                    .ok_or_else(|| Error::NoMatchingExitGatewayForLocation {
+                    .with_preferences(preferences)
+                    .ranked_gateway(ranking)
+                    .ok_or_else(|| Error::FailedToSelectGatewayRandomly)
+            }
+            ExitPoint::RandomLowLatency => {
+                info!("Selecting a random low latency exit gateway");
+                gateways
+                    .with_preferences(preferences)
+                    .random_low_latency_gateway()
+                    .await
+            }
+            ExitPoint::LowLatencyPair { location } => {
+                debug!("Selecting a low latency gateway in location: {}", location);
+                let located_gateways = GatewayList::new(
+                    gateways
+                        .gateways_located_at(location.to_string())
+                        .cloned()
+                        .collect(),
+                )
+                .with_preferences(preferences);
+                if located_gateways.is_empty() {
+                    return Err(Error::NoMatchingExitGatewayForLocation {
                         requested_location: location.clone(),
                         available_countries: gateways.all_iso_codes(),
+                    });
+                }
+                located_gateways.random_low_latency_gateway().await
+            }
+            ExitPoint::Region { region } => {
+                debug!("Selecting gateway by region: {}", region);
+                let requested_region =
+                    Region::lookup(region).ok_or_else(|| Error::UnknownRegion {
+                        requested_region: region.clone(),
+                        available_regions: Region::all_ids(),
+                    })?;
+                GatewayList::new(
+                    gateways
+                        .gateways_in_region(requested_region)
+                        .cloned()
+                        .collect(),
+                )
+                .with_preferences(preferences)
+                .ranked_gateway(ranking)
+                .ok_or_else(|| Error::NoMatchingExitGatewayForLocation {
+                    requested_location: region.clone(),
+                    available_countries: gateways.all_iso_codes(),
+                })
+            }
+            ExitPoint::City { city } => {
+                debug!("Selecting gateway by city: {}", city);
+                GatewayList::new(gateways.gateways_in_city(city).cloned().collect())
+                    .with_preferences(preferences)
+                    .ranked_gateway(ranking)
+                    .ok_or_else(|| Error::NoMatchingExitGatewayForLocation {
+                        requested_location: city.clone(),
+                        available_countries: gateways.all_iso_codes(),
                     })
             }
-            ExitPoint::Random => {
-                info!("Selecting a random exit gateway");
+            ExitPoint::Nearest { position } => {
+                debug!("Selecting the gateway nearest to: {}", position);
                 gateways
-                    .random_gateway()
-                    .ok_or_else(|| Error::FailedToSelectGatewayRandomly)
+                    .with_preferences(preferences)
+                    .nearest_gateway(position)
+                    .ok_or_else(|| Error::NoMatchingExitGatewayForLocation {
+                        requested_location: position.to_string(),
+                        available_countries: gateways.all_iso_codes(),
+                    })
+            }
+            ExitPoint::Area { area } => {
+                debug!("Selecting gateway by area: {}", area);
+                GatewayList::new(gateways.gateways_in_area(area).cloned().collect())
+                    .with_preferences(preferences)
+                    .ranked_gateway(ranking)
+                    .ok_or_else(|| Error::NoMatchingExitGatewayForLocation {
+                        requested_location: area.to_string(),
+                        available_countries: gateways.all_iso_codes(),
+                    })
             }
         }
     }
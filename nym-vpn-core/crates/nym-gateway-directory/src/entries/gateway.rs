@@ -1,15 +1,27 @@
 // Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::{fmt, net::IpAddr, str::FromStr};
-
+use std::{
+    fmt,
+    net::IpAddr,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use futures::StreamExt;
 use itertools::Itertools;
 use nym_sdk::mixnet::NodeIdentity;
 use nym_vpn_api_client::types::Percent;
-use rand::seq::IteratorRandom;
-use tracing::error;
+use rand::seq::{IteratorRandom, SliceRandom};
+use tracing::{debug, error};
+
+use crate::{
+    error::Result, Area, AuthAddress, City, Coordinates, Country, Error, GatewayPreferences,
+    IpPacketRouterAddress, RankingStrategy, Region,
+};
 
-use crate::{error::Result, AuthAddress, Country, Error, IpPacketRouterAddress};
+// Upper bound on the gateways whose latency is measured at the same time.
+const LATENCY_MAX_CONCURRENT: usize = 16;
 
 #[derive(Clone)]
 pub struct Gateway {
@@ -56,6 +68,14 @@ impl Gateway {
         self.two_letter_iso_country_code() == Some(code)
     }
 
+    pub fn city(&self) -> Option<&str> {
+        self.location.as_ref().and_then(|l| l.city.as_deref())
+    }
+
+    pub fn coordinates(&self) -> Option<Coordinates> {
+        self.location.as_ref().and_then(Location::coordinates)
+    }
+
     pub fn has_ipr_address(&self) -> bool {
         self.ipr_address.is_some()
     }
@@ -77,6 +97,20 @@ impl Gateway {
         }
     }
 
+    // Measures the latency of the gateway with the same websocket pings as used to select a random
+    // low latency gateway. Connecting to the gateway is included in the measurement.
+    pub async fn measure_latency(&self) -> Result<Duration> {
+        let start = Instant::now();
+        nym_client_core::init::helpers::choose_gateway_by_latency(
+            &mut rand::rngs::OsRng,
+            std::slice::from_ref(self),
+            false,
+        )
+        .await
+        .map_err(|err| Error::FailedToSelectGatewayBasedOnLowLatency { source: err })?;
+        Ok(start.elapsed())
+    }
+
     pub fn clients_address_no_tls(&self) -> Option<String> {
         match (&self.host, &self.clients_ws_port) {
             (Some(host), Some(port)) => Some(format!("ws://{}:{}", host, port)),
@@ -97,6 +131,17 @@ pub struct Location {
     pub two_letter_iso_country_code: String,
     pub latitude: f64,
     pub longitude: f64,
+    pub city: Option<String>,
+}
+
+impl Location {
+    // Gateways described only by the nym-api have no coordinates, and they are left at 0,0.
+    pub fn coordinates(&self) -> Option<Coordinates> {
+        if self.latitude == 0.0 && self.longitude == 0.0 {
+            return None;
+        }
+        Coordinates::new(self.latitude, self.longitude).ok()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -142,6 +187,7 @@ impl From<nym_vpn_api_client::response::Location> for Location {
             two_letter_iso_country_code: location.two_letter_iso_country_code,
             latitude: location.latitude,
             longitude: location.longitude,
+            city: location.city,
         }
     }
 }
@@ -341,6 +387,26 @@ impl GatewayList {
             .collect()
     }
 
+    pub fn all_cities(&self) -> Vec<City> {
+        self.all_locations()
+            .filter_map(|location| {
+                location.city.as_ref().map(|city| {
+                    City::new(city.clone(), location.two_letter_iso_country_code.clone())
+                })
+            })
+            .unique()
+            .collect()
+    }
+
+    // Returns the regions that contain at least one of the gateways
+    pub fn all_regions(&self) -> Vec<&'static Region> {
+        let iso_codes = self.all_iso_codes();
+        Region::all()
+            .iter()
+            .filter(|region| iso_codes.iter().any(|code| region.contains_country(code)))
+            .collect()
+    }
+
     pub fn gateway_with_identity(&self, identity: &NodeIdentity) -> Option<&Gateway> {
         self.gateways
             .iter()
@@ -355,6 +421,58 @@ impl GatewayList {
         })
     }
 
+    pub fn gateways_in_region<'a>(
+        &'a self,
+        region: &'a Region,
+    ) -> impl Iterator<Item = &'a Gateway> {
+        self.gateways.iter().filter(move |gateway| {
+            gateway
+                .two_letter_iso_country_code()
+                .is_some_and(|code| region.contains_country(code))
+        })
+    }
+
+    pub fn gateways_in_city(&self, city: &str) -> impl Iterator<Item = &Gateway> {
+        let city = city.trim().to_lowercase();
+        self.gateways.iter().filter(move |gateway| {
+            gateway
+                .city()
+                .is_some_and(|gw_city| gw_city.to_lowercase() == city)
+        })
+    }
+
+    pub fn gateways_in_area<'a>(&'a self, area: &'a Area) -> impl Iterator<Item = &'a Gateway> {
+        self.gateways.iter().filter(move |gateway| {
+            gateway
+                .coordinates()
+                .is_some_and(|coordinates| area.contains(&coordinates))
+        })
+    }
+
+    // Returns the gateways with known coordinates, ordered from the nearest to the farthest from
+    // the position, along with their distance in kilometers.
+    pub fn gateways_by_distance(&self, position: &Coordinates) -> Vec<(Gateway, f64)> {
+        let mut gateways: Vec<_> = self
+            .gateways
+            .iter()
+            .filter_map(|gateway| {
+                let distance = gateway.coordinates()?.distance_km(position);
+                Some((gateway.clone(), distance))
+            })
+            .collect();
+        gateways.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        gateways
+    }
+
+    pub(crate) fn nearest_gateway(&self, position: &Coordinates) -> Option<Gateway> {
+        let (gateway, distance) = self.gateways_by_distance(position).into_iter().next()?;
+        debug!(
+            "Selected gateway {} at {:.0} km from {}",
+            gateway.identity, distance, position
+        );
+        Some(gateway)
+    }
+
     pub fn random_gateway(&self) -> Option<Gateway> {
         self.gateways
             .iter()
@@ -362,12 +480,69 @@ impl GatewayList {
             .cloned()
     }
 
+    // Picks a gateway randomly, with a probability proportional to the weight given by the
+    // ranking strategy. Returns `None` if all of them are excluded.
+    pub fn ranked_gateway(&self, ranking: &dyn RankingStrategy) -> Option<Gateway> {
+        let weighted: Vec<_> = self
+            .gateways
+            .iter()
+            .map(|gateway| (gateway, ranking.weight(gateway)))
+            .filter(|(_, weight)| weight.is_finite() && *weight > 0.0)
+            .collect();
+
+        match weighted.choose_weighted(&mut rand::thread_rng(), |(_, weight)| *weight) {
+            Ok((gateway, weight)) => {
+                debug!(
+                    "Selected gateway {} with weight {:.3} out of {} candidates",
+                    gateway.identity,
+                    weight,
+                    weighted.len()
+                );
+                Some((*gateway).clone())
+            }
+            Err(_) => {
+                debug!("No gateway ranked above zero with {:?}", ranking);
+                None
+            }
+        }
+    }
+
     pub fn random_gateway_located_at(&self, code: String) -> Option<Gateway> {
         self.gateways_located_at(code)
             .choose(&mut rand::thread_rng())
             .cloned()
     }
 
+    // Returns the gateways that are not excluded. If any of them are preferred, only the preferred
+    // ones are returned.
+    pub fn with_preferences(&self, preferences: &GatewayPreferences) -> GatewayList {
+        let candidates = self
+            .gateways
+            .iter()
+            .filter(|gateway| !preferences.is_excluded(gateway));
+        let preferred: Vec<_> = candidates
+            .clone()
+            .filter(|gateway| preferences.is_preferred(gateway))
+            .cloned()
+            .collect();
+
+        if preferred.is_empty() {
+            Self::new(candidates.cloned().collect())
+        } else {
+            Self::new(preferred)
+        }
+    }
+
+    pub fn without_gateways(&self, identities: &[NodeIdentity]) -> GatewayList {
+        Self::new(
+            self.gateways
+                .iter()
+                .filter(|gateway| !identities.contains(gateway.identity()))
+                .cloned()
+                .collect(),
+        )
+    }
+
     pub fn remove_gateway(&mut self, entry_gateway: &Gateway) {
         self.gateways
             .retain(|gateway| gateway.identity() != entry_gateway.identity());
@@ -407,6 +582,37 @@ impl GatewayList {
         self.gateways
     }
 
+    // Measures the latency of all gateways concurrently, each of them once. Returns the gateways
+    // that could be measured before the timeout, along with their latency.
+    pub async fn measure_latencies(&self, timeout: Duration) -> Vec<(Gateway, Duration)> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut measurements = futures::stream::iter(&self.gateways)
+            .map(|gateway| async move {
+                let latency = gateway.measure_latency().await;
+                debug!(
+                    "Measured latency of gateway {}: {:?}",
+                    gateway.identity, latency
+                );
+                latency.ok().map(|latency| (gateway.clone(), latency))
+            })
+            .buffer_unordered(LATENCY_MAX_CONCURRENT);
+
+        let mut latencies = Vec::new();
+        while let Ok(Some(measurement)) =
+            tokio::time::timeout_at(deadline, measurements.next()).await
+        {
+            latencies.extend(measurement);
+        }
+        if latencies.len() < self.gateways.len() {
+            debug!(
+                "Measured the latency of {} out of {} gateways",
+                latencies.len(),
+                self.gateways.len()
+            );
+        }
+        latencies
+    }
+
     pub(crate) async fn random_low_latency_gateway(&self) -> Result<Gateway> {
         let mut rng = rand::rngs::OsRng;
         nym_client_core::init::helpers::choose_gateway_by_latency(&mut rng, &self.gateways, false)
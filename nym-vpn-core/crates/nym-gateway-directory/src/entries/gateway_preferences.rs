@@ -0,0 +1,50 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use nym_sdk::mixnet::NodeIdentity;
+use serde::{Deserialize, Serialize};
+
+use super::gateway::Gateway;
+
+// Gateways to avoid or to favor when a gateway is selected randomly, by location or by latency.
+// Gateways requested explicitly by identity or address are always used as is.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GatewayPreferences {
+    // Gateways that are never selected
+    pub excluded_gateways: Vec<NodeIdentity>,
+    // Countries, as two letter ISO codes, whose gateways are never selected
+    pub excluded_countries: Vec<String>,
+    // Gateways selected over other candidates whenever one of them is available
+    pub preferred_gateways: Vec<NodeIdentity>,
+    // Countries, as two letter ISO codes, whose gateways are selected over other candidates
+    // whenever one of them is available
+    pub preferred_countries: Vec<String>,
+}
+
+impl GatewayPreferences {
+    pub fn is_empty(&self) -> bool {
+        self.excluded_gateways.is_empty()
+            && self.excluded_countries.is_empty()
+            && self.preferred_gateways.is_empty()
+            && self.preferred_countries.is_empty()
+    }
+
+    pub fn is_excluded(&self, gateway: &Gateway) -> bool {
+        self.excluded_gateways.contains(gateway.identity())
+            || Self::is_located_in(gateway, &self.excluded_countries)
+    }
+
+    pub fn is_preferred(&self, gateway: &Gateway) -> bool {
+        self.preferred_gateways.contains(gateway.identity())
+            || Self::is_located_in(gateway, &self.preferred_countries)
+    }
+
+    fn is_located_in(gateway: &Gateway, countries: &[String]) -> bool {
+        gateway.two_letter_iso_country_code().is_some_and(|code| {
+            countries
+                .iter()
+                .any(|country| country.eq_ignore_ascii_case(code))
+        })
+    }
+}
@@ -1,6 +1,11 @@
 pub(crate) mod auth_addresses;
+pub(crate) mod city;
+pub(crate) mod coordinates;
 pub(crate) mod country;
 pub(crate) mod entry_point;
 pub(crate) mod exit_point;
 pub(crate) mod gateway;
+pub(crate) mod gateway_preferences;
 pub(crate) mod ipr_addresses;
+pub(crate) mod ranking;
+pub(crate) mod region;
@@ -0,0 +1,297 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{fmt, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::gateway::{Gateway, Probe};
+
+// Weight of a gateway whose performance is unknown, such as gateways described only by the
+// nym-api.
+const UNKNOWN_PERFORMANCE_WEIGHT: f64 = 0.5;
+
+// Gateways with a performance of zero keep a small chance of being selected, so that selection
+// still works when every candidate reports no performance.
+const MIN_PERFORMANCE_WEIGHT: f64 = 0.01;
+
+// Age after which the results of a probe count half as much as fresh results.
+const PROBE_HALF_LIFE: Duration = Duration::from_secs(60 * 60);
+
+// Factor applied to gateways that were never probed, or whose probe is too old to be relevant.
+const MIN_RECENCY_FACTOR: f64 = 0.1;
+
+/// Strategy used to choose between the gateways matching an entry or exit point.
+///
+/// Each candidate is given a weight, and a gateway is then picked randomly with a probability
+/// proportional to its weight. Gateways with a weight of zero are never picked, so no gateway is
+/// picked when all the candidates have a weight of zero.
+pub trait RankingStrategy: fmt::Debug + Send + Sync {
+    fn weight(&self, gateway: &Gateway) -> f64;
+}
+
+/// Every gateway is equally likely to be picked.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UniformRanking;
+
+impl RankingStrategy for UniformRanking {
+    fn weight(&self, _gateway: &Gateway) -> f64 {
+        1.0
+    }
+}
+
+/// Gateways are picked with a probability proportional to their mixnet performance.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PerformanceRanking;
+
+impl RankingStrategy for PerformanceRanking {
+    fn weight(&self, gateway: &Gateway) -> f64 {
+        gateway
+            .mixnet_performance
+            .map_or(UNKNOWN_PERFORMANCE_WEIGHT, |performance| {
+                f64::from(performance.round_to_integer()) / 100.0
+            })
+            .max(MIN_PERFORMANCE_WEIGHT)
+    }
+}
+
+/// IP families routed through the tunnel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpFamilies {
+    pub ipv4: bool,
+    pub ipv6: bool,
+}
+
+impl Default for IpFamilies {
+    fn default() -> Self {
+        Self {
+            ipv4: true,
+            ipv6: true,
+        }
+    }
+}
+
+/// Gateways whose last probe failed to route traffic, over one of the IP families routed through
+/// the tunnel, are never picked. Gateways that were never probed are kept.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ProbeFilterRanking {
+    pub families: IpFamilies,
+}
+
+impl RankingStrategy for ProbeFilterRanking {
+    fn weight(&self, gateway: &Gateway) -> f64 {
+        match &gateway.last_probe {
+            Some(probe) if !probe_can_route(probe, self.families) => 0.0,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Gateways probed recently are favored over gateways whose last probe is older, since the
+/// results of an old probe say little about the current state of the gateway.
+#[derive(Debug, Clone, Copy)]
+pub struct ProbeRecencyRanking {
+    pub half_life: Duration,
+}
+
+impl Default for ProbeRecencyRanking {
+    fn default() -> Self {
+        Self {
+            half_life: PROBE_HALF_LIFE,
+        }
+    }
+}
+
+impl RankingStrategy for ProbeRecencyRanking {
+    fn weight(&self, gateway: &Gateway) -> f64 {
+        gateway
+            .last_probe
+            .as_ref()
+            .map_or(MIN_RECENCY_FACTOR, |probe| {
+                probe_recency_factor(probe, Utc::now(), self.half_life)
+            })
+    }
+}
+
+/// Combines strategies by multiplying the weights they give to each gateway.
+#[derive(Debug, Default)]
+pub struct CombinedRanking {
+    strategies: Vec<Box<dyn RankingStrategy>>,
+}
+
+impl CombinedRanking {
+    pub fn new(strategies: Vec<Box<dyn RankingStrategy>>) -> Self {
+        Self { strategies }
+    }
+}
+
+impl RankingStrategy for CombinedRanking {
+    fn weight(&self, gateway: &Gateway) -> f64 {
+        self.strategies
+            .iter()
+            .map(|strategy| strategy.weight(gateway))
+            .product()
+    }
+}
+
+/// The ranking strategies that can be selected in the settings.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GatewayRanking {
+    /// Pick any of the matching gateways.
+    #[default]
+    Uniform,
+
+    /// Favor gateways with a higher mixnet performance.
+    Performance,
+
+    /// Skip gateways whose last probe failed, and favor gateways with a higher mixnet performance
+    /// and a more recent probe.
+    ProbeScore,
+}
+
+impl GatewayRanking {
+    /// Returns the strategy ranking gateways for a tunnel routing the given IP families.
+    pub fn strategy(&self, families: IpFamilies) -> Box<dyn RankingStrategy> {
+        match self {
+            GatewayRanking::Uniform => Box::new(UniformRanking),
+            GatewayRanking::Performance => Box::new(PerformanceRanking),
+            GatewayRanking::ProbeScore => Box::new(CombinedRanking::new(vec![
+                Box::new(ProbeFilterRanking { families }),
+                Box::new(PerformanceRanking),
+                Box::new(ProbeRecencyRanking::default()),
+            ])),
+        }
+    }
+}
+
+impl fmt::Display for GatewayRanking {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GatewayRanking::Uniform => write!(f, "uniform"),
+            GatewayRanking::Performance => write!(f, "performance"),
+            GatewayRanking::ProbeScore => write!(f, "probe score"),
+        }
+    }
+}
+
+// Exit results are only reported for gateways that were probed as exit gateways.
+fn probe_can_route(probe: &Probe, families: IpFamilies) -> bool {
+    probe.outcome.as_entry.can_route
+        && probe.outcome.as_exit.as_ref().is_none_or(|exit| {
+            (exit.can_route_ip_v4 || !families.ipv4) && (exit.can_route_ip_v6 || !families.ipv6)
+        })
+}
+
+// Halves the factor for every half life elapsed since the probe, down to the minimum factor.
+fn probe_recency_factor(probe: &Probe, now: DateTime<Utc>, half_life: Duration) -> f64 {
+    let Ok(probed_at) = DateTime::parse_from_rfc3339(&probe.last_updated_utc) else {
+        return MIN_RECENCY_FACTOR;
+    };
+    let age = (now - probed_at.with_timezone(&Utc))
+        .to_std()
+        .unwrap_or_default();
+    0.5_f64
+        .powf(age.as_secs_f64() / half_life.as_secs_f64())
+        .max(MIN_RECENCY_FACTOR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Entry, Exit, GatewayList, NodeIdentity, ProbeOutcome};
+
+    fn probe(last_updated_utc: &str, can_route: bool, can_route_ip_v6: bool) -> Probe {
+        Probe {
+            last_updated_utc: last_updated_utc.to_string(),
+            outcome: ProbeOutcome {
+                as_entry: Entry {
+                    can_connect: true,
+                    can_route,
+                },
+                as_exit: Some(Exit {
+                    can_connect: true,
+                    can_route_ip_v4: true,
+                    can_route_ip_external_v4: true,
+                    can_route_ip_v6,
+                    can_route_ip_external_v6: can_route_ip_v6,
+                }),
+                wg: None,
+            },
+        }
+    }
+
+    #[test]
+    fn failed_probes_cannot_route() {
+        let timestamp = "2024-11-20T12:00:00Z";
+        let families = IpFamilies::default();
+        assert!(probe_can_route(&probe(timestamp, true, true), families));
+        assert!(!probe_can_route(&probe(timestamp, false, true), families));
+        assert!(!probe_can_route(&probe(timestamp, true, false), families));
+    }
+
+    #[test]
+    fn ipv6_is_only_required_when_routed() {
+        let ipv4_only = IpFamilies {
+            ipv4: true,
+            ipv6: false,
+        };
+        let probe = probe("2024-11-20T12:00:00Z", true, false);
+        assert!(probe_can_route(&probe, ipv4_only));
+        assert!(!probe_can_route(&probe, IpFamilies::default()));
+    }
+
+    #[test]
+    fn excluded_gateways_are_never_picked() {
+        let gateway = Gateway {
+            identity: NodeIdentity::from_base58_string(
+                "4SPdxfBYsuARBw6REQQa5vFiKcvmYiet9sSWqb751i3Z",
+            )
+            .unwrap(),
+            location: None,
+            ipr_address: None,
+            authenticator_address: None,
+            last_probe: Some(probe("2024-11-20T12:00:00Z", false, true)),
+            host: None,
+            clients_ws_port: None,
+            clients_wss_port: None,
+            mixnet_performance: None,
+            version: None,
+        };
+        let gateways = GatewayList::new(vec![gateway]);
+
+        let ranking = GatewayRanking::ProbeScore.strategy(IpFamilies::default());
+        assert!(gateways.ranked_gateway(ranking.as_ref()).is_none());
+        assert!(gateways.ranked_gateway(&UniformRanking).is_some());
+    }
+
+    #[test]
+    fn recency_factor_halves_every_half_life() {
+        let now = DateTime::parse_from_rfc3339("2024-11-20T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let fresh = probe_recency_factor(
+            &probe("2024-11-20T12:00:00Z", true, true),
+            now,
+            PROBE_HALF_LIFE,
+        );
+        let one_hour = probe_recency_factor(
+            &probe("2024-11-20T11:00:00Z", true, true),
+            now,
+            PROBE_HALF_LIFE,
+        );
+        let one_week = probe_recency_factor(
+            &probe("2024-11-13T12:00:00Z", true, true),
+            now,
+            PROBE_HALF_LIFE,
+        );
+        let invalid = probe_recency_factor(&probe("yesterday", true, true), now, PROBE_HALF_LIFE);
+
+        assert!((fresh - 1.0).abs() < 1e-9);
+        assert!((one_hour - 0.5).abs() < 1e-9);
+        assert_eq!(one_week, MIN_RECENCY_FACTOR);
+        assert_eq!(invalid, MIN_RECENCY_FACTOR);
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+// A group of countries, such as a continent or the European Union, that gateways can be selected
+// from.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Region {
+    id: &'static str,
+    name: &'static str,
+    aliases: &'static [&'static str],
+    // Two letter ISO country codes
+    countries: &'static [&'static str],
+}
+
+impl Region {
+    pub fn id(&self) -> &'static str {
+        self.id
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn countries(&self) -> &'static [&'static str] {
+        self.countries
+    }
+
+    pub fn contains_country(&self, code: &str) -> bool {
+        self.countries
+            .iter()
+            .any(|country| country.eq_ignore_ascii_case(code))
+    }
+
+    pub fn all() -> &'static [Region] {
+        REGIONS
+    }
+
+    pub fn all_ids() -> Vec<String> {
+        REGIONS.iter().map(|region| region.id.to_string()).collect()
+    }
+
+    // Looks up a region by id, name or alias. The lookup ignores case, and spaces and underscores
+    // match dashes, so "North America", "north_america" and "north-america" are the same region.
+    pub fn lookup(name: &str) -> Option<&'static Region> {
+        let name = name.trim().to_lowercase().replace([' ', '_'], "-");
+        REGIONS.iter().find(|region| {
+            region.id == name
+                || region.name.to_lowercase().replace(' ', "-") == name
+                || region.aliases.contains(&name.as_str())
+        })
+    }
+}
+
+const REGIONS: &[Region] = &[
+    Region {
+        id: "eu",
+        name: "European Union",
+        aliases: &[],
+        countries: &[
+            "AT", "BE", "BG", "CY", "CZ", "DE", "DK", "EE", "ES", "FI", "FR", "GR", "HR", "HU",
+            "IE", "IT", "LT", "LU", "LV", "MT", "NL", "PL", "PT", "RO", "SE", "SI", "SK",
+        ],
+    },
+    Region {
+        id: "europe",
+        name: "Europe",
+        aliases: &[],
+        countries: &[
+            "AD", "AL", "AT", "AX", "BA", "BE", "BG", "BY", "CH", "CY", "CZ", "DE", "DK", "EE",
+            "ES", "FI", "FO", "FR", "GB", "GG", "GI", "GR", "HR", "HU", "IE", "IM", "IS", "IT",
+            "JE", "LI", "LT", "LU", "LV", "MC", "MD", "ME", "MK", "MT", "NL", "NO", "PL", "PT",
+            "RO", "RS", "RU", "SE", "SI", "SJ", "SK", "SM", "UA", "VA", "XK",
+        ],
+    },
+    Region {
+        id: "north-america",
+        name: "North America",
+        aliases: &["americas-north"],
+        countries: &[
+            "AG", "AI", "AW", "BB", "BL", "BM", "BQ", "BS", "BZ", "CA", "CR", "CU", "CW", "DM",
+            "DO", "GD", "GL", "GP", "GT", "HN", "HT", "JM", "KN", "KY", "LC", "MF", "MQ", "MS",
+            "MX", "NI", "PA", "PM", "PR", "SV", "SX", "TC", "TT", "US", "VC", "VG", "VI",
+        ],
+    },
+    Region {
+        id: "south-america",
+        name: "South America",
+        aliases: &["americas-south", "latam"],
+        countries: &[
+            "AR", "BO", "BR", "CL", "CO", "EC", "FK", "GF", "GY", "PE", "PY", "SR", "UY", "VE",
+        ],
+    },
+    Region {
+        id: "asia",
+        name: "Asia",
+        aliases: &[],
+        countries: &[
+            "AE", "AF", "AM", "AZ", "BD", "BH", "BN", "BT", "CN", "GE", "HK", "ID", "IL", "IN",
+            "IQ", "IR", "JO", "JP", "KG", "KH", "KP", "KR", "KW", "KZ", "LA", "LB", "LK", "MM",
+            "MN", "MO", "MV", "MY", "NP", "OM", "PH", "PK", "PS", "QA", "SA", "SG", "SY", "TH",
+            "TJ", "TL", "TM", "TR", "TW", "UZ", "VN", "YE",
+        ],
+    },
+    Region {
+        id: "africa",
+        name: "Africa",
+        aliases: &[],
+        countries: &[
+            "AO", "BF", "BI", "BJ", "BW", "CD", "CF", "CG", "CI", "CM", "CV", "DJ", "DZ", "EG",
+            "EH", "ER", "ET", "GA", "GH", "GM", "GN", "GQ", "GW", "KE", "KM", "LR", "LS", "LY",
+            "MA", "MG", "ML", "MR", "MU", "MW", "MZ", "NA", "NE", "NG", "RE", "RW", "SC", "SD",
+            "SH", "SL", "SN", "SO", "SS", "ST", "SZ", "TD", "TG", "TN", "TZ", "UG", "YT", "ZA",
+            "ZM", "ZW",
+        ],
+    },
+    Region {
+        id: "oceania",
+        name: "Oceania",
+        aliases: &["australia-and-oceania"],
+        countries: &[
+            "AS", "AU", "CK", "FJ", "FM", "GU", "KI", "MH", "MP", "NC", "NF", "NR", "NU", "NZ",
+            "PF", "PG", "PN", "PW", "SB", "TK", "TO", "TV", "VU", "WF", "WS",
+        ],
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_by_id_name_and_alias() {
+        assert_eq!(Region::lookup("EU").map(Region::id), Some("eu"));
+        assert_eq!(Region::lookup("European Union").map(Region::id), Some("eu"));
+        assert_eq!(
+            Region::lookup("North America").map(Region::id),
+            Some("north-america")
+        );
+        assert_eq!(
+            Region::lookup("south_america").map(Region::id),
+            Some("south-america")
+        );
+        assert_eq!(
+            Region::lookup("latam").map(Region::id),
+            Some("south-america")
+        );
+        assert_eq!(Region::lookup("atlantis"), None);
+    }
+
+    #[test]
+    fn contains_country_ignores_case() {
+        let eu = Region::lookup("eu").unwrap();
+        assert!(eu.contains_country("de"));
+        assert!(eu.contains_country("FR"));
+        assert!(!eu.contains_country("CH"));
+    }
+}
@@ -60,11 +60,23 @@ pub enum Error {
         available_countries: Vec<String>,
     },
 
+    #[error("invalid coordinates: {latitude},{longitude}")]
+    InvalidCoordinates { latitude: f64, longitude: f64 },
+
+    #[error("unknown region {requested_region}, available regions: {available_regions:?}")]
+    UnknownRegion {
+        requested_region: String,
+        available_regions: Vec<String>,
+    },
+
     #[error("failed to select gateway based on low latency: {source}")]
     FailedToSelectGatewayBasedOnLowLatency {
         source: nym_client_core::error::ClientCoreError,
     },
 
+    #[error("failed to measure the latency of any gateway")]
+    NoGatewayLatency,
+
     #[error("no matching gateway found after selecting low latency: {requested_identity}")]
     NoMatchingGatewayAfterSelectingLowLatency { requested_identity: String },
 
@@ -0,0 +1,587 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+    collections::BTreeSet,
+    future::Future,
+    io,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use nym_vpn_api_client::{
+    response::NymDirectoryGateway,
+    types::{GatewayMinPerformance, Percent},
+    VpnApiClient,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tracing::{debug, info, warn};
+use url::Url;
+
+use crate::{entries::gateway::GatewayType, error::Result};
+
+// Cached gateways younger than this are used without contacting the directory.
+const CACHE_FRESH_DURATION: Duration = Duration::from_secs(10 * 60);
+
+// Cached gateways younger than this are used right away while they are refreshed in the
+// background. Older ones are only used when the directory can't be reached.
+const CACHE_STALE_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+// Cache files being refreshed in the background, so that lookups made while a refresh is in
+// flight don't start another one.
+static REFRESHING: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+// Marks a cache file as being refreshed until dropped, including when the refresh panics or is
+// cancelled.
+struct RefreshingGuard {
+    path: PathBuf,
+}
+
+impl RefreshingGuard {
+    fn acquire(path: PathBuf) -> Option<Self> {
+        let inserted = lock_refreshing().insert(path.clone());
+        inserted.then(|| Self { path })
+    }
+}
+
+impl Drop for RefreshingGuard {
+    fn drop(&mut self) {
+        lock_refreshing().remove(&self.path);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedGateways {
+    fetched_at: SystemTime,
+    mixnet_min_performance: Option<Percent>,
+    vpn_min_performance: Option<Percent>,
+    gateways: Vec<NymDirectoryGateway>,
+}
+
+impl CachedGateways {
+    fn age(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.fetched_at)
+            .unwrap_or_default()
+    }
+
+    fn matches(&self, min_performance: Option<GatewayMinPerformance>) -> bool {
+        let min_performance = min_performance.unwrap_or_default();
+        self.mixnet_min_performance == min_performance.mixnet_min_performance
+            && self.vpn_min_performance == min_performance.vpn_min_performance
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedNodes<T> {
+    fetched_at: SystemTime,
+    nodes: Vec<T>,
+}
+
+// Where the cached gateways come from, which is nym-vpn-api outside of the tests.
+#[async_trait::async_trait]
+pub(crate) trait GatewaySource: Clone + Send + Sync + 'static {
+    async fn fetch_gateways(
+        &self,
+        gw_type: GatewayType,
+        min_performance: Option<GatewayMinPerformance>,
+    ) -> Result<Vec<NymDirectoryGateway>>;
+}
+
+#[async_trait::async_trait]
+impl GatewaySource for VpnApiClient {
+    async fn fetch_gateways(
+        &self,
+        gw_type: GatewayType,
+        min_performance: Option<GatewayMinPerformance>,
+    ) -> Result<Vec<NymDirectoryGateway>> {
+        Ok(self
+            .get_gateways_by_type(gw_type.into(), min_performance)
+            .await?
+            .into_inner())
+    }
+}
+
+// On-disk cache of the gateways returned by nym-vpn-api, with one directory per API and one file
+// per gateway type. It keeps connecting possible from the last known list when the directory is
+// unreachable or rate limited. The nodes returned by nym-api are kept the same way, but only used
+// when nym-api can't be reached.
+#[derive(Clone, Debug)]
+pub(crate) struct GatewayCache {
+    cache_dir: PathBuf,
+}
+
+impl GatewayCache {
+    pub(crate) fn new(cache_dir: PathBuf, api_url: &Url) -> Self {
+        Self {
+            cache_dir: cache_dir.join(api_cache_key(api_url)),
+        }
+    }
+
+    pub(crate) async fn lookup_gateways<S: GatewaySource>(
+        &self,
+        source: &S,
+        gw_type: GatewayType,
+        min_performance: Option<GatewayMinPerformance>,
+    ) -> Result<Vec<NymDirectoryGateway>> {
+        let cached = self.load(&gw_type, min_performance).await;
+
+        if let Some(cached) = cached.as_ref() {
+            let age = cached.age();
+            if age < CACHE_FRESH_DURATION {
+                debug!("Using cached {} gateways from {:?} ago", gw_type, age);
+                return Ok(cached.gateways.clone());
+            }
+            if age < CACHE_STALE_DURATION {
+                info!(
+                    "Using cached {} gateways from {:?} ago, refreshing them in the background",
+                    gw_type, age
+                );
+                self.spawn_refresh(source, gw_type, min_performance);
+                return Ok(cached.gateways.clone());
+            }
+        }
+
+        match self.refresh(source, gw_type.clone(), min_performance).await {
+            Ok(gateways) => Ok(gateways),
+            Err(err) => match cached {
+                Some(cached) => {
+                    warn!(
+                        "Failed to fetch {} gateways, using the last known ones from {:?} ago: {}",
+                        gw_type,
+                        cached.age(),
+                        err
+                    );
+                    Ok(cached.gateways)
+                }
+                None => Err(err),
+            },
+        }
+    }
+
+    fn spawn_refresh<S: GatewaySource>(
+        &self,
+        source: &S,
+        gw_type: GatewayType,
+        min_performance: Option<GatewayMinPerformance>,
+    ) {
+        let Some(refreshing) = RefreshingGuard::acquire(self.cache_file(&gw_type)) else {
+            debug!("Cached {} gateways are already being refreshed", gw_type);
+            return;
+        };
+
+        let cache = self.clone();
+        let source = source.clone();
+        tokio::spawn(async move {
+            let _refreshing = refreshing;
+            if let Err(err) = cache.refresh(&source, gw_type, min_performance).await {
+                warn!("Failed to refresh the cached gateways: {}", err);
+            }
+        });
+    }
+
+    // Fetches the nodes stored as `name`, falling back to the last fetched ones when fetching
+    // fails.
+    pub(crate) async fn fetch_nodes<T, F>(&self, name: &str, fetch: F) -> Result<Vec<T>>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<Vec<T>>>,
+    {
+        let path = self.cache_dir.join(format!("{name}.json"));
+        match fetch.await {
+            Ok(nodes) => {
+                let cached = CachedNodes {
+                    fetched_at: SystemTime::now(),
+                    nodes,
+                };
+                self.write(path, &cached).await;
+                Ok(cached.nodes)
+            }
+            Err(err) => {
+                let cached = tokio::fs::read(&path).await.ok().and_then(|contents| {
+                    serde_json::from_slice::<CachedNodes<T>>(&contents)
+                        .inspect_err(|err| warn!("Failed to parse {}: {}", path.display(), err))
+                        .ok()
+                });
+                let Some(cached) = cached else {
+                    return Err(err);
+                };
+                let age = SystemTime::now()
+                    .duration_since(cached.fetched_at)
+                    .unwrap_or_default();
+                warn!(
+                    "Failed to fetch {}, using the last known ones from {:?} ago: {}",
+                    name, age, err
+                );
+                Ok(cached.nodes)
+            }
+        }
+    }
+
+    async fn refresh<S: GatewaySource>(
+        &self,
+        source: &S,
+        gw_type: GatewayType,
+        min_performance: Option<GatewayMinPerformance>,
+    ) -> Result<Vec<NymDirectoryGateway>> {
+        info!("Fetching {} gateways from nym-vpn-api...", gw_type);
+        let gateways = source
+            .fetch_gateways(gw_type.clone(), min_performance)
+            .await?;
+        self.store(&gw_type, min_performance, &gateways).await;
+        Ok(gateways)
+    }
+
+    async fn load(
+        &self,
+        gw_type: &GatewayType,
+        min_performance: Option<GatewayMinPerformance>,
+    ) -> Option<CachedGateways> {
+        let path = self.cache_file(gw_type);
+        let contents = tokio::fs::read(&path).await.ok()?;
+        let cached: CachedGateways = serde_json::from_slice(&contents)
+            .inspect_err(|err| warn!("Failed to parse {}: {}", path.display(), err))
+            .ok()?;
+        if !cached.matches(min_performance) {
+            debug!(
+                "Ignoring cached {} gateways fetched with other performance thresholds",
+                gw_type
+            );
+            return None;
+        }
+        Some(cached)
+    }
+
+    async fn store(
+        &self,
+        gw_type: &GatewayType,
+        min_performance: Option<GatewayMinPerformance>,
+        gateways: &[NymDirectoryGateway],
+    ) {
+        let min_performance = min_performance.unwrap_or_default();
+        let cached = CachedGateways {
+            fetched_at: SystemTime::now(),
+            mixnet_min_performance: min_performance.mixnet_min_performance,
+            vpn_min_performance: min_performance.vpn_min_performance,
+            gateways: gateways.to_vec(),
+        };
+        self.write(self.cache_file(gw_type), &cached).await;
+    }
+
+    async fn write<T: Serialize>(&self, path: PathBuf, cached: &T) {
+        let contents = match serde_json::to_vec(cached) {
+            Ok(contents) => contents,
+            Err(err) => {
+                warn!("Failed to serialize {}: {}", path.display(), err);
+                return;
+            }
+        };
+
+        let cache_dir = self.cache_dir.clone();
+        let file_path = path.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            std::fs::create_dir_all(&cache_dir)?;
+            nym_common::fs::write_atomically(&file_path, contents)
+        })
+        .await
+        .unwrap_or_else(|err| Err(io::Error::other(err)));
+        if let Err(err) = result {
+            warn!("Failed to write {}: {}", path.display(), err);
+        }
+    }
+
+    fn cache_file(&self, gw_type: &GatewayType) -> PathBuf {
+        let name = match gw_type {
+            GatewayType::MixnetEntry => "mixnet_entry_gateways.json",
+            GatewayType::MixnetExit => "mixnet_exit_gateways.json",
+            GatewayType::Wg => "vpn_gateways.json",
+        };
+        self.cache_dir.join(name)
+    }
+}
+
+fn lock_refreshing() -> std::sync::MutexGuard<'static, BTreeSet<PathBuf>> {
+    REFRESHING
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// Names the directory of the gateways of an API after its URL, such as
+// nym-vpn-api.nymtech.net_api for https://nym-vpn-api.nymtech.net/api, so that the gateways of one
+// network are never used on another.
+fn api_cache_key(api_url: &Url) -> String {
+    let url = api_url.as_str();
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    url.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        .trim_matches('_')
+        .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use nym_vpn_api_client::response::{EntryInformation, Location, Role};
+
+    use super::*;
+    use crate::Error;
+
+    const API_URL: &str = "https://nym-vpn-api.nymtech.net/api";
+
+    #[derive(Clone)]
+    struct FakeSource {
+        gateways: Option<Vec<NymDirectoryGateway>>,
+        delay: Duration,
+        fetches: Arc<AtomicUsize>,
+    }
+
+    impl FakeSource {
+        fn online(gateways: Vec<NymDirectoryGateway>) -> Self {
+            Self {
+                gateways: Some(gateways),
+                delay: Duration::ZERO,
+                fetches: Arc::default(),
+            }
+        }
+
+        fn offline() -> Self {
+            Self {
+                gateways: None,
+                delay: Duration::ZERO,
+                fetches: Arc::default(),
+            }
+        }
+
+        fn fetches(&self) -> usize {
+            self.fetches.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl GatewaySource for FakeSource {
+        async fn fetch_gateways(
+            &self,
+            _gw_type: GatewayType,
+            _min_performance: Option<GatewayMinPerformance>,
+        ) -> Result<Vec<NymDirectoryGateway>> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            self.gateways
+                .clone()
+                .ok_or_else(|| Error::ResolvedHostnameButNoIp("nym-vpn-api".to_owned()))
+        }
+    }
+
+    fn gateway(identity_key: &str) -> NymDirectoryGateway {
+        NymDirectoryGateway {
+            identity_key: identity_key.to_owned(),
+            ip_packet_router: None,
+            authenticator: None,
+            location: Location {
+                two_letter_iso_country_code: "CH".to_owned(),
+                latitude: 47.0,
+                longitude: 8.0,
+                city: None,
+            },
+            last_probe: None,
+            ip_addresses: vec!["1.2.3.4".to_owned()],
+            mix_port: 1789,
+            role: Role::EntryGateway,
+            entry: EntryInformation {
+                hostname: None,
+                ws_port: 9000,
+                wss_port: None,
+            },
+            performance: Percent::from_percentage_value(90).unwrap(),
+            build_information: None,
+        }
+    }
+
+    fn identities(gateways: &[NymDirectoryGateway]) -> Vec<&str> {
+        gateways.iter().map(|gw| gw.identity_key.as_str()).collect()
+    }
+
+    fn cache(dir: &tempfile::TempDir) -> GatewayCache {
+        GatewayCache::new(dir.path().to_owned(), &API_URL.parse().unwrap())
+    }
+
+    async fn store_cached(cache: &GatewayCache, age: Duration, gateways: Vec<NymDirectoryGateway>) {
+        let cached = CachedGateways {
+            fetched_at: SystemTime::now() - age,
+            mixnet_min_performance: None,
+            vpn_min_performance: None,
+            gateways,
+        };
+        cache
+            .write(cache.cache_file(&GatewayType::MixnetEntry), &cached)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn fresh_cache_is_used_without_fetching() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = cache(&dir);
+        store_cached(&cache, Duration::from_secs(60), vec![gateway("cached")]).await;
+        let source = FakeSource::online(vec![gateway("fetched")]);
+
+        let gateways = cache
+            .lookup_gateways(&source, GatewayType::MixnetEntry, None)
+            .await
+            .unwrap();
+
+        assert_eq!(identities(&gateways), ["cached"]);
+        assert_eq!(source.fetches(), 0);
+    }
+
+    #[tokio::test]
+    async fn stale_cache_is_used_while_refreshed_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = cache(&dir);
+        store_cached(
+            &cache,
+            Duration::from_secs(60 * 60),
+            vec![gateway("cached")],
+        )
+        .await;
+        let source = FakeSource {
+            delay: Duration::from_millis(100),
+            ..FakeSource::online(vec![gateway("fetched")])
+        };
+
+        for _ in 0..2 {
+            let gateways = cache
+                .lookup_gateways(&source, GatewayType::MixnetEntry, None)
+                .await
+                .unwrap();
+            assert_eq!(identities(&gateways), ["cached"]);
+        }
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(source.fetches(), 1);
+        let gateways = cache
+            .lookup_gateways(&source, GatewayType::MixnetEntry, None)
+            .await
+            .unwrap();
+        assert_eq!(identities(&gateways), ["fetched"]);
+        assert_eq!(source.fetches(), 1);
+    }
+
+    #[tokio::test]
+    async fn expired_cache_is_replaced_when_online() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = cache(&dir);
+        store_cached(&cache, CACHE_STALE_DURATION, vec![gateway("cached")]).await;
+        let source = FakeSource::online(vec![gateway("fetched")]);
+
+        let gateways = cache
+            .lookup_gateways(&source, GatewayType::MixnetEntry, None)
+            .await
+            .unwrap();
+
+        assert_eq!(identities(&gateways), ["fetched"]);
+        assert_eq!(source.fetches(), 1);
+    }
+
+    #[tokio::test]
+    async fn expired_cache_is_used_when_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = cache(&dir);
+        store_cached(&cache, CACHE_STALE_DURATION, vec![gateway("cached")]).await;
+        let source = FakeSource::offline();
+
+        let gateways = cache
+            .lookup_gateways(&source, GatewayType::MixnetEntry, None)
+            .await
+            .unwrap();
+
+        assert_eq!(identities(&gateways), ["cached"]);
+        assert_eq!(source.fetches(), 1);
+    }
+
+    #[tokio::test]
+    async fn missing_cache_fails_when_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = FakeSource::offline();
+
+        let result = cache(&dir)
+            .lookup_gateways(&source, GatewayType::MixnetEntry, None)
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn cache_is_not_shared_between_apis() {
+        let dir = tempfile::tempdir().unwrap();
+        store_cached(&cache(&dir), Duration::ZERO, vec![gateway("cached")]).await;
+        let other_cache = GatewayCache::new(
+            dir.path().to_owned(),
+            &"https://sandbox-nym-vpn-api.nymtech.net/api"
+                .parse()
+                .unwrap(),
+        );
+
+        assert!(other_cache
+            .load(&GatewayType::MixnetEntry, None)
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn refresh_is_released_when_it_panics() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = cache(&dir).cache_file(&GatewayType::MixnetEntry);
+
+        let refreshing = RefreshingGuard::acquire(path.clone()).unwrap();
+        assert!(RefreshingGuard::acquire(path.clone()).is_none());
+        let result = tokio::spawn(async move {
+            let _refreshing = refreshing;
+            panic!("refresh failed");
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert!(RefreshingGuard::acquire(path).is_some());
+    }
+
+    #[tokio::test]
+    async fn last_fetched_nodes_are_used_when_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = cache(&dir);
+
+        let nodes = cache
+            .fetch_nodes("nodes", async { Ok(vec![1, 2]) })
+            .await
+            .unwrap();
+        assert_eq!(nodes, [1, 2]);
+
+        let offline = async { Err(Error::ResolvedHostnameButNoIp("nym-api".to_owned())) };
+        let nodes: Vec<u32> = cache.fetch_nodes("nodes", offline).await.unwrap();
+        assert_eq!(nodes, [1, 2]);
+
+        let offline = async { Err(Error::ResolvedHostnameButNoIp("nym-api".to_owned())) };
+        let result: Result<Vec<u32>> = cache.fetch_nodes("other_nodes", offline).await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn api_cache_key_is_named_after_url() {
+        let url = "https://nym-vpn-api.nymtech.net/api/".parse().unwrap();
+        assert_eq!(api_cache_key(&url), "nym-vpn-api.nymtech.net_api");
+
+        let url = "http://127.0.0.1:8080".parse().unwrap();
+        assert_eq!(api_cache_key(&url), "127.0.0.1_8080");
+    }
+}
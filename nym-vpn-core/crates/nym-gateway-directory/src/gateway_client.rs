@@ -1,7 +1,7 @@
 // Copyright 2023-2024 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::{fmt, net::IpAddr};
+use std::{fmt, net::IpAddr, path::PathBuf};
 
 use nym_sdk::UserAgent;
 use nym_validator_client::{models::NymNodeDescription, nym_nodes::SkimmedNode, NymApiClient};
@@ -17,6 +17,7 @@ use crate::{
         gateway::{Gateway, GatewayList, GatewayType},
     },
     error::Result,
+    gateway_cache::GatewayCache,
     Error,
 };
 
@@ -25,6 +26,9 @@ pub struct Config {
     pub api_url: Url,
     pub nym_vpn_api_url: Option<Url>,
     pub min_gateway_performance: Option<GatewayMinPerformance>,
+    // Directory where the gateways returned by nym-vpn-api, and the nodes returned by nym-api,
+    // are cached. Gateways are always fetched, and nodes have no fallback, when it's not set.
+    pub cache_dir: Option<PathBuf>,
 }
 
 impl Default for Config {
@@ -69,6 +73,7 @@ impl Config {
             api_url: default_api_url,
             nym_vpn_api_url: Some(default_nym_vpn_api_url),
             min_gateway_performance: None,
+            cache_dir: None,
         }
     }
 
@@ -88,6 +93,7 @@ impl Config {
             api_url,
             nym_vpn_api_url,
             min_gateway_performance: None,
+            cache_dir: None,
         }
     }
 
@@ -116,16 +122,31 @@ impl Config {
         self.min_gateway_performance = Some(min_gateway_performance);
         self
     }
+
+    pub fn with_cache_dir(mut self, cache_dir: PathBuf) -> Self {
+        self.cache_dir = Some(cache_dir);
+        self
+    }
 }
 
 pub struct GatewayClient {
     api_client: NymApiClient,
     nym_vpn_api_client: Option<nym_vpn_api_client::VpnApiClient>,
     min_gateway_performance: Option<GatewayMinPerformance>,
+    cache: Option<GatewayCache>,
+    nym_api_cache: Option<GatewayCache>,
 }
 
 impl GatewayClient {
     pub fn new(config: Config, user_agent: UserAgent) -> Result<Self> {
+        let nym_api_cache = config
+            .cache_dir
+            .clone()
+            .map(|cache_dir| GatewayCache::new(cache_dir, &config.api_url));
+        let cache = config
+            .cache_dir
+            .zip(config.nym_vpn_api_url.as_ref())
+            .map(|(cache_dir, url)| GatewayCache::new(cache_dir, url));
         let api_client = NymApiClient::new_with_user_agent(config.api_url, user_agent.clone());
         let nym_vpn_api_client = config
             .nym_vpn_api_url
@@ -136,6 +157,8 @@ impl GatewayClient {
             api_client,
             nym_vpn_api_client,
             min_gateway_performance: config.min_gateway_performance,
+            cache,
+            nym_api_cache,
         })
     }
 
@@ -153,18 +176,30 @@ impl GatewayClient {
 
     async fn lookup_described_nodes(&self) -> Result<Vec<NymNodeDescription>> {
         info!("Fetching all described nodes from nym-api...");
-        self.api_client
-            .get_all_described_nodes()
-            .await
-            .map_err(Error::FailedToLookupDescribedGateways)
+        let fetch = async {
+            self.api_client
+                .get_all_described_nodes()
+                .await
+                .map_err(Error::FailedToLookupDescribedGateways)
+        };
+        match &self.nym_api_cache {
+            Some(cache) => cache.fetch_nodes("described_nodes", fetch).await,
+            None => fetch.await,
+        }
     }
 
     async fn lookup_skimmed_gateways(&self) -> Result<Vec<SkimmedNode>> {
         info!("Fetching skimmed entry assigned nodes from nym-api...");
-        self.api_client
-            .get_all_basic_entry_assigned_nodes()
-            .await
-            .map_err(Error::FailedToLookupSkimmedGateways)
+        let fetch = async {
+            self.api_client
+                .get_all_basic_entry_assigned_nodes()
+                .await
+                .map_err(Error::FailedToLookupSkimmedGateways)
+        };
+        match &self.nym_api_cache {
+            Some(cache) => cache.fetch_nodes("skimmed_entry_nodes", fetch).await,
+            None => fetch.await,
+        }
     }
 
     pub async fn lookup_low_latency_entry_gateway(&self) -> Result<Gateway> {
@@ -176,8 +211,7 @@ impl GatewayClient {
     pub async fn lookup_gateway_ip_from_nym_api(&self, gateway_identity: &str) -> Result<IpAddr> {
         info!("Fetching gateway ip from nym-api...");
         let mut ips = self
-            .api_client
-            .get_all_described_nodes()
+            .lookup_described_nodes()
             .await?
             .iter()
             .find_map(|node| {
@@ -312,10 +346,21 @@ impl GatewayClient {
 
     pub async fn lookup_gateways(&self, gw_type: GatewayType) -> Result<GatewayList> {
         if let Some(nym_vpn_api_client) = &self.nym_vpn_api_client {
-            info!("Fetching gateways from nym-vpn-api...");
-            let gateways: Vec<_> = nym_vpn_api_client
-                .get_gateways_by_type(gw_type.into(), self.min_gateway_performance)
-                .await?
+            let gateways = match &self.cache {
+                Some(cache) => {
+                    cache
+                        .lookup_gateways(nym_vpn_api_client, gw_type, self.min_gateway_performance)
+                        .await?
+                }
+                None => {
+                    info!("Fetching gateways from nym-vpn-api...");
+                    nym_vpn_api_client
+                        .get_gateways_by_type(gw_type.into(), self.min_gateway_performance)
+                        .await?
+                        .into_inner()
+                }
+            };
+            let gateways: Vec<_> = gateways
                 .into_iter()
                 .filter_map(|gw| {
                     Gateway::try_from(gw)
@@ -3,6 +3,7 @@
 
 mod entries;
 mod error;
+mod gateway_cache;
 mod gateway_client;
 mod helpers;
 
@@ -12,11 +13,19 @@ pub use nym_vpn_api_client::types::{GatewayMinPerformance, Percent};
 pub use crate::{
     entries::{
         auth_addresses::{AuthAddress, AuthAddresses},
+        city::City,
+        coordinates::{Area, Coordinates},
         country::Country,
         entry_point::EntryPoint,
         exit_point::ExitPoint,
         gateway::{Entry, Exit, Gateway, GatewayList, GatewayType, Location, Probe, ProbeOutcome},
+        gateway_preferences::GatewayPreferences,
         ipr_addresses::IpPacketRouterAddress,
+        ranking::{
+            CombinedRanking, GatewayRanking, IpFamilies, PerformanceRanking, ProbeFilterRanking,
+            ProbeRecencyRanking, RankingStrategy, UniformRanking,
+        },
+        region::Region,
     },
     error::Error,
     gateway_client::{Config, GatewayClient},
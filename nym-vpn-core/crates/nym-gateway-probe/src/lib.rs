@@ -23,7 +23,7 @@ use nym_connection_monitor::self_ping_and_wait;
 use nym_gateway_directory::{
     AuthAddress, Config as GatewayDirectoryConfig, EntryPoint,
     GatewayClient as GatewayDirectoryClient, GatewayList, GatewayMinPerformance,
-    IpPacketRouterAddress,
+    GatewayPreferences, IpPacketRouterAddress, UniformRanking,
 };
 use nym_ip_packet_client::IprClientConnect;
 use nym_ip_packet_requests::{
@@ -93,7 +93,9 @@ impl Probe {
 
         // Setup the entry gateways
         let gateways = lookup_gateways(min_gateway_performance).await?;
-        let entry_gateway = entry_point.lookup_gateway(&gateways).await?;
+        let entry_gateway = entry_point
+            .lookup_gateway(&gateways, &GatewayPreferences::default(), &UniformRanking)
+            .await?;
         let exit_router_address = entry_gateway.ipr_address;
         let authenticator = entry_gateway.authenticator_address;
         let gateway_host = entry_gateway.host.clone().unwrap();
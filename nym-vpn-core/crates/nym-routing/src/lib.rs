@@ -30,6 +30,9 @@ use netlink_packet_route::route::RouteHeader;
 #[cfg(target_os = "macos")]
 pub use imp::{imp::RouteError, DefaultRouteEvent, PlatformError};
 
+#[cfg(target_os = "linux")]
+pub use imp::CallbackMessage;
+
 pub use imp::{Error, RouteManagerHandle};
 
 /// Link-layer/MAC adress
@@ -109,6 +112,17 @@ impl Route {
     pub fn get_node(&self) -> &Node {
         &self.node
     }
+
+    /// Returns the destination prefix of the route.
+    pub fn get_prefix(&self) -> IpNetwork {
+        self.prefix
+    }
+
+    /// Returns true if the route belongs to the main routing table.
+    #[cfg(target_os = "linux")]
+    pub fn is_main_table(&self) -> bool {
+        self.table_id == u32::from(RouteHeader::RT_TABLE_MAIN)
+    }
 }
 
 impl fmt::Display for Route {
@@ -0,0 +1,26 @@
+// Copyright 2016-2024 Mullvad VPN AB. All Rights Reserved.
+// Copyright 2024 Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Split tunneling used to let selected processes bypass the tunnel.
+//!
+//! Excluded processes are moved into a dedicated `net_cls` cgroup. Their packets are tagged with
+//! [`NET_CLS_CLASSID`], which the firewall translates into the tunnel fwmark so that the traffic
+//! is routed through the physical interface instead.
+//!
+//! The `net_cls` controller only exists in cgroup v1. On systems that only mount the unified
+//! cgroup v2 hierarchy, creating the split tunnel fails with `Error::UnifiedCgroupHierarchy`.
+#![deny(missing_docs)]
+
+#[cfg(target_os = "linux")]
+#[path = "linux.rs"]
+mod imp;
+
+#[cfg(target_os = "linux")]
+pub mod proc_events;
+
+#[cfg(target_os = "linux")]
+pub use self::imp::{Error, SplitTunnel};
+
+/// Class ID assigned to the exclusion cgroup. Used by the firewall to match excluded traffic.
+pub const NET_CLS_CLASSID: u32 = 0x4e796d;
@@ -0,0 +1,377 @@
+// Copyright 2016-2024 Mullvad VPN AB. All Rights Reserved.
+// Copyright 2024 Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use nix::{
+    mount::{mount, MsFlags},
+    sys::statfs::{statfs, CGROUP2_SUPER_MAGIC},
+};
+
+use super::NET_CLS_CLASSID;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Mount point of the cgroup hierarchies.
+const CGROUP_DIR: &str = "/sys/fs/cgroup";
+
+/// Mount point of the net_cls cgroup hierarchy.
+const NET_CLS_DIR: &str = "/sys/fs/cgroup/net_cls";
+
+/// Name of the cgroup holding excluded processes.
+const CGROUP_NAME: &str = "nym-vpn-exclusions";
+
+/// Path of the proc filesystem.
+const PROC_DIR: &str = "/proc";
+
+/// Enables the reverse path filter to take the fwmark into account, otherwise replies to
+/// excluded processes would be dropped.
+const SRC_VALID_MARK_FILE: &str = "/proc/sys/net/ipv4/conf/all/src_valid_mark";
+
+/// Errors related to split tunneling.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    /// Only the unified cgroup v2 hierarchy is mounted, which has no net_cls controller
+    #[error(
+        "Split tunneling requires the net_cls cgroup controller, which is not available with \
+         the unified cgroup v2 hierarchy"
+    )]
+    UnifiedCgroupHierarchy,
+
+    /// Unable to create the net_cls mount point
+    #[error("Unable to create net_cls mount point")]
+    CreateNetClsDir(#[source] io::Error),
+
+    /// Unable to mount the net_cls controller
+    #[error("Unable to mount net_cls controller")]
+    MountNetCls(#[source] nix::Error),
+
+    /// Unable to create the exclusion cgroup
+    #[error("Unable to create cgroup for excluded processes")]
+    CreateCgroup(#[source] io::Error),
+
+    /// Unable to set the class ID of the exclusion cgroup
+    #[error("Unable to set class ID for cgroup")]
+    SetClassId(#[source] io::Error),
+
+    /// Unable to enable src_valid_mark
+    #[error("Unable to enable src_valid_mark")]
+    SetSrcValidMark(#[source] io::Error),
+
+    /// Unable to add a process to the exclusion cgroup
+    #[error("Unable to add PID {pid} to cgroup")]
+    AddPid {
+        /// Process ID
+        pid: u32,
+        /// Underlying error
+        #[source]
+        source: io::Error,
+    },
+
+    /// Unable to remove a process from the exclusion cgroup
+    #[error("Unable to remove PID {pid} from cgroup")]
+    RemovePid {
+        /// Process ID
+        pid: u32,
+        /// Underlying error
+        #[source]
+        source: io::Error,
+    },
+
+    /// Unable to list the processes in the exclusion cgroup
+    #[error("Unable to list PIDs in cgroup")]
+    ListPids(#[source] io::Error),
+
+    /// Unable to list running processes
+    #[error("Unable to list running processes")]
+    ListProcesses(#[source] io::Error),
+}
+
+/// Manages the cgroup of processes excluded from the tunnel.
+pub struct SplitTunnel {
+    net_cls_dir: PathBuf,
+    proc_dir: PathBuf,
+    executables: BTreeSet<PathBuf>,
+    _src_valid_mark: Option<SrcValidMark>,
+}
+
+impl SplitTunnel {
+    /// Mounts the net_cls controller if needed and creates the exclusion cgroup.
+    pub fn new() -> Result<Self> {
+        let net_cls_dir = Path::new(NET_CLS_DIR);
+        if !net_cls_dir.exists() {
+            // The directory would be created as a cgroup v2 group, which net_cls cannot be
+            // mounted on.
+            if is_unified_hierarchy(Path::new(CGROUP_DIR)) {
+                return Err(Error::UnifiedCgroupHierarchy);
+            }
+            fs::create_dir_all(net_cls_dir).map_err(Error::CreateNetClsDir)?;
+            mount(
+                Some("net_cls"),
+                net_cls_dir,
+                Some("cgroup"),
+                MsFlags::empty(),
+                Some("net_cls"),
+            )
+            .map_err(Error::MountNetCls)?;
+        }
+
+        let mut split_tunnel = Self::with_paths(net_cls_dir, Path::new(PROC_DIR))?;
+        split_tunnel._src_valid_mark = Some(SrcValidMark::enable(Path::new(SRC_VALID_MARK_FILE))?);
+
+        Ok(split_tunnel)
+    }
+
+    fn with_paths(net_cls_dir: &Path, proc_dir: &Path) -> Result<Self> {
+        let split_tunnel = Self {
+            net_cls_dir: net_cls_dir.to_owned(),
+            proc_dir: proc_dir.to_owned(),
+            executables: BTreeSet::new(),
+            _src_valid_mark: None,
+        };
+
+        let cgroup_dir = split_tunnel.cgroup_dir();
+        if !cgroup_dir.exists() {
+            fs::create_dir(&cgroup_dir).map_err(Error::CreateCgroup)?;
+        }
+        fs::write(
+            cgroup_dir.join("net_cls.classid"),
+            NET_CLS_CLASSID.to_string(),
+        )
+        .map_err(Error::SetClassId)?;
+
+        Ok(split_tunnel)
+    }
+
+    /// Moves a process into the exclusion cgroup.
+    pub fn add_pid(&mut self, pid: u32) -> Result<()> {
+        fs::write(self.cgroup_dir().join("cgroup.procs"), pid.to_string())
+            .map_err(|source| Error::AddPid { pid, source })
+    }
+
+    /// Moves a process back into the root cgroup.
+    pub fn remove_pid(&mut self, pid: u32) -> Result<()> {
+        fs::write(self.net_cls_dir.join("cgroup.procs"), pid.to_string())
+            .map_err(|source| Error::RemovePid { pid, source })
+    }
+
+    /// Returns the processes currently in the exclusion cgroup.
+    pub fn list_pids(&self) -> Result<Vec<u32>> {
+        let procs =
+            fs::read_to_string(self.cgroup_dir().join("cgroup.procs")).map_err(Error::ListPids)?;
+        Ok(procs
+            .lines()
+            .filter_map(|line| line.trim().parse().ok())
+            .collect())
+    }
+
+    /// Moves all processes back into the root cgroup and forgets all excluded executables.
+    pub fn clear(&mut self) -> Result<()> {
+        self.executables.clear();
+        for pid in self.list_pids()? {
+            self.remove_pid(pid)?;
+        }
+        Ok(())
+    }
+
+    /// Excludes all current and future processes running the given executable.
+    pub fn add_executable(&mut self, path: PathBuf) -> Result<()> {
+        self.executables.insert(path);
+        self.sync_executables()
+    }
+
+    /// Stops excluding processes running the given executable, moving running processes back
+    /// into the root cgroup.
+    pub fn remove_executable(&mut self, path: &Path) -> Result<()> {
+        if !self.executables.remove(path) {
+            return Ok(());
+        }
+        for pid in self.list_pids()? {
+            if self.process_executable(pid).as_deref() == Some(path) {
+                self.remove_pid(pid)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the excluded executables.
+    pub fn list_executables(&self) -> Vec<PathBuf> {
+        self.executables.iter().cloned().collect()
+    }
+
+    /// Moves the process into the exclusion cgroup if it runs an excluded executable. Used to
+    /// exclude processes as they execute a new program.
+    pub fn sync_process(&mut self, pid: u32) -> Result<()> {
+        let is_excluded = self
+            .process_executable(pid)
+            .is_some_and(|exe| self.executables.contains(&exe));
+        if is_excluded {
+            self.add_pid(pid)?;
+            log::debug!("Excluded process {} from the tunnel", pid);
+        }
+        Ok(())
+    }
+
+    /// Moves all running processes of excluded executables into the exclusion cgroup.
+    /// Used to pick up processes started before an executable was excluded, or whose
+    /// process events were missed.
+    pub fn sync_executables(&mut self) -> Result<()> {
+        if self.executables.is_empty() {
+            return Ok(());
+        }
+
+        let excluded = self.list_pids()?;
+        let entries = fs::read_dir(&self.proc_dir).map_err(Error::ListProcesses)?;
+        for entry in entries.flatten() {
+            let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse().ok()) else {
+                continue;
+            };
+            if excluded.contains(&pid) {
+                continue;
+            }
+            // The process may have exited in the meantime.
+            if let Err(e) = self.sync_process(pid) {
+                log::debug!("{}", e);
+            }
+        }
+        Ok(())
+    }
+
+    fn process_executable(&self, pid: u32) -> Option<PathBuf> {
+        fs::read_link(self.proc_dir.join(pid.to_string()).join("exe")).ok()
+    }
+
+    fn cgroup_dir(&self) -> PathBuf {
+        self.net_cls_dir.join(CGROUP_NAME)
+    }
+}
+
+/// Enables src_valid_mark, restoring the previous value when dropped.
+struct SrcValidMark {
+    path: PathBuf,
+    previous: String,
+}
+
+impl SrcValidMark {
+    fn enable(path: &Path) -> Result<Self> {
+        let previous = fs::read_to_string(path).map_err(Error::SetSrcValidMark)?;
+        fs::write(path, b"1").map_err(Error::SetSrcValidMark)?;
+        Ok(Self {
+            path: path.to_owned(),
+            previous,
+        })
+    }
+}
+
+impl Drop for SrcValidMark {
+    fn drop(&mut self) {
+        if let Err(e) = fs::write(&self.path, &self.previous) {
+            log::error!("Unable to restore src_valid_mark: {}", e);
+        }
+    }
+}
+
+fn is_unified_hierarchy(cgroup_dir: &Path) -> bool {
+    statfs(cgroup_dir).is_ok_and(|stat| stat.filesystem_type() == CGROUP2_SUPER_MAGIC)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use super::*;
+
+    fn setup() -> (tempfile::TempDir, SplitTunnel) {
+        let dir = tempfile::tempdir().unwrap();
+        let net_cls_dir = dir.path().join("net_cls");
+        let proc_dir = dir.path().join("proc");
+        fs::create_dir(&proc_dir).unwrap();
+        // Files created by the kernel in a real cgroup hierarchy.
+        fs::create_dir_all(net_cls_dir.join(CGROUP_NAME)).unwrap();
+        fs::write(net_cls_dir.join(CGROUP_NAME).join("cgroup.procs"), "").unwrap();
+
+        let split_tunnel = SplitTunnel::with_paths(&net_cls_dir, &proc_dir).unwrap();
+        (dir, split_tunnel)
+    }
+
+    fn spawn_fake_process(dir: &Path, pid: u32, exe: &str) {
+        let process_dir = dir.join("proc").join(pid.to_string());
+        fs::create_dir(&process_dir).unwrap();
+        symlink(exe, process_dir.join("exe")).unwrap();
+    }
+
+    #[test]
+    fn creates_cgroup_with_classid() {
+        let (dir, _split_tunnel) = setup();
+
+        let classid = fs::read_to_string(
+            dir.path()
+                .join("net_cls/nym-vpn-exclusions/net_cls.classid"),
+        )
+        .unwrap();
+        assert_eq!(classid, NET_CLS_CLASSID.to_string());
+    }
+
+    #[test]
+    fn add_and_remove_pid() {
+        let (dir, mut split_tunnel) = setup();
+
+        split_tunnel.add_pid(42).unwrap();
+        assert_eq!(split_tunnel.list_pids().unwrap(), vec![42]);
+
+        split_tunnel.remove_pid(42).unwrap();
+        let root_procs = fs::read_to_string(dir.path().join("net_cls/cgroup.procs")).unwrap();
+        assert_eq!(root_procs, "42");
+    }
+
+    #[test]
+    fn sync_excludes_matching_executables() {
+        let (dir, mut split_tunnel) = setup();
+        spawn_fake_process(dir.path(), 100, "/usr/bin/curl");
+        spawn_fake_process(dir.path(), 200, "/usr/bin/firefox");
+        fs::create_dir(dir.path().join("proc/self")).unwrap();
+
+        split_tunnel
+            .add_executable(PathBuf::from("/usr/bin/firefox"))
+            .unwrap();
+
+        assert_eq!(split_tunnel.list_pids().unwrap(), vec![200]);
+        assert_eq!(
+            split_tunnel.list_executables(),
+            vec![PathBuf::from("/usr/bin/firefox")]
+        );
+    }
+
+    #[test]
+    fn sync_process_excludes_new_process() {
+        let (dir, mut split_tunnel) = setup();
+        split_tunnel
+            .add_executable(PathBuf::from("/usr/bin/firefox"))
+            .unwrap();
+
+        spawn_fake_process(dir.path(), 100, "/usr/bin/curl");
+        spawn_fake_process(dir.path(), 200, "/usr/bin/firefox");
+        split_tunnel.sync_process(100).unwrap();
+        split_tunnel.sync_process(200).unwrap();
+
+        assert_eq!(split_tunnel.list_pids().unwrap(), vec![200]);
+    }
+
+    #[test]
+    fn restores_src_valid_mark_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("src_valid_mark");
+        fs::write(&path, "0\n").unwrap();
+
+        let src_valid_mark = SrcValidMark::enable(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1");
+
+        drop(src_valid_mark);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "0\n");
+    }
+}
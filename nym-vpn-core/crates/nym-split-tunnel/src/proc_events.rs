@@ -0,0 +1,212 @@
+// Copyright 2024 Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Process events received from the kernel through the netlink process connector.
+
+use std::{
+    io, mem,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    time::Duration,
+};
+
+/// Connector index and value of the process events connector.
+const CN_IDX_PROC: u32 = 1;
+const CN_VAL_PROC: u32 = 1;
+
+/// Operation subscribing to the process events.
+const PROC_CN_MCAST_LISTEN: u32 = 1;
+
+/// Event sent when a process executes a new program.
+const PROC_EVENT_EXEC: u32 = 2;
+
+/// Size of `struct nlmsghdr`.
+const NLMSG_HEADER_LEN: usize = 16;
+
+/// Size of `struct cn_msg`, without its payload.
+const CN_MSG_HEADER_LEN: usize = 20;
+
+/// Offset of the event data in `struct proc_event`, following `what`, `cpu` and `timestamp_ns`.
+const PROC_EVENT_DATA_OFFSET: usize = 16;
+
+/// Errors related to process events.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    /// Unable to open the netlink socket
+    #[error("Unable to open process connector socket")]
+    Open(#[source] io::Error),
+
+    /// Unable to subscribe to the process events
+    #[error("Unable to subscribe to process events")]
+    Subscribe(#[source] io::Error),
+
+    /// Unable to receive process events
+    #[error("Unable to receive process events")]
+    Receive(#[source] io::Error),
+}
+
+/// Listens for processes executing a new program.
+pub struct ExecListener {
+    socket: OwnedFd,
+}
+
+impl ExecListener {
+    /// Subscribes to the process events. Requires `CAP_NET_ADMIN`.
+    ///
+    /// `timeout` bounds how long [`ExecListener::next_exec`] blocks for, so that the caller can
+    /// stop listening.
+    pub fn new(timeout: Duration) -> Result<Self, Error> {
+        // SAFETY: the returned descriptor is checked before being owned.
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::NETLINK_CONNECTOR,
+            )
+        };
+        if fd < 0 {
+            return Err(Error::Open(io::Error::last_os_error()));
+        }
+        // SAFETY: `fd` is a valid descriptor owned by nothing else.
+        let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        // SAFETY: an all-zero `sockaddr_nl` is valid.
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        addr.nl_groups = CN_IDX_PROC;
+        // SAFETY: `addr` is a valid `sockaddr_nl` of the given length.
+        let result = unsafe {
+            libc::bind(
+                socket.as_raw_fd(),
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(Error::Open(io::Error::last_os_error()));
+        }
+
+        let timeout = libc::timeval {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+        };
+        // SAFETY: `timeout` is a valid `timeval` of the given length.
+        let result = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &timeout as *const libc::timeval as *const libc::c_void,
+                mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(Error::Open(io::Error::last_os_error()));
+        }
+
+        let listener = Self { socket };
+        listener.subscribe()?;
+        Ok(listener)
+    }
+
+    fn subscribe(&self) -> Result<(), Error> {
+        let payload = PROC_CN_MCAST_LISTEN.to_ne_bytes();
+        let len = NLMSG_HEADER_LEN + CN_MSG_HEADER_LEN + payload.len();
+
+        let mut message = Vec::with_capacity(len);
+        // struct nlmsghdr
+        message.extend_from_slice(&(len as u32).to_ne_bytes());
+        message.extend_from_slice(&(libc::NLMSG_DONE as u16).to_ne_bytes());
+        message.extend_from_slice(&0u16.to_ne_bytes());
+        message.extend_from_slice(&0u32.to_ne_bytes());
+        message.extend_from_slice(&std::process::id().to_ne_bytes());
+        // struct cn_msg
+        message.extend_from_slice(&CN_IDX_PROC.to_ne_bytes());
+        message.extend_from_slice(&CN_VAL_PROC.to_ne_bytes());
+        message.extend_from_slice(&0u32.to_ne_bytes());
+        message.extend_from_slice(&0u32.to_ne_bytes());
+        message.extend_from_slice(&(payload.len() as u16).to_ne_bytes());
+        message.extend_from_slice(&0u16.to_ne_bytes());
+        message.extend_from_slice(&payload);
+
+        // SAFETY: `message` is valid for reads of its length.
+        let sent = unsafe {
+            libc::send(
+                self.socket.as_raw_fd(),
+                message.as_ptr() as *const libc::c_void,
+                message.len(),
+                0,
+            )
+        };
+        if sent < 0 {
+            return Err(Error::Subscribe(io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+
+    /// Waits for the next process to execute a new program, returning its PID. Returns `None`
+    /// when the timeout expires first.
+    pub fn next_exec(&mut self) -> Result<Option<u32>, Error> {
+        let mut buffer = [0u8; 1024];
+        loop {
+            // SAFETY: `buffer` is valid for writes of its length.
+            let received = unsafe {
+                libc::recv(
+                    self.socket.as_raw_fd(),
+                    buffer.as_mut_ptr() as *mut libc::c_void,
+                    buffer.len(),
+                    0,
+                )
+            };
+            if received < 0 {
+                let error = io::Error::last_os_error();
+                return match error.kind() {
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => Ok(None),
+                    io::ErrorKind::Interrupted => continue,
+                    _ => Err(Error::Receive(error)),
+                };
+            }
+            if let Some(pid) = parse_exec_event(&buffer[..received as usize]) {
+                return Ok(Some(pid));
+            }
+        }
+    }
+}
+
+/// Returns the PID of the process if the message is an exec event.
+fn parse_exec_event(message: &[u8]) -> Option<u32> {
+    let event = message.get(NLMSG_HEADER_LEN + CN_MSG_HEADER_LEN..)?;
+    let what = u32::from_ne_bytes(event.get(0..4)?.try_into().ok()?);
+    if what != PROC_EVENT_EXEC {
+        return None;
+    }
+    // struct exec_proc_event holds the thread ID followed by the process ID.
+    let tgid = event.get(PROC_EVENT_DATA_OFFSET + 4..PROC_EVENT_DATA_OFFSET + 8)?;
+    Some(u32::from_ne_bytes(tgid.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(what: u32, pid: u32, tgid: u32) -> Vec<u8> {
+        let mut message = vec![0u8; NLMSG_HEADER_LEN + CN_MSG_HEADER_LEN];
+        message.extend_from_slice(&what.to_ne_bytes());
+        message.extend_from_slice(&0u32.to_ne_bytes());
+        message.extend_from_slice(&0u64.to_ne_bytes());
+        message.extend_from_slice(&pid.to_ne_bytes());
+        message.extend_from_slice(&tgid.to_ne_bytes());
+        message
+    }
+
+    #[test]
+    fn parses_exec_events() {
+        assert_eq!(parse_exec_event(&event(PROC_EVENT_EXEC, 43, 42)), Some(42));
+    }
+
+    #[test]
+    fn ignores_other_events() {
+        // PROC_EVENT_FORK
+        assert_eq!(parse_exec_event(&event(1, 43, 42)), None);
+        assert_eq!(parse_exec_event(&[0u8; 8]), None);
+    }
+}
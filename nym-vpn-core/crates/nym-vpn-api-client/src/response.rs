@@ -350,6 +350,8 @@ pub struct Location {
     pub two_letter_iso_country_code: String,
     pub latitude: f64,
     pub longitude: f64,
+    #[serde(default)]
+    pub city: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,7 +9,7 @@ use std::{
 };
 
 use clap::{Args, Parser, Subcommand};
-use ipnetwork::{Ipv4Network, Ipv6Network};
+use ipnetwork::{IpNetwork, Ipv4Network, Ipv6Network};
 use nym_vpn_lib::nym_config::defaults::mixnet_vpn::{
     NYM_TUN_DEVICE_ADDRESS_V4, NYM_TUN_DEVICE_ADDRESS_V6, NYM_TUN_DEVICE_NETMASK_V4,
     NYM_TUN_DEVICE_NETMASK_V6,
@@ -83,6 +83,18 @@ pub(crate) struct RunArgs {
     #[arg(long)]
     pub(crate) disable_routing: bool,
 
+    /// Allow access to local networks while connected.
+    #[arg(long)]
+    pub(crate) allow_lan: bool,
+
+    /// Route the given network outside of the tunnel. Can be repeated.
+    #[arg(long)]
+    pub(crate) exclude_route: Vec<IpNetwork>,
+
+    /// Only route the given network through the tunnel. Can be repeated.
+    #[arg(long)]
+    pub(crate) include_route: Vec<IpNetwork>,
+
     /// Disable the Poisson process rate limiting of outbound traffic.
     #[arg(long, hide = true)]
     pub(crate) disable_poisson_rate: bool,
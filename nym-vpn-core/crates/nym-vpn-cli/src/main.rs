@@ -14,12 +14,13 @@ use tokio_util::sync::CancellationToken;
 
 use nym_vpn_api_client::types::GatewayMinPerformance;
 use nym_vpn_lib::{
-    gateway_directory::{Config as GatewayConfig, EntryPoint, ExitPoint},
+    gateway_directory::{Config as GatewayConfig, EntryPoint, ExitPoint, GatewayPreferences},
     nym_config::defaults::{setup_env, var_names},
     tunnel_state_machine::{
-        DnsOptions, GatewayPerformanceOptions, MixnetTunnelOptions, NymConfig, TunnelCommand,
-        TunnelEvent, TunnelSettings, TunnelStateMachine, TunnelType, WireguardMultihopMode,
-        WireguardTunnelOptions,
+        ConnectTimeouts, DnsBlockingCounters, DnsBlockingOptions, DnsOptions,
+        GatewayPerformanceOptions, GatewayQuarantine, MixnetTunnelOptions, NymConfig, RetryPolicy,
+        SplitRoutes, TunnelCommand, TunnelEvent, TunnelSettings, TunnelStateMachine, TunnelType,
+        WireguardMultihopMode, WireguardTunnelOptions,
     },
     IpPair, MixnetClientConfig, NodeIdentity, Recipient,
 };
@@ -231,8 +232,20 @@ async fn run_vpn(args: commands::RunArgs, data_path: Option<PathBuf>) -> anyhow:
         wireguard_tunnel_options,
         entry_point: Box::new(entry_point),
         exit_point: Box::new(exit_point),
+        gateway_preferences: GatewayPreferences::default(),
+        sticky_gateways: None,
+        retry_policy: RetryPolicy::default(),
+        connect_timeouts: ConnectTimeouts::default(),
         dns,
+        dns_blocking: DnsBlockingOptions::default(),
+        dns_domain_rules: Vec::new(),
         user_agent: None,
+        lockdown_mode: false,
+        allow_lan: args.allow_lan,
+        split_routes: SplitRoutes {
+            excluded: args.exclude_route.clone(),
+            included: args.include_route.clone(),
+        },
     };
 
     let state_machine_handle = TunnelStateMachine::spawn(
@@ -240,6 +253,8 @@ async fn run_vpn(args: commands::RunArgs, data_path: Option<PathBuf>) -> anyhow:
         event_tx,
         nym_config,
         tunnel_settings,
+        GatewayQuarantine::default(),
+        DnsBlockingCounters::default(),
         shutdown_token.child_token(),
     )
     .await
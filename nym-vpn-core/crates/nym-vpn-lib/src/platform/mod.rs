@@ -24,7 +24,7 @@ use tokio::{
 };
 use tokio_util::sync::CancellationToken;
 
-use nym_gateway_directory::Config as GatewayDirectoryConfig;
+use nym_gateway_directory::{Config as GatewayDirectoryConfig, GatewayPreferences};
 
 use self::error::VpnError;
 use crate::platform::account::start_account_controller_handle;
@@ -35,9 +35,10 @@ use crate::tunnel_provider::ios::OSTunProvider;
 use crate::{
     gateway_directory::GatewayClient,
     tunnel_state_machine::{
-        BandwidthEvent, ConnectionEvent, DnsOptions, GatewayPerformanceOptions,
-        MixnetTunnelOptions, NymConfig, TunnelCommand, TunnelEvent, TunnelSettings, TunnelState,
-        TunnelStateMachine, TunnelType, WireguardTunnelOptions,
+        BandwidthEvent, ConnectTimeouts, ConnectionEvent, DnsBlockingOptions, DnsOptions,
+        GatewayPerformanceOptions, GatewayQuarantine, MixnetTunnelOptions, NymConfig, RetryPolicy,
+        SplitRoutes, TunnelCommand, TunnelEvent, TunnelSettings, TunnelState, TunnelStateMachine,
+        TunnelType, WireguardTunnelOptions,
     },
     uniffi_custom_impls::{
         AccountLinks, AccountStateSummary, BandwidthStatus, ConnectionStatus, EntryPoint,
@@ -333,6 +334,7 @@ async fn get_gateway_countries(
         api_url,
         nym_vpn_api_url,
         min_gateway_performance,
+        cache_dir: None,
     };
     GatewayClient::new(directory_config, user_agent.into())?
         .lookup_countries(gw_type.into())
@@ -439,8 +441,17 @@ async fn start_state_machine(
         mixnet_client_config: None,
         entry_point: Box::new(entry_point),
         exit_point: Box::new(exit_point),
+        gateway_preferences: GatewayPreferences::default(),
+        sticky_gateways: None,
+        retry_policy: RetryPolicy::default(),
+        connect_timeouts: ConnectTimeouts::default(),
         dns: DnsOptions::default(),
+        dns_blocking: DnsBlockingOptions::default(),
+        dns_domain_rules: Vec::new(),
         user_agent: Some(config.user_agent.into()),
+        lockdown_mode: false,
+        allow_lan: false,
+        split_routes: SplitRoutes::default(),
     };
 
     let (command_sender, command_receiver) = mpsc::unbounded_channel();
@@ -461,6 +472,9 @@ async fn start_state_machine(
         event_sender,
         nym_config,
         tunnel_settings,
+        GatewayQuarantine::default(),
+        #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+        crate::tunnel_state_machine::DnsBlockingCounters::default(),
         #[cfg(any(target_os = "ios", target_os = "android"))]
         config.tun_provider,
         shutdown_token.child_token(),
@@ -484,6 +498,7 @@ impl From<&TunnelState> for TunStatus {
             TunnelState::Disconnecting { .. } => Self::Disconnecting,
             TunnelState::Disconnected => Self::Down,
             TunnelState::Error(_) => Self::Down,
+            TunnelState::Offline => Self::Down,
         }
     }
 }
@@ -0,0 +1,101 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Default interval between reloads of the blocklists.
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// DNS-based blocking of ads, trackers or malware domains.
+///
+/// Blocked names are answered by the local DNS forwarder, so the queries never leave the device.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DnsBlockingOptions {
+    /// Paths of the blocklists, in hosts-file or domain-list format.
+    /// Blocking is disabled when empty.
+    pub blocklists: Vec<PathBuf>,
+
+    /// Answer given to queries for blocked names.
+    pub blocked_response: BlockedResponse,
+
+    /// Interval between reloads of the blocklists from disk.
+    pub refresh_interval: Duration,
+}
+
+impl DnsBlockingOptions {
+    pub fn is_enabled(&self) -> bool {
+        !self.blocklists.is_empty()
+    }
+}
+
+impl Default for DnsBlockingOptions {
+    fn default() -> Self {
+        Self {
+            blocklists: Vec::new(),
+            blocked_response: BlockedResponse::default(),
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockedResponse {
+    /// Answer that the name does not exist.
+    #[default]
+    NxDomain,
+
+    /// Answer with the unspecified address, 0.0.0.0 or ::.
+    UnspecifiedAddress,
+}
+
+/// Number of DNS queries handled by the local forwarder since it was started.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct DnsBlockingStats {
+    /// Queries received by the forwarder.
+    pub queries: u64,
+
+    /// Queries for blocked names.
+    pub blocked: u64,
+}
+
+/// DNS blocking counters, shared between the DNS forwarder and the owner of the tunnel state
+/// machine, which can inspect them.
+#[derive(Debug, Clone, Default)]
+pub struct DnsBlockingCounters {
+    queries: Arc<AtomicU64>,
+    blocked: Arc<AtomicU64>,
+}
+
+impl DnsBlockingCounters {
+    /// Returns the current value of the counters.
+    pub fn stats(&self) -> DnsBlockingStats {
+        DnsBlockingStats {
+            queries: self.queries.load(Ordering::Relaxed),
+            blocked: self.blocked.load(Ordering::Relaxed),
+        }
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    pub(super) fn reset(&self) {
+        self.queries.store(0, Ordering::Relaxed);
+        self.blocked.store(0, Ordering::Relaxed);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    pub(super) fn add_query(&self, is_blocked: bool) {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        if is_blocked {
+            self.blocked.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
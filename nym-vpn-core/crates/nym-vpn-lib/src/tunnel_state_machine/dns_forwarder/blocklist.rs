@@ -0,0 +1,153 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{collections::HashSet, net::IpAddr, path::Path};
+
+/// Names blocked by the DNS forwarder, along with all their subdomains.
+#[derive(Debug, Default)]
+pub struct Blocklist {
+    domains: HashSet<String>,
+}
+
+impl Blocklist {
+    /// Loads the blocklists, skipping the ones that cannot be read.
+    pub async fn load(paths: &[impl AsRef<Path>]) -> Self {
+        let mut blocklist = Self::default();
+
+        for path in paths {
+            let path = path.as_ref();
+            match tokio::fs::read_to_string(path).await {
+                Ok(contents) => blocklist.extend_from_str(&contents),
+                Err(e) => tracing::warn!("Failed to read blocklist {}: {}", path.display(), e),
+            }
+        }
+        tracing::info!("Loaded {} blocked domains", blocklist.domains.len());
+
+        blocklist
+    }
+
+    /// Adds the names of a blocklist in hosts-file format, e.g. `0.0.0.0 ads.example.com`, or
+    /// domain-list format, with one name per line.
+    pub fn extend_from_str(&mut self, contents: &str) {
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut tokens = line.split_whitespace();
+            let Some(first) = tokens.next() else {
+                continue;
+            };
+
+            if first.parse::<IpAddr>().is_ok() {
+                for name in tokens {
+                    self.insert(name);
+                }
+            } else {
+                self.insert(first);
+            }
+        }
+    }
+
+    /// Returns true if the name, or any of its parent domains, is blocked.
+    pub fn contains(&self, name: &str) -> bool {
+        if self.domains.is_empty() {
+            return false;
+        }
+
+        let name = name.trim_end_matches('.').to_ascii_lowercase();
+        let mut domain = name.as_str();
+        loop {
+            if self.domains.contains(domain) {
+                return true;
+            }
+            match domain.split_once('.') {
+                Some((_, parent)) => domain = parent,
+                None => return false,
+            }
+        }
+    }
+
+    fn insert(&mut self, name: &str) {
+        let name = name.trim_start_matches("*.").trim_end_matches('.');
+
+        // Hosts files usually map local names such as localhost, which must keep resolving.
+        if !name.contains('.') || name.parse::<IpAddr>().is_ok() {
+            return;
+        }
+        self.domains.insert(name.to_ascii_lowercase());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocklist(contents: &str) -> Blocklist {
+        let mut blocklist = Blocklist::default();
+        blocklist.extend_from_str(contents);
+        blocklist
+    }
+
+    #[test]
+    fn parses_hosts_format() {
+        let blocklist = blocklist(
+            "127.0.0.1 localhost\n\
+             ::1 localhost ip6-localhost\n\
+             0.0.0.0 ads.example.com\n\
+             0.0.0.0\ttracker.example.com  metrics.example.net\n\
+             0.0.0.0 0.0.0.0\n",
+        );
+
+        assert!(blocklist.contains("ads.example.com"));
+        assert!(blocklist.contains("tracker.example.com"));
+        assert!(blocklist.contains("metrics.example.net"));
+        assert!(!blocklist.contains("localhost"));
+        assert!(!blocklist.contains("ip6-localhost"));
+        assert!(!blocklist.contains("example.com"));
+        assert_eq!(blocklist.domains.len(), 3);
+    }
+
+    #[test]
+    fn parses_domain_lists() {
+        let blocklist = blocklist("ads.example.com\n*.tracker.example.net\nAds.Example.ORG.\n\n");
+
+        assert!(blocklist.contains("ads.example.com"));
+        assert!(blocklist.contains("tracker.example.net"));
+        assert!(blocklist.contains("ads.example.org"));
+        assert_eq!(blocklist.domains.len(), 3);
+    }
+
+    #[test]
+    fn skips_comments() {
+        let blocklist = blocklist(
+            "# Title: example blocklist\n\
+             #0.0.0.0 commented.example.com\n\
+             0.0.0.0 ads.example.com # trailing comment\n\
+             tracker.example.com#inline\n",
+        );
+
+        assert!(blocklist.contains("ads.example.com"));
+        assert!(blocklist.contains("tracker.example.com"));
+        assert!(!blocklist.contains("commented.example.com"));
+        assert_eq!(blocklist.domains.len(), 2);
+    }
+
+    #[test]
+    fn matches_subdomains() {
+        let blocklist = blocklist("ads.example.com");
+
+        assert!(blocklist.contains("ads.example.com"));
+        assert!(blocklist.contains("ads.example.com."));
+        assert!(blocklist.contains("ADS.Example.com"));
+        assert!(blocklist.contains("eu.cdn.ads.example.com."));
+        assert!(!blocklist.contains("example.com"));
+        assert!(!blocklist.contains("badads.example.com"));
+        assert!(!blocklist.contains("ads.example.com.evil.net"));
+    }
+
+    #[test]
+    fn empty_blocklist_contains_nothing() {
+        let blocklist = blocklist("# nothing here\n\n");
+
+        assert!(!blocklist.contains("example.com"));
+        assert!(!blocklist.contains(""));
+    }
+}
@@ -0,0 +1,724 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Local DNS resolver forwarding the queries to the upstream resolver.
+//!
+//! The system resolver is pointed at the forwarder while connected. The forwarder sockets follow
+//! the routing table, so the queries are sent through the tunnel. When using DNS-over-HTTPS or
+//! DNS-over-TLS, the exit gateway can neither see nor tamper with them. Queries for names on the
+//! blocklists are answered locally, and queries for the domains of the DNS domain rules are sent
+//! to the resolvers of the rule, which are routed outside of the tunnel.
+
+mod blocklist;
+
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use hickory_resolver::{
+    config::{NameServerConfigGroup, ResolverConfig, ResolverOpts},
+    error::ResolveErrorKind,
+    proto::{
+        op::{Edns, Message, MessageType, OpCode, Query, ResponseCode},
+        rr::{
+            rdata::{A, AAAA},
+            RData, Record, RecordType,
+        },
+    },
+    TokioAsyncResolver,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream, UdpSocket},
+    task::JoinHandle,
+};
+use tokio_util::sync::CancellationToken;
+
+use self::blocklist::Blocklist;
+use super::{
+    BlockedResponse, DnsBlockingCounters, DnsBlockingOptions, DnsDomainRule, DnsOptions,
+    EncryptedDnsProtocol,
+};
+
+/// Addresses the forwarder listens on, in order of preference.
+/// The system resolver configuration only takes addresses, hence the standard port. The alternate
+/// loopback addresses are used when another resolver, such as dnsmasq, holds the port on
+/// 127.0.0.1.
+pub(super) const LISTEN_IPS: [Ipv4Addr; 3] = [
+    Ipv4Addr::LOCALHOST,
+    Ipv4Addr::new(127, 0, 77, 1),
+    Ipv4Addr::new(127, 0, 77, 2),
+];
+
+/// Port of plain DNS upstream resolvers, and of the forwarder.
+const DNS_PORT: u16 = 53;
+
+/// Port of DNS-over-HTTPS upstream resolvers.
+const DOH_PORT: u16 = 443;
+
+/// Port of DNS-over-TLS upstream resolvers.
+const DOT_PORT: u16 = 853;
+
+/// Max size of the responses sent over UDP, advertised to clients using EDNS.
+const MAX_UDP_PAYLOAD: u16 = 1232;
+
+/// Max size of the requests received over UDP.
+const MAX_UDP_REQUEST_LEN: usize = 4096;
+
+/// Time after which idle TCP connections are closed.
+const TCP_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time to live of the answers to blocked names.
+const BLOCKED_TTL: u32 = 60;
+
+/// Min interval between reloads of the blocklists.
+const MIN_BLOCKLIST_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Handle to the DNS forwarder running in the background.
+pub struct DnsForwarderHandle {
+    listen_addr: SocketAddr,
+    cancel_token: CancellationToken,
+    join_handle: JoinHandle<()>,
+}
+
+impl DnsForwarderHandle {
+    /// Starts listening for DNS requests and forwarding them to the upstream resolver.
+    pub async fn start(
+        dns: &DnsOptions,
+        dns_blocking: &DnsBlockingOptions,
+        dns_domain_rules: &[DnsDomainRule],
+        counters: DnsBlockingCounters,
+    ) -> Result<Self> {
+        let listen_addrs = LISTEN_IPS
+            .iter()
+            .map(|ip| SocketAddr::new(IpAddr::V4(*ip), DNS_PORT))
+            .collect::<Vec<_>>();
+        let (udp_socket, tcp_listener, listen_addr) = Self::bind(&listen_addrs).await?;
+
+        let forwarder = DnsForwarder::new(dns, dns_blocking, dns_domain_rules, counters).await;
+
+        Ok(Self::spawn(
+            forwarder,
+            udp_socket,
+            tcp_listener,
+            listen_addr,
+            dns_blocking.refresh_interval,
+        ))
+    }
+
+    /// Binds the first of the addresses that is available for both UDP and TCP.
+    async fn bind(listen_addrs: &[SocketAddr]) -> Result<(UdpSocket, TcpListener, SocketAddr)> {
+        let mut first_error = None;
+
+        for addr in listen_addrs {
+            let result = async {
+                let udp_socket = UdpSocket::bind(addr).await?;
+                // Use the same port for TCP when the system picked it.
+                let listen_addr = udp_socket.local_addr()?;
+                let tcp_listener = TcpListener::bind(listen_addr).await?;
+                Ok::<_, io::Error>((udp_socket, tcp_listener, listen_addr))
+            }
+            .await;
+
+            match result {
+                Ok(sockets) => return Ok(sockets),
+                Err(e) => {
+                    tracing::warn!("Failed to listen for dns requests on {}: {}", addr, e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        Err(Error::Bind(
+            listen_addrs.to_vec(),
+            first_error.unwrap_or_else(|| io::ErrorKind::AddrNotAvailable.into()),
+        ))
+    }
+
+    fn spawn(
+        forwarder: DnsForwarder,
+        udp_socket: UdpSocket,
+        tcp_listener: TcpListener,
+        listen_addr: SocketAddr,
+        blocklist_refresh_interval: Duration,
+    ) -> Self {
+        let cancel_token = CancellationToken::new();
+        let join_handle = tokio::spawn(Arc::new(forwarder).run(
+            udp_socket,
+            tcp_listener,
+            blocklist_refresh_interval,
+            cancel_token.clone(),
+        ));
+
+        tracing::info!("Started dns forwarder on {}", listen_addr);
+
+        Self {
+            listen_addr,
+            cancel_token,
+            join_handle,
+        }
+    }
+
+    /// Returns the address the system resolver should be pointed at.
+    pub fn listen_ip(&self) -> IpAddr {
+        self.listen_addr.ip()
+    }
+
+    /// Stops the forwarder and waits until the listening sockets are closed.
+    pub async fn stop(self) {
+        self.cancel_token.cancel();
+
+        if let Err(e) = self.join_handle.await {
+            tracing::error!("Failed to join on dns forwarder: {}", e);
+        }
+    }
+}
+
+struct DnsForwarder {
+    resolver: TokioAsyncResolver,
+    /// Resolvers of the DNS domain rules, keyed by domain.
+    domain_resolvers: Vec<(String, TokioAsyncResolver)>,
+    blocklist_paths: Vec<PathBuf>,
+    blocklist: RwLock<Blocklist>,
+    blocked_response: BlockedResponse,
+    counters: DnsBlockingCounters,
+}
+
+impl DnsForwarder {
+    async fn new(
+        dns: &DnsOptions,
+        dns_blocking: &DnsBlockingOptions,
+        dns_domain_rules: &[DnsDomainRule],
+        counters: DnsBlockingCounters,
+    ) -> Self {
+        let name_servers = match dns {
+            DnsOptions::Encrypted(upstream) => match upstream.protocol {
+                EncryptedDnsProtocol::Https => NameServerConfigGroup::from_ips_https(
+                    &upstream.addresses,
+                    DOH_PORT,
+                    upstream.tls_name.clone(),
+                    true,
+                ),
+                EncryptedDnsProtocol::Tls => NameServerConfigGroup::from_ips_tls(
+                    &upstream.addresses,
+                    DOT_PORT,
+                    upstream.tls_name.clone(),
+                    true,
+                ),
+            },
+            dns => NameServerConfigGroup::from_ips_clear(dns.ip_addresses(), DNS_PORT, true),
+        };
+        let domain_resolvers = dns_domain_rules
+            .iter()
+            .map(|rule| {
+                let name_servers =
+                    NameServerConfigGroup::from_ips_clear(&rule.servers, DNS_PORT, true);
+                (rule.domain_name(), Self::create_resolver(name_servers))
+            })
+            .collect();
+
+        let blocklist = Blocklist::load(&dns_blocking.blocklists).await;
+        counters.reset();
+
+        Self {
+            resolver: Self::create_resolver(name_servers),
+            domain_resolvers,
+            blocklist_paths: dns_blocking.blocklists.clone(),
+            blocklist: RwLock::new(blocklist),
+            blocked_response: dns_blocking.blocked_response,
+            counters,
+        }
+    }
+
+    fn create_resolver(name_servers: NameServerConfigGroup) -> TokioAsyncResolver {
+        let config = ResolverConfig::from_parts(None, vec![], name_servers);
+
+        let mut opts = ResolverOpts::default();
+        // Local names are resolved by the system before reaching the forwarder.
+        opts.use_hosts_file = false;
+        // Keep the CNAME chain in the answers.
+        opts.preserve_intermediates = true;
+        opts.edns0 = true;
+
+        TokioAsyncResolver::tokio(config, opts)
+    }
+
+    async fn run(
+        self: Arc<Self>,
+        udp_socket: UdpSocket,
+        tcp_listener: TcpListener,
+        blocklist_refresh_interval: Duration,
+        cancel_token: CancellationToken,
+    ) {
+        let udp_socket = Arc::new(udp_socket);
+        let mut buf = vec![0u8; MAX_UDP_REQUEST_LEN];
+
+        let refresh_interval = blocklist_refresh_interval.max(MIN_BLOCKLIST_REFRESH_INTERVAL);
+        let mut refresh_blocklist = tokio::time::interval_at(
+            tokio::time::Instant::now() + refresh_interval,
+            refresh_interval,
+        );
+
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => break,
+                _ = refresh_blocklist.tick(), if !self.blocklist_paths.is_empty() => {
+                    let forwarder = self.clone();
+                    let cancel_token = cancel_token.child_token();
+
+                    tokio::spawn(async move {
+                        if let Some(blocklist) = cancel_token
+                            .run_until_cancelled(Blocklist::load(&forwarder.blocklist_paths))
+                            .await
+                        {
+                            *forwarder
+                                .blocklist
+                                .write()
+                                .unwrap_or_else(|poisoned| poisoned.into_inner()) = blocklist;
+                        }
+                    });
+                }
+                result = udp_socket.recv_from(&mut buf) => match result {
+                    Ok((len, peer)) => {
+                        let forwarder = self.clone();
+                        let udp_socket = udp_socket.clone();
+                        let request = buf[..len].to_vec();
+                        let cancel_token = cancel_token.child_token();
+
+                        tokio::spawn(async move {
+                            let Some(Some(response)) = cancel_token
+                                .run_until_cancelled(forwarder.handle_udp_request(&request))
+                                .await
+                            else {
+                                return;
+                            };
+                            if let Err(e) = udp_socket.send_to(&response, peer).await {
+                                tracing::debug!("Failed to send dns response to {}: {}", peer, e);
+                            }
+                        });
+                    }
+                    // Sending a response to a client that went away may fail the next receive on
+                    // some platforms, so keep going.
+                    Err(e) => tracing::debug!("Failed to receive dns request: {}", e),
+                },
+                result = tcp_listener.accept() => match result {
+                    Ok((stream, peer)) => {
+                        let forwarder = self.clone();
+                        let cancel_token = cancel_token.child_token();
+
+                        tokio::spawn(async move {
+                            if let Some(Err(e)) = cancel_token
+                                .run_until_cancelled(forwarder.handle_tcp_connection(stream))
+                                .await
+                            {
+                                tracing::debug!("Dns connection with {} failed: {}", peer, e);
+                            }
+                        });
+                    }
+                    Err(e) => tracing::warn!("Failed to accept dns connection: {}", e),
+                },
+            }
+        }
+
+        tracing::debug!("Exiting dns forwarder loop");
+    }
+
+    async fn handle_udp_request(&self, request: &[u8]) -> Option<Vec<u8>> {
+        let request = Self::parse_request(request)?;
+        let mut response = self.resolve(&request).await;
+
+        let encoded = Self::encode_response(&response)?;
+        if encoded.len() <= usize::from(request.max_payload()) {
+            return Some(encoded);
+        }
+
+        // Let the client retry over TCP.
+        response.take_answers();
+        response.take_name_servers();
+        response.take_additionals();
+        response.set_truncated(true);
+        Self::encode_response(&response)
+    }
+
+    async fn handle_tcp_connection(&self, mut stream: TcpStream) -> io::Result<()> {
+        loop {
+            let len = match tokio::time::timeout(TCP_IDLE_TIMEOUT, stream.read_u16()).await {
+                Ok(Ok(len)) => len,
+                Ok(Err(e)) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                Ok(Err(e)) => return Err(e),
+                Err(_) => return Ok(()),
+            };
+            let mut request = vec![0u8; usize::from(len)];
+            stream.read_exact(&mut request).await?;
+
+            let Some(request) = Self::parse_request(&request) else {
+                return Ok(());
+            };
+            let response = self.resolve(&request).await;
+            let Some(encoded) = Self::encode_response(&response) else {
+                continue;
+            };
+            let len = u16::try_from(encoded.len())
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "response too large"))?;
+
+            stream.write_u16(len).await?;
+            stream.write_all(&encoded).await?;
+        }
+    }
+
+    /// Resolves the question of the request using the upstream resolver.
+    async fn resolve(&self, request: &Message) -> Message {
+        let mut response = Message::new();
+        response
+            .set_id(request.id())
+            .set_message_type(MessageType::Response)
+            .set_op_code(request.op_code())
+            .set_recursion_desired(request.recursion_desired())
+            .set_recursion_available(true)
+            .add_queries(request.queries().to_vec());
+
+        if request.extensions().is_some() {
+            let mut edns = Edns::new();
+            edns.set_max_payload(MAX_UDP_PAYLOAD);
+            response.set_edns(edns);
+        }
+
+        let query = match (request.op_code(), request.queries()) {
+            (OpCode::Query, [query]) => query,
+            (OpCode::Query, _) => {
+                response.set_response_code(ResponseCode::FormErr);
+                return response;
+            }
+            _ => {
+                response.set_response_code(ResponseCode::NotImp);
+                return response;
+            }
+        };
+
+        let is_blocked = self.is_blocked(query);
+        self.counters.add_query(is_blocked);
+        if is_blocked {
+            tracing::trace!("Blocked {}", query.name());
+            self.add_blocked_answer(&mut response, query);
+            return response;
+        }
+
+        match self
+            .resolver_for(query)
+            .lookup(query.name().clone(), query.query_type())
+            .await
+        {
+            Ok(lookup) => {
+                response.add_answers(lookup.records().to_vec());
+            }
+            Err(e) => {
+                let response_code = match e.kind() {
+                    ResolveErrorKind::NoRecordsFound { response_code, .. } => *response_code,
+                    _ => {
+                        tracing::debug!("Failed to resolve {}: {}", query.name(), e);
+                        ResponseCode::ServFail
+                    }
+                };
+                response.set_response_code(response_code);
+            }
+        }
+
+        response
+    }
+
+    /// Returns the resolver of the most specific domain rule matching the name, or the upstream
+    /// resolver if none does.
+    fn resolver_for(&self, query: &Query) -> &TokioAsyncResolver {
+        let name = query.name().to_ascii();
+        let name = name.trim_end_matches('.').to_ascii_lowercase();
+
+        self.domain_resolvers
+            .iter()
+            .filter(|(domain, _)| {
+                name == *domain
+                    || name
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|prefix| prefix.ends_with('.'))
+            })
+            .max_by_key(|(domain, _)| domain.len())
+            .map(|(_, resolver)| resolver)
+            .unwrap_or(&self.resolver)
+    }
+
+    fn is_blocked(&self, query: &Query) -> bool {
+        self.blocklist
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .contains(&query.name().to_ascii())
+    }
+
+    fn add_blocked_answer(&self, response: &mut Message, query: &Query) {
+        match self.blocked_response {
+            BlockedResponse::NxDomain => {
+                response.set_response_code(ResponseCode::NXDomain);
+            }
+            BlockedResponse::UnspecifiedAddress => {
+                let rdata = match query.query_type() {
+                    RecordType::A => RData::A(A(Ipv4Addr::UNSPECIFIED)),
+                    RecordType::AAAA => RData::AAAA(AAAA(Ipv6Addr::UNSPECIFIED)),
+                    // Answer that the name has no records of other types.
+                    _ => return,
+                };
+                response.add_answer(Record::from_rdata(query.name().clone(), BLOCKED_TTL, rdata));
+            }
+        }
+    }
+
+    fn parse_request(request: &[u8]) -> Option<Message> {
+        Message::from_vec(request)
+            .inspect_err(|e| tracing::debug!("Received malformed dns request: {}", e))
+            .ok()
+            .filter(|request| request.message_type() == MessageType::Query)
+    }
+
+    fn encode_response(response: &Message) -> Option<Vec<u8>> {
+        response
+            .to_vec()
+            .inspect_err(|e| tracing::error!("Failed to encode dns response: {}", e))
+            .ok()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(
+        "failed to listen on any of {:?}, the port may be held by another resolver: {}",
+        _0,
+        _1
+    )]
+    Bind(Vec<SocketAddr>, #[source] io::Error),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use hickory_resolver::proto::rr::Name;
+
+    use super::*;
+
+    const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Stand-in upstream resolver answering the A queries with the same address.
+    struct Upstream {
+        addr: SocketAddr,
+        queries: Arc<AtomicUsize>,
+    }
+
+    impl Upstream {
+        async fn start(answer: Ipv4Addr) -> Self {
+            let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+            let addr = socket.local_addr().unwrap();
+            let queries = Arc::new(AtomicUsize::new(0));
+
+            let counter = queries.clone();
+            tokio::spawn(async move {
+                let mut buf = vec![0u8; MAX_UDP_REQUEST_LEN];
+                while let Ok((len, peer)) = socket.recv_from(&mut buf).await {
+                    let request = Message::from_vec(&buf[..len]).unwrap();
+                    counter.fetch_add(1, Ordering::Relaxed);
+
+                    let mut response = Message::new();
+                    response
+                        .set_id(request.id())
+                        .set_message_type(MessageType::Response)
+                        .set_op_code(OpCode::Query)
+                        .set_recursion_desired(request.recursion_desired())
+                        .set_recursion_available(true)
+                        .add_queries(request.queries().to_vec());
+                    for query in request.queries() {
+                        if query.query_type() == RecordType::A {
+                            let rdata = RData::A(A(answer));
+                            response.add_answer(Record::from_rdata(
+                                query.name().clone(),
+                                300,
+                                rdata,
+                            ));
+                        }
+                    }
+                    let _ = socket.send_to(&response.to_vec().unwrap(), peer).await;
+                }
+            });
+
+            Self { addr, queries }
+        }
+
+        fn resolver(&self) -> TokioAsyncResolver {
+            DnsForwarder::create_resolver(NameServerConfigGroup::from_ips_clear(
+                &[self.addr.ip()],
+                self.addr.port(),
+                true,
+            ))
+        }
+
+        fn queries(&self) -> usize {
+            self.queries.load(Ordering::Relaxed)
+        }
+    }
+
+    fn forwarder(upstream: &Upstream) -> DnsForwarder {
+        DnsForwarder {
+            resolver: upstream.resolver(),
+            domain_resolvers: Vec::new(),
+            blocklist_paths: Vec::new(),
+            blocklist: RwLock::new(Blocklist::default()),
+            blocked_response: BlockedResponse::NxDomain,
+            counters: DnsBlockingCounters::default(),
+        }
+    }
+
+    async fn start(forwarder: DnsForwarder) -> DnsForwarderHandle {
+        let listen_addrs = [SocketAddr::from((Ipv4Addr::LOCALHOST, 0))];
+        let (udp_socket, tcp_listener, listen_addr) =
+            DnsForwarderHandle::bind(&listen_addrs).await.unwrap();
+
+        DnsForwarderHandle::spawn(
+            forwarder,
+            udp_socket,
+            tcp_listener,
+            listen_addr,
+            MIN_BLOCKLIST_REFRESH_INTERVAL,
+        )
+    }
+
+    fn request(name: &str) -> Vec<u8> {
+        let mut request = Message::new();
+        request
+            .set_id(42)
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(true)
+            .add_query(Query::query(Name::from_ascii(name).unwrap(), RecordType::A));
+        request.to_vec().unwrap()
+    }
+
+    async fn query_udp(forwarder: &DnsForwarderHandle, name: &str) -> Message {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        socket
+            .send_to(&request(name), forwarder.listen_addr)
+            .await
+            .unwrap();
+
+        let mut buf = vec![0u8; MAX_UDP_REQUEST_LEN];
+        let len = tokio::time::timeout(QUERY_TIMEOUT, socket.recv(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        Message::from_vec(&buf[..len]).unwrap()
+    }
+
+    async fn query_tcp(forwarder: &DnsForwarderHandle, name: &str) -> Message {
+        let mut stream = TcpStream::connect(forwarder.listen_addr).await.unwrap();
+        let request = request(name);
+        stream.write_u16(request.len() as u16).await.unwrap();
+        stream.write_all(&request).await.unwrap();
+
+        let len = tokio::time::timeout(QUERY_TIMEOUT, stream.read_u16())
+            .await
+            .unwrap()
+            .unwrap();
+        let mut response = vec![0u8; usize::from(len)];
+        stream.read_exact(&mut response).await.unwrap();
+        Message::from_vec(&response).unwrap()
+    }
+
+    fn answers(response: &Message) -> Vec<IpAddr> {
+        response
+            .answers()
+            .iter()
+            .filter_map(|record| record.data()?.ip_addr())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn forwards_queries_to_upstream() {
+        let upstream = Upstream::start(Ipv4Addr::new(10, 0, 0, 1)).await;
+        let forwarder = forwarder(&upstream);
+        let counters = forwarder.counters.clone();
+        let handle = start(forwarder).await;
+
+        let response = query_udp(&handle, "nymtech.net.").await;
+        assert_eq!(response.id(), 42);
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(answers(&response), vec![IpAddr::from([10, 0, 0, 1])]);
+
+        let response = query_tcp(&handle, "nym.com.").await;
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(answers(&response), vec![IpAddr::from([10, 0, 0, 1])]);
+
+        assert_eq!(upstream.queries(), 2);
+        assert_eq!(counters.stats().queries, 2);
+        assert_eq!(counters.stats().blocked, 0);
+
+        handle.stop().await;
+    }
+
+    #[tokio::test]
+    async fn answers_blocked_names_locally() {
+        let upstream = Upstream::start(Ipv4Addr::new(10, 0, 0, 1)).await;
+        let mut forwarder = forwarder(&upstream);
+        forwarder
+            .blocklist
+            .get_mut()
+            .unwrap()
+            .extend_from_str("0.0.0.0 ads.example.com");
+        let counters = forwarder.counters.clone();
+        let handle = start(forwarder).await;
+
+        let response = query_udp(&handle, "tracker.ads.example.com.").await;
+        assert_eq!(response.response_code(), ResponseCode::NXDomain);
+        assert!(response.answers().is_empty());
+
+        assert_eq!(upstream.queries(), 0);
+        assert_eq!(counters.stats().queries, 1);
+        assert_eq!(counters.stats().blocked, 1);
+
+        handle.stop().await;
+    }
+
+    #[tokio::test]
+    async fn sends_domain_rule_queries_to_their_resolvers() {
+        let upstream = Upstream::start(Ipv4Addr::new(10, 0, 0, 1)).await;
+        let rule_upstream = Upstream::start(Ipv4Addr::new(192, 168, 1, 1)).await;
+        let mut forwarder = forwarder(&upstream);
+        forwarder.domain_resolvers = vec![("corp.example".to_owned(), rule_upstream.resolver())];
+        let handle = start(forwarder).await;
+
+        let response = query_udp(&handle, "intranet.corp.example.").await;
+        assert_eq!(answers(&response), vec![IpAddr::from([192, 168, 1, 1])]);
+
+        let response = query_udp(&handle, "othercorp.example.").await;
+        assert_eq!(answers(&response), vec![IpAddr::from([10, 0, 0, 1])]);
+
+        assert_eq!(rule_upstream.queries(), 1);
+        assert_eq!(upstream.queries(), 1);
+
+        handle.stop().await;
+    }
+
+    #[tokio::test]
+    async fn binds_next_address_when_port_is_taken() {
+        let taken = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let taken_addr = taken.local_addr().unwrap();
+
+        let (_, _, listen_addr) =
+            DnsForwarderHandle::bind(&[taken_addr, SocketAddr::from((Ipv4Addr::LOCALHOST, 0))])
+                .await
+                .unwrap();
+        assert_ne!(listen_addr, taken_addr);
+
+        let result = DnsForwarderHandle::bind(&[taken_addr]).await;
+        assert!(matches!(result, Err(Error::Bind(addrs, _)) if addrs == [taken_addr]));
+    }
+}
@@ -2,8 +2,10 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use std::net::IpAddr;
+#[cfg(target_os = "linux")]
+use std::path::{Path, PathBuf};
 
-use nym_dns::{DnsConfig, DnsMonitor};
+use nym_dns::{DnsConfig, DnsMonitor, DomainResolver};
 use tokio::{
     sync::{mpsc, oneshot},
     task::JoinHandle,
@@ -13,6 +15,10 @@ use tokio_util::sync::CancellationToken;
 #[cfg(target_os = "linux")]
 use super::route_handler::RouteHandler;
 
+/// File in the data directory journaling the DNS configuration replaced while connected.
+#[cfg(target_os = "linux")]
+const DNS_JOURNAL_FILE_NAME: &str = "dns_journal.json";
+
 struct DnsHandler {
     inner: DnsMonitor,
 }
@@ -20,20 +26,33 @@ struct DnsHandler {
 impl DnsHandler {
     fn new(
         #[cfg(target_os = "linux")] route_handler: &RouteHandler,
+        #[cfg(target_os = "linux")] journal_path: Option<PathBuf>,
     ) -> Result<Self, nym_dns::Error> {
-        Ok(Self {
-            inner: DnsMonitor::new(
-                #[cfg(target_os = "linux")]
-                tokio::runtime::Handle::current(),
-                #[cfg(target_os = "linux")]
-                route_handler.inner_handle(),
-            )?,
+        // Restoring DNS left over by a previous process blocks
+        tokio::task::block_in_place(|| {
+            Ok(Self {
+                inner: DnsMonitor::new(
+                    #[cfg(target_os = "linux")]
+                    tokio::runtime::Handle::current(),
+                    #[cfg(target_os = "linux")]
+                    route_handler.inner_handle(),
+                    #[cfg(target_os = "linux")]
+                    journal_path,
+                )?,
+            })
         })
     }
 
-    pub fn set(&mut self, interface: &str, servers: &[IpAddr]) -> Result<(), nym_dns::Error> {
+    pub fn set(
+        &mut self,
+        interface: &str,
+        servers: &[IpAddr],
+        domain_resolvers: Vec<DomainResolver>,
+    ) -> Result<(), nym_dns::Error> {
         tokio::task::block_in_place(|| {
-            let dns_config = DnsConfig::default().resolve(servers);
+            let dns_config = DnsConfig::default()
+                .resolve(servers)
+                .with_domain_resolvers(domain_resolvers);
 
             self.inner.set(interface, dns_config)
         })
@@ -52,6 +71,7 @@ enum DnsHandlerCommand {
     Set {
         interface: String,
         servers: Vec<IpAddr>,
+        domain_resolvers: Vec<DomainResolver>,
         reply_tx: oneshot::Sender<Result<(), nym_dns::Error>>,
     },
     Reset {
@@ -70,11 +90,14 @@ pub struct DnsHandlerHandle {
 impl DnsHandlerHandle {
     pub fn spawn(
         #[cfg(target_os = "linux")] route_handler: &RouteHandler,
+        #[cfg(target_os = "linux")] data_path: Option<&Path>,
         shutdown_token: CancellationToken,
     ) -> Result<(Self, JoinHandle<()>)> {
         let mut dns_handler = DnsHandler::new(
             #[cfg(target_os = "linux")]
             route_handler,
+            #[cfg(target_os = "linux")]
+            data_path.map(|data_path| data_path.join(DNS_JOURNAL_FILE_NAME)),
         )?;
 
         let (tx, mut rx) = mpsc::unbounded_channel();
@@ -86,9 +109,10 @@ impl DnsHandlerHandle {
                             DnsHandlerCommand::Set {
                                 interface,
                                 servers,
+                                domain_resolvers,
                                 reply_tx,
                             } => {
-                                _ = reply_tx.send(dns_handler.set(&interface, &servers));
+                                _ = reply_tx.send(dns_handler.set(&interface, &servers, domain_resolvers));
                             }
                             DnsHandlerCommand::Reset { reply_tx } => {
                                 _ = reply_tx.send(dns_handler.reset());
@@ -108,13 +132,19 @@ impl DnsHandlerHandle {
         Ok((Self { tx }, join_handle))
     }
 
-    pub async fn set(&mut self, interface: String, servers: Vec<IpAddr>) -> Result<()> {
+    pub async fn set(
+        &mut self,
+        interface: String,
+        servers: Vec<IpAddr>,
+        domain_resolvers: Vec<DomainResolver>,
+    ) -> Result<()> {
         let (reply_tx, reply_rx) = oneshot::channel();
 
         self.send_and_wait(
             DnsHandlerCommand::Set {
                 interface,
                 servers,
+                domain_resolvers,
                 reply_tx,
             },
             reply_rx,
@@ -1,62 +1,143 @@
 // Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
-#![allow(dead_code)]
-use std::fmt;
+use std::{fmt, net::IpAddr};
 
-use talpid_core::firewall::{Firewall, FirewallArguments, InitialFirewallState};
+use hickory_resolver::config::ResolverConfig;
+use nym_firewall::{Firewall, FirewallArguments, InitialFirewallState};
+use nym_gateway_directory::{Config as GatewayDirectoryConfig, Gateway};
 
-pub use talpid_core::firewall::FirewallPolicy;
+pub use nym_firewall::{Endpoint, FirewallPolicy, TransportProtocol, TunnelInterface};
+
+/// User the systemd-resolved service runs as.
+const SYSTEMD_RESOLVE_USER: &str = "systemd-resolve";
 
 pub struct FirewallHandler {
     inner: Firewall,
 }
 
 impl FirewallHandler {
-    pub fn new() -> Result<Self> {
+    pub fn new(allow_lan: bool) -> Result<Self> {
         Ok(Self {
             inner: Firewall::from_args(FirewallArguments {
-                allow_lan: true,
+                allow_lan,
                 initial_state: InitialFirewallState::None,
-                #[cfg(target_os = "linux")]
                 fwmark: super::route_handler::TUNNEL_FWMARK,
+                exclusion_classid: Some(nym_split_tunnel::NET_CLS_CLASSID),
+                resolver_uid: resolver_uid(),
             })?,
         })
     }
 
-    // todo: hide firewall policy
     pub fn apply_policy(&mut self, policy: FirewallPolicy) -> Result<()> {
-        Ok(self.inner.apply_policy(policy)?)
+        tokio::task::block_in_place(|| Ok(self.inner.apply_policy(policy)?))
     }
 
     pub fn reset_policy(&mut self) -> Result<()> {
-        Ok(self.inner.reset_policy()?)
+        tokio::task::block_in_place(|| Ok(self.inner.reset_policy()?))
+    }
+}
+
+/// Returns the user ID of the system resolver service, which sends the DNS requests of the
+/// daemon when the hosts are resolved through the system resolver.
+fn resolver_uid() -> Option<u32> {
+    match nix::unistd::User::from_name(SYSTEMD_RESOLVE_USER) {
+        Ok(user) => user.map(|user| user.uid.as_raw()),
+        Err(e) => {
+            tracing::warn!("Failed to look up user {}: {}", SYSTEMD_RESOLVE_USER, e);
+            None
+        }
+    }
+}
+
+/// Returns the endpoints the mixnet client connects to on the gateway at `ip`.
+pub fn gateway_client_endpoints(gateway: &Gateway, ip: IpAddr) -> Vec<Endpoint> {
+    [gateway.clients_ws_port, gateway.clients_wss_port]
+        .into_iter()
+        .flatten()
+        .map(|port| Endpoint::new((ip, port), TransportProtocol::Tcp))
+        .collect()
+}
+
+/// Resolves the endpoints of the directory and API services used to look up the gateways.
+pub async fn resolve_api_endpoints(gateway_config: &GatewayDirectoryConfig) -> Vec<Endpoint> {
+    let mut endpoints = Vec::new();
+    for url in std::iter::once(&gateway_config.api_url).chain(&gateway_config.nym_vpn_api_url) {
+        let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+            continue;
+        };
+        match tokio::net::lookup_host((host, port)).await {
+            Ok(addrs) => {
+                for addr in addrs {
+                    let endpoint = Endpoint::new(addr, TransportProtocol::Tcp);
+                    if !endpoints.contains(&endpoint) {
+                        endpoints.push(endpoint);
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Failed to resolve {}: {}", host, e),
+        }
     }
+    endpoints
+}
+
+/// Returns the servers the gateway and API hosts are resolved with before the tunnel is up: the
+/// upstream servers of the system resolver, and the default servers of the resolver used for
+/// gateway hostnames.
+pub async fn lookup_dns_servers() -> Vec<IpAddr> {
+    let mut servers =
+        match tokio::task::spawn_blocking(nym_dns::diagnostics::inspect_system_dns).await {
+            Ok(Ok(config)) => config
+                .interfaces
+                .into_iter()
+                .flat_map(|entry| entry.servers)
+                .collect(),
+            Ok(Err(e)) => {
+                tracing::warn!("Failed to read system DNS servers: {}", e);
+                Vec::new()
+            }
+            Err(e) => {
+                tracing::warn!("Failed to read system DNS servers: {}", e);
+                Vec::new()
+            }
+        };
+    servers.extend(
+        ResolverConfig::default()
+            .name_servers()
+            .iter()
+            .map(|name_server| name_server.socket_addr.ip()),
+    );
+
+    // Requests to a local resolver are always allowed.
+    let mut lookup_servers = Vec::new();
+    for server in servers {
+        if !server.is_loopback() && !lookup_servers.contains(&server) {
+            lookup_servers.push(server);
+        }
+    }
+    lookup_servers
 }
 
 #[derive(Debug)]
 pub struct Error {
-    inner: talpid_core::firewall::Error,
+    inner: nym_firewall::Error,
 }
 
-unsafe impl Send for Error {}
-unsafe impl Sync for Error {}
-
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         Some(&self.inner)
     }
 }
 
-impl From<talpid_core::firewall::Error> for Error {
-    fn from(value: talpid_core::firewall::Error) -> Self {
+impl From<nym_firewall::Error> for Error {
+    fn from(value: nym_firewall::Error) -> Self {
         Self { inner: value }
     }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Firewall error")
+        write!(f, "firewall error: {}", self.inner)
     }
 }
 
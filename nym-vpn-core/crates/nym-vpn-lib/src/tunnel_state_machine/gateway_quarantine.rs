@@ -0,0 +1,170 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use nym_gateway_directory::NodeIdentity;
+use time::OffsetDateTime;
+
+use super::tunnel::SelectedGateways;
+
+/// Time during which a gateway that failed to connect is avoided.
+const QUARANTINE_DURATION: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct QuarantinedGateway {
+    /// Gateway identity.
+    pub identity: NodeIdentity,
+
+    /// Why the connection to the gateway failed.
+    pub reason: String,
+
+    /// When the gateway is released from the quarantine.
+    pub quarantined_until: OffsetDateTime,
+}
+
+/// Gateways that recently failed to connect.
+///
+/// Quarantined gateways are avoided when gateways are selected randomly or by location, so that a
+/// reconnect does not keep retrying the same broken gateway. The quarantine is shared between the
+/// tunnel state machine and its owner, which can inspect it.
+#[derive(Debug, Clone, Default)]
+pub struct GatewayQuarantine {
+    gateways: Arc<Mutex<Vec<QuarantinedGateway>>>,
+}
+
+impl GatewayQuarantine {
+    /// Quarantine the gateway, or extend its quarantine if it is already quarantined.
+    pub fn add(&self, identity: NodeIdentity, reason: String) {
+        self.add_until(
+            identity,
+            reason,
+            OffsetDateTime::now_utc() + QUARANTINE_DURATION,
+        );
+    }
+
+    fn add_until(&self, identity: NodeIdentity, reason: String, quarantined_until: OffsetDateTime) {
+        tracing::info!(
+            "Quarantining gateway {} until {}: {}",
+            identity,
+            quarantined_until,
+            reason
+        );
+
+        let mut gateways = self.lock();
+        gateways.retain(|gateway| gateway.identity != identity);
+        gateways.push(QuarantinedGateway {
+            identity,
+            reason,
+            quarantined_until,
+        });
+    }
+
+    /// Release the gateways from the quarantine.
+    pub fn release(&self, selected_gateways: &SelectedGateways) {
+        self.lock().retain(|gateway| {
+            let is_selected = gateway.identity == *selected_gateways.entry.identity()
+                || gateway.identity == *selected_gateways.exit.identity();
+            if is_selected {
+                tracing::info!("Released gateway {} from quarantine", gateway.identity);
+            }
+            !is_selected
+        });
+    }
+
+    /// Returns true if any of the gateways is quarantined.
+    pub fn contains_any(&self, selected_gateways: &SelectedGateways) -> bool {
+        self.lock().iter().any(|gateway| {
+            gateway.identity == *selected_gateways.entry.identity()
+                || gateway.identity == *selected_gateways.exit.identity()
+        })
+    }
+
+    /// Returns the identities of all quarantined gateways.
+    pub fn identities(&self) -> Vec<NodeIdentity> {
+        self.lock().iter().map(|gateway| gateway.identity).collect()
+    }
+
+    /// Returns all quarantined gateways, sorted by the time of their release.
+    pub fn quarantined_gateways(&self) -> Vec<QuarantinedGateway> {
+        let mut gateways = self.lock().clone();
+        gateways.sort_by_key(|gateway| gateway.quarantined_until);
+        gateways
+    }
+
+    /// Locks the gateways, dropping the ones whose quarantine has expired.
+    fn lock(&self) -> MutexGuard<'_, Vec<QuarantinedGateway>> {
+        let mut gateways = self
+            .gateways
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = OffsetDateTime::now_utc();
+        gateways.retain(|gateway| gateway.quarantined_until > now);
+        gateways
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tunnel_state_machine::test_utils::{
+        identity, selected_gateways, ENTRY_IDENTITY, EXIT_IDENTITY,
+    };
+
+    #[test]
+    fn quarantined_gateway_is_avoided() {
+        let quarantine = GatewayQuarantine::default();
+        quarantine.add(identity(EXIT_IDENTITY), "handshake timed out".to_owned());
+
+        assert!(quarantine.contains_any(&selected_gateways()));
+        assert_eq!(quarantine.identities(), vec![identity(EXIT_IDENTITY)]);
+    }
+
+    #[test]
+    fn expired_quarantine_readmits_gateway() {
+        let quarantine = GatewayQuarantine::default();
+        let now = OffsetDateTime::now_utc();
+        quarantine.add_until(
+            identity(ENTRY_IDENTITY),
+            "handshake timed out".to_owned(),
+            now - Duration::from_secs(1),
+        );
+        quarantine.add_until(
+            identity(EXIT_IDENTITY),
+            "handshake timed out".to_owned(),
+            now + Duration::from_secs(60),
+        );
+
+        assert_eq!(quarantine.identities(), vec![identity(EXIT_IDENTITY)]);
+        assert_eq!(quarantine.quarantined_gateways().len(), 1);
+    }
+
+    #[test]
+    fn release_readmits_selected_gateways() {
+        let quarantine = GatewayQuarantine::default();
+        quarantine.add(identity(ENTRY_IDENTITY), "entry failed".to_owned());
+        quarantine.add(identity(EXIT_IDENTITY), "exit failed".to_owned());
+
+        quarantine.release(&selected_gateways());
+
+        assert!(!quarantine.contains_any(&selected_gateways()));
+        assert!(quarantine.quarantined_gateways().is_empty());
+    }
+
+    #[test]
+    fn quarantining_again_extends_quarantine() {
+        let quarantine = GatewayQuarantine::default();
+        let soon = OffsetDateTime::now_utc() + Duration::from_secs(1);
+        quarantine.add_until(identity(ENTRY_IDENTITY), "first".to_owned(), soon);
+
+        quarantine.add(identity(ENTRY_IDENTITY), "second".to_owned());
+
+        let gateways = quarantine.quarantined_gateways();
+        assert_eq!(gateways.len(), 1);
+        assert_eq!(gateways[0].reason, "second");
+        assert!(gateways[0].quarantined_until > soon);
+    }
+}
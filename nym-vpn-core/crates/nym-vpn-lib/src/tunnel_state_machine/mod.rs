@@ -3,12 +3,20 @@
 
 #[cfg(target_os = "linux")]
 mod default_interface;
+mod dns_blocking;
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+mod dns_forwarder;
 #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
 mod dns_handler;
-//mod firewall_handler;
+#[cfg(target_os = "linux")]
+mod firewall_handler;
+mod gateway_quarantine;
 #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
 mod route_handler;
 mod states;
+mod sticky_gateways;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
 mod tun_ipv6;
 pub mod tunnel;
@@ -22,26 +30,28 @@ use wintun::SetupWintunAdapterError;
 #[cfg(any(target_os = "ios", target_os = "android"))]
 use std::sync::Arc;
 use std::{
-    fmt,
+    cmp, fmt,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     path::PathBuf,
+    time::Duration,
 };
 
+use ipnetwork::IpNetwork;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 use si_scale::helpers::bibytes2;
 use time::OffsetDateTime;
 use tokio::{sync::mpsc, task::JoinHandle};
 use tokio_util::sync::CancellationToken;
 
 use nym_gateway_directory::{
-    Config as GatewayDirectoryConfig, EntryPoint, ExitPoint, NodeIdentity, Recipient,
+    Config as GatewayDirectoryConfig, EntryPoint, ExitPoint, GatewayPreferences, GatewayRanking,
+    IpFamilies, NodeIdentity, Recipient,
 };
 use nym_ip_packet_requests::IpPair;
 use nym_wg_gateway_client::{Error as WgGatewayClientError, GatewayData};
 use nym_wg_go::PublicKey;
 
-#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
-use dns_handler::DnsHandlerHandle;
-//use firewall_handler::FirewallHandler;
 #[cfg(target_os = "android")]
 use crate::tunnel_provider::android::AndroidTunProvider;
 #[cfg(target_os = "ios")]
@@ -51,8 +61,19 @@ use crate::{
     MixnetClientConfig,
 };
 #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+use dns_handler::DnsHandlerHandle;
+#[cfg(target_os = "linux")]
+use firewall_handler::FirewallHandler;
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
 use route_handler::RouteHandler;
 use states::DisconnectedState;
+use sticky_gateways::StickyGateways;
+use tunnel::SelectedGateways;
+
+pub use dns_blocking::{
+    BlockedResponse, DnsBlockingCounters, DnsBlockingOptions, DnsBlockingStats,
+};
+pub use gateway_quarantine::{GatewayQuarantine, QuarantinedGateway};
 
 #[async_trait::async_trait]
 trait TunnelStateHandler: Send {
@@ -106,17 +127,52 @@ pub struct TunnelSettings {
     /// Exit node.
     pub exit_point: Box<ExitPoint>,
 
+    /// Gateways to avoid or to favor when selecting gateways.
+    pub gateway_preferences: GatewayPreferences,
+
+    /// Reconnect with the gateways of the previous connection when the connection is lost, for
+    /// up to this long after it was lost. New gateways are only selected once the previous ones
+    /// stop working. Leave `None` to select new gateways on every reconnect.
+    pub sticky_gateways: Option<Duration>,
+
+    /// How to retry after failing to connect.
+    pub retry_policy: RetryPolicy,
+
+    /// Time allowed for each phase of connecting, before the attempt is given up.
+    pub connect_timeouts: ConnectTimeouts,
+
     /// DNS configuration.
     pub dns: DnsOptions,
 
+    /// Blocking of DNS queries for names on blocklists.
+    /// Only supported on desktop.
+    pub dns_blocking: DnsBlockingOptions,
+
+    /// DNS servers resolving specific domains instead of the tunnel DNS servers.
+    /// Only supported on desktop.
+    pub dns_domain_rules: Vec<DnsDomainRule>,
+
     /// The user agent used for HTTP requests.
     pub user_agent: Option<UserAgent>,
+
+    /// Keep blocking all traffic outside of the tunnel while disconnected.
+    pub lockdown_mode: bool,
+
+    /// Allow access to local networks, including link-local addresses and local discovery such
+    /// as mDNS. DHCP is always allowed.
+    pub allow_lan: bool,
+
+    /// Networks routed outside of, or exclusively through, the tunnel.
+    pub split_routes: SplitRoutes,
 }
 
 #[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
 pub struct GatewayPerformanceOptions {
     pub mixnet_min_performance: Option<u8>,
     pub vpn_min_performance: Option<u8>,
+
+    /// How to choose between the gateways matching the entry or exit point.
+    pub ranking: GatewayRanking,
 }
 
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
@@ -157,22 +213,270 @@ pub struct WireguardTunnelOptions {
     pub multihop_mode: WireguardMultihopMode,
 }
 
+/// Delay before the first retry by default, growing by as much before each following retry.
+pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(4);
+
+/// Longest delay between retries by default.
+pub const DEFAULT_MAX_RETRY_DELAY: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /// Number of consecutive failed attempts after which the tunnel enters the error state.
+    /// At least one attempt is always made. Leave `None` to retry until connected.
+    pub max_attempts: Option<u32>,
+
+    /// Delay before each retry.
+    pub backoff: Backoff,
+
+    /// Upper bound of a random delay added before each retry, so that clients disconnected at
+    /// the same time do not all reconnect at once.
+    pub jitter: Duration,
+}
+
+impl RetryPolicy {
+    /// Returns the delay before the given retry, starting at 1 for the first retry.
+    pub fn delay(&self, retry_attempt: u32) -> Duration {
+        let jitter = if self.jitter.is_zero() {
+            Duration::ZERO
+        } else {
+            rand::thread_rng().gen_range(Duration::ZERO..=self.jitter)
+        };
+        self.backoff.delay(retry_attempt).saturating_add(jitter)
+    }
+
+    /// Returns true if no more attempts are allowed after the given number of failed attempts.
+    pub fn is_exhausted(&self, failed_attempts: u32) -> bool {
+        self.max_attempts
+            .is_some_and(|max_attempts| failed_attempts >= max_attempts)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: None,
+            backoff: Backoff::Linear {
+                step: DEFAULT_RETRY_DELAY,
+                max: DEFAULT_MAX_RETRY_DELAY,
+            },
+            jitter: Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Backoff {
+    /// Wait the same time before every retry.
+    Constant(Duration),
+
+    /// Wait one step longer before each retry, up to the maximum.
+    Linear { step: Duration, max: Duration },
+
+    /// Double the wait before each retry, starting with the initial delay, up to the maximum.
+    Exponential { initial: Duration, max: Duration },
+}
+
+impl Backoff {
+    /// Returns the delay before the given retry, starting at 1 for the first retry.
+    pub fn delay(&self, retry_attempt: u32) -> Duration {
+        match *self {
+            Self::Constant(delay) => delay,
+            Self::Linear { step, max } => cmp::min(step.saturating_mul(retry_attempt), max),
+            Self::Exponential { initial, max } => {
+                let multiplier = 2u32.saturating_pow(retry_attempt.saturating_sub(1));
+                cmp::min(initial.saturating_mul(multiplier), max)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ConnectTimeouts {
+    /// Registering with the authenticators of the entry and exit gateways.
+    /// Only used by WireGuard tunnels.
+    pub authenticator_registration: Duration,
+
+    /// Connecting to the IPR of the exit gateway.
+    /// Only used by mixnet tunnels.
+    pub ipr_connect: Duration,
+
+    /// Completing the first WireGuard handshake with the exit gateway once the tunnel is up.
+    /// Only used by WireGuard tunnels.
+    pub wireguard_handshake: Duration,
+}
+
+impl Default for ConnectTimeouts {
+    fn default() -> Self {
+        Self {
+            authenticator_registration: Duration::from_secs(60),
+            ipr_connect: Duration::from_secs(60),
+            wireguard_handshake: Duration::from_secs(30),
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
 pub enum DnsOptions {
     #[default]
     Default,
     Custom(Vec<IpAddr>),
+
+    /// Resolve through a local forwarder sending the queries encrypted to the upstream resolver.
+    /// Only supported on desktop, other platforms use the upstream resolver directly.
+    Encrypted(EncryptedDnsUpstream),
 }
 
 impl DnsOptions {
+    /// Returns the addresses of the DNS servers reached through the tunnel.
     fn ip_addresses(&self) -> &[IpAddr] {
         match self {
             Self::Default => &crate::DEFAULT_DNS_SERVERS,
             Self::Custom(addrs) => addrs,
+            Self::Encrypted(upstream) => &upstream.addresses,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EncryptedDnsUpstream {
+    pub protocol: EncryptedDnsProtocol,
+
+    /// Addresses of the upstream resolver.
+    pub addresses: Vec<IpAddr>,
+
+    /// Name used to verify the certificate of the upstream resolver.
+    pub tls_name: String,
+}
+
+impl Default for EncryptedDnsUpstream {
+    fn default() -> Self {
+        Self {
+            protocol: EncryptedDnsProtocol::Https,
+            addresses: crate::DEFAULT_DNS_SERVERS.to_vec(),
+            tls_name: "cloudflare-dns.com".to_owned(),
         }
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncryptedDnsProtocol {
+    /// DNS-over-HTTPS
+    #[default]
+    Https,
+
+    /// DNS-over-TLS
+    Tls,
+}
+
+/// Resolve the names under a domain using specific DNS servers, reached outside of the tunnel.
+/// Useful for internal names only known to the resolvers of the local network.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DnsDomainRule {
+    /// Domain name, e.g. `corp.example` or `*.corp.example`. Subdomains are always included.
+    pub domain: String,
+
+    /// Addresses of the DNS servers.
+    pub servers: Vec<IpAddr>,
+}
+
+impl DnsDomainRule {
+    /// Returns the domain name without the wildcard label and the trailing dot.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    fn domain_name(&self) -> String {
+        self.domain
+            .trim_start_matches("*.")
+            .trim_end_matches('.')
+            .to_ascii_lowercase()
+    }
+}
+
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct SplitRoutes {
+    /// Networks routed through the physical interface instead of the tunnel.
+    pub excluded: Vec<IpNetwork>,
+
+    /// When not empty, only these networks are routed through the tunnel.
+    pub included: Vec<IpNetwork>,
+}
+
+impl SplitRoutes {
+    /// Returns true when only the included networks are routed through the tunnel.
+    pub fn is_include_only(&self) -> bool {
+        !self.included.is_empty()
+    }
+
+    /// Returns the IP families routed through the tunnel.
+    pub fn routed_ip_families(&self) -> IpFamilies {
+        if self.is_include_only() {
+            IpFamilies {
+                ipv4: self.included.iter().any(IpNetwork::is_ipv4),
+                ipv6: self.included.iter().any(IpNetwork::is_ipv6),
+            }
+        } else {
+            let excludes_all = |is_family: fn(&IpNetwork) -> bool| {
+                self.excluded
+                    .iter()
+                    .any(|net| is_family(net) && net.prefix() == 0)
+            };
+            IpFamilies {
+                ipv4: !excludes_all(IpNetwork::is_ipv4),
+                ipv6: !excludes_all(IpNetwork::is_ipv6),
+            }
+        }
+    }
+
+    /// Returns the networks the firewall lets through outside of the tunnel once connected. They
+    /// stay blocked while connecting since in include-only mode they cover all addresses.
+    #[cfg(target_os = "linux")]
+    fn firewall_excluded_networks(&self) -> Vec<IpNetwork> {
+        if self.is_include_only() {
+            vec!["0.0.0.0/0".parse().unwrap(), "::0/0".parse().unwrap()]
+        } else {
+            self.excluded.clone()
+        }
+    }
+}
+
+impl TunnelSettings {
+    /// Returns the split routes, also routing the DNS servers of the domain rules outside of the
+    /// tunnel.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    fn split_routes_with_dns_domain_rules(&self) -> SplitRoutes {
+        let mut split_routes = self.split_routes.clone();
+
+        if !split_routes.is_include_only() {
+            split_routes.excluded.extend(
+                self.dns_domain_rules
+                    .iter()
+                    .flat_map(|rule| rule.servers.iter())
+                    .map(|addr| IpNetwork::from(*addr)),
+            );
+        }
+
+        split_routes
+    }
+
+    /// Returns true if the system resolver applies the DNS domain rules by itself, rather than the
+    /// local DNS forwarder.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    fn system_resolver_handles_dns_domain_rules(&self) -> bool {
+        // Only systemd-resolved routes the rule domains to their resolvers
+        #[cfg(target_os = "linux")]
+        return !self.dns_domain_rules.is_empty() && nym_dns::will_use_systemd_resolved();
+        #[cfg(not(target_os = "linux"))]
+        return false;
+    }
+
+    /// Returns true if the system resolver is pointed at the local DNS forwarder while connected.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    fn needs_dns_forwarder(&self, system_resolver_handles_dns_domain_rules: bool) -> bool {
+        matches!(self.dns, DnsOptions::Encrypted(_))
+            || self.dns_blocking.is_enabled()
+            || (!self.dns_domain_rules.is_empty() && !system_resolver_handles_dns_domain_rules)
+    }
+}
+
 impl Default for TunnelSettings {
     fn default() -> Self {
         Self {
@@ -185,8 +489,17 @@ impl Default for TunnelSettings {
             gateway_performance_options: GatewayPerformanceOptions::default(),
             entry_point: Box::new(EntryPoint::Random),
             exit_point: Box::new(ExitPoint::Random),
+            gateway_preferences: GatewayPreferences::default(),
+            sticky_gateways: None,
+            retry_policy: RetryPolicy::default(),
+            connect_timeouts: ConnectTimeouts::default(),
             dns: DnsOptions::default(),
+            dns_blocking: DnsBlockingOptions::default(),
+            dns_domain_rules: Vec::new(),
             user_agent: None,
+            lockdown_mode: false,
+            allow_lan: false,
+            split_routes: SplitRoutes::default(),
         }
     }
 }
@@ -217,6 +530,13 @@ pub struct ConnectionData {
 
     /// Tunnel connection data.
     pub tunnel: TunnelConnectionData,
+
+    /// Gateways tried while connecting, in order. The last attempt is the one in use.
+    pub connection_attempts: Vec<ConnectionAttempt>,
+
+    /// DNS servers the system resolver is pointed at, which is the address the DNS forwarder
+    /// listens on when it is used.
+    pub system_dns_servers: Vec<IpAddr>,
 }
 
 impl fmt::Debug for ConnectionData {
@@ -226,10 +546,127 @@ impl fmt::Debug for ConnectionData {
             .field("exit_gateway", &self.exit_gateway.to_base58_string())
             .field("connected_at", &self.connected_at)
             .field("tunnel", &self.tunnel)
+            .field("connection_attempts", &self.connection_attempts)
+            .field("system_dns_servers", &self.system_dns_servers)
+            .finish()
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, uniffi::Record)]
+pub struct ConnectionAttempt {
+    /// Mixnet entry gateway
+    pub entry_gateway: Box<NodeIdentity>,
+
+    /// Mixnet exit gateway
+    pub exit_gateway: Box<NodeIdentity>,
+
+    /// Why connecting with the gateways failed.
+    /// Not set for the attempt in use.
+    pub error: Option<String>,
+
+    /// Time spent in each phase of the attempt.
+    pub timings: ConnectionTimings,
+}
+
+impl ConnectionAttempt {
+    fn new(
+        selected_gateways: &SelectedGateways,
+        error: Option<String>,
+        timings: ConnectionTimings,
+    ) -> Self {
+        Self {
+            entry_gateway: Box::new(*selected_gateways.entry.identity()),
+            exit_gateway: Box::new(*selected_gateways.exit.identity()),
+            error,
+            timings,
+        }
+    }
+}
+
+impl fmt::Debug for ConnectionAttempt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionAttempt")
+            .field("entry_gateway", &self.entry_gateway.to_base58_string())
+            .field("exit_gateway", &self.exit_gateway.to_base58_string())
+            .field("error", &self.error)
+            .field("timings", &self.timings)
             .finish()
     }
 }
 
+/// Time spent in each phase of a connection attempt.
+/// Phases that were skipped or not reached are not set.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, uniffi::Record)]
+pub struct ConnectionTimings {
+    /// Looking up and selecting the gateways.
+    /// Not set when the gateways of a previous attempt are reused.
+    pub gateway_lookup: Option<Duration>,
+
+    /// Connecting the mixnet client and registering with the entry gateway.
+    pub registration: Option<Duration>,
+
+    /// Setting up the tunnel with the gateways, either through the IPR or by exchanging the
+    /// WireGuard keys.
+    pub handshake: Option<Duration>,
+
+    /// Creating the tunnel devices and configuring the routes and DNS.
+    pub route_dns_setup: Option<Duration>,
+}
+
+impl ConnectionTimings {
+    /// Accounts the time from the start of the handshake until the tunnel is ready. Everything
+    /// following the handshake is spent setting up the devices, routes and DNS.
+    pub fn set_tunnel_setup(&mut self, tunnel_setup: Duration) {
+        if let Some(handshake) = self.handshake {
+            self.route_dns_setup = Some(tunnel_setup.saturating_sub(handshake));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn route_dns_setup_excludes_handshake() {
+        let mut timings = ConnectionTimings {
+            handshake: Some(Duration::from_millis(300)),
+            ..Default::default()
+        };
+        timings.set_tunnel_setup(Duration::from_millis(1000));
+        assert_eq!(timings.route_dns_setup, Some(Duration::from_millis(700)));
+
+        // The handshake was not reached.
+        let mut timings = ConnectionTimings::default();
+        timings.set_tunnel_setup(Duration::from_millis(1000));
+        assert_eq!(timings.route_dns_setup, None);
+    }
+
+    #[test]
+    fn routed_ip_families_follow_split_routes() {
+        let ipv4_only = IpFamilies {
+            ipv4: true,
+            ipv6: false,
+        };
+        assert_eq!(
+            SplitRoutes::default().routed_ip_families(),
+            IpFamilies::default()
+        );
+
+        let included = SplitRoutes {
+            included: vec!["10.0.0.0/8".parse().unwrap()],
+            ..Default::default()
+        };
+        assert_eq!(included.routed_ip_families(), ipv4_only);
+
+        let excluded = SplitRoutes {
+            excluded: vec!["::/0".parse().unwrap(), "10.0.0.0/8".parse().unwrap()],
+            ..Default::default()
+        };
+        assert_eq!(excluded.routed_ip_families(), ipv4_only);
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, uniffi::Enum)]
 pub enum TunnelConnectionData {
     Mixnet(MixnetConnectionData),
@@ -275,6 +712,9 @@ pub enum TunnelState {
     Disconnected,
     Connecting {
         connection_data: Option<ConnectionData>,
+        /// Gateways tried so far, in order. Failed attempts are reported as soon as they fail,
+        /// before the connection data of the next attempt is known.
+        connection_attempts: Vec<ConnectionAttempt>,
     },
     Connected {
         connection_data: ConnectionData,
@@ -283,6 +723,7 @@ pub enum TunnelState {
         after_disconnect: ActionAfterDisconnect,
     },
     Error(ErrorStateReason),
+    Offline,
 }
 
 impl From<PrivateTunnelState> for TunnelState {
@@ -292,13 +733,18 @@ impl From<PrivateTunnelState> for TunnelState {
             PrivateTunnelState::Connected { connection_data } => {
                 Self::Connected { connection_data }
             }
-            PrivateTunnelState::Connecting { connection_data } => {
-                Self::Connecting { connection_data }
-            }
+            PrivateTunnelState::Connecting {
+                connection_data,
+                connection_attempts,
+            } => Self::Connecting {
+                connection_data,
+                connection_attempts,
+            },
             PrivateTunnelState::Disconnecting { after_disconnect } => Self::Disconnecting {
                 after_disconnect: ActionAfterDisconnect::from(after_disconnect),
             },
             PrivateTunnelState::Error(reason) => Self::Error(reason),
+            PrivateTunnelState::Offline => Self::Offline,
         }
     }
 }
@@ -309,6 +755,7 @@ enum PrivateTunnelState {
     Disconnected,
     Connecting {
         connection_data: Option<ConnectionData>,
+        connection_attempts: Vec<ConnectionAttempt>,
     },
     Connected {
         connection_data: ConnectionData,
@@ -317,6 +764,7 @@ enum PrivateTunnelState {
         after_disconnect: PrivateActionAfterDisconnect,
     },
     Error(ErrorStateReason),
+    Offline,
 }
 
 /// Public enum describing action to perform after disconnect
@@ -388,6 +836,9 @@ pub enum ErrorStateReason {
     /// Failure to duplicate tunnel file descriptor.
     DuplicateTunFd,
 
+    /// Connecting failed as many times in a row as the retry policy allows.
+    ConnectionAttemptsExhausted,
+
     /// Program errors that must not happen.
     Internal,
 }
@@ -505,11 +956,20 @@ pub struct SharedState {
     mixnet_event_sender: mpsc::UnboundedSender<MixnetEvent>,
     #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
     route_handler: RouteHandler,
-    //firewall_handler: FirewallHandler,
+    #[cfg(target_os = "linux")]
+    firewall_handler: FirewallHandler,
+    /// Last resolved API endpoints, used when they cannot be resolved under the current
+    /// firewall policy.
+    #[cfg(target_os = "linux")]
+    api_endpoints: Vec<firewall_handler::Endpoint>,
     #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
     dns_handler: DnsHandlerHandle,
     nym_config: NymConfig,
     tunnel_settings: TunnelSettings,
+    gateway_quarantine: GatewayQuarantine,
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    dns_blocking_counters: DnsBlockingCounters,
+    sticky_gateways: StickyGateways,
     status_listener_handle: Option<JoinHandle<()>>,
     #[cfg(target_os = "ios")]
     tun_provider: Arc<dyn OSTunProvider>,
@@ -540,12 +1000,13 @@ impl TunnelStateMachine {
         event_sender: mpsc::UnboundedSender<TunnelEvent>,
         nym_config: NymConfig,
         tunnel_settings: TunnelSettings,
+        gateway_quarantine: GatewayQuarantine,
+        #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+        dns_blocking_counters: DnsBlockingCounters,
         #[cfg(target_os = "ios")] tun_provider: Arc<dyn OSTunProvider>,
         #[cfg(target_os = "android")] tun_provider: Arc<dyn AndroidTunProvider>,
         shutdown_token: CancellationToken,
     ) -> Result<JoinHandle<()>> {
-        let (current_state_handler, _) = DisconnectedState::enter();
-
         #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
         let route_handler = RouteHandler::new()
             .await
@@ -554,27 +1015,40 @@ impl TunnelStateMachine {
         let (dns_handler, dns_handler_task) = DnsHandlerHandle::spawn(
             #[cfg(target_os = "linux")]
             &route_handler,
+            #[cfg(target_os = "linux")]
+            nym_config.data_path.as_deref(),
             shutdown_token.child_token(),
         )
         .map_err(Error::CreateDnsHandler)?;
-        //let firewall_handler = FirewallHandler::new().map_err(Error::CreateFirewallHandler)?;
+        #[cfg(target_os = "linux")]
+        let firewall_handler = FirewallHandler::new(tunnel_settings.allow_lan)
+            .map_err(Error::CreateFirewallHandler)?;
 
         let (mixnet_event_sender, mixnet_event_receiver) = mpsc::unbounded_channel();
 
-        let shared_state: SharedState = SharedState {
+        let mut shared_state: SharedState = SharedState {
             mixnet_event_sender,
             #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
             route_handler,
-            //firewall_handler,
+            #[cfg(target_os = "linux")]
+            firewall_handler,
+            #[cfg(target_os = "linux")]
+            api_endpoints: Vec::new(),
             #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
             dns_handler,
             nym_config,
             tunnel_settings,
+            gateway_quarantine,
+            #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+            dns_blocking_counters,
+            sticky_gateways: StickyGateways::default(),
             status_listener_handle: None,
             #[cfg(any(target_os = "ios", target_os = "android"))]
             tun_provider,
         };
 
+        let (current_state_handler, _) = DisconnectedState::enter(&mut shared_state);
+
         let tunnel_state_machine = Self {
             current_state_handler,
             shared_state,
@@ -632,6 +1106,14 @@ impl TunnelStateMachine {
             tracing::error!("Failed to join on dns handler task: {}", e)
         }
 
+        // Leave the blocking policy in place when in lockdown mode.
+        #[cfg(target_os = "linux")]
+        if !self.shared_state.tunnel_settings.lockdown_mode {
+            if let Err(e) = self.shared_state.firewall_handler.reset_policy() {
+                tracing::error!("Failed to reset firewall policy: {}", e);
+            }
+        }
+
         #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
         self.shared_state.route_handler.stop().await;
     }
@@ -647,8 +1129,14 @@ pub enum Error {
     #[error("failed to create a dns handler: {}", _0)]
     CreateDnsHandler(#[source] dns_handler::Error),
 
-    //#[error("failed to create firewall handler: {}", _0)]
-    //CreateFirewallHandler(#[source] firewall_handler::Error),
+    #[cfg(target_os = "linux")]
+    #[error("failed to create firewall handler: {}", _0)]
+    CreateFirewallHandler(#[source] firewall_handler::Error),
+
+    #[cfg(target_os = "linux")]
+    #[error("failed to apply firewall policy: {}", _0)]
+    SetFirewallPolicy(#[source] firewall_handler::Error),
+
     #[error("failed to create tunnel device: {}", _0)]
     CreateTunDevice(#[source] tun::Error),
 
@@ -688,6 +1176,10 @@ pub enum Error {
     #[error("failed to set dns: {}", _0)]
     SetDns(#[source] dns_handler::Error),
 
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    #[error("failed to start dns forwarder: {}", _0)]
+    StartDnsForwarder(#[source] dns_forwarder::Error),
+
     #[error("tunnel error: {}", _0)]
     Tunnel(#[from] tunnel::Error),
 }
@@ -698,8 +1190,13 @@ impl Error {
             #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
             Self::CreateRouteHandler(_) | Self::AddRoutes(_) => ErrorStateReason::Routing,
             #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
-            Self::CreateDnsHandler(_) | Self::SetDns(_) => ErrorStateReason::Dns,
-            //Self::CreateFirewallHandler(_) => ErrorStateReason::Firewall,
+            Self::CreateDnsHandler(_) | Self::SetDns(_) | Self::StartDnsForwarder(_) => {
+                ErrorStateReason::Dns
+            }
+            #[cfg(target_os = "linux")]
+            Self::CreateFirewallHandler(_) | Self::SetFirewallPolicy(_) => {
+                ErrorStateReason::Firewall
+            }
             Self::CreateTunDevice(_) => ErrorStateReason::TunDevice,
 
             #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
@@ -722,7 +1219,8 @@ impl Error {
             Self::GetRouteHandle(_) => ErrorStateReason::Internal,
 
             #[cfg(target_os = "linux")]
-            Self::GetDefaultInterface(_) => ErrorStateReason::Internal,
+            // Reconnect and let the connecting state detect that the device is offline.
+            Self::GetDefaultInterface(_) => return None,
         })
     }
 }
@@ -765,7 +1263,9 @@ impl fmt::Display for TunnelState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Disconnected => f.write_str("Disconnected"),
-            Self::Connecting { connection_data } => match connection_data {
+            Self::Connecting {
+                connection_data, ..
+            } => match connection_data {
                 Some(data) => match data.tunnel {
                     TunnelConnectionData::Mixnet(ref data) => {
                         write!(
@@ -810,6 +1310,7 @@ impl fmt::Display for TunnelState {
             Self::Error(reason) => {
                 write!(f, "Error state: {:?}", reason)
             }
+            Self::Offline => f.write_str("Offline"),
         }
     }
 }
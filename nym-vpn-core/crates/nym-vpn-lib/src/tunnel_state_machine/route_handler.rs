@@ -3,8 +3,14 @@
 
 use std::{collections::HashSet, fmt, net::IpAddr};
 
+#[cfg(target_os = "linux")]
+use futures::future;
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use futures::{Stream, StreamExt};
 use ipnetwork::IpNetwork;
 
+#[cfg(target_os = "linux")]
+use nym_routing::CallbackMessage;
 #[cfg(not(target_os = "linux"))]
 use nym_routing::NetNode;
 #[cfg(windows)]
@@ -13,6 +19,7 @@ use nym_routing::{Node, RequiredRoute, RouteManagerHandle};
 
 #[cfg(target_os = "linux")]
 use super::default_interface::DefaultInterface;
+use super::SplitRoutes;
 
 #[cfg(target_os = "linux")]
 pub const TUNNEL_TABLE_ID: u32 = 0x14d;
@@ -23,6 +30,7 @@ pub enum RoutingConfig {
     Mixnet {
         tun_name: String,
         entry_gateway_address: IpAddr,
+        split_routes: SplitRoutes,
         #[cfg(target_os = "linux")]
         physical_interface: DefaultInterface,
     },
@@ -31,12 +39,14 @@ pub enum RoutingConfig {
         exit_tun_name: String,
         entry_gateway_address: IpAddr,
         exit_gateway_address: IpAddr,
+        split_routes: SplitRoutes,
         #[cfg(target_os = "linux")]
         physical_interface: DefaultInterface,
     },
     WireguardNetstack {
         exit_tun_name: String,
         entry_gateway_address: IpAddr,
+        split_routes: SplitRoutes,
         #[cfg(target_os = "linux")]
         physical_interface: DefaultInterface,
     },
@@ -59,8 +69,12 @@ impl RouteHandler {
         Ok(Self { route_manager })
     }
 
-    pub async fn add_routes(&mut self, routing_config: RoutingConfig) -> Result<()> {
-        let routes = Self::get_routes(routing_config);
+    pub async fn add_routes(
+        &mut self,
+        routing_config: RoutingConfig,
+        allow_lan: bool,
+    ) -> Result<()> {
+        let routes = Self::get_routes(routing_config, allow_lan);
 
         #[cfg(target_os = "linux")]
         self.route_manager.create_routing_rules().await?;
@@ -98,18 +112,80 @@ impl RouteHandler {
         _ = tokio::task::spawn_blocking(|| drop(self.route_manager)).await;
     }
 
+    /// Returns a stream notifying about non-tunnel default route changes.
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub async fn default_route_listener(&self) -> Result<impl Stream<Item = ()>> {
+        #[cfg(target_os = "linux")]
+        let stream = self
+            .route_manager
+            .change_listener()
+            .await?
+            .filter_map(|message| {
+                let route = match message {
+                    CallbackMessage::NewRoute(route) | CallbackMessage::DelRoute(route) => route,
+                };
+                future::ready(
+                    (route.is_main_table() && route.get_prefix().prefix() == 0).then_some(()),
+                )
+            });
+
+        #[cfg(target_os = "macos")]
+        let stream = self
+            .route_manager
+            .default_route_listener()
+            .await?
+            .map(|_| ());
+
+        Ok(stream)
+    }
+
+    /// Returns true if a non-tunnel default route is available.
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub async fn has_default_route(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        {
+            DefaultInterface::current().is_ok()
+        }
+
+        #[cfg(target_os = "macos")]
+        match self.route_manager.get_default_routes().await {
+            Ok((v4_route, v6_route)) => v4_route.is_some() || v6_route.is_some(),
+            Err(e) => {
+                tracing::error!("Failed to get default routes: {}", e);
+                true
+            }
+        }
+    }
+
     #[cfg(target_os = "linux")]
     pub(super) fn inner_handle(&self) -> nym_routing::RouteManagerHandle {
         self.route_manager.clone()
     }
 
-    fn get_routes(routing_config: RoutingConfig) -> HashSet<RequiredRoute> {
+    fn get_routes(routing_config: RoutingConfig, allow_lan: bool) -> HashSet<RequiredRoute> {
         let mut routes = HashSet::new();
 
+        if allow_lan {
+            routes.extend(Self::get_bypass_routes(
+                nym_firewall::allowed_lan_nets()
+                    .into_iter()
+                    .chain(nym_firewall::allowed_lan_multicast_nets()),
+                #[cfg(target_os = "linux")]
+                routing_config.physical_interface(),
+            ));
+        }
+
+        routes.extend(Self::get_bypass_routes(
+            routing_config.split_routes().excluded.iter().copied(),
+            #[cfg(target_os = "linux")]
+            routing_config.physical_interface(),
+        ));
+
         match routing_config {
             RoutingConfig::Mixnet {
                 tun_name,
                 entry_gateway_address,
+                split_routes,
                 #[cfg(target_os = "linux")]
                 physical_interface,
             } => {
@@ -125,21 +201,14 @@ impl RouteHandler {
                     physical_interface.as_node(),
                 ));
 
-                routes.insert(RequiredRoute::new(
-                    "0.0.0.0/0".parse().unwrap(),
-                    Node::device(tun_name.to_owned()),
-                ));
-
-                routes.insert(RequiredRoute::new(
-                    "::0/0".parse().unwrap(),
-                    Node::device(tun_name.to_owned()),
-                ));
+                routes.extend(Self::get_tunnel_routes(&tun_name, &split_routes));
             }
             RoutingConfig::Wireguard {
                 entry_tun_name,
                 exit_tun_name,
                 entry_gateway_address,
                 exit_gateway_address,
+                split_routes,
                 #[cfg(target_os = "linux")]
                 physical_interface,
             } => {
@@ -160,19 +229,12 @@ impl RouteHandler {
                     Node::device(entry_tun_name.to_owned()),
                 ));
 
-                routes.insert(RequiredRoute::new(
-                    "0.0.0.0/0".parse().unwrap(),
-                    Node::device(exit_tun_name.to_owned()),
-                ));
-
-                routes.insert(RequiredRoute::new(
-                    "::0/0".parse().unwrap(),
-                    Node::device(exit_tun_name.to_owned()),
-                ));
+                routes.extend(Self::get_tunnel_routes(&exit_tun_name, &split_routes));
             }
             RoutingConfig::WireguardNetstack {
                 exit_tun_name,
                 entry_gateway_address,
+                split_routes,
                 #[cfg(target_os = "linux")]
                 physical_interface,
             } => {
@@ -188,15 +250,7 @@ impl RouteHandler {
                     physical_interface.as_node(),
                 ));
 
-                routes.insert(RequiredRoute::new(
-                    "0.0.0.0/0".parse().unwrap(),
-                    Node::device(exit_tun_name.to_owned()),
-                ));
-
-                routes.insert(RequiredRoute::new(
-                    "::0/0".parse().unwrap(),
-                    Node::device(exit_tun_name.to_owned()),
-                ));
+                routes.extend(Self::get_tunnel_routes(&exit_tun_name, &split_routes));
             }
         }
 
@@ -210,6 +264,65 @@ impl RouteHandler {
 
         routes
     }
+
+    /// Returns routes sending traffic to the given networks outside of the tunnel.
+    fn get_bypass_routes(
+        networks: impl Iterator<Item = IpNetwork>,
+        #[cfg(target_os = "linux")] physical_interface: &DefaultInterface,
+    ) -> Vec<RequiredRoute> {
+        networks
+            .map(|network| {
+                #[cfg(not(target_os = "linux"))]
+                let node = NetNode::DefaultNode;
+                #[cfg(target_os = "linux")]
+                let node = physical_interface.as_bypass_node(&network);
+
+                RequiredRoute::new(network, node)
+            })
+            .collect()
+    }
+
+    /// Returns routes sending traffic through the tunnel. Only the included networks are routed
+    /// through the tunnel when set, otherwise all traffic is.
+    fn get_tunnel_routes(tun_name: &str, split_routes: &SplitRoutes) -> Vec<RequiredRoute> {
+        let networks = if split_routes.is_include_only() {
+            split_routes.included.clone()
+        } else {
+            vec!["0.0.0.0/0".parse().unwrap(), "::0/0".parse().unwrap()]
+        };
+
+        networks
+            .into_iter()
+            .map(|network| RequiredRoute::new(network, Node::device(tun_name.to_owned())))
+            .collect()
+    }
+}
+
+impl RoutingConfig {
+    fn split_routes(&self) -> &SplitRoutes {
+        match self {
+            Self::Mixnet { split_routes, .. }
+            | Self::Wireguard { split_routes, .. }
+            | Self::WireguardNetstack { split_routes, .. } => split_routes,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl RoutingConfig {
+    fn physical_interface(&self) -> &DefaultInterface {
+        match self {
+            Self::Mixnet {
+                physical_interface, ..
+            }
+            | Self::Wireguard {
+                physical_interface, ..
+            }
+            | Self::WireguardNetstack {
+                physical_interface, ..
+            } => physical_interface,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -251,4 +364,25 @@ impl DefaultInterface {
             Node::device(iface_name)
         }
     }
+
+    /// Link-local and multicast networks are reachable on-link, other networks are reached
+    /// through the gateway when it belongs to the same address family.
+    fn as_bypass_node(&self, network: &IpNetwork) -> Node {
+        let iface_name = self.interface_name().to_owned();
+        let is_on_link = match network {
+            IpNetwork::V4(net) => {
+                net.ip().is_link_local() || net.ip().is_multicast() || net.ip().is_broadcast()
+            }
+            IpNetwork::V6(net) => {
+                net.ip().is_multicast() || (net.ip().segments()[0] & 0xffc0) == 0xfe80
+            }
+        };
+
+        match self.gateway_ip() {
+            Some(gateway) if !is_on_link && gateway.is_ipv4() == network.is_ipv4() => {
+                Node::new(gateway, iface_name)
+            }
+            _ => Node::device(iface_name),
+        }
+    }
 }
@@ -1,9 +1,17 @@
 // Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
+#[cfg(target_os = "linux")]
+use std::net::IpAddr;
+
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 
+#[cfg(target_os = "linux")]
+use crate::tunnel_state_machine::{
+    firewall_handler::{Endpoint, FirewallPolicy, TunnelInterface},
+    ErrorStateReason,
+};
 use crate::tunnel_state_machine::{
     states::DisconnectingState,
     tunnel_monitor::{TunnelMonitorEvent, TunnelMonitorEventReceiver, TunnelMonitorHandle},
@@ -21,8 +29,34 @@ impl ConnectedState {
         connection_data: ConnectionData,
         monitor_handle: TunnelMonitorHandle,
         monitor_event_receiver: TunnelMonitorEventReceiver,
+        #[cfg(target_os = "linux")] peer_endpoints: Vec<Endpoint>,
+        #[cfg(target_os = "linux")] tunnel_interface: TunnelInterface,
+        #[cfg(target_os = "linux")] dns_servers: Vec<IpAddr>,
         _shared_state: &mut SharedState,
     ) -> (Box<dyn TunnelStateHandler>, PrivateTunnelState) {
+        #[cfg(target_os = "linux")]
+        {
+            let policy = FirewallPolicy::Connected {
+                peer_endpoints,
+                tunnel: tunnel_interface,
+                allow_lan: _shared_state.tunnel_settings.allow_lan,
+                dns_servers,
+                excluded_networks: _shared_state
+                    .tunnel_settings
+                    .split_routes_with_dns_domain_rules()
+                    .firewall_excluded_networks(),
+            };
+
+            if let Err(e) = _shared_state.firewall_handler.apply_policy(policy) {
+                tracing::error!("Failed to apply firewall policy for connected state: {}", e);
+                return DisconnectingState::enter(
+                    PrivateActionAfterDisconnect::Error(ErrorStateReason::Firewall),
+                    monitor_handle,
+                    _shared_state,
+                );
+            }
+        }
+
         (
             Box::new(Self {
                 monitor_handle,
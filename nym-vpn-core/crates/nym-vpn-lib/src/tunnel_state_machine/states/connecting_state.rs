@@ -1,17 +1,27 @@
 // Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
+#[cfg(target_os = "linux")]
+use std::net::IpAddr;
+
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use crate::tunnel_state_machine::states::OfflineState;
+#[cfg(target_os = "linux")]
+use crate::tunnel_state_machine::{
+    firewall_handler::{self, Endpoint, FirewallPolicy, TunnelInterface},
+    Error,
+};
 use crate::tunnel_state_machine::{
-    states::{ConnectedState, DisconnectingState},
+    states::{ConnectedState, DisconnectingState, ErrorState},
     tunnel::{SelectedGateways, Tombstone},
     tunnel_monitor::{
         TunnelMonitor, TunnelMonitorEvent, TunnelMonitorEventReceiver, TunnelMonitorHandle,
     },
-    NextTunnelState, PrivateActionAfterDisconnect, PrivateTunnelState, SharedState, TunnelCommand,
-    TunnelStateHandler,
+    ErrorStateReason, NextTunnelState, PrivateActionAfterDisconnect, PrivateTunnelState,
+    SharedState, TunnelCommand, TunnelStateHandler,
 };
 
 pub struct ConnectingState {
@@ -19,14 +29,53 @@ pub struct ConnectingState {
     monitor_event_receiver: TunnelMonitorEventReceiver,
     retry_attempt: u32,
     selected_gateways: Option<SelectedGateways>,
+    #[cfg(target_os = "linux")]
+    peer_endpoints: Vec<Endpoint>,
+    #[cfg(target_os = "linux")]
+    api_endpoints: Vec<Endpoint>,
+    #[cfg(target_os = "linux")]
+    probe_endpoints: Vec<Endpoint>,
+    #[cfg(target_os = "linux")]
+    lookup_dns_servers: Vec<IpAddr>,
+    #[cfg(target_os = "linux")]
+    tunnel_interface: Option<TunnelInterface>,
+    #[cfg(target_os = "linux")]
+    dns_servers: Vec<IpAddr>,
 }
 
 impl ConnectingState {
-    pub fn enter(
+    pub async fn enter(
         retry_attempt: u32,
         selected_gateways: Option<SelectedGateways>,
         shared_state: &mut SharedState,
     ) -> (Box<dyn TunnelStateHandler>, PrivateTunnelState) {
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        if !shared_state.route_handler.has_default_route().await {
+            tracing::info!("No default route available. Waiting for connectivity.");
+            return OfflineState::enter(shared_state).await;
+        }
+
+        #[cfg(target_os = "linux")]
+        let lookup_dns_servers = firewall_handler::lookup_dns_servers().await;
+
+        #[cfg(target_os = "linux")]
+        let (peer_endpoints, api_endpoints) = match Self::resolve_endpoints(
+            selected_gateways.as_ref(),
+            lookup_dns_servers.clone(),
+            shared_state,
+        )
+        .await
+        {
+            Ok(endpoints) => endpoints,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to apply firewall policy for connecting state: {}",
+                    e
+                );
+                return ErrorState::enter(ErrorStateReason::Firewall, shared_state).await;
+            }
+        };
+
         let (monitor_event_sender, monitor_event_receiver) = mpsc::unbounded_channel();
         let monitor_handle = TunnelMonitor::start(
             retry_attempt,
@@ -37,10 +86,13 @@ impl ConnectingState {
             shared_state.route_handler.clone(),
             #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
             shared_state.dns_handler.clone(),
+            #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+            shared_state.dns_blocking_counters.clone(),
             #[cfg(any(target_os = "ios", target_os = "android"))]
             shared_state.tun_provider.clone(),
             shared_state.nym_config.clone(),
             shared_state.tunnel_settings.clone(),
+            shared_state.gateway_quarantine.clone(),
         );
 
         (
@@ -49,13 +101,140 @@ impl ConnectingState {
                 monitor_event_receiver,
                 retry_attempt,
                 selected_gateways,
+                #[cfg(target_os = "linux")]
+                peer_endpoints,
+                #[cfg(target_os = "linux")]
+                api_endpoints,
+                #[cfg(target_os = "linux")]
+                probe_endpoints: Vec::new(),
+                #[cfg(target_os = "linux")]
+                lookup_dns_servers,
+                #[cfg(target_os = "linux")]
+                tunnel_interface: None,
+                #[cfg(target_os = "linux")]
+                dns_servers: Vec::new(),
             }),
             PrivateTunnelState::Connecting {
                 connection_data: None,
+                connection_attempts: Vec::new(),
             },
         )
     }
 
+    /// Resolves the endpoints of the entry gateway and of the APIs, and applies the policy letting
+    /// them through.
+    ///
+    /// The DNS requests are let through first since nothing may have been resolved yet, such as
+    /// when coming from the blocked policy of lockdown mode after boot.
+    #[cfg(target_os = "linux")]
+    async fn resolve_endpoints(
+        selected_gateways: Option<&SelectedGateways>,
+        lookup_dns_servers: Vec<IpAddr>,
+        shared_state: &mut SharedState,
+    ) -> Result<(Vec<Endpoint>, Vec<Endpoint>), Error> {
+        let last_api_endpoints = shared_state.api_endpoints.clone();
+        Self::set_firewall_policy(
+            shared_state,
+            Vec::new(),
+            last_api_endpoints,
+            lookup_dns_servers.clone(),
+            None,
+        )?;
+
+        let peer_endpoints = match selected_gateways {
+            Some(selected_gateways) => Self::lookup_peer_endpoints(selected_gateways).await,
+            None => Vec::new(),
+        };
+        let api_endpoints = Self::resolve_api_endpoints(shared_state).await;
+
+        Self::set_firewall_policy(
+            shared_state,
+            peer_endpoints.clone(),
+            api_endpoints.clone(),
+            lookup_dns_servers,
+            None,
+        )?;
+
+        Ok((peer_endpoints, api_endpoints))
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn lookup_peer_endpoints(selected_gateways: &SelectedGateways) -> Vec<Endpoint> {
+        match selected_gateways.entry.lookup_ip().await {
+            Some(addr) => {
+                firewall_handler::gateway_client_endpoints(&selected_gateways.entry, addr)
+            }
+            None => {
+                tracing::warn!("Failed to resolve entry gateway address");
+                Vec::new()
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn resolve_api_endpoints(shared_state: &mut SharedState) -> Vec<Endpoint> {
+        let api_endpoints =
+            firewall_handler::resolve_api_endpoints(&shared_state.nym_config.gateway_config).await;
+        if api_endpoints.is_empty() {
+            tracing::warn!("Failed to resolve API endpoints, using the last resolved ones");
+        } else {
+            shared_state.api_endpoints = api_endpoints;
+        }
+        shared_state.api_endpoints.clone()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn set_firewall_policy(
+        shared_state: &mut SharedState,
+        peer_endpoints: Vec<Endpoint>,
+        api_endpoints: Vec<Endpoint>,
+        dns_servers: Vec<IpAddr>,
+        tunnel: Option<TunnelInterface>,
+    ) -> Result<(), Error> {
+        let policy = FirewallPolicy::Connecting {
+            peer_endpoints,
+            tunnel,
+            allow_lan: shared_state.tunnel_settings.allow_lan,
+            api_endpoints,
+            dns_servers,
+        };
+
+        shared_state
+            .firewall_handler
+            .apply_policy(policy)
+            .map_err(Error::SetFirewallPolicy)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn update_firewall_policy(self: Box<Self>, shared_state: &mut SharedState) -> NextTunnelState {
+        // The gateways whose latency is measured are reached the same way as the APIs.
+        let api_endpoints = self
+            .api_endpoints
+            .iter()
+            .chain(&self.probe_endpoints)
+            .cloned()
+            .collect();
+        if let Err(e) = Self::set_firewall_policy(
+            shared_state,
+            self.peer_endpoints.clone(),
+            api_endpoints,
+            self.lookup_dns_servers.clone(),
+            self.tunnel_interface.clone(),
+        ) {
+            tracing::error!(
+                "Failed to update firewall policy for connecting state: {}",
+                e
+            );
+            NextTunnelState::NewState(DisconnectingState::enter(
+                PrivateActionAfterDisconnect::Error(ErrorStateReason::Firewall),
+                self.monitor_handle,
+                shared_state,
+            ))
+        } else {
+            NextTunnelState::SameState(self)
+        }
+    }
+
     async fn on_tunnel_exit(mut tombstone: Tombstone, _shared_state: &mut SharedState) {
         #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
         {
@@ -95,15 +274,66 @@ impl TunnelStateHandler for ConnectingState {
                 TunnelMonitorEvent::InitializingClient => {
                     NextTunnelState::SameState(self)
                 }
+                TunnelMonitorEvent::ConnectionAttemptFailed(connection_attempts) => {
+                    NextTunnelState::NewState((self, PrivateTunnelState::Connecting { connection_data: None, connection_attempts }))
+                }
                 TunnelMonitorEvent::EstablishingTunnel(conn_data) => {
-                    NextTunnelState::NewState((self, PrivateTunnelState::Connecting { connection_data: Some(*conn_data) }))
+                    let connection_attempts = conn_data.connection_attempts.clone();
+                    NextTunnelState::NewState((self, PrivateTunnelState::Connecting { connection_data: Some(*conn_data), connection_attempts }))
+                }
+                #[cfg(target_os = "linux")]
+                TunnelMonitorEvent::ProbingGateways { endpoints, ack } => {
+                    self.probe_endpoints = endpoints;
+                    let next_state = self.update_firewall_policy(shared_state);
+                    _ = ack.send(());
+                    next_state
                 }
                 TunnelMonitorEvent::SelectedGateways(new_gateways) => {
-                    self.selected_gateways = Some(*new_gateways);
-                    NextTunnelState::SameState(self)
+                    #[cfg(target_os = "linux")]
+                    {
+                        // The latency of the gateways has been measured by now.
+                        self.probe_endpoints.clear();
+                        self.peer_endpoints = Self::lookup_peer_endpoints(&new_gateways).await;
+                        self.selected_gateways = Some(*new_gateways);
+                        self.update_firewall_policy(shared_state)
+                    }
+
+                    #[cfg(not(target_os = "linux"))]
+                    {
+                        self.selected_gateways = Some(*new_gateways);
+                        NextTunnelState::SameState(self)
+                    }
+                }
+                #[cfg(target_os = "linux")]
+                TunnelMonitorEvent::TunnelInterfacesUp { interfaces, peer_endpoints, dns_servers } => {
+                    for peer_endpoint in peer_endpoints {
+                        if !self.peer_endpoints.contains(&peer_endpoint) {
+                            self.peer_endpoints.push(peer_endpoint);
+                        }
+                    }
+                    // The gateways have been registered with, so the APIs are not needed anymore.
+                    self.api_endpoints.clear();
+                    self.lookup_dns_servers.clear();
+                    self.tunnel_interface = Some(TunnelInterface { interfaces });
+                    self.dns_servers = dns_servers;
+                    self.update_firewall_policy(shared_state)
                 }
                 TunnelMonitorEvent::Up(conn_data) => {
-                    NextTunnelState::NewState(ConnectedState::enter(conn_data, self.monitor_handle, self.monitor_event_receiver, shared_state))
+                    if let Some(selected_gateways) = &self.selected_gateways {
+                        shared_state.sticky_gateways.set(selected_gateways.clone(), &shared_state.tunnel_settings);
+                    }
+                    NextTunnelState::NewState(ConnectedState::enter(
+                        conn_data,
+                        self.monitor_handle,
+                        self.monitor_event_receiver,
+                        #[cfg(target_os = "linux")]
+                        self.peer_endpoints,
+                        #[cfg(target_os = "linux")]
+                        self.tunnel_interface.unwrap_or_default(),
+                        #[cfg(target_os = "linux")]
+                        self.dns_servers,
+                        shared_state,
+                    ))
                 }
                 TunnelMonitorEvent::Down(reason) => {
                     if let Some(reason) = reason {
@@ -112,7 +342,18 @@ impl TunnelStateHandler for ConnectingState {
                         let tombstone = self.monitor_handle.wait().await;
                         Self::on_tunnel_exit(tombstone, shared_state).await;
 
-                        NextTunnelState::NewState(ConnectingState::enter(self.retry_attempt.saturating_add(1), self.selected_gateways, shared_state))
+                        let failed_attempts = self.retry_attempt.saturating_add(1);
+                        if shared_state.tunnel_settings.retry_policy.is_exhausted(failed_attempts) {
+                            tracing::warn!("Giving up connecting after {} failed attempts", failed_attempts);
+                            return NextTunnelState::NewState(ErrorState::enter(ErrorStateReason::ConnectionAttemptsExhausted, shared_state).await);
+                        }
+
+                        // Select new gateways if any of the current ones has been quarantined
+                        let selected_gateways = self.selected_gateways.filter(|selected_gateways| {
+                            !shared_state.gateway_quarantine.contains_any(selected_gateways)
+                        });
+
+                        NextTunnelState::NewState(ConnectingState::enter(self.retry_attempt.saturating_add(1), selected_gateways, shared_state).await)
                     }
                 }
             }
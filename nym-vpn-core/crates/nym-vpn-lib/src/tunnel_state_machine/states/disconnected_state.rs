@@ -4,6 +4,8 @@
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 
+#[cfg(target_os = "linux")]
+use crate::tunnel_state_machine::firewall_handler::FirewallPolicy;
 use crate::tunnel_state_machine::{
     states::ConnectingState, NextTunnelState, PrivateTunnelState, SharedState, TunnelCommand,
     TunnelStateHandler,
@@ -12,9 +14,35 @@ use crate::tunnel_state_machine::{
 pub struct DisconnectedState;
 
 impl DisconnectedState {
-    pub fn enter() -> (Box<dyn TunnelStateHandler>, PrivateTunnelState) {
+    pub fn enter(
+        _shared_state: &mut SharedState,
+    ) -> (Box<dyn TunnelStateHandler>, PrivateTunnelState) {
+        #[cfg(target_os = "linux")]
+        Self::set_firewall_policy(_shared_state);
+
         (Box::new(Self), PrivateTunnelState::Disconnected)
     }
+
+    /// Block all traffic in lockdown mode, otherwise remove the firewall rules.
+    #[cfg(target_os = "linux")]
+    fn set_firewall_policy(shared_state: &mut SharedState) {
+        let result = if shared_state.tunnel_settings.lockdown_mode {
+            shared_state
+                .firewall_handler
+                .apply_policy(FirewallPolicy::Blocked {
+                    allow_lan: shared_state.tunnel_settings.allow_lan,
+                })
+        } else {
+            shared_state.firewall_handler.reset_policy()
+        };
+
+        if let Err(e) = result {
+            tracing::error!(
+                "Failed to set firewall policy for disconnected state: {}",
+                e
+            );
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -32,11 +60,15 @@ impl TunnelStateHandler for DisconnectedState {
             Some(command) = command_rx.recv() => {
                 match command {
                     TunnelCommand::Connect => {
-                        NextTunnelState::NewState(ConnectingState::enter(0, None, shared_state))
+                        NextTunnelState::NewState(ConnectingState::enter(0, None, shared_state).await)
                     },
                     TunnelCommand::Disconnect => NextTunnelState::SameState(self),
                     TunnelCommand::SetTunnelSettings(tunnel_settings) => {
                         shared_state.tunnel_settings = tunnel_settings;
+
+                        #[cfg(target_os = "linux")]
+                        Self::set_firewall_policy(shared_state);
+
                         NextTunnelState::SameState(self)
                     }
                 }
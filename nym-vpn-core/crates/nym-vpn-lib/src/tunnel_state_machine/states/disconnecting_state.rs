@@ -5,6 +5,8 @@ use futures::future::{BoxFuture, Fuse, FutureExt};
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 
+#[cfg(target_os = "linux")]
+use crate::tunnel_state_machine::firewall_handler::FirewallPolicy;
 use crate::tunnel_state_machine::{
     states::{ConnectingState, DisconnectedState, ErrorState},
     tunnel::Tombstone,
@@ -33,6 +35,20 @@ impl DisconnectingState {
         }
         monitor_handle.cancel();
 
+        // Block traffic until the tunnel is torn down.
+        #[cfg(target_os = "linux")]
+        if let Err(e) = shared_state
+            .firewall_handler
+            .apply_policy(FirewallPolicy::Blocked {
+                allow_lan: shared_state.tunnel_settings.allow_lan,
+            })
+        {
+            tracing::error!(
+                "Failed to apply firewall policy for disconnecting state: {}",
+                e
+            );
+        }
+
         let retry_attempt =
             if let PrivateActionAfterDisconnect::Reconnect { retry_attempt } = &after_disconnect {
                 *retry_attempt
@@ -67,8 +83,6 @@ impl DisconnectingState {
         #[cfg(windows)]
         tombstone.wg_instances.clear();
         tombstone.tun_devices.clear();
-
-        // todo: reset firewall
     }
 }
 
@@ -86,18 +100,24 @@ impl TunnelStateHandler for DisconnectingState {
                 let result = self.wait_handle.await;
                 Self::on_tunnel_exit(result, shared_state).await;
 
-                NextTunnelState::NewState(DisconnectedState::enter())
+                NextTunnelState::NewState(DisconnectedState::enter(shared_state))
             }
             result = (&mut self.wait_handle) => {
                 Self::on_tunnel_exit(result, shared_state).await;
 
                 match self.after_disconnect {
-                    PrivateActionAfterDisconnect::Nothing => NextTunnelState::NewState(DisconnectedState::enter()),
+                    PrivateActionAfterDisconnect::Nothing => {
+                        shared_state.sticky_gateways.clear();
+                        NextTunnelState::NewState(DisconnectedState::enter(shared_state))
+                    },
                     PrivateActionAfterDisconnect::Error(reason) => {
                         NextTunnelState::NewState(ErrorState::enter(reason, shared_state).await)
                     },
                     PrivateActionAfterDisconnect::Reconnect { retry_attempt } => {
-                        NextTunnelState::NewState(ConnectingState::enter(retry_attempt, None, shared_state))
+                        let selected_gateways = shared_state
+                            .sticky_gateways
+                            .reconnect_gateways(&shared_state.tunnel_settings, &shared_state.gateway_quarantine);
+                        NextTunnelState::NewState(ConnectingState::enter(retry_attempt, selected_gateways, shared_state).await)
                     }
                 }
             }
@@ -14,6 +14,8 @@ use tokio_util::sync::CancellationToken;
 
 #[cfg(target_os = "ios")]
 use crate::tunnel_provider::{ios::OSTunProvider, tunnel_settings::TunnelSettings};
+#[cfg(target_os = "linux")]
+use crate::tunnel_state_machine::firewall_handler::FirewallPolicy;
 #[cfg(target_os = "ios")]
 use crate::tunnel_state_machine::tunnel::wireguard::two_hop_config::MIN_IPV6_MTU;
 use crate::tunnel_state_machine::{
@@ -43,9 +45,24 @@ impl ErrorState {
             Self::set_blocking_network_settings(_shared_state.tun_provider.clone()).await;
         }
 
+        #[cfg(target_os = "linux")]
+        Self::set_firewall_policy(_shared_state);
+
         (Box::new(Self), PrivateTunnelState::Error(reason))
     }
 
+    #[cfg(target_os = "linux")]
+    fn set_firewall_policy(shared_state: &mut SharedState) {
+        if let Err(e) = shared_state
+            .firewall_handler
+            .apply_policy(FirewallPolicy::Blocked {
+                allow_lan: shared_state.tunnel_settings.allow_lan,
+            })
+        {
+            tracing::error!("Failed to apply firewall policy for error state: {}", e);
+        }
+    }
+
     /// Configure tunnel with network settings blocking all traffic
     #[cfg(target_os = "ios")]
     async fn set_blocking_network_settings(tun_provider: Arc<dyn OSTunProvider>) {
@@ -80,11 +97,15 @@ impl TunnelStateHandler for ErrorState {
             Some(command) = command_rx.recv() => {
                 match command {
                     TunnelCommand::Connect => {
-                        NextTunnelState::NewState(ConnectingState::enter(0, None, shared_state))
+                        NextTunnelState::NewState(ConnectingState::enter(0, None, shared_state).await)
                     },
-                    TunnelCommand::Disconnect => NextTunnelState::NewState(DisconnectedState::enter()),
+                    TunnelCommand::Disconnect => NextTunnelState::NewState(DisconnectedState::enter(shared_state)),
                     TunnelCommand::SetTunnelSettings(tunnel_settings) => {
                         shared_state.tunnel_settings = tunnel_settings;
+
+                        #[cfg(target_os = "linux")]
+                        Self::set_firewall_policy(shared_state);
+
                         NextTunnelState::SameState(self)
                     }
                 }
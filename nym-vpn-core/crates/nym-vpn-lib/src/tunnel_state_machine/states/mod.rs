@@ -6,9 +6,13 @@ mod connecting_state;
 mod disconnected_state;
 mod disconnecting_state;
 mod error_state;
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod offline_state;
 
 pub use connected_state::ConnectedState;
 pub use connecting_state::ConnectingState;
 pub use disconnected_state::DisconnectedState;
 pub use disconnecting_state::DisconnectingState;
 pub use error_state::ErrorState;
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub use offline_state::OfflineState;
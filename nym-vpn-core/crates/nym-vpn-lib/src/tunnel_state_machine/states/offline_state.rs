@@ -0,0 +1,125 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::time::Duration;
+
+use futures::{
+    future::{BoxFuture, Fuse, FutureExt},
+    StreamExt,
+};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+#[cfg(target_os = "linux")]
+use crate::tunnel_state_machine::firewall_handler::FirewallPolicy;
+use crate::tunnel_state_machine::{
+    route_handler::RouteHandler,
+    states::{ConnectingState, DisconnectedState},
+    NextTunnelState, PrivateTunnelState, SharedState, TunnelCommand, TunnelStateHandler,
+};
+
+type WaitHandle = BoxFuture<'static, ()>;
+
+/// Delay before listening for default route changes again after the listener failed.
+const ROUTE_LISTENER_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// State entered when connecting without a default route. Traffic stays blocked and the state
+/// machine resumes connecting as soon as a default route becomes available.
+pub struct OfflineState {
+    wait_handle: Fuse<WaitHandle>,
+}
+
+impl OfflineState {
+    pub async fn enter(
+        shared_state: &mut SharedState,
+    ) -> (Box<dyn TunnelStateHandler>, PrivateTunnelState) {
+        #[cfg(target_os = "linux")]
+        Self::set_firewall_policy(shared_state);
+
+        let wait_handle = Self::wait_for_default_route(shared_state.route_handler.clone())
+            .boxed()
+            .fuse();
+
+        (Box::new(Self { wait_handle }), PrivateTunnelState::Offline)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn set_firewall_policy(shared_state: &mut SharedState) {
+        if let Err(e) = shared_state
+            .firewall_handler
+            .apply_policy(FirewallPolicy::Blocked {
+                allow_lan: shared_state.tunnel_settings.allow_lan,
+            })
+        {
+            tracing::error!("Failed to apply firewall policy for offline state: {}", e);
+        }
+    }
+
+    /// Resolves once a default route is available. Stays offline when the route listener fails,
+    /// retrying it after a delay, so that a broken listener does not loop back into connecting.
+    async fn wait_for_default_route(route_handler: RouteHandler) {
+        loop {
+            // Subscribe before checking the current route to avoid missing a change in between.
+            let route_listener = route_handler
+                .default_route_listener()
+                .await
+                .inspect_err(|e| tracing::error!("Failed to add default route listener: {}", e))
+                .ok();
+
+            if route_handler.has_default_route().await {
+                return;
+            }
+
+            if let Some(route_listener) = route_listener {
+                let mut route_listener = Box::pin(route_listener);
+                while route_listener.next().await.is_some() {
+                    if route_handler.has_default_route().await {
+                        return;
+                    }
+                }
+                tracing::error!("Default route listener has been dropped.");
+            }
+
+            tracing::debug!(
+                "Listening for default route changes again in {}s",
+                ROUTE_LISTENER_RETRY_DELAY.as_secs()
+            );
+            tokio::time::sleep(ROUTE_LISTENER_RETRY_DELAY).await;
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TunnelStateHandler for OfflineState {
+    async fn handle_event(
+        mut self: Box<Self>,
+        shutdown_token: &CancellationToken,
+        command_rx: &'async_trait mut mpsc::UnboundedReceiver<TunnelCommand>,
+        shared_state: &'async_trait mut SharedState,
+    ) -> NextTunnelState {
+        tokio::select! {
+            _ = shutdown_token.cancelled() => {
+                NextTunnelState::Finished
+            }
+            _ = &mut self.wait_handle => {
+                tracing::info!("Default route is available. Resuming connection.");
+                NextTunnelState::NewState(ConnectingState::enter(0, None, shared_state).await)
+            }
+            Some(command) = command_rx.recv() => {
+                match command {
+                    TunnelCommand::Connect => NextTunnelState::SameState(self),
+                    TunnelCommand::Disconnect => NextTunnelState::NewState(DisconnectedState::enter(shared_state)),
+                    TunnelCommand::SetTunnelSettings(tunnel_settings) => {
+                        shared_state.tunnel_settings = tunnel_settings;
+
+                        #[cfg(target_os = "linux")]
+                        Self::set_firewall_policy(shared_state);
+
+                        NextTunnelState::SameState(self)
+                    }
+                }
+            }
+            else => NextTunnelState::Finished
+        }
+    }
+}
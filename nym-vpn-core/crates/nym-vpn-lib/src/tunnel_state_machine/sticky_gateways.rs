@@ -0,0 +1,205 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::time::Instant;
+
+use nym_gateway_directory::{EntryPoint, ExitPoint, GatewayPreferences};
+
+use super::{
+    tunnel::SelectedGateways, GatewayPerformanceOptions, GatewayQuarantine, TunnelSettings,
+    TunnelType,
+};
+
+/// Gateways of the last established connection, kept so that reconnects use the same gateways.
+///
+/// Reusing the gateways keeps the exit IP address stable across reconnects, so that long lived
+/// sessions with websites are not disrupted. The gateways are kept while the connection is up and
+/// for the configured time after it is lost. They are dropped early when the settings they were
+/// selected with change, or when one of them is quarantined after failing to connect.
+#[derive(Debug, Default)]
+pub struct StickyGateways {
+    connection: Option<StickyConnection>,
+}
+
+#[derive(Debug)]
+struct StickyConnection {
+    selected_gateways: SelectedGateways,
+    tunnel_type: TunnelType,
+    entry_point: EntryPoint,
+    exit_point: ExitPoint,
+    gateway_preferences: GatewayPreferences,
+    gateway_performance_options: GatewayPerformanceOptions,
+
+    /// When the gateways are dropped. Not set while the connection is up.
+    expires_at: Option<Instant>,
+}
+
+impl StickyGateways {
+    /// Remember the gateways of a connection that has just been established.
+    pub fn set(&mut self, selected_gateways: SelectedGateways, tunnel_settings: &TunnelSettings) {
+        self.connection = Some(StickyConnection {
+            selected_gateways,
+            tunnel_type: tunnel_settings.tunnel_type,
+            entry_point: *tunnel_settings.entry_point.clone(),
+            exit_point: *tunnel_settings.exit_point.clone(),
+            gateway_preferences: tunnel_settings.gateway_preferences.clone(),
+            gateway_performance_options: tunnel_settings.gateway_performance_options,
+            expires_at: None,
+        });
+    }
+
+    /// Returns the gateways to reconnect with, if they can still be used with the tunnel
+    /// settings. The time window starts with the first reconnect after the connection was lost.
+    pub fn reconnect_gateways(
+        &mut self,
+        tunnel_settings: &TunnelSettings,
+        gateway_quarantine: &GatewayQuarantine,
+    ) -> Option<SelectedGateways> {
+        let Some(window) = tunnel_settings.sticky_gateways else {
+            self.connection = None;
+            return None;
+        };
+
+        let connection = self.connection.as_mut()?;
+        let now = Instant::now();
+        let expires_at = *connection.expires_at.get_or_insert(now + window);
+
+        let is_expired = now >= expires_at;
+        let is_outdated = connection.is_outdated(tunnel_settings);
+        let is_quarantined = gateway_quarantine.contains_any(&connection.selected_gateways);
+
+        if is_expired || is_outdated || is_quarantined {
+            tracing::info!(
+                "Selecting new gateways instead of the previous ones (expired: {}, settings changed: {}, quarantined: {})",
+                is_expired,
+                is_outdated,
+                is_quarantined
+            );
+            self.connection = None;
+            return None;
+        }
+
+        tracing::info!(
+            "Reconnecting with the previous gateways for another {}s",
+            expires_at.saturating_duration_since(now).as_secs()
+        );
+        Some(connection.selected_gateways.clone())
+    }
+
+    /// Forget the gateways, so that the next connection selects new ones.
+    pub fn clear(&mut self) {
+        self.connection = None;
+    }
+}
+
+impl StickyConnection {
+    /// Whether the gateways would be selected differently with the tunnel settings, because the
+    /// entry or exit point, the gateway preferences or the ranking inputs changed.
+    fn is_outdated(&self, tunnel_settings: &TunnelSettings) -> bool {
+        self.tunnel_type != tunnel_settings.tunnel_type
+            || self.entry_point != *tunnel_settings.entry_point
+            || self.exit_point != *tunnel_settings.exit_point
+            || self.gateway_preferences != tunnel_settings.gateway_preferences
+            || self.gateway_performance_options != tunnel_settings.gateway_performance_options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use nym_gateway_directory::{GatewayRanking, NodeIdentity};
+
+    use super::*;
+    use crate::tunnel_state_machine::test_utils::{
+        identity, selected_gateways, ENTRY_IDENTITY, EXIT_IDENTITY,
+    };
+
+    fn tunnel_settings() -> TunnelSettings {
+        TunnelSettings {
+            sticky_gateways: Some(Duration::from_secs(60)),
+            ..Default::default()
+        }
+    }
+
+    fn sticky_gateways(tunnel_settings: &TunnelSettings) -> StickyGateways {
+        let mut sticky_gateways = StickyGateways::default();
+        sticky_gateways.set(selected_gateways(), tunnel_settings);
+        sticky_gateways
+    }
+
+    fn reconnect_identities(
+        sticky_gateways: &mut StickyGateways,
+        tunnel_settings: &TunnelSettings,
+    ) -> Option<(NodeIdentity, NodeIdentity)> {
+        sticky_gateways
+            .reconnect_gateways(tunnel_settings, &GatewayQuarantine::default())
+            .map(|gateways| (gateways.entry.identity, gateways.exit.identity))
+    }
+
+    #[test]
+    fn reconnects_with_previous_gateways() {
+        let tunnel_settings = tunnel_settings();
+        let mut sticky = sticky_gateways(&tunnel_settings);
+
+        for _ in 0..2 {
+            assert_eq!(
+                reconnect_identities(&mut sticky, &tunnel_settings),
+                Some((identity(ENTRY_IDENTITY), identity(EXIT_IDENTITY)))
+            );
+        }
+    }
+
+    #[test]
+    fn drops_gateways_when_disabled_or_expired() {
+        let mut tunnel_settings = tunnel_settings();
+        let mut sticky = sticky_gateways(&tunnel_settings);
+        tunnel_settings.sticky_gateways = None;
+        assert_eq!(reconnect_identities(&mut sticky, &tunnel_settings), None);
+
+        tunnel_settings.sticky_gateways = Some(Duration::ZERO);
+        let mut sticky = sticky_gateways(&tunnel_settings);
+        assert_eq!(reconnect_identities(&mut sticky, &tunnel_settings), None);
+    }
+
+    #[test]
+    fn drops_gateways_when_quarantined() {
+        let tunnel_settings = tunnel_settings();
+        let mut sticky = sticky_gateways(&tunnel_settings);
+        let quarantine = GatewayQuarantine::default();
+        quarantine.add(identity(EXIT_IDENTITY), "handshake timed out".to_owned());
+
+        assert!(sticky
+            .reconnect_gateways(&tunnel_settings, &quarantine)
+            .is_none());
+        assert_eq!(reconnect_identities(&mut sticky, &tunnel_settings), None);
+    }
+
+    #[test]
+    fn drops_gateways_when_settings_change() {
+        let changes: [fn(&mut TunnelSettings); 5] = [
+            |settings| settings.tunnel_type = TunnelType::Mixnet,
+            |settings| {
+                settings.exit_point = Box::new(ExitPoint::Location {
+                    location: "CH".to_owned(),
+                })
+            },
+            |settings| {
+                settings
+                    .gateway_preferences
+                    .excluded_countries
+                    .push("CH".to_owned())
+            },
+            |settings| settings.gateway_performance_options.mixnet_min_performance = Some(90),
+            |settings| settings.gateway_performance_options.ranking = GatewayRanking::ProbeScore,
+        ];
+
+        for change in changes {
+            let mut tunnel_settings = tunnel_settings();
+            let mut sticky = sticky_gateways(&tunnel_settings);
+            change(&mut tunnel_settings);
+
+            assert_eq!(reconnect_identities(&mut sticky, &tunnel_settings), None);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Fixtures shared by the tests of the state machine and of its consumers.
+
+use nym_gateway_directory::{Gateway, NodeIdentity};
+
+use super::tunnel::SelectedGateways;
+
+/// Identity of the entry gateway of [`selected_gateways`].
+pub const ENTRY_IDENTITY: &str = "4SPdxfBYsuARBw6REQQa5vFiKcvmYiet9sSWqb751i3Z";
+
+/// Identity of the exit gateway of [`selected_gateways`].
+pub const EXIT_IDENTITY: &str = "FJDUECYAeosXhNGjxf8w5MJM7N2DfDwQznvWwTxJz6ft";
+
+/// Parses one of the identities above.
+pub fn identity(identity: &str) -> NodeIdentity {
+    NodeIdentity::from_base58_string(identity).unwrap()
+}
+
+/// Returns a gateway with the given identity and nothing else known about it.
+pub fn gateway(identity: NodeIdentity) -> Gateway {
+    Gateway {
+        identity,
+        location: None,
+        ipr_address: None,
+        authenticator_address: None,
+        last_probe: None,
+        host: None,
+        clients_ws_port: None,
+        clients_wss_port: None,
+        mixnet_performance: None,
+        version: None,
+    }
+}
+
+/// Returns the entry and exit gateways with [`ENTRY_IDENTITY`] and [`EXIT_IDENTITY`].
+pub fn selected_gateways() -> SelectedGateways {
+    SelectedGateways {
+        entry: gateway(identity(ENTRY_IDENTITY)),
+        exit: gateway(identity(EXIT_IDENTITY)),
+    }
+}
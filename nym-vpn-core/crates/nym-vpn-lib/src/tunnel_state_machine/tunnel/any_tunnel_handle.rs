@@ -1,6 +1,8 @@
 // Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
+use std::time::Duration;
+
 use super::{Error, Result, Tombstone};
 
 use super::{
@@ -37,6 +39,15 @@ impl AnyTunnelHandle {
         }
     }
 
+    /// Waits until the tunnel is able to pass traffic.
+    /// Mixnet tunnels are ready as soon as they are created.
+    pub async fn wait_for_handshake(&mut self, timeout: Duration) -> Result<()> {
+        match self {
+            Self::Mixnet(_) => Ok(()),
+            Self::Wireguard(handle) => handle.wait_for_handshake(timeout).await,
+        }
+    }
+
     pub async fn recv_error(
         &mut self,
     ) -> Option<Box<dyn std::error::Error + 'static + Send + Sync>> {
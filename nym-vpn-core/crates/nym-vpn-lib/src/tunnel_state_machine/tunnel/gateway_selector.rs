@@ -1,26 +1,78 @@
 // Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
-use nym_gateway_directory::{EntryPoint, ExitPoint, Gateway, GatewayClient, GatewayType};
+use std::time::Duration;
+
+use nym_gateway_directory::{
+    EntryPoint, ExitPoint, Gateway, GatewayClient, GatewayList, GatewayPreferences, GatewayRanking,
+    GatewayType, IpFamilies, NodeIdentity, RankingStrategy,
+};
+use rand::seq::SliceRandom;
 
 use crate::{tunnel_state_machine::TunnelType, GatewayDirectoryError};
 
+/// Maximum number of entry and exit gateway pairs tried in a row when connecting.
+const MAX_GATEWAY_CANDIDATES: usize = 3;
+
+/// Time given to measure the latency of the candidates of a low latency pair.
+const LATENCY_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Debug, Clone)]
 pub struct SelectedGateways {
     pub entry: Gateway,
     pub exit: Gateway,
 }
 
+impl SelectedGateways {
+    fn is_same_pair(&self, other: &SelectedGateways) -> bool {
+        self.entry.identity() == other.entry.identity()
+            && self.exit.identity() == other.exit.identity()
+    }
+}
+
+/// Gateways to connect with, in order of preference.
+#[derive(Debug, Clone)]
+pub struct GatewayCandidates {
+    /// Gateways tried first.
+    pub selected: SelectedGateways,
+
+    /// Gateways tried in turn when connecting with the previous ones fails.
+    pub alternatives: Vec<SelectedGateways>,
+}
+
+/// Gives access to the gateways whose latency is about to be measured, as the firewall blocks
+/// them while connecting.
+#[async_trait::async_trait]
+pub trait LatencyProbeAccess: Send + Sync {
+    /// Makes the client ports of the gateways reachable, returning once they are.
+    async fn allow(&self, gateways: &[Gateway]);
+}
+
+impl From<SelectedGateways> for GatewayCandidates {
+    fn from(selected: SelectedGateways) -> Self {
+        Self {
+            selected,
+            alternatives: Vec::new(),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn select_gateways(
     gateway_directory_client: &GatewayClient,
     tunnel_type: TunnelType,
     entry_point: Box<EntryPoint>,
     exit_point: Box<ExitPoint>,
-) -> Result<SelectedGateways, GatewayDirectoryError> {
+    gateway_preferences: &GatewayPreferences,
+    gateway_ranking: GatewayRanking,
+    routed_ip_families: IpFamilies,
+    quarantined_gateways: &[NodeIdentity],
+    probe_access: &dyn LatencyProbeAccess,
+) -> Result<GatewayCandidates, GatewayDirectoryError> {
     // The set of exit gateways is smaller than the set of entry gateways, so we start by selecting
     // the exit gateway and then filter out the exit gateway from the set of entry gateways.
 
-    let (mut entry_gateways, exit_gateways) = match tunnel_type {
+    let (entry_gateways, exit_gateways) = match tunnel_type {
         TunnelType::Wireguard => {
             let all_gateways = gateway_directory_client
                 .lookup_gateways(GatewayType::Wg)
@@ -46,30 +98,160 @@ pub async fn select_gateways(
     tracing::info!("Found {} entry gateways", entry_gateways.len());
     tracing::info!("Found {} exit gateways", exit_gateways.len());
 
-    let exit_gateway = exit_point
-        .lookup_gateway(&exit_gateways)
-        .map_err(|source| GatewayDirectoryError::FailedToSelectExitGateway { source })?;
+    tracing::debug!("Ranking gateways by {}", gateway_ranking);
+    let ranking = gateway_ranking.strategy(routed_ip_families);
+
+    if let ExitPoint::LowLatencyPair { ref location } = *exit_point {
+        tracing::info!(
+            "Selecting low latency gateways in {}, ignoring entry point: {}",
+            location,
+            entry_point
+        );
+        let (entry_gateway, exit_gateway) = select_low_latency_pair(
+            location,
+            &entry_gateways,
+            &exit_gateways,
+            gateway_preferences,
+            quarantined_gateways,
+            probe_access,
+        )
+        .await?;
+        let selected = SelectedGateways {
+            entry: entry_gateway,
+            exit: exit_gateway,
+        };
+        log_selected_gateways(&selected);
+        return Ok(GatewayCandidates::from(selected));
+    }
+
+    let is_entry_latency_based = matches!(*entry_point, EntryPoint::RandomLowLatency);
+    let is_exit_latency_based = matches!(*exit_point, ExitPoint::RandomLowLatency);
+    let mut probed_gateways = Vec::new();
+    if is_exit_latency_based {
+        probed_gateways.extend(exit_gateways.clone());
+    }
+    if is_entry_latency_based {
+        probed_gateways.extend(entry_gateways.clone());
+    }
+    if !probed_gateways.is_empty() {
+        probe_access.allow(&probed_gateways).await;
+    }
+
+    let selected = select_gateway_pair(
+        &entry_point,
+        &exit_point,
+        &entry_gateways,
+        &exit_gateways,
+        gateway_preferences,
+        ranking.as_ref(),
+        quarantined_gateways,
+    )
+    .await?;
+    log_selected_gateways(&selected);
+
+    // Alternatives avoid the gateways of the previous candidates, and stop as soon as the entry
+    // and exit points leave no other choice. Gateways selected by latency are not given
+    // alternatives, since measuring the latency again would delay connecting too much.
+    let is_latency_based = is_entry_latency_based || is_exit_latency_based;
+    let mut alternatives: Vec<SelectedGateways> = Vec::new();
+    while !is_latency_based && alternatives.len() + 1 < MAX_GATEWAY_CANDIDATES {
+        let avoided_gateways: Vec<_> = quarantined_gateways
+            .iter()
+            .copied()
+            .chain(
+                std::iter::once(&selected)
+                    .chain(&alternatives)
+                    .flat_map(|candidate| {
+                        [*candidate.entry.identity(), *candidate.exit.identity()]
+                    }),
+            )
+            .collect();
+        let Ok(alternative) = select_gateway_pair(
+            &entry_point,
+            &exit_point,
+            &entry_gateways,
+            &exit_gateways,
+            gateway_preferences,
+            ranking.as_ref(),
+            &avoided_gateways,
+        )
+        .await
+        else {
+            break;
+        };
+        let is_new = !selected.is_same_pair(&alternative)
+            && !alternatives
+                .iter()
+                .any(|candidate| candidate.is_same_pair(&alternative));
+        if !is_new {
+            break;
+        }
+        tracing::debug!(
+            "Alternative gateways: entry {}, exit {}",
+            alternative.entry.identity(),
+            alternative.exit.identity()
+        );
+        alternatives.push(alternative);
+    }
+
+    Ok(GatewayCandidates {
+        selected,
+        alternatives,
+    })
+}
+
+async fn select_gateway_pair(
+    entry_point: &EntryPoint,
+    exit_point: &ExitPoint,
+    entry_gateways: &GatewayList,
+    exit_gateways: &GatewayList,
+    gateway_preferences: &GatewayPreferences,
+    ranking: &dyn RankingStrategy,
+    avoided_gateways: &[NodeIdentity],
+) -> Result<SelectedGateways, GatewayDirectoryError> {
+    let exit_gateway = lookup_exit_gateway(
+        exit_point,
+        exit_gateways,
+        gateway_preferences,
+        ranking,
+        avoided_gateways,
+    )
+    .await
+    .map_err(|source| GatewayDirectoryError::FailedToSelectExitGateway { source })?;
 
     // Exclude the exit gateway from the list of entry gateways for privacy reasons
+    let mut entry_gateways = entry_gateways.clone();
     entry_gateways.remove_gateway(&exit_gateway);
 
-    let entry_gateway = entry_point
-        .lookup_gateway(&entry_gateways)
-        .await
-        .map_err(|source| match source {
-            nym_gateway_directory::Error::NoMatchingEntryGatewayForLocation {
-                requested_location,
-                available_countries: _,
-            } if Some(requested_location.as_str())
-                == exit_gateway.two_letter_iso_country_code() =>
-            {
-                GatewayDirectoryError::SameEntryAndExitGatewayFromCountry {
-                    requested_location: requested_location.to_string(),
-                }
+    let entry_gateway = lookup_entry_gateway(
+        entry_point,
+        &entry_gateways,
+        gateway_preferences,
+        ranking,
+        avoided_gateways,
+    )
+    .await
+    .map_err(|source| match source {
+        nym_gateway_directory::Error::NoMatchingEntryGatewayForLocation {
+            requested_location,
+            available_countries: _,
+        } if Some(requested_location.as_str()) == exit_gateway.two_letter_iso_country_code() => {
+            GatewayDirectoryError::SameEntryAndExitGatewayFromCountry {
+                requested_location: requested_location.to_string(),
             }
-            _ => GatewayDirectoryError::FailedToSelectEntryGateway { source },
-        })?;
+        }
+        _ => GatewayDirectoryError::FailedToSelectEntryGateway { source },
+    })?;
 
+    Ok(SelectedGateways {
+        entry: entry_gateway,
+        exit: exit_gateway,
+    })
+}
+
+fn log_selected_gateways(selected_gateways: &SelectedGateways) {
+    let entry_gateway = &selected_gateways.entry;
+    let exit_gateway = &selected_gateways.exit;
     tracing::info!(
         "Using entry gateway: {}, location: {}, performance: {}",
         *entry_gateway.identity(),
@@ -96,9 +278,177 @@ pub async fn select_gateways(
             .ipr_address
             .map_or_else(|| "none".to_string(), |ipr| ipr.to_string())
     );
+}
 
-    Ok(SelectedGateways {
-        entry: entry_gateway,
-        exit: exit_gateway,
-    })
+// Quarantined gateways are avoided unless they are requested explicitly, or no other gateway
+// matches the exit point.
+async fn lookup_exit_gateway(
+    exit_point: &ExitPoint,
+    exit_gateways: &GatewayList,
+    gateway_preferences: &GatewayPreferences,
+    ranking: &dyn RankingStrategy,
+    quarantined_gateways: &[NodeIdentity],
+) -> Result<Gateway, nym_gateway_directory::Error> {
+    let is_explicit = matches!(
+        exit_point,
+        ExitPoint::Address { .. } | ExitPoint::Gateway { .. }
+    );
+    if !is_explicit && !quarantined_gateways.is_empty() {
+        let available_gateways = exit_gateways.without_gateways(quarantined_gateways);
+        match exit_point
+            .lookup_gateway(&available_gateways, gateway_preferences, ranking)
+            .await
+        {
+            Ok(exit_gateway) => return Ok(exit_gateway),
+            Err(e) => tracing::warn!(
+                "No exit gateway available outside of quarantine, using all gateways: {}",
+                e
+            ),
+        }
+    }
+    exit_point
+        .lookup_gateway(exit_gateways, gateway_preferences, ranking)
+        .await
+}
+
+// Quarantined gateways are avoided unless they are requested explicitly, or no other gateway
+// matches the entry point.
+async fn lookup_entry_gateway(
+    entry_point: &EntryPoint,
+    entry_gateways: &GatewayList,
+    gateway_preferences: &GatewayPreferences,
+    ranking: &dyn RankingStrategy,
+    quarantined_gateways: &[NodeIdentity],
+) -> Result<Gateway, nym_gateway_directory::Error> {
+    let is_explicit = matches!(entry_point, EntryPoint::Gateway { .. });
+    if !is_explicit && !quarantined_gateways.is_empty() {
+        let available_gateways = entry_gateways.without_gateways(quarantined_gateways);
+        match entry_point
+            .lookup_gateway(&available_gateways, gateway_preferences, ranking)
+            .await
+        {
+            Ok(entry_gateway) => return Ok(entry_gateway),
+            Err(e) => tracing::warn!(
+                "No entry gateway available outside of quarantine, using all gateways: {}",
+                e
+            ),
+        }
+    }
+    entry_point
+        .lookup_gateway(entry_gateways, gateway_preferences, ranking)
+        .await
+}
+
+// Selects low latency entry and exit gateways in the location, measuring their latency the same
+// way as for the random low latency entry and exit points. The entry and exit candidates are
+// measured together, each gateway once. Quarantined gateways are avoided unless there is no other
+// candidate.
+async fn select_low_latency_pair(
+    location: &str,
+    entry_gateways: &GatewayList,
+    exit_gateways: &GatewayList,
+    gateway_preferences: &GatewayPreferences,
+    quarantined_gateways: &[NodeIdentity],
+    probe_access: &dyn LatencyProbeAccess,
+) -> Result<(Gateway, Gateway), GatewayDirectoryError> {
+    let candidates_located_at = |gateways: &GatewayList| {
+        let candidates = GatewayList::new(
+            gateways
+                .gateways_located_at(location.to_string())
+                .cloned()
+                .collect(),
+        )
+        .with_preferences(gateway_preferences);
+        let available_candidates = candidates.without_gateways(quarantined_gateways);
+        if available_candidates.is_empty() {
+            candidates
+        } else {
+            available_candidates
+        }
+    };
+    let entry_candidates = candidates_located_at(entry_gateways);
+    let exit_candidates = candidates_located_at(exit_gateways);
+
+    let mut candidates = entry_candidates.clone().into_inner();
+    for exit_candidate in exit_candidates.clone() {
+        if entry_candidates
+            .gateway_with_identity(exit_candidate.identity())
+            .is_none()
+        {
+            candidates.push(exit_candidate);
+        }
+    }
+    if exit_candidates.is_empty() {
+        return Err(GatewayDirectoryError::FailedToSelectExitGateway {
+            source: nym_gateway_directory::Error::NoMatchingExitGatewayForLocation {
+                requested_location: location.to_string(),
+                available_countries: exit_gateways.all_iso_codes(),
+            },
+        });
+    }
+
+    probe_access.allow(&candidates).await;
+    let latencies = GatewayList::new(candidates)
+        .measure_latencies(LATENCY_PROBE_TIMEOUT)
+        .await;
+
+    let exit_gateway = choose_low_latency_gateway(&latencies, &exit_candidates).ok_or(
+        GatewayDirectoryError::FailedToSelectExitGateway {
+            source: nym_gateway_directory::Error::NoGatewayLatency,
+        },
+    )?;
+
+    // The entry and exit gateways must differ for privacy reasons
+    let entry_candidates = entry_candidates.without_gateways(&[*exit_gateway.identity()]);
+    if entry_candidates.is_empty() {
+        let has_entry_gateways = entry_gateways
+            .gateways_located_at(location.to_string())
+            .next()
+            .is_some();
+        return Err(if has_entry_gateways {
+            GatewayDirectoryError::SameEntryAndExitGatewayFromCountry {
+                requested_location: location.to_string(),
+            }
+        } else {
+            GatewayDirectoryError::FailedToSelectEntryGateway {
+                source: nym_gateway_directory::Error::NoMatchingEntryGatewayForLocation {
+                    requested_location: location.to_string(),
+                    available_countries: entry_gateways.all_iso_codes(),
+                },
+            }
+        });
+    }
+    let entry_gateway = choose_low_latency_gateway(&latencies, &entry_candidates).ok_or(
+        GatewayDirectoryError::FailedToSelectEntryGateway {
+            source: nym_gateway_directory::Error::NoGatewayLatency,
+        },
+    )?;
+
+    Ok((entry_gateway, exit_gateway))
+}
+
+// Picks one of the candidates whose latency was measured at random, favoring the lower latencies.
+fn choose_low_latency_gateway(
+    latencies: &[(Gateway, Duration)],
+    candidates: &GatewayList,
+) -> Option<Gateway> {
+    let measured: Vec<_> = latencies
+        .iter()
+        .filter(|(gateway, _)| {
+            candidates
+                .gateway_with_identity(gateway.identity())
+                .is_some()
+        })
+        .collect();
+    let (gateway, latency) = measured
+        .choose_weighted(&mut rand::thread_rng(), |(_, latency)| {
+            1.0 / latency.as_secs_f64().max(f64::EPSILON)
+        })
+        .ok()?;
+    tracing::debug!(
+        "Selected gateway {} with latency {:?}",
+        gateway.identity(),
+        latency
+    );
+    Some(gateway.clone())
 }
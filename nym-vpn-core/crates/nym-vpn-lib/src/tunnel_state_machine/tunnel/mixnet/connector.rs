@@ -1,7 +1,7 @@
 // Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::net::IpAddr;
+use std::{net::IpAddr, time::Duration};
 
 use nym_gateway_directory::{GatewayClient, IpPacketRouterAddress, Recipient};
 use nym_ip_packet_client::IprClientConnect;
@@ -47,10 +47,12 @@ impl Connector {
         self,
         selected_gateways: SelectedGateways,
         nym_ips: Option<IpPair>,
+        connect_timeout: Duration,
     ) -> Result<ConnectedTunnel, ConnectorError> {
         let result = Self::connect_inner(
             selected_gateways,
             nym_ips,
+            connect_timeout,
             self.mixnet_client.clone(),
             &self.gateway_directory_client,
         )
@@ -76,6 +78,7 @@ impl Connector {
     async fn connect_inner(
         selected_gateways: SelectedGateways,
         nym_ips: Option<IpPair>,
+        connect_timeout: Duration,
         mixnet_client: SharedMixnetClient,
         gateway_directory_client: &GatewayClient,
     ) -> Result<AssignedAddresses> {
@@ -92,10 +95,13 @@ impl Connector {
         let exit_mix_addresses = selected_gateways.exit.ipr_address.unwrap();
 
         let mut ipr_client = IprClientConnect::new(mixnet_client.clone()).await;
-        let interface_addresses = ipr_client
-            .connect(exit_mix_addresses.0, nym_ips)
-            .await
-            .map_err(Error::ConnectToIpPacketRouter)?;
+        let interface_addresses = tokio::time::timeout(
+            connect_timeout,
+            ipr_client.connect(exit_mix_addresses.0, nym_ips),
+        )
+        .await
+        .map_err(|_| Error::ConnectToIpPacketRouterTimeout)?
+        .map_err(Error::ConnectToIpPacketRouter)?;
 
         if let Some(exit_country_code) = selected_gateways.exit.two_letter_iso_country_code() {
             mixnet_client
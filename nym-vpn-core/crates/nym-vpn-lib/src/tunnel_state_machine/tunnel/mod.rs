@@ -12,8 +12,11 @@ use std::{error::Error as StdError, fmt, path::PathBuf, time::Duration};
 #[cfg(unix)]
 use std::{os::fd::RawFd, sync::Arc};
 
-pub use gateway_selector::SelectedGateways;
-use nym_gateway_directory::{EntryPoint, ExitPoint, GatewayClient, Recipient};
+pub use gateway_selector::{GatewayCandidates, LatencyProbeAccess, SelectedGateways};
+use nym_gateway_directory::{
+    EntryPoint, ExitPoint, GatewayClient, GatewayPreferences, GatewayRanking, IpFamilies,
+    NodeIdentity, Recipient,
+};
 use nym_ip_packet_requests::IpPair;
 use nym_mixnet_client::SharedMixnetClient;
 use nym_sdk::UserAgent;
@@ -25,8 +28,8 @@ use tokio_util::sync::CancellationToken;
 use super::route_handler;
 use super::{MixnetEvent, TunnelType};
 use crate::{
-    bandwidth_controller::ReconnectMixnetClientData, GatewayDirectoryError, MixnetClientConfig,
-    MixnetError,
+    bandwidth_controller::{Error as BandwidthControllerError, ReconnectMixnetClientData},
+    GatewayDirectoryError, MixnetClientConfig, MixnetError,
 };
 pub use any_tunnel_handle::AnyTunnelHandle;
 use status_listener::StatusListener;
@@ -72,6 +75,7 @@ impl ConnectedMixnet {
     pub async fn connect_mixnet_tunnel(
         self,
         interface_addresses: Option<IpPair>, // known as config.nym_ips
+        connect_timeout: Duration,
     ) -> Result<mixnet::connected_tunnel::ConnectedTunnel> {
         let connector = mixnet::connector::Connector::new(
             self.task_manager,
@@ -80,7 +84,7 @@ impl ConnectedMixnet {
         );
 
         match connector
-            .connect(self.selected_gateways, interface_addresses)
+            .connect(self.selected_gateways, interface_addresses, connect_timeout)
             .await
         {
             Ok(connected_tunnel) => Ok(connected_tunnel),
@@ -95,6 +99,7 @@ impl ConnectedMixnet {
     pub async fn connect_wireguard_tunnel(
         self,
         enable_credentials_mode: bool,
+        registration_timeout: Duration,
     ) -> Result<wireguard::connected_tunnel::ConnectedTunnel> {
         let connector = wireguard::connector::Connector::new(
             self.task_manager,
@@ -108,6 +113,7 @@ impl ConnectedMixnet {
                 self.selected_gateways,
                 self.data_path,
                 self.reconnect_mixnet_client_data,
+                registration_timeout,
             )
             .await
         {
@@ -137,14 +143,20 @@ pub struct MixnetConnectOptions {
     pub user_agent: Option<UserAgent>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn select_gateways(
     gateway_config: nym_gateway_directory::Config,
     tunnel_type: TunnelType,
     entry_point: Box<EntryPoint>,
     exit_point: Box<ExitPoint>,
+    gateway_preferences: &GatewayPreferences,
+    gateway_ranking: GatewayRanking,
+    routed_ip_families: IpFamilies,
+    quarantined_gateways: &[NodeIdentity],
+    probe_access: &dyn LatencyProbeAccess,
     user_agent: Option<UserAgent>,
     cancel_token: CancellationToken,
-) -> Result<SelectedGateways> {
+) -> Result<GatewayCandidates> {
     let user_agent =
         user_agent.unwrap_or(UserAgent::from(nym_bin_common::bin_info_local_vergen!()));
     let gateway_directory_client =
@@ -155,6 +167,11 @@ pub async fn select_gateways(
         tunnel_type,
         entry_point,
         exit_point,
+        gateway_preferences,
+        gateway_ranking,
+        routed_ip_families,
+        quarantined_gateways,
+        probe_access,
     );
     cancel_token
         .run_until_cancelled(select_gateways_fut)
@@ -272,6 +289,18 @@ pub enum Error {
     #[error("failed to connect ot ip packet router: {}", _0)]
     ConnectToIpPacketRouter(#[source] nym_ip_packet_client::Error),
 
+    #[error("connecting to ip packet router timed out")]
+    ConnectToIpPacketRouterTimeout,
+
+    #[error(
+        "registering with the authenticator of gateway {} timed out",
+        gateway_id
+    )]
+    AuthenticatorRegistrationTimeout { gateway_id: String },
+
+    #[error("wireguard handshake with the exit gateway timed out")]
+    WireguardHandshakeTimeout,
+
     #[error("wireguard authentication is not possible due to one of the gateways not running the authenticator process: {0}")]
     AuthenticationNotPossible(String),
 
@@ -306,6 +335,29 @@ pub enum Error {
     Cancelled,
 }
 
+impl Error {
+    /// Returns the gateway that could not be connected to, if the error was caused by one of the
+    /// selected gateways.
+    pub fn failed_gateway(&self, selected_gateways: &SelectedGateways) -> Option<NodeIdentity> {
+        match self {
+            Self::StartMixnetClientTimeout
+            | Self::MixnetClient(MixnetError::FailedToConnectToMixnet(_)) => {
+                Some(*selected_gateways.entry.identity())
+            }
+            Self::ConnectToIpPacketRouter(_)
+            | Self::ConnectToIpPacketRouterTimeout
+            | Self::WireguardHandshakeTimeout => Some(*selected_gateways.exit.identity()),
+            Self::MixnetClient(MixnetError::EntryGateway { gateway_id, .. })
+            | Self::AuthenticatorRegistrationTimeout { gateway_id }
+            | Self::BandwidthController(BandwidthControllerError::RegisterWireguard {
+                gateway_id,
+                ..
+            }) => NodeIdentity::from_base58_string(gateway_id).ok(),
+            _ => None,
+        }
+    }
+}
+
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 /// Tunnel connector container.
@@ -357,3 +409,46 @@ impl fmt::Display for ConnectorError {
         self.error.fmt(f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::tunnel_state_machine::test_utils::{self, ENTRY_IDENTITY, EXIT_IDENTITY};
+
+    #[test]
+    fn entry_connect_errors_fail_entry_gateway() {
+        let selected_gateways = test_utils::selected_gateways();
+        let entry = Some(test_utils::identity(ENTRY_IDENTITY));
+
+        let errors = [
+            Error::StartMixnetClientTimeout,
+            Error::MixnetClient(MixnetError::EntryGateway {
+                gateway_id: ENTRY_IDENTITY.to_owned(),
+                source: Box::new(io::Error::other("connection refused")),
+            }),
+        ];
+        for error in errors {
+            assert_eq!(error.failed_gateway(&selected_gateways), entry);
+        }
+
+        let error = Error::MixnetClient(MixnetError::InvalidCredential);
+        assert_eq!(error.failed_gateway(&selected_gateways), None);
+    }
+
+    #[test]
+    fn registration_timeout_fails_registered_gateway() {
+        let selected_gateways = test_utils::selected_gateways();
+
+        for identity in [ENTRY_IDENTITY, EXIT_IDENTITY] {
+            let error = Error::AuthenticatorRegistrationTimeout {
+                gateway_id: identity.to_owned(),
+            };
+            assert_eq!(
+                error.failed_gateway(&selected_gateways),
+                Some(test_utils::identity(identity))
+            );
+        }
+    }
+}
@@ -1,11 +1,14 @@
 // Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::{error::Error as StdError, net::IpAddr};
+use std::{error::Error as StdError, net::IpAddr, time::Duration};
 
 #[cfg(windows)]
 use tokio::sync::mpsc;
-use tokio::task::{JoinError, JoinHandle};
+use tokio::{
+    sync::oneshot,
+    task::{JoinError, JoinHandle},
+};
 use tokio_util::sync::CancellationToken;
 #[cfg(unix)]
 use tun::AsyncDevice;
@@ -141,6 +144,7 @@ impl ConnectedTunnel {
 
         let shutdown_token = CancellationToken::new();
         let child_shutdown_token = shutdown_token.child_token();
+        let (handshake_tx, handshake_rx) = oneshot::channel();
 
         #[cfg(windows)]
         let wintun_entry_interface = entry_tunnel.wintun_interface().clone();
@@ -148,6 +152,11 @@ impl ConnectedTunnel {
         let wintun_exit_interface = exit_tunnel.wintun_interface().clone();
 
         let event_handler_task = tokio::spawn(async move {
+            tokio::select! {
+                _ = child_shutdown_token.cancelled() => {}
+                _ = super::notify_handshake(&exit_tunnel, handshake_tx) => {}
+            }
+
             #[cfg(windows)]
             {
                 let (default_route_tx, mut default_route_rx) = mpsc::unbounded_channel();
@@ -197,6 +206,7 @@ impl ConnectedTunnel {
             shutdown_token,
             event_handler_task,
             bandwidth_controller_handle: self.bandwidth_controller_handle,
+            handshake_rx: Some(handshake_rx),
             #[cfg(windows)]
             wintun_entry_interface: Some(wintun_entry_interface),
             #[cfg(windows)]
@@ -249,11 +259,17 @@ impl ConnectedTunnel {
 
         let shutdown_token = CancellationToken::new();
         let child_shutdown_token = shutdown_token.child_token();
+        let (handshake_tx, handshake_rx) = oneshot::channel();
 
         #[cfg(windows)]
         let wintun_exit_interface = exit_tunnel.wintun_interface().clone();
 
         let event_handler_task = tokio::spawn(async move {
+            tokio::select! {
+                _ = child_shutdown_token.cancelled() => {}
+                _ = super::notify_handshake(&exit_tunnel, handshake_tx) => {}
+            }
+
             #[cfg(windows)]
             {
                 let (default_route_tx, mut default_route_rx) = mpsc::unbounded_channel();
@@ -305,6 +321,7 @@ impl ConnectedTunnel {
             shutdown_token,
             event_handler_task,
             bandwidth_controller_handle: self.bandwidth_controller_handle,
+            handshake_rx: Some(handshake_rx),
             #[cfg(windows)]
             wintun_entry_interface: None,
             #[cfg(windows)]
@@ -428,6 +445,7 @@ pub struct TunnelHandle {
     shutdown_token: CancellationToken,
     event_handler_task: JoinHandle<Tombstone>,
     bandwidth_controller_handle: JoinHandle<()>,
+    handshake_rx: Option<oneshot::Receiver<()>>,
     #[cfg(windows)]
     wintun_entry_interface: Option<WintunInterface>,
     #[cfg(windows)]
@@ -452,6 +470,11 @@ impl TunnelHandle {
         self.task_manager.wait_for_error().await
     }
 
+    /// Wait until the exit tunnel completes its first handshake with the exit gateway.
+    pub async fn wait_for_handshake(&mut self, timeout: Duration) -> Result<()> {
+        super::wait_for_handshake(&mut self.handshake_rx, timeout).await
+    }
+
     /// Wait until the tunnel finished execution.
     ///
     /// Returns a tombstone containing the no longer used tunnel devices and wireguard tunnels (on Windows).
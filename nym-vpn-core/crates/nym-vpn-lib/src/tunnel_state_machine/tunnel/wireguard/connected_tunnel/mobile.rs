@@ -1,11 +1,14 @@
 // Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::{error::Error as StdError, net::IpAddr, sync::Arc};
+use std::{error::Error as StdError, net::IpAddr, sync::Arc, time::Duration};
 
 #[cfg(target_os = "ios")]
 use tokio::sync::mpsc;
-use tokio::task::{JoinError, JoinHandle};
+use tokio::{
+    sync::oneshot,
+    task::{JoinError, JoinHandle},
+};
 use tokio_util::sync::CancellationToken;
 use tun::AsyncDevice;
 
@@ -133,6 +136,7 @@ impl ConnectedTunnel {
 
         let shutdown_token = CancellationToken::new();
         let cloned_shutdown_token = shutdown_token.child_token();
+        let (handshake_tx, handshake_rx) = oneshot::channel();
 
         #[cfg(target_os = "ios")]
         let mut default_path_rx = {
@@ -148,6 +152,11 @@ impl ConnectedTunnel {
         };
 
         let event_loop_handle = tokio::spawn(async move {
+            tokio::select! {
+                _ = cloned_shutdown_token.cancelled() => {}
+                _ = super::notify_handshake(&exit_tunnel, handshake_tx) => {}
+            }
+
             #[cfg(target_os = "ios")]
             loop {
                 tokio::select! {
@@ -209,6 +218,7 @@ impl ConnectedTunnel {
             shutdown_token,
             event_loop_handle,
             bandwidth_controller_handle: self.bandwidth_controller_handle,
+            handshake_rx: Some(handshake_rx),
         })
     }
 }
@@ -218,6 +228,7 @@ pub struct TunnelHandle {
     shutdown_token: CancellationToken,
     event_loop_handle: JoinHandle<Tombstone>,
     bandwidth_controller_handle: JoinHandle<()>,
+    handshake_rx: Option<oneshot::Receiver<()>>,
 }
 
 impl TunnelHandle {
@@ -238,6 +249,11 @@ impl TunnelHandle {
         self.task_manager.wait_for_error().await
     }
 
+    /// Wait until the exit tunnel completes its first handshake with the exit gateway.
+    pub async fn wait_for_handshake(&mut self, timeout: Duration) -> Result<()> {
+        super::wait_for_handshake(&mut self.handshake_rx, timeout).await
+    }
+
     /// Wait until the tunnel finished execution.
     ///
     /// Returns an array with a single tunnel device that is no longer in use.
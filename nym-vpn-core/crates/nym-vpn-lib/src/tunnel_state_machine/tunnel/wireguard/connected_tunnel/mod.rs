@@ -14,3 +14,38 @@ pub use desktop::{
 
 #[cfg(any(target_os = "ios", target_os = "android"))]
 pub use mobile::{ConnectedTunnel, TunnelHandle};
+
+use std::time::Duration;
+
+use nym_wg_go::wireguard_go;
+use tokio::sync::oneshot;
+
+use crate::tunnel_state_machine::tunnel::{Error, Result};
+
+/// Interval at which the exit tunnel is checked for a completed handshake.
+const HANDSHAKE_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Notifies once the exit tunnel completes its first handshake.
+async fn notify_handshake(exit_tunnel: &wireguard_go::Tunnel, handshake_tx: oneshot::Sender<()>) {
+    while !exit_tunnel.has_handshake() {
+        tokio::time::sleep(HANDSHAKE_POLL_INTERVAL).await;
+    }
+    tracing::debug!("Exit tunnel completed the handshake");
+    let _ = handshake_tx.send(());
+}
+
+/// Waits for the notification sent by `notify_handshake`, unless it was already received.
+async fn wait_for_handshake(
+    handshake_rx: &mut Option<oneshot::Receiver<()>>,
+    timeout: Duration,
+) -> Result<()> {
+    let Some(handshake_rx) = handshake_rx.take() else {
+        return Ok(());
+    };
+    match tokio::time::timeout(timeout, handshake_rx).await {
+        Ok(Ok(())) => Ok(()),
+        // The tunnel was shut down before completing the handshake.
+        Ok(Err(_)) => Err(Error::Cancelled),
+        Err(_) => Err(Error::WireguardHandshakeTimeout),
+    }
+}
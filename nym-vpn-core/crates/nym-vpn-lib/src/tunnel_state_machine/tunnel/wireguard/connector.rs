@@ -1,13 +1,13 @@
 // Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
 use tokio::task::JoinHandle;
 
 use nym_authenticator_client::AuthClient;
 use nym_credentials_interface::TicketType;
-use nym_gateway_directory::{AuthAddresses, Gateway, GatewayClient};
+use nym_gateway_directory::{AuthAddresses, Gateway, GatewayClient, NodeIdentity};
 use nym_mixnet_client::SharedMixnetClient;
 use nym_sdk::mixnet::{ConnectionStatsEvent, EphemeralCredentialStorage, StoragePaths};
 use nym_task::TaskManager;
@@ -50,8 +50,10 @@ impl Connector {
         selected_gateways: SelectedGateways,
         data_path: Option<PathBuf>,
         reconnect_mixnet_client_data: ReconnectMixnetClientData,
+        registration_timeout: Duration,
     ) -> Result<ConnectedTunnel, ConnectorError> {
-        let result = Self::connect_inner(
+        let mut registered_gateway = *selected_gateways.entry.identity();
+        let connect_fut = Self::connect_inner(
             &self.task_manager,
             self.mixnet_client.clone(),
             &self.gateway_directory_client,
@@ -59,8 +61,14 @@ impl Connector {
             selected_gateways,
             data_path,
             reconnect_mixnet_client_data,
-        )
-        .await;
+            &mut registered_gateway,
+        );
+        let result = tokio::time::timeout(registration_timeout, connect_fut).await;
+        let result = result.unwrap_or_else(|_| {
+            Err(Error::AuthenticatorRegistrationTimeout {
+                gateway_id: registered_gateway.to_base58_string(),
+            })
+        });
 
         match result {
             Ok(connect_result) => Ok(ConnectedTunnel::new(
@@ -81,6 +89,9 @@ impl Connector {
         }
     }
 
+    // The gateway being registered with is kept in `registered_gateway`, so that it is known
+    // when registering times out.
+    #[allow(clippy::too_many_arguments)]
     async fn connect_inner(
         task_manager: &TaskManager,
         mixnet_client: SharedMixnetClient,
@@ -89,6 +100,7 @@ impl Connector {
         selected_gateways: SelectedGateways,
         data_path: Option<PathBuf>,
         reconnect_mixnet_client_data: ReconnectMixnetClientData,
+        registered_gateway: &mut NodeIdentity,
     ) -> Result<ConnectResult> {
         let auth_addresses =
             Self::setup_auth_addresses(&selected_gateways.entry, &selected_gateways.exit)?;
@@ -156,6 +168,7 @@ impl Connector {
                     &mut wg_entry_gateway_client,
                 )
                 .await?;
+            *registered_gateway = *selected_gateways.exit.identity();
             let exit = bw
                 .get_initial_bandwidth(
                     enable_credentials_mode,
@@ -185,6 +198,7 @@ impl Connector {
                     &mut wg_entry_gateway_client,
                 )
                 .await?;
+            *registered_gateway = *selected_gateways.exit.identity();
             let exit = bw
                 .get_initial_bandwidth(
                     enable_credentials_mode,
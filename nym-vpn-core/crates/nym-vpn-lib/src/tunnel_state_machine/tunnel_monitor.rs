@@ -1,19 +1,26 @@
+use std::net::IpAddr;
 #[cfg(any(target_os = "linux", target_os = "macos"))]
 use std::net::Ipv4Addr;
 #[cfg(any(target_os = "android", target_os = "ios"))]
 use std::os::fd::{AsRawFd, IntoRawFd};
 #[cfg(target_os = "android")]
 use std::os::fd::{FromRawFd, OwnedFd};
-use std::{cmp, time::Duration};
+use std::time::{Duration, Instant};
 #[cfg(unix)]
 use std::{os::fd::RawFd, sync::Arc};
 
 #[cfg(windows)]
 use super::wintun::{self, WintunAdapterConfig};
+#[cfg(target_os = "linux")]
+use futures::StreamExt;
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+use ipnetwork::IpNetwork;
 #[cfg(any(target_os = "ios", target_os = "android"))]
 use ipnetwork::{IpNetwork, Ipv4Network, Ipv6Network};
-use nym_gateway_directory::GatewayMinPerformance;
+use nym_gateway_directory::{Gateway, GatewayMinPerformance};
 use time::OffsetDateTime;
+#[cfg(target_os = "linux")]
+use tokio::sync::oneshot;
 use tokio::{sync::mpsc, task::JoinHandle};
 use tokio_util::sync::CancellationToken;
 use tun::AsyncDevice;
@@ -23,18 +30,28 @@ use tun::Device;
 #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
 use nym_ip_packet_requests::IpPair;
 
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+use super::SplitRoutes;
 #[cfg(target_os = "linux")]
-use super::default_interface::DefaultInterface;
+use super::{
+    default_interface::DefaultInterface,
+    firewall_handler::{self, Endpoint, TransportProtocol},
+};
 #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
-use super::{dns_handler::DnsHandlerHandle, route_handler::RouteHandler};
+use super::{
+    dns_forwarder::DnsForwarderHandle, dns_handler::DnsHandlerHandle, route_handler::RouteHandler,
+    DnsBlockingCounters, DnsOptions,
+};
 #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
 use super::{route_handler::RoutingConfig, tun_ipv6};
 use super::{
     tunnel::{
-        self, AnyTunnelHandle, ConnectedMixnet, MixnetConnectOptions, SelectedGateways, Tombstone,
+        self, AnyTunnelHandle, ConnectedMixnet, GatewayCandidates, LatencyProbeAccess,
+        MixnetConnectOptions, SelectedGateways, Tombstone,
     },
-    ConnectionData, Error, ErrorStateReason, MixnetConnectionData, MixnetEvent, NymConfig, Result,
-    TunnelConnectionData, TunnelSettings, TunnelType, WireguardConnectionData, WireguardNode,
+    ConnectionAttempt, ConnectionData, ConnectionTimings, Error, ErrorStateReason,
+    GatewayQuarantine, MixnetConnectionData, MixnetEvent, NymConfig, Result, TunnelConnectionData,
+    TunnelSettings, TunnelType, WireguardConnectionData, WireguardNode,
 };
 
 #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
@@ -85,23 +102,38 @@ const WG_EXIT_WINTUN_GUID: &str = "{AFE43773-E1F8-4EBB-8536-176AB86AFE9C}";
 
 pub type TunnelMonitorEventReceiver = mpsc::UnboundedReceiver<TunnelMonitorEvent>;
 
-/// Initial delay between retry attempts.
-const INITIAL_WAIT_DELAY: Duration = Duration::from_secs(2);
-
-/// Wait delay multiplier used for each subsequent retry attempt.
-const DELAY_MULTIPLIER: u32 = 2;
-
-/// Max wait delay between retry attempts.
-const MAX_WAIT_DELAY: Duration = Duration::from_secs(15);
+/// Time to wait for the default route to settle before acting on a change.
+#[cfg(target_os = "linux")]
+const DEFAULT_ROUTE_SETTLE_DELAY: Duration = Duration::from_secs(2);
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub enum TunnelMonitorEvent {
     /// Initializing mixnet client
     InitializingClient,
 
+    /// Measuring the latency of gateways, whose endpoints must be reachable before the `ack`
+    /// is sent back
+    #[cfg(target_os = "linux")]
+    ProbingGateways {
+        endpoints: Vec<Endpoint>,
+        ack: oneshot::Sender<()>,
+    },
+
     /// Selected gateways
     SelectedGateways(Box<SelectedGateways>),
 
+    /// Connecting with the selected gateways failed, with all the attempts made so far
+    ConnectionAttemptFailed(Vec<ConnectionAttempt>),
+
+    /// Tunnel interfaces are created and the traffic is routed through them
+    #[cfg(target_os = "linux")]
+    TunnelInterfacesUp {
+        interfaces: Vec<String>,
+        peer_endpoints: Vec<Endpoint>,
+        /// Servers that DNS requests are sent to through the tunnel.
+        dns_servers: Vec<IpAddr>,
+    },
+
     /// Establishing tunnel connection
     EstablishingTunnel(Box<ConnectionData>),
 
@@ -139,12 +171,18 @@ pub struct TunnelMonitor {
     route_handler: RouteHandler,
     #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
     dns_handler: DnsHandlerHandle,
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    dns_forwarder: Option<DnsForwarderHandle>,
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    dns_blocking_counters: DnsBlockingCounters,
     #[cfg(target_os = "ios")]
     tun_provider: Arc<dyn OSTunProvider>,
     #[cfg(target_os = "android")]
     tun_provider: Arc<dyn AndroidTunProvider>,
     nym_config: NymConfig,
     tunnel_settings: TunnelSettings,
+    gateway_quarantine: GatewayQuarantine,
+    timings: ConnectionTimings,
     cancel_token: CancellationToken,
 }
 
@@ -160,10 +198,13 @@ impl TunnelMonitor {
         route_handler: RouteHandler,
         #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
         dns_handler: DnsHandlerHandle,
+        #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+        dns_blocking_counters: DnsBlockingCounters,
         #[cfg(target_os = "ios")] tun_provider: Arc<dyn OSTunProvider>,
         #[cfg(target_os = "android")] tun_provider: Arc<dyn AndroidTunProvider>,
         nym_config: NymConfig,
         tunnel_settings: TunnelSettings,
+        gateway_quarantine: GatewayQuarantine,
     ) -> TunnelMonitorHandle {
         let cancel_token = CancellationToken::new();
         let tunnel_monitor = Self {
@@ -173,10 +214,16 @@ impl TunnelMonitor {
             route_handler,
             #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
             dns_handler,
+            #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+            dns_forwarder: None,
+            #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+            dns_blocking_counters,
             #[cfg(any(target_os = "ios", target_os = "android"))]
             tun_provider,
             nym_config,
             tunnel_settings,
+            gateway_quarantine,
+            timings: ConnectionTimings::default(),
             cancel_token: cancel_token.clone(),
         };
         let join_handle = tokio::spawn(tunnel_monitor.run(retry_attempt, selected_gateways));
@@ -200,6 +247,11 @@ impl TunnelMonitor {
             }
         };
 
+        #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+        if let Some(dns_forwarder) = self.dns_forwarder.take() {
+            dns_forwarder.stop().await;
+        }
+
         self.send_event(TunnelMonitorEvent::Down(reason));
 
         tombstone
@@ -211,7 +263,7 @@ impl TunnelMonitor {
         selected_gateways: Option<SelectedGateways>,
     ) -> Result<Tombstone> {
         if retry_attempt > 0 {
-            let delay = wait_delay(retry_attempt);
+            let delay = self.tunnel_settings.retry_policy.delay(retry_attempt);
             tracing::debug!("Waiting for {}s before connecting.", delay.as_secs());
 
             self.cancel_token
@@ -245,26 +297,144 @@ impl TunnelMonitor {
             }
         }
 
-        let selected_gateways = if let Some(selected_gateways) = selected_gateways {
-            selected_gateways
+        let is_new_selection = selected_gateways.is_none();
+        let lookup_started = Instant::now();
+        let candidates = if let Some(selected_gateways) = selected_gateways {
+            GatewayCandidates::from(selected_gateways)
         } else {
-            let new_gateways = tunnel::select_gateways(
+            tunnel::select_gateways(
                 gateway_config.clone(),
                 self.tunnel_settings.tunnel_type,
                 self.tunnel_settings.entry_point.clone(),
                 self.tunnel_settings.exit_point.clone(),
+                &self.tunnel_settings.gateway_preferences,
+                gateway_performance_options.ranking,
+                self.tunnel_settings.split_routes.routed_ip_families(),
+                &self.gateway_quarantine.identities(),
+                &LatencyProbeFirewall {
+                    #[cfg(target_os = "linux")]
+                    monitor_event_sender: self.monitor_event_sender.clone(),
+                },
                 self.tunnel_settings.user_agent.clone(),
                 self.cancel_token.child_token(),
             )
-            .await?;
+            .await?
+        };
+        // The lookup is accounted to the first attempt only.
+        let mut gateway_lookup = is_new_selection.then(|| lookup_started.elapsed());
+
+        // Try the candidates in order, moving on to the next ones right away when the gateways
+        // fail to register or to set up the tunnel.
+        let mut alternatives = candidates.alternatives.into_iter();
+        let mut selected_gateways = candidates.selected;
+        let mut connection_attempts = Vec::new();
+        let (tunnel_conn_data, mut tunnel_handle, status_listener_handle) = loop {
+            self.timings = ConnectionTimings {
+                gateway_lookup: gateway_lookup.take(),
+                ..Default::default()
+            };
+            if is_new_selection {
+                self.send_event(TunnelMonitorEvent::SelectedGateways(Box::new(
+                    selected_gateways.clone(),
+                )));
+            }
 
-            self.send_event(TunnelMonitorEvent::SelectedGateways(Box::new(
-                new_gateways.clone(),
-            )));
+            match self
+                .connect_tunnel(gateway_config.clone(), selected_gateways.clone())
+                .await
+            {
+                Ok(connected) => break connected,
+                Err(e) => {
+                    connection_attempts.push(ConnectionAttempt::new(
+                        &selected_gateways,
+                        Some(e.to_string()),
+                        self.timings,
+                    ));
+                    // Reported right away, so that the attempt is known even when connecting
+                    // gives up here.
+                    self.send_event(TunnelMonitorEvent::ConnectionAttemptFailed(
+                        connection_attempts.clone(),
+                    ));
+                    let is_gateway_failure = matches!(
+                        &e,
+                        Error::Tunnel(e) if e.failed_gateway(&selected_gateways).is_some()
+                    );
+                    match alternatives.next() {
+                        Some(next_gateways) if is_gateway_failure => {
+                            tracing::warn!(
+                                "Failed to connect with entry gateway {} and exit gateway {}, trying the next gateways: {}",
+                                selected_gateways.entry.identity(),
+                                selected_gateways.exit.identity(),
+                                e
+                            );
+                            #[cfg(any(
+                                target_os = "linux",
+                                target_os = "macos",
+                                target_os = "windows"
+                            ))]
+                            self.reset_routes_and_dns().await;
+                            selected_gateways = next_gateways;
+                        }
+                        _ => return Err(e),
+                    }
+                }
+            }
+        };
+        self.gateway_quarantine.release(&selected_gateways);
+        connection_attempts.push(ConnectionAttempt::new(
+            &selected_gateways,
+            None,
+            self.timings,
+        ));
 
-            new_gateways
+        let conn_data = ConnectionData {
+            entry_gateway: Box::new(*selected_gateways.entry.identity()),
+            exit_gateway: Box::new(*selected_gateways.exit.identity()),
+            connected_at: None,
+            tunnel: tunnel_conn_data,
+            connection_attempts,
+            system_dns_servers: self.system_dns_servers(),
+        };
+        self.send_event(TunnelMonitorEvent::EstablishingTunnel(Box::new(
+            conn_data.clone(),
+        )));
+
+        // todo: do initial ping
+
+        let conn_data = ConnectionData {
+            connected_at: Some(OffsetDateTime::now_utc()),
+            ..conn_data
         };
+        self.send_event(TunnelMonitorEvent::Up(conn_data));
+
+        self.wait_for_tunnel_exit(&mut tunnel_handle).await;
+
+        tracing::debug!("Wait for tunnel to exit");
+        tunnel_handle.cancel();
+
+        let tun_devices = tunnel_handle
+            .wait()
+            .await
+            .inspect_err(|e| {
+                tracing::error!("Failed to gracefully shutdown the tunnel: {}", e);
+            })
+            .unwrap_or_default();
+
+        tracing::debug!("Wait for status listener to exit");
+        if let Err(e) = status_listener_handle.await {
+            tracing::error!("Failed to join on status listener: {}", e);
+        }
+
+        Ok(tun_devices)
+    }
 
+    /// Connects the mixnet client and sets up the tunnel with the gateways. Gateways responsible
+    /// for a failure are quarantined.
+    async fn connect_tunnel(
+        &mut self,
+        gateway_config: nym_gateway_directory::Config,
+        selected_gateways: SelectedGateways,
+    ) -> Result<(TunnelConnectionData, AnyTunnelHandle, JoinHandle<()>)> {
         let connect_options = MixnetConnectOptions {
             data_path: self.nym_config.data_path.clone(),
             gateway_config,
@@ -287,13 +457,19 @@ impl TunnelMonitor {
             #[cfg(target_os = "android")]
             tun_provider.bypass(_fd);
         };
+        let registration_started = Instant::now();
         let mut connected_mixnet = tunnel::connect_mixnet(
             connect_options,
             self.cancel_token.child_token(),
             #[cfg(unix)]
             Arc::new(connection_fd_callback),
         )
-        .await?;
+        .await
+        .map_err(|e| {
+            self.quarantine_failed_gateway(&e, &selected_gateways);
+            Error::Tunnel(e)
+        })?;
+        self.timings.registration = Some(registration_started.elapsed());
 
         // Route mixnet client outside the tunnel.
         #[cfg(target_os = "android")]
@@ -310,67 +486,155 @@ impl TunnelMonitor {
             .start_event_listener(self.mixnet_event_sender.clone())
             .await;
 
-        let selected_gateways = connected_mixnet.selected_gateways().clone();
-        let (tunnel_conn_data, mut tunnel_handle) = match self.tunnel_settings.tunnel_type {
-            TunnelType::Mixnet => self.start_mixnet_tunnel(connected_mixnet).await?,
+        let tunnel_started = Instant::now();
+        let tunnel_result = match self.tunnel_settings.tunnel_type {
+            TunnelType::Mixnet => self.start_mixnet_tunnel(connected_mixnet).await,
             TunnelType::Wireguard => {
                 match self.tunnel_settings.wireguard_tunnel_options.multihop_mode {
                     #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
                     WireguardMultihopMode::TunTun => {
-                        self.start_wireguard_tunnel(connected_mixnet).await?
+                        self.start_wireguard_tunnel(connected_mixnet).await
                     }
                     WireguardMultihopMode::Netstack => {
-                        self.start_wireguard_netstack_tunnel(connected_mixnet)
-                            .await?
+                        self.start_wireguard_netstack_tunnel(connected_mixnet).await
                     }
                 }
             }
         };
+        self.timings.set_tunnel_setup(tunnel_started.elapsed());
 
-        let conn_data = ConnectionData {
-            entry_gateway: Box::new(*selected_gateways.entry.identity()),
-            exit_gateway: Box::new(*selected_gateways.exit.identity()),
-            connected_at: None,
-            tunnel: tunnel_conn_data,
+        let tunnel_result = match tunnel_result {
+            Ok((tunnel_conn_data, tunnel_handle)) => self
+                .wait_for_handshake(tunnel_handle)
+                .await
+                .map(|tunnel_handle| (tunnel_conn_data, tunnel_handle)),
+            Err(e) => Err(e),
         };
-        self.send_event(TunnelMonitorEvent::EstablishingTunnel(Box::new(
-            conn_data.clone(),
-        )));
 
-        // todo: do initial ping
+        match tunnel_result {
+            Ok((tunnel_conn_data, tunnel_handle)) => {
+                Ok((tunnel_conn_data, tunnel_handle, status_listener_handle))
+            }
+            Err(e) => {
+                if let Error::Tunnel(e) = &e {
+                    self.quarantine_failed_gateway(e, &selected_gateways);
+                }
+                status_listener_handle.abort();
+                Err(e)
+            }
+        }
+    }
 
-        let conn_data = ConnectionData {
-            connected_at: Some(OffsetDateTime::now_utc()),
-            ..conn_data
-        };
-        self.send_event(TunnelMonitorEvent::Up(conn_data));
+    /// Returns the DNS servers the system resolver is pointed at.
+    fn system_dns_servers(&self) -> Vec<IpAddr> {
+        #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+        if let Some(dns_forwarder) = &self.dns_forwarder {
+            return vec![dns_forwarder.listen_ip()];
+        }
+        self.tunnel_settings.dns.ip_addresses().to_vec()
+    }
+
+    /// Quarantines the gateway responsible for the error, if any.
+    fn quarantine_failed_gateway(&self, e: &tunnel::Error, selected_gateways: &SelectedGateways) {
+        if let Some(failed_gateway) = e.failed_gateway(selected_gateways) {
+            self.gateway_quarantine.add(failed_gateway, e.to_string());
+        }
+    }
 
-        let task_error = self
+    /// Waits until the WireGuard exit tunnel completes its first handshake, so that gateways
+    /// that accepted the registration but do not pass traffic are detected while connecting.
+    /// The tunnel is shut down if the handshake does not complete in time.
+    async fn wait_for_handshake(
+        &mut self,
+        mut tunnel_handle: AnyTunnelHandle,
+    ) -> Result<AnyTunnelHandle> {
+        let handshake_started = Instant::now();
+        let timeout = self.tunnel_settings.connect_timeouts.wireguard_handshake;
+        let result = self
             .cancel_token
-            .run_until_cancelled(tunnel_handle.recv_error())
-            .await;
+            .run_until_cancelled(tunnel_handle.wait_for_handshake(timeout))
+            .await
+            .unwrap_or(Err(tunnel::Error::Cancelled));
 
-        if let Some(Some(task_error)) = task_error {
-            tracing::error!("Task manager quit with error: {}", task_error);
+        if let Some(handshake) = self.timings.handshake.as_mut() {
+            *handshake += handshake_started.elapsed();
         }
 
-        tracing::debug!("Wait for tunnel to exit");
-        tunnel_handle.cancel();
+        match result {
+            Ok(()) => Ok(tunnel_handle),
+            Err(e) => {
+                tunnel_handle.cancel();
+                if let Err(e) = tunnel_handle.wait().await {
+                    tracing::error!("Failed to shutdown the tunnel: {}", e);
+                }
+                Err(Error::Tunnel(e))
+            }
+        }
+    }
 
-        let tun_devices = tunnel_handle
-            .wait()
-            .await
-            .inspect_err(|e| {
-                tracing::error!("Failed to gracefully shutdown the tunnel: {}", e);
-            })
-            .unwrap_or_default();
+    /// Waits until the tunnel fails, the monitor is cancelled or the default route changes.
+    async fn wait_for_tunnel_exit(&self, tunnel_handle: &mut AnyTunnelHandle) {
+        #[cfg(target_os = "linux")]
+        let default_route_change = self.wait_for_default_route_change();
+        #[cfg(not(target_os = "linux"))]
+        let default_route_change = std::future::pending::<()>();
+
+        tokio::select! {
+            _ = self.cancel_token.cancelled() => {}
+            task_error = tunnel_handle.recv_error() => {
+                if let Some(task_error) = task_error {
+                    tracing::error!("Task manager quit with error: {}", task_error);
+                }
+            }
+            _ = default_route_change => {}
+        }
+    }
 
-        tracing::debug!("Wait for status listener to exit");
-        if let Err(e) = status_listener_handle.await {
-            tracing::error!("Failed to join on status listener: {}", e);
+    /// Waits until the physical default route changes or goes away, so that routes to the entry
+    /// gateway can be re-created by reconnecting.
+    #[cfg(target_os = "linux")]
+    async fn wait_for_default_route_change(&self) {
+        let mut route_listener = match self.route_handler.default_route_listener().await {
+            Ok(route_listener) => Box::pin(route_listener),
+            Err(e) => {
+                tracing::error!("Failed to add default route listener: {}", e);
+                return std::future::pending().await;
+            }
+        };
+
+        let current_route = || {
+            DefaultInterface::current()
+                .ok()
+                .map(|iface| (iface.interface_name().to_owned(), iface.gateway_ip()))
+        };
+        let initial_route = current_route();
+
+        while route_listener.next().await.is_some() {
+            // Route changes usually arrive in bursts, e.g when switching networks.
+            while let Ok(Some(_)) =
+                tokio::time::timeout(DEFAULT_ROUTE_SETTLE_DELAY, route_listener.next()).await
+            {
+            }
+
+            match current_route() {
+                None => {
+                    tracing::info!("Default route is gone. Reconnecting.");
+                    return;
+                }
+                Some(route) if Some(&route) != initial_route.as_ref() => {
+                    tracing::info!(
+                        "Default route changed to {} via {:?}. Reconnecting.",
+                        route.0,
+                        route.1
+                    );
+                    return;
+                }
+                Some(_) => {}
+            }
         }
 
-        Ok(tun_devices)
+        tracing::error!("Default route listener has been dropped.");
+        std::future::pending().await
     }
 
     fn send_event(&mut self, event: TunnelMonitorEvent) {
@@ -384,10 +648,17 @@ impl TunnelMonitor {
         connected_mixnet: ConnectedMixnet,
     ) -> Result<(TunnelConnectionData, AnyTunnelHandle)> {
         let interface_addrs = self.tunnel_settings.mixnet_tunnel_options.interface_addrs;
+        #[cfg(target_os = "linux")]
+        let entry_gateway = connected_mixnet.selected_gateways().entry.clone();
 
+        let handshake_started = Instant::now();
         let connected_tunnel = connected_mixnet
-            .connect_mixnet_tunnel(interface_addrs)
+            .connect_mixnet_tunnel(
+                interface_addrs,
+                self.tunnel_settings.connect_timeouts.ipr_connect,
+            )
             .await?;
+        self.timings.handshake = Some(handshake_started.elapsed());
         let assigned_addresses = connected_tunnel.assigned_addresses();
 
         let mtu: u16 = self
@@ -432,12 +703,23 @@ impl TunnelMonitor {
             let routing_config = RoutingConfig::Mixnet {
                 tun_name: tun_name.clone(),
                 entry_gateway_address: assigned_addresses.entry_mixnet_gateway_ip,
+                split_routes: self.split_routes(),
                 #[cfg(target_os = "linux")]
                 physical_interface: DefaultInterface::current()?,
             };
 
             self.set_routes(routing_config).await?;
-            self.set_dns(&tun_name).await?;
+            let _dns_servers = self.set_dns(&tun_name).await?;
+
+            #[cfg(target_os = "linux")]
+            self.send_event(TunnelMonitorEvent::TunnelInterfacesUp {
+                interfaces: vec![tun_name],
+                peer_endpoints: firewall_handler::gateway_client_endpoints(
+                    &entry_gateway,
+                    assigned_addresses.entry_mixnet_gateway_ip,
+                ),
+                dns_servers: _dns_servers,
+            });
         }
 
         let tunnel_conn_data = TunnelConnectionData::Mixnet(MixnetConnectionData {
@@ -457,9 +739,16 @@ impl TunnelMonitor {
         &mut self,
         connected_mixnet: ConnectedMixnet,
     ) -> Result<(TunnelConnectionData, AnyTunnelHandle)> {
+        let handshake_started = Instant::now();
         let connected_tunnel = connected_mixnet
-            .connect_wireguard_tunnel(self.tunnel_settings.enable_credentials_mode)
+            .connect_wireguard_tunnel(
+                self.tunnel_settings.enable_credentials_mode,
+                self.tunnel_settings
+                    .connect_timeouts
+                    .authenticator_registration,
+            )
             .await?;
+        self.timings.handshake = Some(handshake_started.elapsed());
         let conn_data = connected_tunnel.connection_data();
 
         let exit_tun = Self::create_wireguard_device(
@@ -476,12 +765,23 @@ impl TunnelMonitor {
         let routing_config = RoutingConfig::WireguardNetstack {
             exit_tun_name: exit_tun_name.clone(),
             entry_gateway_address: conn_data.entry.endpoint.ip(),
+            split_routes: self.split_routes(),
             #[cfg(target_os = "linux")]
             physical_interface: DefaultInterface::current()?,
         };
 
         self.set_routes(routing_config).await?;
-        self.set_dns(&exit_tun_name).await?;
+        let _dns_servers = self.set_dns(&exit_tun_name).await?;
+
+        #[cfg(target_os = "linux")]
+        self.send_event(TunnelMonitorEvent::TunnelInterfacesUp {
+            interfaces: vec![exit_tun_name],
+            peer_endpoints: vec![Endpoint::new(
+                conn_data.entry.endpoint,
+                TransportProtocol::Udp,
+            )],
+            dns_servers: _dns_servers,
+        });
 
         let tunnel_conn_data = TunnelConnectionData::Wireguard(WireguardConnectionData {
             entry: WireguardNode::from(conn_data.entry.clone()),
@@ -505,9 +805,16 @@ impl TunnelMonitor {
         &mut self,
         connected_mixnet: ConnectedMixnet,
     ) -> Result<(TunnelConnectionData, AnyTunnelHandle)> {
+        let handshake_started = Instant::now();
         let connected_tunnel = connected_mixnet
-            .connect_wireguard_tunnel(self.tunnel_settings.enable_credentials_mode)
+            .connect_wireguard_tunnel(
+                self.tunnel_settings.enable_credentials_mode,
+                self.tunnel_settings
+                    .connect_timeouts
+                    .authenticator_registration,
+            )
             .await?;
+        self.timings.handshake = Some(handshake_started.elapsed());
         let conn_data = connected_tunnel.connection_data();
         let entry_gateway_address = conn_data.entry.endpoint.ip();
 
@@ -549,6 +856,7 @@ impl TunnelMonitor {
         let routing_config = RoutingConfig::WireguardNetstack {
             exit_tun_name: wintun_exit_interface.name.clone(),
             entry_gateway_address,
+            split_routes: self.split_routes(),
             #[cfg(target_os = "linux")]
             physical_interface: DefaultInterface::current()?,
         };
@@ -566,9 +874,16 @@ impl TunnelMonitor {
         &mut self,
         connected_mixnet: ConnectedMixnet,
     ) -> Result<(TunnelConnectionData, AnyTunnelHandle)> {
+        let handshake_started = Instant::now();
         let connected_tunnel = connected_mixnet
-            .connect_wireguard_tunnel(self.tunnel_settings.enable_credentials_mode)
+            .connect_wireguard_tunnel(
+                self.tunnel_settings.enable_credentials_mode,
+                self.tunnel_settings
+                    .connect_timeouts
+                    .authenticator_registration,
+            )
             .await?;
+        self.timings.handshake = Some(handshake_started.elapsed());
         let conn_data = connected_tunnel.connection_data();
 
         let entry_tun = Self::create_wireguard_device(
@@ -597,15 +912,26 @@ impl TunnelMonitor {
         tracing::info!("Created exit tun device: {}", exit_tun_name);
 
         let routing_config = RoutingConfig::Wireguard {
-            entry_tun_name,
+            entry_tun_name: entry_tun_name.clone(),
             exit_tun_name: exit_tun_name.clone(),
             entry_gateway_address: conn_data.entry.endpoint.ip(),
             exit_gateway_address: conn_data.exit.endpoint.ip(),
+            split_routes: self.split_routes(),
             #[cfg(target_os = "linux")]
             physical_interface: DefaultInterface::current()?,
         };
         self.set_routes(routing_config).await?;
-        self.set_dns(&exit_tun_name).await?;
+        let _dns_servers = self.set_dns(&exit_tun_name).await?;
+
+        #[cfg(target_os = "linux")]
+        self.send_event(TunnelMonitorEvent::TunnelInterfacesUp {
+            interfaces: vec![entry_tun_name, exit_tun_name],
+            peer_endpoints: vec![Endpoint::new(
+                conn_data.entry.endpoint,
+                TransportProtocol::Udp,
+            )],
+            dns_servers: _dns_servers,
+        });
 
         let tunnel_conn_data = TunnelConnectionData::Wireguard(WireguardConnectionData {
             entry: WireguardNode::from(conn_data.entry.clone()),
@@ -629,9 +955,16 @@ impl TunnelMonitor {
         &mut self,
         connected_mixnet: ConnectedMixnet,
     ) -> Result<(TunnelConnectionData, AnyTunnelHandle)> {
+        let handshake_started = Instant::now();
         let connected_tunnel = connected_mixnet
-            .connect_wireguard_tunnel(self.tunnel_settings.enable_credentials_mode)
+            .connect_wireguard_tunnel(
+                self.tunnel_settings.enable_credentials_mode,
+                self.tunnel_settings
+                    .connect_timeouts
+                    .authenticator_registration,
+            )
             .await?;
+        self.timings.handshake = Some(handshake_started.elapsed());
         let conn_data = connected_tunnel.connection_data();
 
         let entry_gateway_address = conn_data.entry.endpoint.ip();
@@ -693,6 +1026,7 @@ impl TunnelMonitor {
             exit_tun_name: wintun_exit_interface.name.clone(),
             entry_gateway_address,
             exit_gateway_address,
+            split_routes: self.split_routes(),
         };
         // todo: make sure to shutdown tunnel_handle on failure!
         self.set_routes(routing_config).await?;
@@ -705,12 +1039,19 @@ impl TunnelMonitor {
 
     #[cfg(any(target_os = "ios", target_os = "android"))]
     async fn start_wireguard_netstack_tunnel(
-        &self,
+        &mut self,
         connected_mixnet: ConnectedMixnet,
     ) -> Result<(TunnelConnectionData, AnyTunnelHandle)> {
+        let handshake_started = Instant::now();
         let connected_tunnel = connected_mixnet
-            .connect_wireguard_tunnel(self.tunnel_settings.enable_credentials_mode)
+            .connect_wireguard_tunnel(
+                self.tunnel_settings.enable_credentials_mode,
+                self.tunnel_settings
+                    .connect_timeouts
+                    .authenticator_registration,
+            )
             .await?;
+        self.timings.handshake = Some(handshake_started.elapsed());
 
         let conn_data = connected_tunnel.connection_data();
 
@@ -746,26 +1087,116 @@ impl TunnelMonitor {
         Ok((tunnel_conn_data, any_tunnel_handle))
     }
 
+    /// Points the system resolver at the DNS servers, or at the forwarder if it is needed.
+    ///
+    /// Returns the servers that DNS requests are sent to through the tunnel: the system resolver
+    /// servers, and the upstream servers of the forwarder unless they are reached using
+    /// DNS-over-HTTPS or DNS-over-TLS.
     #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
-    async fn set_dns(&mut self, tun_name: &str) -> Result<()> {
-        let dns_servers = self.tunnel_settings.dns.ip_addresses().to_vec();
+    async fn set_dns(&mut self, tun_name: &str) -> Result<Vec<IpAddr>> {
+        let dns = &self.tunnel_settings.dns;
+        let dns_blocking = &self.tunnel_settings.dns_blocking;
+        let dns_domain_rules = &self.tunnel_settings.dns_domain_rules;
+
+        let resolver_handles_domain_rules = self
+            .tunnel_settings
+            .system_resolver_handles_dns_domain_rules();
+        let forwarder_domain_rules = if resolver_handles_domain_rules {
+            &[]
+        } else {
+            dns_domain_rules.as_slice()
+        };
+
+        let dns_servers = if self
+            .tunnel_settings
+            .needs_dns_forwarder(resolver_handles_domain_rules)
+        {
+            // Keep the forwarder started for the previous gateways, if any.
+            let dns_forwarder = match self.dns_forwarder.take() {
+                Some(dns_forwarder) => dns_forwarder,
+                None => DnsForwarderHandle::start(
+                    dns,
+                    dns_blocking,
+                    forwarder_domain_rules,
+                    self.dns_blocking_counters.clone(),
+                )
+                .await
+                .map_err(Error::StartDnsForwarder)?,
+            };
+            let listen_ip = dns_forwarder.listen_ip();
+            self.dns_forwarder = Some(dns_forwarder);
+            vec![listen_ip]
+        } else {
+            dns.ip_addresses().to_vec()
+        };
+
+        let mut tunnel_dns_servers = dns_servers.clone();
+        if !matches!(dns, DnsOptions::Encrypted(_)) {
+            for server in dns.ip_addresses() {
+                if !tunnel_dns_servers.contains(server) {
+                    tunnel_dns_servers.push(*server);
+                }
+            }
+        }
+
+        let domain_resolvers = if resolver_handles_domain_rules {
+            dns_domain_rules
+                .iter()
+                .map(|rule| nym_dns::DomainResolver {
+                    domain: rule.domain_name(),
+                    servers: rule.servers.clone(),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
 
         self.dns_handler
-            .set(tun_name.to_owned(), dns_servers)
+            .set(tun_name.to_owned(), dns_servers, domain_resolvers)
             .await
-            .map_err(Error::SetDns)
+            .map_err(Error::SetDns)?;
+
+        Ok(tunnel_dns_servers)
     }
 
     #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
     async fn set_routes(&mut self, routing_config: RoutingConfig) -> Result<()> {
         self.route_handler
-            .add_routes(routing_config)
+            .add_routes(routing_config, self.tunnel_settings.allow_lan)
             .await
             .map_err(Error::AddRoutes)?;
 
         Ok(())
     }
 
+    /// Removes the routes and DNS configuration set up for gateways that failed to connect, so
+    /// that the next gateways are tried from a clean state.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    async fn reset_routes_and_dns(&mut self) {
+        if let Err(e) = self.dns_handler.reset().await {
+            tracing::error!("Failed to reset dns: {}", e);
+        }
+        self.route_handler.remove_routes().await;
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    fn split_routes(&self) -> SplitRoutes {
+        let mut split_routes = self.tunnel_settings.split_routes_with_dns_domain_rules();
+
+        // Keep DNS requests inside of the tunnel when only some networks are routed through it
+        if split_routes.is_include_only() {
+            split_routes.included.extend(
+                self.tunnel_settings
+                    .dns
+                    .ip_addresses()
+                    .iter()
+                    .map(|addr| IpNetwork::from(*addr)),
+            );
+        }
+
+        split_routes
+    }
+
     #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
     fn create_mixnet_device(interface_addresses: IpPair, mtu: u16) -> Result<AsyncDevice> {
         let mut tun_config = tun::Configuration::default();
@@ -871,8 +1302,37 @@ impl TunnelMonitor {
     }
 }
 
-fn wait_delay(retry_attempt: u32) -> Duration {
-    let multiplier = retry_attempt.saturating_mul(DELAY_MULTIPLIER);
-    let delay = INITIAL_WAIT_DELAY.saturating_mul(multiplier);
-    cmp::min(delay, MAX_WAIT_DELAY)
+/// Lets the latency of the gateways be measured through the firewall of the connecting state.
+struct LatencyProbeFirewall {
+    #[cfg(target_os = "linux")]
+    monitor_event_sender: mpsc::UnboundedSender<TunnelMonitorEvent>,
+}
+
+#[async_trait::async_trait]
+impl LatencyProbeAccess for LatencyProbeFirewall {
+    #[cfg(target_os = "linux")]
+    async fn allow(&self, gateways: &[Gateway]) {
+        let endpoints = futures::future::join_all(gateways.iter().map(|gateway| async move {
+            match gateway.lookup_ip().await {
+                Some(ip) => firewall_handler::gateway_client_endpoints(gateway, ip),
+                None => Vec::new(),
+            }
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let (ack_tx, ack_rx) = oneshot::channel();
+        let event = TunnelMonitorEvent::ProbingGateways {
+            endpoints,
+            ack: ack_tx,
+        };
+        if self.monitor_event_sender.send(event).is_ok() {
+            _ = ack_rx.await;
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn allow(&self, _gateways: &[Gateway]) {}
 }
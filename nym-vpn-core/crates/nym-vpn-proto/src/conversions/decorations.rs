@@ -9,6 +9,7 @@ impl crate::EntryNode {
                     two_letter_iso_country_code: country_code.to_string(),
                     latitude: None,
                     longitude: None,
+                    city: None,
                 },
             )),
         }
@@ -20,6 +21,41 @@ impl crate::EntryNode {
         }
     }
 
+    pub fn new_from_region(region: &str) -> Self {
+        Self {
+            entry_node_enum: Some(crate::entry_node::EntryNodeEnum::Region(region.to_string())),
+        }
+    }
+
+    pub fn new_from_city(city: &str) -> Self {
+        Self {
+            entry_node_enum: Some(crate::entry_node::EntryNodeEnum::City(city.to_string())),
+        }
+    }
+
+    pub fn new_nearest(latitude: f64, longitude: f64) -> Self {
+        Self {
+            entry_node_enum: Some(crate::entry_node::EntryNodeEnum::Nearest(
+                crate::Coordinates {
+                    latitude,
+                    longitude,
+                },
+            )),
+        }
+    }
+
+    pub fn new_from_area(latitude: f64, longitude: f64, radius_km: u32) -> Self {
+        Self {
+            entry_node_enum: Some(crate::entry_node::EntryNodeEnum::Area(crate::Area {
+                center: Some(crate::Coordinates {
+                    latitude,
+                    longitude,
+                }),
+                radius_km,
+            })),
+        }
+    }
+
     pub fn new_random_low_latency() -> Self {
         Self {
             entry_node_enum: Some(crate::entry_node::EntryNodeEnum::RandomLowLatency(
@@ -40,6 +76,7 @@ impl crate::ExitNode {
                 two_letter_iso_country_code: country_code.to_string(),
                 latitude: None,
                 longitude: None,
+                city: None,
             })),
         }
     }
@@ -50,6 +87,62 @@ impl crate::ExitNode {
         }
     }
 
+    pub fn new_random_low_latency() -> Self {
+        Self {
+            exit_node_enum: Some(crate::exit_node::ExitNodeEnum::RandomLowLatency(
+                crate::Empty {},
+            )),
+        }
+    }
+
+    pub fn new_from_region(region: &str) -> Self {
+        Self {
+            exit_node_enum: Some(crate::exit_node::ExitNodeEnum::Region(region.to_string())),
+        }
+    }
+
+    pub fn new_from_city(city: &str) -> Self {
+        Self {
+            exit_node_enum: Some(crate::exit_node::ExitNodeEnum::City(city.to_string())),
+        }
+    }
+
+    pub fn new_nearest(latitude: f64, longitude: f64) -> Self {
+        Self {
+            exit_node_enum: Some(crate::exit_node::ExitNodeEnum::Nearest(
+                crate::Coordinates {
+                    latitude,
+                    longitude,
+                },
+            )),
+        }
+    }
+
+    pub fn new_from_area(latitude: f64, longitude: f64, radius_km: u32) -> Self {
+        Self {
+            exit_node_enum: Some(crate::exit_node::ExitNodeEnum::Area(crate::Area {
+                center: Some(crate::Coordinates {
+                    latitude,
+                    longitude,
+                }),
+                radius_km,
+            })),
+        }
+    }
+
+    pub fn new_low_latency_pair(country_code: &str) -> Self {
+        Self {
+            exit_node_enum: Some(crate::exit_node::ExitNodeEnum::LowLatencyPair(
+                crate::Location {
+                    two_letter_iso_country_code: country_code.to_string(),
+                    latitude: None,
+                    longitude: None,
+                    city: None,
+                },
+            )),
+        }
+    }
+
     pub fn new_from_gateway(identity: &nym_sdk::mixnet::NodeIdentity) -> Self {
         identity.into()
     }
@@ -25,6 +25,19 @@ impl TryFrom<crate::GatewayType> for nym_gateway_directory::GatewayType {
     }
 }
 
+impl From<crate::GatewayRanking> for nym_gateway_directory::GatewayRanking {
+    fn from(ranking: crate::GatewayRanking) -> Self {
+        use nym_gateway_directory::GatewayRanking;
+        match ranking {
+            crate::GatewayRanking::Unspecified | crate::GatewayRanking::Uniform => {
+                GatewayRanking::Uniform
+            }
+            crate::GatewayRanking::Performance => GatewayRanking::Performance,
+            crate::GatewayRanking::ProbeScore => GatewayRanking::ProbeScore,
+        }
+    }
+}
+
 impl From<crate::UserAgent> for nym_sdk::UserAgent {
     fn from(user_agent: crate::UserAgent) -> Self {
         Self {
@@ -9,6 +9,7 @@ impl From<crate::Location> for nym_vpnd_types::gateway::Location {
             two_letter_iso_country_code: location.two_letter_iso_country_code,
             latitude: location.latitude,
             longitude: location.longitude,
+            city: location.city,
         }
     }
 }
@@ -95,3 +96,22 @@ impl From<crate::Location> for nym_vpnd_types::gateway::Country {
         }
     }
 }
+
+impl From<crate::City> for nym_vpnd_types::gateway::City {
+    fn from(city: crate::City) -> Self {
+        Self {
+            name: city.name,
+            iso_code: city.two_letter_iso_country_code,
+        }
+    }
+}
+
+impl From<crate::Region> for nym_vpnd_types::gateway::Region {
+    fn from(region: crate::Region) -> Self {
+        Self {
+            id: region.id,
+            name: region.name,
+            iso_codes: region.two_letter_iso_country_codes,
+        }
+    }
+}
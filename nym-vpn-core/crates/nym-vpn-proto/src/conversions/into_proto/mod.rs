@@ -49,6 +49,17 @@ impl From<&nym_sdk::mixnet::Recipient> for crate::ExitNode {
     }
 }
 
+impl From<nym_gateway_directory::GatewayRanking> for crate::GatewayRanking {
+    fn from(ranking: nym_gateway_directory::GatewayRanking) -> Self {
+        use nym_gateway_directory::GatewayRanking;
+        match ranking {
+            GatewayRanking::Uniform => crate::GatewayRanking::Uniform,
+            GatewayRanking::Performance => crate::GatewayRanking::Performance,
+            GatewayRanking::ProbeScore => crate::GatewayRanking::ProbeScore,
+        }
+    }
+}
+
 impl From<std::net::IpAddr> for crate::Dns {
     fn from(ip: std::net::IpAddr) -> Self {
         Self { ip: ip.to_string() }
@@ -9,6 +9,7 @@ impl From<nym_vpnd_types::gateway::Location> for crate::Location {
             two_letter_iso_country_code: location.two_letter_iso_country_code,
             latitude: location.latitude,
             longitude: location.longitude,
+            city: location.city,
         }
     }
 }
@@ -83,6 +84,26 @@ impl From<nym_vpnd_types::gateway::Country> for crate::Location {
             two_letter_iso_country_code: country.iso_code().to_string(),
             latitude: None,
             longitude: None,
+            city: None,
+        }
+    }
+}
+
+impl From<nym_vpnd_types::gateway::City> for crate::City {
+    fn from(city: nym_vpnd_types::gateway::City) -> Self {
+        crate::City {
+            name: city.name,
+            two_letter_iso_country_code: city.iso_code,
+        }
+    }
+}
+
+impl From<nym_vpnd_types::gateway::Region> for crate::Region {
+    fn from(region: nym_vpnd_types::gateway::Region) -> Self {
+        crate::Region {
+            id: region.id,
+            name: region.name,
+            two_letter_iso_country_codes: region.iso_codes,
         }
     }
 }
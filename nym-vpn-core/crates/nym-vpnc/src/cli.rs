@@ -1,11 +1,11 @@
 // Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::net::IpAddr;
+use std::{net::IpAddr, path::PathBuf};
 
 use anyhow::{anyhow, Result};
-use clap::{Args, Parser, Subcommand};
-use nym_gateway_directory::{EntryPoint, ExitPoint, NodeIdentity, Recipient};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use nym_gateway_directory::{Area, Coordinates, EntryPoint, ExitPoint, NodeIdentity, Recipient};
 
 #[derive(Parser)]
 #[clap(author = "Nymtech", version, about)]
@@ -58,6 +58,76 @@ pub(crate) enum Command {
     /// Set the network to be used. This requires a restart of the daemon (`nym-vpnd`)
     SetNetwork(SetNetworkArgs),
 
+    /// Check if lockdown mode is enabled.
+    GetLockdownMode,
+
+    /// Enable or disable lockdown mode. When enabled, all traffic outside of the tunnel is
+    /// blocked, even while disconnected.
+    SetLockdownMode(SetLockdownModeArgs),
+
+    /// Check if the daemon connects on startup.
+    GetAutoConnect,
+
+    /// Enable or disable connecting on startup with the last used settings, once the account is
+    /// ready to connect.
+    SetAutoConnect(SetAutoConnectArgs),
+
+    /// Get all settings persisted by the daemon.
+    GetSettings,
+
+    /// Reset all settings persisted by the daemon to their defaults.
+    ResetSettings,
+
+    /// List the gateways and countries that are excluded or preferred when selecting gateways.
+    GetGatewayPreferences,
+
+    /// Exclude or prefer a gateway or a country when selecting gateways by location or at random.
+    AddGatewayPreference(GatewayPreferenceArgs),
+
+    /// Remove a gateway or a country from the excluded or preferred gateways.
+    RemoveGatewayPreference(GatewayPreferenceArgs),
+
+    /// List the gateways that recently failed to connect and are avoided for a while.
+    ListQuarantinedGateways,
+
+    /// Show the recent connections, with the gateways tried and the time spent in each phase
+    /// of connecting.
+    History,
+
+    /// Exclude a process, or all processes running an executable, from the tunnel.
+    AddExcludedApp(ExcludedAppArgs),
+
+    /// Stop excluding a process or an executable from the tunnel.
+    RemoveExcludedApp(ExcludedAppArgs),
+
+    /// List the processes and executables excluded from the tunnel.
+    ListExcludedApps,
+
+    /// Get the networks routed outside of, or exclusively through, the tunnel.
+    GetSplitRoutes,
+
+    /// Set the networks routed outside of, or exclusively through, the tunnel. Replaces the
+    /// current lists, passing no networks clears them.
+    SetSplitRoutes(SetSplitRoutesArgs),
+
+    /// Get the blocklists used to block DNS queries.
+    GetDnsBlocking,
+
+    /// Set the blocklists used to block DNS queries. Replaces the current lists, passing no
+    /// blocklists disables blocking.
+    SetDnsBlocking(SetDnsBlockingArgs),
+
+    /// Get the domains resolved using dedicated resolvers.
+    GetDnsDomainRules,
+
+    /// Set the domains resolved using dedicated resolvers, reached outside of the tunnel. Replaces
+    /// the current rules, passing no rules clears them.
+    SetDnsDomainRules(SetDnsDomainRulesArgs),
+
+    /// Check the system configuration while connected.
+    #[clap(subcommand)]
+    Diagnose(Diagnose),
+
     /// Store the account recovery phrase.
     StoreAccount(StoreAccountArgs),
 
@@ -98,11 +168,36 @@ pub(crate) enum Command {
     /// List the set of countries with available entry and exit gateways for dVPN mode.
     ListVpnCountries(ListCountriesArgs),
 
+    /// List the set of cities with available entry gateways for mixnet mode.
+    ListEntryCities(ListCountriesArgs),
+
+    /// List the set of cities with available exit gateways for mixnet mode.
+    ListExitCities(ListCountriesArgs),
+
+    /// List the set of cities with available entry and exit gateways for dVPN mode.
+    ListVpnCities(ListCountriesArgs),
+
+    /// List the set of regions with available entry gateways for mixnet mode.
+    ListEntryRegions(ListCountriesArgs),
+
+    /// List the set of regions with available exit gateways for mixnet mode.
+    ListExitRegions(ListCountriesArgs),
+
+    /// List the set of regions with available entry and exit gateways for dVPN mode.
+    ListVpnRegions(ListCountriesArgs),
+
     /// Internal commands for development and debugging.
     #[clap(subcommand, hide = true)]
     Internal(Internal),
 }
 
+#[derive(Subcommand)]
+pub(crate) enum Diagnose {
+    /// Check that only the tunnel DNS servers are in use and reached through the tunnel, reporting
+    /// any other DNS servers of the system resolver configuration.
+    Dns,
+}
+
 #[derive(Subcommand)]
 pub(crate) enum Internal {
     /// Get the list of system messages provided by the nym-vpn-api.
@@ -157,6 +252,9 @@ pub(crate) enum Internal {
 
     /// Listen to the state change stream from nym-vpnd.
     ListenToStateChanges,
+
+    /// Listen to the settings change stream from nym-vpnd.
+    ListenToSettingsChanges,
 }
 
 #[derive(Args)]
@@ -171,12 +269,33 @@ pub(crate) struct ConnectArgs {
     #[arg(long)]
     pub(crate) dns: Option<IpAddr>,
 
+    /// Resolve DNS through a local forwarder encrypting the queries, so that the exit gateway
+    /// cannot see or tamper with them.
+    #[arg(long, value_enum, conflicts_with = "dns")]
+    pub(crate) encrypted_dns: Option<EncryptedDnsProtocol>,
+
+    /// The IP addresses of the encrypted DNS resolver. Defaults to Cloudflare.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        requires_all = ["encrypted_dns", "encrypted_dns_name"]
+    )]
+    pub(crate) encrypted_dns_ip: Vec<IpAddr>,
+
+    /// The name used to verify the certificate of the encrypted DNS resolver.
+    #[arg(long, requires = "encrypted_dns_ip")]
+    pub(crate) encrypted_dns_name: Option<String>,
+
     /// Disable routing all traffic through the nym TUN device. When the flag is set, the nym TUN
     /// device will be created, but to route traffic through it you will need to do it manually,
     /// e.g. ping -Itun0.
     #[arg(long)]
     pub(crate) disable_routing: bool,
 
+    /// Allow access to local networks, such as printers and NAS devices, while connected.
+    #[arg(long)]
+    pub(crate) allow_lan: bool,
+
     /// Enable two-hop wireguard traffic. This means that traffic jumps directly from entry gateway to
     /// exit gateway using Wireguard protocol.
     #[arg(long)]
@@ -216,6 +335,48 @@ pub(crate) struct ConnectArgs {
     /// consider a gateway for routing traffic.
     #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
     pub(crate) min_gateway_vpn_performance: Option<u8>,
+
+    /// How to choose between the gateways matching the entry or exit point.
+    #[arg(long, value_enum)]
+    pub(crate) gateway_ranking: Option<GatewayRanking>,
+
+    /// Reconnect with the same gateways for up to this many seconds after the connection was
+    /// lost, to keep the same exit IP address.
+    #[arg(long, value_name = "SECONDS")]
+    pub(crate) sticky_gateways: Option<u32>,
+
+    /// Give up connecting after this many consecutive failed attempts, instead of retrying until
+    /// connected.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub(crate) max_connect_attempts: Option<u32>,
+
+    /// How the delay between connection attempts grows.
+    #[arg(long, value_enum)]
+    pub(crate) retry_backoff: Option<RetryBackoff>,
+
+    /// Delay before the first retry.
+    #[arg(long, value_name = "SECONDS")]
+    pub(crate) retry_delay: Option<u32>,
+
+    /// Longest delay between retries.
+    #[arg(long, value_name = "SECONDS")]
+    pub(crate) max_retry_delay: Option<u32>,
+
+    /// Upper bound of a random delay added before each retry.
+    #[arg(long, value_name = "SECONDS")]
+    pub(crate) retry_jitter: Option<u32>,
+
+    /// Time allowed to register with the authenticators of the gateways, for two-hop.
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u32).range(1..))]
+    pub(crate) authenticator_registration_timeout: Option<u32>,
+
+    /// Time allowed to connect to the IPR of the exit gateway, for the mixnet.
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u32).range(1..))]
+    pub(crate) ipr_connect_timeout: Option<u32>,
+
+    /// Time allowed to complete the first WireGuard handshake, for two-hop.
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u32).range(1..))]
+    pub(crate) wireguard_handshake_timeout: Option<u32>,
 }
 
 #[derive(Args)]
@@ -229,6 +390,23 @@ pub(crate) struct CliEntry {
     #[arg(long, alias = "entry-country")]
     pub(crate) entry_gateway_country: Option<String>,
 
+    /// Auto-select entry gateway by region or continent, e.g. "eu" or "north-america".
+    #[arg(long, alias = "entry-region")]
+    pub(crate) entry_gateway_region: Option<String>,
+
+    /// Auto-select entry gateway by city.
+    #[arg(long, alias = "entry-city")]
+    pub(crate) entry_gateway_city: Option<String>,
+
+    /// Auto-select the entry gateway nearest to the position, given as LATITUDE,LONGITUDE.
+    #[arg(long, alias = "entry-nearest", value_parser = parse_coordinates)]
+    pub(crate) entry_gateway_nearest: Option<Coordinates>,
+
+    /// Auto-select entry gateway within a radius around a position, given as
+    /// LATITUDE,LONGITUDE,RADIUS_KM.
+    #[arg(long, alias = "entry-area", value_parser = parse_area)]
+    pub(crate) entry_gateway_area: Option<Area>,
+
     /// Auto-select entry gateway by latency
     #[arg(long, alias = "entry-fastest")]
     pub(crate) entry_gateway_low_latency: bool,
@@ -253,6 +431,31 @@ pub(crate) struct CliExit {
     #[clap(long, alias = "exit-country")]
     pub(crate) exit_gateway_country: Option<String>,
 
+    /// Auto-select exit gateway by region or continent, e.g. "eu" or "north-america".
+    #[clap(long, alias = "exit-region")]
+    pub(crate) exit_gateway_region: Option<String>,
+
+    /// Auto-select exit gateway by city.
+    #[clap(long, alias = "exit-city")]
+    pub(crate) exit_gateway_city: Option<String>,
+
+    /// Auto-select the exit gateway nearest to the position, given as LATITUDE,LONGITUDE.
+    #[clap(long, alias = "exit-nearest", value_parser = parse_coordinates)]
+    pub(crate) exit_gateway_nearest: Option<Coordinates>,
+
+    /// Auto-select exit gateway within a radius around a position, given as
+    /// LATITUDE,LONGITUDE,RADIUS_KM.
+    #[clap(long, alias = "exit-area", value_parser = parse_area)]
+    pub(crate) exit_gateway_area: Option<Area>,
+
+    /// Auto-select exit gateway by latency.
+    #[clap(long, alias = "exit-fastest")]
+    pub(crate) exit_gateway_low_latency: bool,
+
+    /// Auto-select low latency entry and exit gateways in the country ISO.
+    #[clap(long, alias = "fastest-pair", conflicts_with = "CliEntry")]
+    pub(crate) low_latency_pair_country: Option<String>,
+
     /// Auto-select exit gateway randomly.
     #[clap(long, alias = "exit-random")]
     pub(crate) exit_gateway_random: bool,
@@ -264,6 +467,156 @@ pub(crate) struct SetNetworkArgs {
     pub(crate) network: String,
 }
 
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+pub(crate) struct SetLockdownModeArgs {
+    /// Block all traffic outside of the tunnel, even while disconnected.
+    #[arg(long)]
+    pub(crate) enable: bool,
+
+    /// Allow traffic outside of the tunnel while disconnected.
+    #[arg(long)]
+    pub(crate) disable: bool,
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+pub(crate) struct ExcludedAppArgs {
+    /// The ID of a running process.
+    #[arg(long)]
+    pub(crate) pid: Option<u32>,
+
+    /// The path of an executable. Applies to all processes running it, including future ones.
+    #[arg(long)]
+    pub(crate) executable: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub(crate) struct SetSplitRoutesArgs {
+    /// Route the network (CIDR notation) outside of the tunnel. Can be repeated.
+    #[arg(long)]
+    pub(crate) exclude: Vec<String>,
+
+    /// Only route the network (CIDR notation) through the tunnel. Can be repeated.
+    #[arg(long)]
+    pub(crate) include: Vec<String>,
+}
+
+#[derive(Args)]
+pub(crate) struct SetDnsBlockingArgs {
+    /// Absolute path of a blocklist, in hosts-file or domain-list format. Can be repeated.
+    #[arg(long)]
+    pub(crate) blocklist: Vec<PathBuf>,
+
+    /// Answer given to queries for blocked names.
+    #[arg(long, value_enum, default_value_t = BlockedResponse::Nxdomain)]
+    pub(crate) blocked_response: BlockedResponse,
+
+    /// Interval in seconds between reloads of the blocklists from disk.
+    #[arg(long)]
+    pub(crate) refresh_interval: Option<u32>,
+}
+
+#[derive(Args)]
+pub(crate) struct SetDnsDomainRulesArgs {
+    /// Resolve the domain and its subdomains using the given resolvers, e.g.
+    /// `*.corp.example=10.0.0.53,10.0.0.54`. Can be repeated.
+    #[arg(long, value_parser = parse_dns_domain_rule)]
+    pub(crate) rule: Vec<DnsDomainRule>,
+}
+
+#[derive(Clone)]
+pub(crate) struct DnsDomainRule {
+    pub(crate) domain: String,
+    pub(crate) servers: Vec<IpAddr>,
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+pub(crate) struct SetAutoConnectArgs {
+    /// Connect on startup with the last used settings.
+    #[arg(long)]
+    pub(crate) enable: bool,
+
+    /// Only connect when requested.
+    #[arg(long)]
+    pub(crate) disable: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum RetryBackoff {
+    /// Wait the same delay before every retry.
+    Constant,
+
+    /// Wait one delay longer before each retry.
+    Linear,
+
+    /// Double the delay before each retry.
+    Exponential,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum GatewayRanking {
+    /// Pick any of the matching gateways.
+    Uniform,
+
+    /// Favor gateways with a higher mixnet performance.
+    Performance,
+
+    /// Skip gateways whose last probe failed, and favor gateways with a higher mixnet performance
+    /// and a more recent probe.
+    ProbeScore,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum EncryptedDnsProtocol {
+    /// DNS-over-HTTPS.
+    Https,
+
+    /// DNS-over-TLS.
+    Tls,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum BlockedResponse {
+    /// Answer that the name does not exist.
+    Nxdomain,
+
+    /// Answer with the unspecified address, 0.0.0.0 or ::.
+    UnspecifiedAddress,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum GatewayPreferenceList {
+    /// Never select the gateway, or gateways in the country.
+    Exclude,
+
+    /// Select the gateway, or gateways in the country, whenever available.
+    Prefer,
+}
+
+#[derive(Args)]
+pub(crate) struct GatewayPreferenceArgs {
+    /// The list to update.
+    #[arg(value_enum)]
+    pub(crate) list: GatewayPreferenceList,
+
+    #[command(flatten)]
+    pub(crate) target: GatewayPreferenceTarget,
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+pub(crate) struct GatewayPreferenceTarget {
+    /// The identity of a gateway.
+    #[arg(long)]
+    pub(crate) gateway_id: Option<String>,
+
+    /// The two letter ISO country code of a country.
+    #[arg(long)]
+    pub(crate) country: Option<String>,
+}
+
 #[derive(Args)]
 pub(crate) struct StoreAccountArgs {
     /// The account mnemonic to be stored.
@@ -339,6 +692,16 @@ pub(crate) fn parse_entry_point(args: &ConnectArgs) -> Result<Option<EntryPoint>
         Ok(Some(EntryPoint::Location {
             location: entry_gateway_country.clone(),
         }))
+    } else if let Some(ref region) = args.entry.entry_gateway_region {
+        Ok(Some(EntryPoint::Region {
+            region: region.clone(),
+        }))
+    } else if let Some(ref city) = args.entry.entry_gateway_city {
+        Ok(Some(EntryPoint::City { city: city.clone() }))
+    } else if let Some(position) = args.entry.entry_gateway_nearest {
+        Ok(Some(EntryPoint::Nearest { position }))
+    } else if let Some(area) = args.entry.entry_gateway_area {
+        Ok(Some(EntryPoint::Area { area }))
     } else if args.entry.entry_gateway_low_latency {
         Ok(Some(EntryPoint::RandomLowLatency))
     } else if args.entry.entry_gateway_random {
@@ -363,9 +726,58 @@ pub(crate) fn parse_exit_point(args: &ConnectArgs) -> Result<Option<ExitPoint>>
         Ok(Some(ExitPoint::Location {
             location: exit_gateway_country.clone(),
         }))
+    } else if let Some(ref region) = args.exit.exit_gateway_region {
+        Ok(Some(ExitPoint::Region {
+            region: region.clone(),
+        }))
+    } else if let Some(ref city) = args.exit.exit_gateway_city {
+        Ok(Some(ExitPoint::City { city: city.clone() }))
+    } else if let Some(position) = args.exit.exit_gateway_nearest {
+        Ok(Some(ExitPoint::Nearest { position }))
+    } else if let Some(area) = args.exit.exit_gateway_area {
+        Ok(Some(ExitPoint::Area { area }))
+    } else if args.exit.exit_gateway_low_latency {
+        Ok(Some(ExitPoint::RandomLowLatency))
+    } else if let Some(ref country) = args.exit.low_latency_pair_country {
+        Ok(Some(ExitPoint::LowLatencyPair {
+            location: country.clone(),
+        }))
     } else if args.exit.exit_gateway_random {
         Ok(Some(ExitPoint::Random))
     } else {
         Ok(None)
     }
 }
+
+fn parse_coordinates(s: &str) -> Result<Coordinates> {
+    let (latitude, longitude) = s
+        .split_once(',')
+        .ok_or_else(|| anyhow!("Expected LATITUDE,LONGITUDE"))?;
+    Ok(Coordinates::new(
+        latitude.trim().parse()?,
+        longitude.trim().parse()?,
+    )?)
+}
+
+fn parse_dns_domain_rule(s: &str) -> Result<DnsDomainRule> {
+    let (domain, servers) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Expected DOMAIN=IP[,IP...]"))?;
+    Ok(DnsDomainRule {
+        domain: domain.trim().to_owned(),
+        servers: servers
+            .split(',')
+            .map(|server| server.trim().parse())
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn parse_area(s: &str) -> Result<Area> {
+    let (position, radius_km) = s
+        .rsplit_once(',')
+        .ok_or_else(|| anyhow!("Expected LATITUDE,LONGITUDE,RADIUS_KM"))?;
+    Ok(Area {
+        center: parse_coordinates(position)?,
+        radius_km: radius_km.trim().parse()?,
+    })
+}
@@ -9,18 +9,25 @@ mod vpnd_client;
 
 use anyhow::{anyhow, Context, Result};
 use clap::Parser;
-use cli::Internal;
+use cli::{Diagnose, Internal};
 use itertools::Itertools;
 use nym_gateway_directory::GatewayType;
 use nym_vpn_proto::{
-    ConfirmZkNymDownloadedRequest, ConnectRequest, DisconnectRequest, Empty, ForgetAccountRequest,
-    GetAccountIdentityRequest, GetAccountLinksRequest, GetAccountStateRequest,
-    GetAccountUsageRequest, GetActiveDevicesRequest, GetAvailableTicketsRequest,
-    GetDeviceIdentityRequest, GetDeviceZkNymsRequest, GetDevicesRequest, GetFeatureFlagsRequest,
-    GetSystemMessagesRequest, GetZkNymByIdRequest, GetZkNymsAvailableForDownloadRequest,
-    InfoRequest, InfoResponse, IsAccountStoredRequest, IsReadyToConnectRequest,
-    ListCountriesRequest, ListGatewaysRequest, RefreshAccountStateRequest, RegisterDeviceRequest,
-    RequestZkNymRequest, ResetDeviceIdentityRequest, SetNetworkRequest, StatusRequest,
+    AddExcludedAppRequest, ConfirmZkNymDownloadedRequest, ConnectRequest, DiagnoseDnsRequest,
+    DisconnectRequest, Empty, ExcludedApp, ForgetAccountRequest, GetAccountIdentityRequest,
+    GetAccountLinksRequest, GetAccountStateRequest, GetAccountUsageRequest,
+    GetActiveDevicesRequest, GetAutoConnectRequest, GetAvailableTicketsRequest,
+    GetConnectionHistoryRequest, GetDeviceIdentityRequest, GetDeviceZkNymsRequest,
+    GetDevicesRequest, GetDnsBlockingRequest, GetDnsDomainRulesRequest, GetFeatureFlagsRequest,
+    GetGatewayPreferencesRequest, GetLockdownModeRequest, GetSettingsRequest,
+    GetSplitRoutesRequest, GetSystemMessagesRequest, GetZkNymByIdRequest,
+    GetZkNymsAvailableForDownloadRequest, InfoRequest, InfoResponse, IsAccountStoredRequest,
+    IsReadyToConnectRequest, ListCitiesRequest, ListCountriesRequest, ListExcludedAppsRequest,
+    ListGatewaysRequest, ListQuarantinedGatewaysRequest, ListRegionsRequest,
+    RefreshAccountStateRequest, RegisterDeviceRequest, RemoveExcludedAppRequest,
+    RequestZkNymRequest, ResetDeviceIdentityRequest, ResetSettingsRequest, SetAutoConnectRequest,
+    SetDnsBlockingRequest, SetDnsDomainRulesRequest, SetGatewayPreferencesRequest,
+    SetLockdownModeRequest, SetNetworkRequest, SetSplitRoutesRequest, StatusRequest,
     StoreAccountRequest, UserAgent,
 };
 use protobuf_conversion::into_gateway_type;
@@ -29,7 +36,10 @@ use vpnd_client::ClientType;
 
 use crate::{
     cli::Command,
-    protobuf_conversion::{into_entry_point, into_exit_point},
+    protobuf_conversion::{
+        into_connect_timeouts, into_dns_blocking, into_dns_domain_rule, into_encrypted_dns,
+        into_entry_point, into_exit_point, into_gateway_ranking, into_retry_policy,
+    },
 };
 
 #[derive(Clone, Debug)]
@@ -59,6 +69,35 @@ async fn main() -> Result<()> {
         Command::Status => status(opts).await?,
         Command::Info => info(opts.client_type).await?,
         Command::SetNetwork(ref args) => set_network(opts.client_type, args).await?,
+        Command::GetLockdownMode => get_lockdown_mode(opts.client_type).await?,
+        Command::SetLockdownMode(ref args) => set_lockdown_mode(opts.client_type, args).await?,
+        Command::GetAutoConnect => get_auto_connect(opts.client_type).await?,
+        Command::SetAutoConnect(ref args) => set_auto_connect(opts.client_type, args).await?,
+        Command::GetSettings => get_settings(opts.client_type).await?,
+        Command::ResetSettings => reset_settings(opts.client_type).await?,
+        Command::GetGatewayPreferences => get_gateway_preferences(opts.client_type).await?,
+        Command::AddGatewayPreference(ref args) => {
+            update_gateway_preferences(opts.client_type, args, true).await?
+        }
+        Command::RemoveGatewayPreference(ref args) => {
+            update_gateway_preferences(opts.client_type, args, false).await?
+        }
+        Command::ListQuarantinedGateways => list_quarantined_gateways(opts.client_type).await?,
+        Command::History => get_connection_history(opts.client_type).await?,
+        Command::AddExcludedApp(ref args) => add_excluded_app(opts.client_type, args).await?,
+        Command::RemoveExcludedApp(ref args) => remove_excluded_app(opts.client_type, args).await?,
+        Command::ListExcludedApps => list_excluded_apps(opts.client_type).await?,
+        Command::GetSplitRoutes => get_split_routes(opts.client_type).await?,
+        Command::SetSplitRoutes(ref args) => set_split_routes(opts.client_type, args).await?,
+        Command::GetDnsBlocking => get_dns_blocking(opts.client_type).await?,
+        Command::SetDnsBlocking(ref args) => set_dns_blocking(opts.client_type, args).await?,
+        Command::GetDnsDomainRules => get_dns_domain_rules(opts.client_type).await?,
+        Command::SetDnsDomainRules(ref args) => {
+            set_dns_domain_rules(opts.client_type, args).await?
+        }
+        Command::Diagnose(diagnose) => match diagnose {
+            Diagnose::Dns => diagnose_dns(opts.client_type).await?,
+        },
         Command::StoreAccount(ref store_args) => store_account(opts, store_args).await?,
         Command::IsAccountStored => is_account_stored(opts.client_type).await?,
         Command::ForgetAccount => forget_account(opts.client_type).await?,
@@ -83,6 +122,24 @@ async fn main() -> Result<()> {
         Command::ListVpnCountries(ref list_args) => {
             list_countries(opts, list_args, GatewayType::Wg).await?
         }
+        Command::ListEntryCities(ref list_args) => {
+            list_cities(opts, list_args, GatewayType::MixnetEntry).await?
+        }
+        Command::ListExitCities(ref list_args) => {
+            list_cities(opts, list_args, GatewayType::MixnetExit).await?
+        }
+        Command::ListVpnCities(ref list_args) => {
+            list_cities(opts, list_args, GatewayType::Wg).await?
+        }
+        Command::ListEntryRegions(ref list_args) => {
+            list_regions(opts, list_args, GatewayType::MixnetEntry).await?
+        }
+        Command::ListExitRegions(ref list_args) => {
+            list_regions(opts, list_args, GatewayType::MixnetExit).await?
+        }
+        Command::ListVpnRegions(ref list_args) => {
+            list_regions(opts, list_args, GatewayType::Wg).await?
+        }
         Command::GetDeviceId => get_device_id(opts.client_type).await?,
         Command::Internal(internal) => match internal {
             Internal::GetSystemMessages => get_system_messages(opts.client_type).await?,
@@ -92,6 +149,9 @@ async fn main() -> Result<()> {
             Internal::IsReadyToConnect => is_ready_to_connect(opts.client_type).await?,
             Internal::ListenToStatus => listen_to_status(opts.client_type).await?,
             Internal::ListenToStateChanges => listen_to_state_changes(opts.client_type).await?,
+            Internal::ListenToSettingsChanges => {
+                listen_to_settings_changes(opts.client_type).await?
+            }
             Internal::ResetDeviceIdentity(ref args) => {
                 reset_device_identity(opts.client_type, args).await?
             }
@@ -152,6 +212,7 @@ async fn connect(opts: CliOptions, connect_args: &cli::ConnectArgs) -> Result<()
         entry: entry.map(into_entry_point),
         exit: exit.map(into_exit_point),
         dns: connect_args.dns.map(nym_vpn_proto::Dns::from),
+        encrypted_dns: into_encrypted_dns(connect_args),
         disable_routing: connect_args.disable_routing,
         enable_two_hop: connect_args.enable_two_hop,
         netstack: connect_args.netstack,
@@ -168,6 +229,17 @@ async fn connect(opts: CliOptions, connect_args: &cli::ConnectArgs) -> Result<()
         min_gateway_vpn_performance: connect_args
             .min_gateway_vpn_performance
             .map(nym_vpn_proto::Threshold::from),
+        allow_lan: connect_args.allow_lan,
+        gateway_ranking: connect_args
+            .gateway_ranking
+            .map_or(
+                nym_vpn_proto::GatewayRanking::Unspecified,
+                into_gateway_ranking,
+            )
+            .into(),
+        sticky_gateways_secs: connect_args.sticky_gateways,
+        retry_policy: Some(into_retry_policy(connect_args)),
+        connect_timeouts: Some(into_connect_timeouts(connect_args)),
     });
 
     let response = client.vpn_connect(request).await?.into_inner();
@@ -325,6 +397,237 @@ async fn set_network(client_type: ClientType, args: &cli::SetNetworkArgs) -> Res
     Ok(())
 }
 
+async fn get_lockdown_mode(client_type: ClientType) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(GetLockdownModeRequest {});
+    let response = client.get_lockdown_mode(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn set_lockdown_mode(client_type: ClientType, args: &cli::SetLockdownModeArgs) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(SetLockdownModeRequest {
+        enabled: args.enable,
+    });
+    let response = client.set_lockdown_mode(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+fn into_excluded_app(args: &cli::ExcludedAppArgs) -> ExcludedApp {
+    let app = if let Some(pid) = args.pid {
+        nym_vpn_proto::excluded_app::App::Pid(pid)
+    } else {
+        let executable = args
+            .executable
+            .as_ref()
+            .expect("pid or executable is required");
+        nym_vpn_proto::excluded_app::App::Executable(executable.display().to_string())
+    };
+    ExcludedApp { app: Some(app) }
+}
+
+async fn add_excluded_app(client_type: ClientType, args: &cli::ExcludedAppArgs) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(AddExcludedAppRequest {
+        app: Some(into_excluded_app(args)),
+    });
+    let response = client.add_excluded_app(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn remove_excluded_app(client_type: ClientType, args: &cli::ExcludedAppArgs) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(RemoveExcludedAppRequest {
+        app: Some(into_excluded_app(args)),
+    });
+    let response = client.remove_excluded_app(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn list_excluded_apps(client_type: ClientType) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(ListExcludedAppsRequest {});
+    let response = client.list_excluded_apps(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn get_split_routes(client_type: ClientType) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(GetSplitRoutesRequest {});
+    let response = client.get_split_routes(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn set_split_routes(client_type: ClientType, args: &cli::SetSplitRoutesArgs) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(SetSplitRoutesRequest {
+        excluded_routes: args.exclude.clone(),
+        included_routes: args.include.clone(),
+    });
+    let response = client.set_split_routes(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn get_dns_blocking(client_type: ClientType) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(GetDnsBlockingRequest {});
+    let response = client.get_dns_blocking(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn set_dns_blocking(client_type: ClientType, args: &cli::SetDnsBlockingArgs) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(SetDnsBlockingRequest {
+        dns_blocking: Some(into_dns_blocking(args)),
+    });
+    let response = client.set_dns_blocking(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn get_dns_domain_rules(client_type: ClientType) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(GetDnsDomainRulesRequest {});
+    let response = client.get_dns_domain_rules(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn set_dns_domain_rules(
+    client_type: ClientType,
+    args: &cli::SetDnsDomainRulesArgs,
+) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(SetDnsDomainRulesRequest {
+        dns_domain_rules: args.rule.iter().map(into_dns_domain_rule).collect(),
+    });
+    let response = client.set_dns_domain_rules(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn diagnose_dns(client_type: ClientType) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(DiagnoseDnsRequest {});
+    let response = client.diagnose_dns(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn get_auto_connect(client_type: ClientType) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(GetAutoConnectRequest {});
+    let response = client.get_auto_connect(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn set_auto_connect(client_type: ClientType, args: &cli::SetAutoConnectArgs) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(SetAutoConnectRequest {
+        enabled: args.enable,
+    });
+    let response = client.set_auto_connect(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn get_settings(client_type: ClientType) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(GetSettingsRequest {});
+    let response = client.get_settings(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn reset_settings(client_type: ClientType) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(ResetSettingsRequest {});
+    let response = client.reset_settings(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn get_gateway_preferences(client_type: ClientType) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(GetGatewayPreferencesRequest {});
+    let response = client.get_gateway_preferences(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn update_gateway_preferences(
+    client_type: ClientType,
+    args: &cli::GatewayPreferenceArgs,
+    add: bool,
+) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(GetGatewayPreferencesRequest {});
+    let mut preferences = client
+        .get_gateway_preferences(request)
+        .await?
+        .into_inner()
+        .preferences
+        .unwrap_or_default();
+
+    let (list, value) = match (args.list, &args.target.gateway_id, &args.target.country) {
+        (cli::GatewayPreferenceList::Exclude, Some(gateway_id), _) => {
+            (&mut preferences.excluded_gateways, gateway_id.clone())
+        }
+        (cli::GatewayPreferenceList::Prefer, Some(gateway_id), _) => {
+            (&mut preferences.preferred_gateways, gateway_id.clone())
+        }
+        (cli::GatewayPreferenceList::Exclude, None, Some(country)) => {
+            (&mut preferences.excluded_countries, country.to_uppercase())
+        }
+        (cli::GatewayPreferenceList::Prefer, None, Some(country)) => {
+            (&mut preferences.preferred_countries, country.to_uppercase())
+        }
+        (_, None, None) => return Err(anyhow!("No gateway or country provided")),
+    };
+    if add {
+        if !list.contains(&value) {
+            list.push(value);
+        }
+    } else {
+        list.retain(|item| item != &value);
+    }
+
+    let request = tonic::Request::new(SetGatewayPreferencesRequest {
+        preferences: Some(preferences),
+    });
+    let response = client.set_gateway_preferences(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn list_quarantined_gateways(client_type: ClientType) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(ListQuarantinedGatewaysRequest {});
+    let response = client
+        .list_quarantined_gateways(request)
+        .await?
+        .into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
+async fn get_connection_history(client_type: ClientType) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(GetConnectionHistoryRequest {});
+    let response = client.get_connection_history(request).await?.into_inner();
+    println!("{:#?}", response);
+    Ok(())
+}
+
 async fn get_system_messages(client_type: ClientType) -> Result<()> {
     let mut client = vpnd_client::get_client(&client_type).await?;
     let request = tonic::Request::new(GetSystemMessagesRequest {});
@@ -580,6 +883,19 @@ async fn listen_to_state_changes(client_type: ClientType) -> Result<()> {
     Ok(())
 }
 
+async fn listen_to_settings_changes(client_type: ClientType) -> Result<()> {
+    let mut client = vpnd_client::get_client(&client_type).await?;
+    let request = tonic::Request::new(Empty {});
+    let mut stream = client
+        .listen_to_settings_changes(request)
+        .await?
+        .into_inner();
+    while let Some(response) = stream.message().await? {
+        println!("{:#?}", response);
+    }
+    Ok(())
+}
+
 async fn list_gateways(
     opts: CliOptions,
     list_args: &cli::ListGatewaysArgs,
@@ -659,3 +975,89 @@ async fn list_countries(
 
     Ok(())
 }
+
+async fn list_cities(
+    opts: CliOptions,
+    list_args: &cli::ListCountriesArgs,
+    gw_type: GatewayType,
+) -> Result<()> {
+    let mut client = vpnd_client::get_client(&opts.client_type).await?;
+
+    let info_request = tonic::Request::new(InfoRequest {});
+    let info = client.info(info_request).await?.into_inner();
+    let user_agent = setup_user_agent(&opts, info);
+
+    let request = tonic::Request::new(ListCitiesRequest {
+        kind: into_gateway_type(gw_type.clone()) as i32,
+        user_agent: Some(user_agent),
+        min_mixnet_performance: list_args
+            .min_mixnet_performance
+            .map(nym_vpn_proto::Threshold::from),
+        min_vpn_performance: list_args
+            .min_vpn_performance
+            .map(nym_vpn_proto::Threshold::from),
+    });
+
+    let response = client.list_cities(request).await?.into_inner();
+    if opts.verbose {
+        println!("{:#?}", response);
+    }
+
+    let cities = response
+        .cities
+        .into_iter()
+        .map(nym_vpnd_types::gateway::City::from)
+        .collect::<Vec<_>>();
+
+    println!(
+        "Cities for {} ({}): {}",
+        gw_type,
+        cities.len(),
+        cities.iter().join(", ")
+    );
+
+    Ok(())
+}
+
+async fn list_regions(
+    opts: CliOptions,
+    list_args: &cli::ListCountriesArgs,
+    gw_type: GatewayType,
+) -> Result<()> {
+    let mut client = vpnd_client::get_client(&opts.client_type).await?;
+
+    let info_request = tonic::Request::new(InfoRequest {});
+    let info = client.info(info_request).await?.into_inner();
+    let user_agent = setup_user_agent(&opts, info);
+
+    let request = tonic::Request::new(ListRegionsRequest {
+        kind: into_gateway_type(gw_type.clone()) as i32,
+        user_agent: Some(user_agent),
+        min_mixnet_performance: list_args
+            .min_mixnet_performance
+            .map(nym_vpn_proto::Threshold::from),
+        min_vpn_performance: list_args
+            .min_vpn_performance
+            .map(nym_vpn_proto::Threshold::from),
+    });
+
+    let response = client.list_regions(request).await?.into_inner();
+    if opts.verbose {
+        println!("{:#?}", response);
+    }
+
+    let regions = response
+        .regions
+        .into_iter()
+        .map(nym_vpnd_types::gateway::Region::from)
+        .collect::<Vec<_>>();
+
+    println!(
+        "Regions for {} ({}): {}",
+        gw_type,
+        regions.len(),
+        regions.iter().join(", ")
+    );
+
+    Ok(())
+}
@@ -3,12 +3,27 @@
 
 use nym_gateway_directory::{EntryPoint, ExitPoint, GatewayType};
 
+use crate::cli::{
+    BlockedResponse, ConnectArgs, DnsDomainRule, EncryptedDnsProtocol, GatewayRanking,
+    RetryBackoff, SetDnsBlockingArgs,
+};
+
 pub(crate) fn into_entry_point(entry: EntryPoint) -> nym_vpn_proto::EntryNode {
     match entry {
         EntryPoint::Gateway { identity } => nym_vpn_proto::EntryNode::from(&identity),
         EntryPoint::Location { location } => nym_vpn_proto::EntryNode::new_from_location(&location),
         EntryPoint::RandomLowLatency => nym_vpn_proto::EntryNode::new_random_low_latency(),
         EntryPoint::Random => nym_vpn_proto::EntryNode::new_random(),
+        EntryPoint::Region { region } => nym_vpn_proto::EntryNode::new_from_region(&region),
+        EntryPoint::City { city } => nym_vpn_proto::EntryNode::new_from_city(&city),
+        EntryPoint::Nearest { position } => {
+            nym_vpn_proto::EntryNode::new_nearest(position.latitude(), position.longitude())
+        }
+        EntryPoint::Area { area } => nym_vpn_proto::EntryNode::new_from_area(
+            area.center.latitude(),
+            area.center.longitude(),
+            area.radius_km,
+        ),
     }
 }
 
@@ -18,6 +33,20 @@ pub(crate) fn into_exit_point(exit: ExitPoint) -> nym_vpn_proto::ExitNode {
         ExitPoint::Gateway { identity } => nym_vpn_proto::ExitNode::from(&identity),
         ExitPoint::Location { location } => nym_vpn_proto::ExitNode::new_from_location(&location),
         ExitPoint::Random => nym_vpn_proto::ExitNode::new_random(),
+        ExitPoint::RandomLowLatency => nym_vpn_proto::ExitNode::new_random_low_latency(),
+        ExitPoint::LowLatencyPair { location } => {
+            nym_vpn_proto::ExitNode::new_low_latency_pair(&location)
+        }
+        ExitPoint::Region { region } => nym_vpn_proto::ExitNode::new_from_region(&region),
+        ExitPoint::City { city } => nym_vpn_proto::ExitNode::new_from_city(&city),
+        ExitPoint::Nearest { position } => {
+            nym_vpn_proto::ExitNode::new_nearest(position.latitude(), position.longitude())
+        }
+        ExitPoint::Area { area } => nym_vpn_proto::ExitNode::new_from_area(
+            area.center.latitude(),
+            area.center.longitude(),
+            area.radius_km,
+        ),
     }
 }
 
@@ -28,3 +57,81 @@ pub(crate) fn into_gateway_type(gateway_type: GatewayType) -> nym_vpn_proto::Gat
         GatewayType::Wg => nym_vpn_proto::GatewayType::Wg,
     }
 }
+
+pub(crate) fn into_gateway_ranking(ranking: GatewayRanking) -> nym_vpn_proto::GatewayRanking {
+    match ranking {
+        GatewayRanking::Uniform => nym_vpn_proto::GatewayRanking::Uniform,
+        GatewayRanking::Performance => nym_vpn_proto::GatewayRanking::Performance,
+        GatewayRanking::ProbeScore => nym_vpn_proto::GatewayRanking::ProbeScore,
+    }
+}
+
+pub(crate) fn into_encrypted_dns(
+    connect_args: &ConnectArgs,
+) -> Option<nym_vpn_proto::EncryptedDns> {
+    let protocol = match connect_args.encrypted_dns? {
+        EncryptedDnsProtocol::Https => nym_vpn_proto::encrypted_dns::Protocol::Https,
+        EncryptedDnsProtocol::Tls => nym_vpn_proto::encrypted_dns::Protocol::Tls,
+    };
+
+    Some(nym_vpn_proto::EncryptedDns {
+        protocol: protocol.into(),
+        ips: connect_args
+            .encrypted_dns_ip
+            .iter()
+            .map(|ip| ip.to_string())
+            .collect(),
+        tls_name: connect_args.encrypted_dns_name.clone().unwrap_or_default(),
+    })
+}
+
+pub(crate) fn into_retry_policy(connect_args: &ConnectArgs) -> nym_vpn_proto::RetryPolicy {
+    let backoff = match connect_args.retry_backoff {
+        None => nym_vpn_proto::retry_policy::Backoff::Unspecified,
+        Some(RetryBackoff::Constant) => nym_vpn_proto::retry_policy::Backoff::Constant,
+        Some(RetryBackoff::Linear) => nym_vpn_proto::retry_policy::Backoff::Linear,
+        Some(RetryBackoff::Exponential) => nym_vpn_proto::retry_policy::Backoff::Exponential,
+    };
+
+    nym_vpn_proto::RetryPolicy {
+        max_attempts: connect_args.max_connect_attempts,
+        backoff: backoff.into(),
+        delay_secs: connect_args.retry_delay,
+        max_delay_secs: connect_args.max_retry_delay,
+        jitter_secs: connect_args.retry_jitter.unwrap_or_default(),
+    }
+}
+
+pub(crate) fn into_connect_timeouts(connect_args: &ConnectArgs) -> nym_vpn_proto::ConnectTimeouts {
+    nym_vpn_proto::ConnectTimeouts {
+        authenticator_registration_secs: connect_args.authenticator_registration_timeout,
+        ipr_connect_secs: connect_args.ipr_connect_timeout,
+        wireguard_handshake_secs: connect_args.wireguard_handshake_timeout,
+    }
+}
+
+pub(crate) fn into_dns_blocking(args: &SetDnsBlockingArgs) -> nym_vpn_proto::DnsBlocking {
+    let blocked_response = match args.blocked_response {
+        BlockedResponse::Nxdomain => nym_vpn_proto::dns_blocking::BlockedResponse::Nxdomain,
+        BlockedResponse::UnspecifiedAddress => {
+            nym_vpn_proto::dns_blocking::BlockedResponse::UnspecifiedAddress
+        }
+    };
+
+    nym_vpn_proto::DnsBlocking {
+        blocklists: args
+            .blocklist
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect(),
+        blocked_response: blocked_response.into(),
+        refresh_interval_secs: args.refresh_interval,
+    }
+}
+
+pub(crate) fn into_dns_domain_rule(rule: &DnsDomainRule) -> nym_vpn_proto::DnsDomainRule {
+    nym_vpn_proto::DnsDomainRule {
+        domain: rule.domain.clone(),
+        servers: rule.servers.iter().map(|ip| ip.to_string()).collect(),
+    }
+}
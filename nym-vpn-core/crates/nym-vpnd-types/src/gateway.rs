@@ -17,6 +17,7 @@ pub struct Location {
     pub two_letter_iso_country_code: String,
     pub latitude: Option<f64>,
     pub longitude: Option<f64>,
+    pub city: Option<String>,
 }
 
 impl fmt::Display for Location {
@@ -70,6 +71,19 @@ impl Country {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct City {
+    pub name: String,
+    pub iso_code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Region {
+    pub id: String,
+    pub name: String,
+    pub iso_codes: Vec<String>,
+}
+
 impl fmt::Display for Gateway {
     #[rustfmt::skip]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -102,6 +116,45 @@ impl From<nym_vpn_lib::gateway_directory::Country> for Country {
     }
 }
 
+impl fmt::Display for City {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}", self.name, self.iso_code)
+    }
+}
+
+impl From<nym_vpn_lib::gateway_directory::City> for City {
+    fn from(city: nym_vpn_lib::gateway_directory::City) -> Self {
+        Self {
+            name: city.name().to_string(),
+            iso_code: city.iso_code().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.id)
+    }
+}
+
+impl Region {
+    // Keeps only the countries of the region that are in the list
+    pub fn with_countries(
+        region: &nym_vpn_lib::gateway_directory::Region,
+        iso_codes: &[String],
+    ) -> Self {
+        Self {
+            id: region.id().to_string(),
+            name: region.name().to_string(),
+            iso_codes: iso_codes
+                .iter()
+                .filter(|code| region.contains_country(code))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
 impl From<nym_validator_client::models::NymNodeDescription> for Gateway {
     fn from(node_description: nym_validator_client::models::NymNodeDescription) -> Self {
         Self {
@@ -123,6 +176,7 @@ impl From<nym_vpn_lib::gateway_directory::Location> for Location {
             two_letter_iso_country_code: location.two_letter_iso_country_code,
             latitude: Some(location.latitude),
             longitude: Some(location.longitude),
+            city: location.city,
         }
     }
 }
@@ -9,13 +9,20 @@ use nym_vpn_api_client::{
     response::{NymVpnDevice, NymVpnUsage},
     types::GatewayMinPerformance,
 };
-use nym_vpn_lib::gateway_directory::{EntryPoint, ExitPoint, GatewayClient, GatewayType};
+use nym_vpn_lib::{
+    gateway_directory::{EntryPoint, ExitPoint, GatewayClient, GatewayPreferences, GatewayType},
+    tunnel_state_machine::{DnsDomainRule, QuarantinedGateway, SplitRoutes},
+};
 use nym_vpnd_types::gateway;
 use zeroize::Zeroizing;
 
 use crate::service::{
-    AccountError, ConnectArgs, ConnectOptions, SetNetworkError, VpnServiceCommand,
-    VpnServiceConnectError, VpnServiceDisconnectError, VpnServiceInfo, VpnServiceStatus,
+    AccountError, ConnectArgs, ConnectOptions, ConnectionHistoryRecord, DiagnoseDnsError,
+    DnsBlockingConfig, DnsDiagnostics, ExcludedApp, ExcludedApps, NymVpnServiceConfig,
+    SetAutoConnectError, SetDnsBlockingError, SetDnsDomainRulesError, SetGatewayPreferencesError,
+    SetLockdownModeError, SetNetworkError, SetSplitRoutesError, SettingsError, SplitTunnelError,
+    VpnServiceCommand, VpnServiceConnectError, VpnServiceDisconnectError, VpnServiceInfo,
+    VpnServiceStatus,
 };
 
 use super::protobuf::error::VpnCommandSendError;
@@ -38,6 +45,18 @@ pub enum ListGatewayError {
         gw_type: GatewayType,
         source: nym_vpn_lib::gateway_directory::Error,
     },
+
+    #[error("failed to get cities ({gw_type}): {source}")]
+    GetCities {
+        gw_type: GatewayType,
+        source: nym_vpn_lib::gateway_directory::Error,
+    },
+
+    #[error("failed to get regions ({gw_type}): {source}")]
+    GetRegions {
+        gw_type: GatewayType,
+        source: nym_vpn_lib::gateway_directory::Error,
+    },
 }
 
 pub(super) struct CommandInterfaceConnectionHandler {
@@ -102,6 +121,154 @@ impl CommandInterfaceConnectionHandler {
         self.send_and_wait(VpnServiceCommand::Status, ()).await
     }
 
+    pub(crate) async fn handle_get_lockdown_mode(&self) -> Result<bool, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::GetLockdownMode, ())
+            .await
+    }
+
+    pub(crate) async fn handle_set_lockdown_mode(
+        &self,
+        enabled: bool,
+    ) -> Result<Result<(), SetLockdownModeError>, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::SetLockdownMode, enabled)
+            .await
+    }
+
+    pub(crate) async fn handle_get_auto_connect(&self) -> Result<bool, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::GetAutoConnect, ())
+            .await
+    }
+
+    pub(crate) async fn handle_set_auto_connect(
+        &self,
+        enabled: bool,
+    ) -> Result<Result<(), SetAutoConnectError>, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::SetAutoConnect, enabled)
+            .await
+    }
+
+    pub(crate) async fn handle_get_settings(
+        &self,
+    ) -> Result<Result<NymVpnServiceConfig, SettingsError>, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::GetSettings, ()).await
+    }
+
+    pub(crate) async fn handle_set_settings(
+        &self,
+        settings: NymVpnServiceConfig,
+    ) -> Result<Result<(), SettingsError>, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::SetSettings, Box::new(settings))
+            .await
+    }
+
+    pub(crate) async fn handle_reset_settings(
+        &self,
+    ) -> Result<Result<(), SettingsError>, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::ResetSettings, ())
+            .await
+    }
+
+    pub(crate) async fn handle_get_gateway_preferences(
+        &self,
+    ) -> Result<GatewayPreferences, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::GetGatewayPreferences, ())
+            .await
+    }
+
+    pub(crate) async fn handle_set_gateway_preferences(
+        &self,
+        preferences: GatewayPreferences,
+    ) -> Result<Result<(), SetGatewayPreferencesError>, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::SetGatewayPreferences, preferences)
+            .await
+    }
+
+    pub(crate) async fn handle_list_quarantined_gateways(
+        &self,
+    ) -> Result<Vec<QuarantinedGateway>, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::ListQuarantinedGateways, ())
+            .await
+    }
+
+    pub(crate) async fn handle_get_connection_history(
+        &self,
+    ) -> Result<Vec<ConnectionHistoryRecord>, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::GetConnectionHistory, ())
+            .await
+    }
+
+    pub(crate) async fn handle_add_excluded_app(
+        &self,
+        app: ExcludedApp,
+    ) -> Result<Result<(), SplitTunnelError>, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::AddExcludedApp, app)
+            .await
+    }
+
+    pub(crate) async fn handle_remove_excluded_app(
+        &self,
+        app: ExcludedApp,
+    ) -> Result<Result<(), SplitTunnelError>, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::RemoveExcludedApp, app)
+            .await
+    }
+
+    pub(crate) async fn handle_list_excluded_apps(
+        &self,
+    ) -> Result<Result<ExcludedApps, SplitTunnelError>, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::ListExcludedApps, ())
+            .await
+    }
+
+    pub(crate) async fn handle_get_split_routes(&self) -> Result<SplitRoutes, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::GetSplitRoutes, ())
+            .await
+    }
+
+    pub(crate) async fn handle_set_split_routes(
+        &self,
+        split_routes: SplitRoutes,
+    ) -> Result<Result<(), SetSplitRoutesError>, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::SetSplitRoutes, split_routes)
+            .await
+    }
+
+    pub(crate) async fn handle_get_dns_blocking(
+        &self,
+    ) -> Result<DnsBlockingConfig, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::GetDnsBlocking, ())
+            .await
+    }
+
+    pub(crate) async fn handle_set_dns_blocking(
+        &self,
+        dns_blocking: DnsBlockingConfig,
+    ) -> Result<Result<(), SetDnsBlockingError>, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::SetDnsBlocking, dns_blocking)
+            .await
+    }
+
+    pub(crate) async fn handle_get_dns_domain_rules(
+        &self,
+    ) -> Result<Vec<DnsDomainRule>, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::GetDnsDomainRules, ())
+            .await
+    }
+
+    pub(crate) async fn handle_set_dns_domain_rules(
+        &self,
+        dns_domain_rules: Vec<DnsDomainRule>,
+    ) -> Result<Result<(), SetDnsDomainRulesError>, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::SetDnsDomainRules, dns_domain_rules)
+            .await
+    }
+
+    pub(crate) async fn handle_diagnose_dns(
+        &self,
+    ) -> Result<Result<DnsDiagnostics, DiagnoseDnsError>, VpnCommandSendError> {
+        self.send_and_wait(VpnServiceCommand::DiagnoseDns, ()).await
+    }
+
     pub(crate) async fn handle_list_gateways(
         &self,
         gw_type: GatewayType,
@@ -130,6 +297,43 @@ impl CommandInterfaceConnectionHandler {
         Ok(gateways.into_iter().map(gateway::Country::from).collect())
     }
 
+    pub(crate) async fn handle_list_cities(
+        &self,
+        gw_type: GatewayType,
+        user_agent: nym_vpn_lib::UserAgent,
+        min_gateway_performance: GatewayMinPerformance,
+    ) -> Result<Vec<gateway::City>, ListGatewayError> {
+        let gateways = directory_client(user_agent, min_gateway_performance)?
+            .lookup_gateways(gw_type.clone())
+            .await
+            .map_err(|source| ListGatewayError::GetCities { gw_type, source })?;
+
+        Ok(gateways
+            .all_cities()
+            .into_iter()
+            .map(gateway::City::from)
+            .collect())
+    }
+
+    pub(crate) async fn handle_list_regions(
+        &self,
+        gw_type: GatewayType,
+        user_agent: nym_vpn_lib::UserAgent,
+        min_gateway_performance: GatewayMinPerformance,
+    ) -> Result<Vec<gateway::Region>, ListGatewayError> {
+        let gateways = directory_client(user_agent, min_gateway_performance)?
+            .lookup_gateways(gw_type.clone())
+            .await
+            .map_err(|source| ListGatewayError::GetRegions { gw_type, source })?;
+
+        let iso_codes = gateways.all_iso_codes();
+        Ok(gateways
+            .all_regions()
+            .into_iter()
+            .map(|region| gateway::Region::with_countries(region, &iso_codes))
+            .collect())
+    }
+
     pub(crate) async fn handle_store_account(
         &self,
         account: Zeroizing<String>,
@@ -5,4 +5,10 @@ pub(crate) enum CommandInterfaceError {
         ip: String,
         source: std::net::AddrParseError,
     },
+
+    #[error("invalid encrypted DNS settings: {0}")]
+    InvalidEncryptedDns(String),
+
+    #[error("invalid connect options: {0}")]
+    InvalidConnectOptions(String),
 }
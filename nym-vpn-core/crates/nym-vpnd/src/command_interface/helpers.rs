@@ -1,13 +1,27 @@
 // Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
+use std::{net::IpAddr, path::PathBuf};
+
+use ipnetwork::IpNetwork;
 use nym_vpn_api_client::types::Percent;
 use nym_vpn_lib::{
-    gateway_directory::{EntryPoint, ExitPoint},
+    gateway_directory::{Area, Coordinates, EntryPoint, ExitPoint, GatewayPreferences, Region},
+    tunnel_state_machine::{
+        BlockedResponse, DnsDomainRule, EncryptedDnsProtocol, EncryptedDnsUpstream, SplitRoutes,
+    },
     NodeIdentity, Recipient,
 };
 use tracing::{error, info};
 
+use super::error::CommandInterfaceError;
+use crate::service::{
+    is_valid_country_code, is_valid_domain, ConnectOptions, ConnectTimeoutOptions,
+    DnsBlockingConfig, ExcludedApp, NymVpnServiceConfig, RetryBackoff, RetryOptions,
+    SetDnsBlockingError, SetDnsDomainRulesError, SetGatewayPreferencesError, SetSplitRoutesError,
+    SettingsError,
+};
+
 pub(super) fn parse_entry_point(
     entry: nym_vpn_proto::entry_node::EntryNodeEnum,
 ) -> Result<EntryPoint, tonic::Status> {
@@ -37,6 +51,26 @@ pub(super) fn parse_entry_point(
             info!("Connecting to random entry node");
             EntryPoint::Random
         }
+        nym_vpn_proto::entry_node::EntryNodeEnum::Region(region) => {
+            info!("Connecting to entry node in region: {:?}", region);
+            EntryPoint::Region {
+                region: parse_region(&region)?,
+            }
+        }
+        nym_vpn_proto::entry_node::EntryNodeEnum::City(city) => {
+            info!("Connecting to entry node in city: {:?}", city);
+            EntryPoint::City { city }
+        }
+        nym_vpn_proto::entry_node::EntryNodeEnum::Nearest(position) => {
+            let position = parse_coordinates(position)?;
+            info!("Connecting to entry node nearest to: {}", position);
+            EntryPoint::Nearest { position }
+        }
+        nym_vpn_proto::entry_node::EntryNodeEnum::Area(area) => {
+            let area = parse_area(area)?;
+            info!("Connecting to entry node in area: {}", area);
+            EntryPoint::Area { area }
+        }
     })
 }
 
@@ -77,9 +111,392 @@ pub(super) fn parse_exit_point(
             info!("Connecting to low latency exit node");
             ExitPoint::Random
         }
+        nym_vpn_proto::exit_node::ExitNodeEnum::RandomLowLatency(_) => {
+            info!("Connecting to low latency exit node");
+            ExitPoint::RandomLowLatency
+        }
+        nym_vpn_proto::exit_node::ExitNodeEnum::LowLatencyPair(location) => {
+            info!(
+                "Connecting to low latency entry and exit nodes in country: {:?}",
+                location.two_letter_iso_country_code
+            );
+            ExitPoint::LowLatencyPair {
+                location: location.two_letter_iso_country_code.to_string(),
+            }
+        }
+        nym_vpn_proto::exit_node::ExitNodeEnum::Region(region) => {
+            info!("Connecting to exit node in region: {:?}", region);
+            ExitPoint::Region {
+                region: parse_region(&region)?,
+            }
+        }
+        nym_vpn_proto::exit_node::ExitNodeEnum::City(city) => {
+            info!("Connecting to exit node in city: {:?}", city);
+            ExitPoint::City { city }
+        }
+        nym_vpn_proto::exit_node::ExitNodeEnum::Nearest(position) => {
+            let position = parse_coordinates(position)?;
+            info!("Connecting to exit node nearest to: {}", position);
+            ExitPoint::Nearest { position }
+        }
+        nym_vpn_proto::exit_node::ExitNodeEnum::Area(area) => {
+            let area = parse_area(area)?;
+            info!("Connecting to exit node in area: {}", area);
+            ExitPoint::Area { area }
+        }
+    })
+}
+
+fn parse_region(region: &str) -> Result<String, tonic::Status> {
+    Region::lookup(region)
+        .map(|region| region.id().to_string())
+        .ok_or_else(|| {
+            error!("Unknown region: {}", region);
+            tonic::Status::invalid_argument("Unknown region")
+        })
+}
+
+fn parse_coordinates(
+    coordinates: nym_vpn_proto::Coordinates,
+) -> Result<Coordinates, tonic::Status> {
+    Coordinates::new(coordinates.latitude, coordinates.longitude).map_err(|err| {
+        error!("Failed to parse coordinates: {}", err);
+        tonic::Status::invalid_argument("Invalid coordinates")
+    })
+}
+
+fn parse_area(area: nym_vpn_proto::Area) -> Result<Area, tonic::Status> {
+    let center = area
+        .center
+        .ok_or_else(|| tonic::Status::invalid_argument("Missing area center"))?;
+    Ok(Area {
+        center: parse_coordinates(center)?,
+        radius_km: area.radius_km,
+    })
+}
+
+pub(super) fn parse_excluded_app(
+    app: Option<nym_vpn_proto::ExcludedApp>,
+) -> Result<ExcludedApp, tonic::Status> {
+    match app.and_then(|app| app.app) {
+        Some(nym_vpn_proto::excluded_app::App::Pid(pid)) => Ok(ExcludedApp::Pid(pid)),
+        Some(nym_vpn_proto::excluded_app::App::Executable(path)) if !path.is_empty() => {
+            Ok(ExcludedApp::Executable(path.into()))
+        }
+        _ => Err(tonic::Status::invalid_argument("Missing excluded app")),
+    }
+}
+
+pub(super) fn parse_split_routes(
+    request: nym_vpn_proto::SetSplitRoutesRequest,
+) -> Result<SplitRoutes, SetSplitRoutesError> {
+    let parse_networks = |networks: Vec<String>| {
+        networks
+            .into_iter()
+            .map(|network| {
+                network
+                    .parse::<IpNetwork>()
+                    .map_err(|_| SetSplitRoutesError::InvalidNetwork(network))
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    Ok(SplitRoutes {
+        excluded: parse_networks(request.excluded_routes)?,
+        included: parse_networks(request.included_routes)?,
+    })
+}
+
+pub(super) fn parse_dns_blocking(
+    dns_blocking: nym_vpn_proto::DnsBlocking,
+) -> Result<DnsBlockingConfig, SetDnsBlockingError> {
+    let blocked_response = match dns_blocking.blocked_response() {
+        nym_vpn_proto::dns_blocking::BlockedResponse::Unspecified
+        | nym_vpn_proto::dns_blocking::BlockedResponse::Nxdomain => BlockedResponse::NxDomain,
+        nym_vpn_proto::dns_blocking::BlockedResponse::UnspecifiedAddress => {
+            BlockedResponse::UnspecifiedAddress
+        }
+    };
+
+    // The daemon may run with a different working directory than the client
+    let blocklists = dns_blocking
+        .blocklists
+        .into_iter()
+        .map(|path| {
+            let path = PathBuf::from(path);
+            if path.is_absolute() {
+                Ok(path)
+            } else {
+                Err(SetDnsBlockingError::InvalidBlocklist(format!(
+                    "blocklist path is not absolute: {}",
+                    path.display()
+                )))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if dns_blocking.refresh_interval_secs == Some(0) {
+        return Err(SetDnsBlockingError::InvalidBlocklist(
+            "refresh interval must not be zero".to_owned(),
+        ));
+    }
+
+    Ok(DnsBlockingConfig {
+        blocklists,
+        blocked_response,
+        refresh_interval_secs: dns_blocking.refresh_interval_secs,
     })
 }
 
+pub(super) fn parse_dns_domain_rules(
+    rules: Vec<nym_vpn_proto::DnsDomainRule>,
+) -> Result<Vec<DnsDomainRule>, SetDnsDomainRulesError> {
+    rules
+        .into_iter()
+        .map(|rule| {
+            let domain = rule.domain.trim().to_owned();
+            if !is_valid_domain(&domain) {
+                return Err(SetDnsDomainRulesError::InvalidRule(format!(
+                    "invalid domain: {domain:?}"
+                )));
+            }
+
+            let servers = rule
+                .servers
+                .iter()
+                .map(|server| {
+                    server.parse::<IpAddr>().map_err(|_| {
+                        SetDnsDomainRulesError::InvalidRule(format!(
+                            "invalid resolver address for {domain}: {server}"
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if servers.is_empty() {
+                return Err(SetDnsDomainRulesError::InvalidRule(format!(
+                    "no resolver address for {domain}"
+                )));
+            }
+
+            Ok(DnsDomainRule { domain, servers })
+        })
+        .collect()
+}
+
+pub(super) fn parse_gateway_preferences(
+    preferences: nym_vpn_proto::GatewayPreferences,
+) -> Result<GatewayPreferences, SetGatewayPreferencesError> {
+    let parse_identities = |identities: Vec<String>| {
+        identities
+            .into_iter()
+            .map(|identity| {
+                NodeIdentity::from_base58_string(&identity)
+                    .map_err(|_| SetGatewayPreferencesError::InvalidGateway(identity))
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let parse_countries = |countries: Vec<String>| {
+        countries
+            .into_iter()
+            .map(|country| {
+                if is_valid_country_code(&country) {
+                    Ok(country.to_ascii_uppercase())
+                } else {
+                    Err(SetGatewayPreferencesError::InvalidCountry(country))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    Ok(GatewayPreferences {
+        excluded_gateways: parse_identities(preferences.excluded_gateways)?,
+        excluded_countries: parse_countries(preferences.excluded_countries)?,
+        preferred_gateways: parse_identities(preferences.preferred_gateways)?,
+        preferred_countries: parse_countries(preferences.preferred_countries)?,
+    })
+}
+
+pub(super) fn parse_encrypted_dns(
+    encrypted_dns: nym_vpn_proto::EncryptedDns,
+) -> Result<EncryptedDnsUpstream, CommandInterfaceError> {
+    let protocol = match encrypted_dns.protocol() {
+        nym_vpn_proto::encrypted_dns::Protocol::Unspecified
+        | nym_vpn_proto::encrypted_dns::Protocol::Https => EncryptedDnsProtocol::Https,
+        nym_vpn_proto::encrypted_dns::Protocol::Tls => EncryptedDnsProtocol::Tls,
+    };
+
+    if encrypted_dns.ips.is_empty() {
+        return Ok(EncryptedDnsUpstream {
+            protocol,
+            ..Default::default()
+        });
+    }
+
+    let addresses = encrypted_dns
+        .ips
+        .into_iter()
+        .map(|ip| {
+            ip.parse()
+                .map_err(|source| CommandInterfaceError::FailedToParseDnsIp { ip, source })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if encrypted_dns.tls_name.is_empty() {
+        return Err(CommandInterfaceError::InvalidEncryptedDns(
+            "the tls name of the resolver is required".to_owned(),
+        ));
+    }
+
+    Ok(EncryptedDnsUpstream {
+        protocol,
+        addresses,
+        tls_name: encrypted_dns.tls_name,
+    })
+}
+
+pub(super) fn parse_retry_policy(retry_policy: nym_vpn_proto::RetryPolicy) -> RetryOptions {
+    let backoff = match retry_policy.backoff() {
+        nym_vpn_proto::retry_policy::Backoff::Constant => RetryBackoff::Constant,
+        nym_vpn_proto::retry_policy::Backoff::Unspecified
+        | nym_vpn_proto::retry_policy::Backoff::Linear => RetryBackoff::Linear,
+        nym_vpn_proto::retry_policy::Backoff::Exponential => RetryBackoff::Exponential,
+    };
+
+    RetryOptions {
+        max_attempts: retry_policy.max_attempts,
+        backoff,
+        delay_secs: retry_policy.delay_secs,
+        max_delay_secs: retry_policy.max_delay_secs,
+        jitter_secs: retry_policy.jitter_secs,
+    }
+}
+
+pub(super) fn parse_connect_timeouts(
+    connect_timeouts: nym_vpn_proto::ConnectTimeouts,
+) -> ConnectTimeoutOptions {
+    ConnectTimeoutOptions {
+        authenticator_registration_secs: connect_timeouts.authenticator_registration_secs,
+        ipr_connect_secs: connect_timeouts.ipr_connect_secs,
+        wireguard_handshake_secs: connect_timeouts.wireguard_handshake_secs,
+    }
+}
+
 pub(super) fn threshold_into_percent(threshold: nym_vpn_proto::Threshold) -> Percent {
     Percent::from_percentage_value(threshold.min_performance.clamp(0, 100) as u64).unwrap()
 }
+
+pub(super) fn parse_settings(
+    settings: nym_vpn_proto::Settings,
+) -> Result<NymVpnServiceConfig, SettingsError> {
+    let invalid_argument =
+        |status: tonic::Status| SettingsError::InvalidSettings(status.message().to_owned());
+
+    let entry_point = settings
+        .entry
+        .and_then(|e| e.entry_node_enum)
+        .map(parse_entry_point)
+        .transpose()
+        .map_err(invalid_argument)?
+        .unwrap_or(EntryPoint::Random);
+    let exit_point = settings
+        .exit
+        .and_then(|e| e.exit_node_enum)
+        .map(parse_exit_point)
+        .transpose()
+        .map_err(invalid_argument)?
+        .unwrap_or(ExitPoint::Random);
+
+    let dns = settings
+        .dns
+        .map(|dns| {
+            dns.ip
+                .parse()
+                .map_err(|_| SettingsError::InvalidSettings(format!("invalid dns ip: {}", dns.ip)))
+        })
+        .transpose()?;
+    let encrypted_dns = settings
+        .encrypted_dns
+        .map(parse_encrypted_dns)
+        .transpose()
+        .map_err(|err| SettingsError::InvalidSettings(err.to_string()))?;
+
+    let parse_threshold = |threshold: Option<nym_vpn_proto::Threshold>| {
+        threshold
+            .map(|threshold| {
+                Percent::from_percentage_value(u64::from(threshold.min_performance)).map_err(|_| {
+                    SettingsError::InvalidSettings(format!(
+                        "performance threshold out of range: {}",
+                        threshold.min_performance
+                    ))
+                })
+            })
+            .transpose()
+    };
+
+    let parse_networks = |networks: Vec<String>| {
+        networks
+            .into_iter()
+            .map(|network| {
+                network.parse::<IpNetwork>().map_err(|_| {
+                    SettingsError::InvalidSettings(format!("invalid network: {network}"))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    let gateway_preferences =
+        parse_gateway_preferences(settings.gateway_preferences.unwrap_or_default())
+            .map_err(|err| SettingsError::InvalidSettings(err.to_string()))?;
+    let dns_blocking = parse_dns_blocking(settings.dns_blocking.unwrap_or_default())
+        .map_err(|err| SettingsError::InvalidSettings(err.to_string()))?;
+    let dns_domain_rules = parse_dns_domain_rules(settings.dns_domain_rules)
+        .map_err(|err| SettingsError::InvalidSettings(err.to_string()))?;
+
+    let config = NymVpnServiceConfig {
+        entry_point,
+        exit_point,
+        lockdown_mode: settings.lockdown_mode,
+        excluded_executables: settings
+            .excluded_executables
+            .into_iter()
+            .map(PathBuf::from)
+            .collect(),
+        excluded_routes: parse_networks(settings.excluded_routes)?,
+        included_routes: parse_networks(settings.included_routes)?,
+        auto_connect: settings.auto_connect,
+        connect_options: ConnectOptions {
+            dns,
+            encrypted_dns,
+            disable_routing: settings.disable_routing,
+            enable_two_hop: settings.enable_two_hop,
+            netstack: settings.netstack,
+            disable_poisson_rate: settings.disable_poisson_rate,
+            // If two-hop is enabled, we always disable background cover traffic
+            disable_background_cover_traffic: settings.enable_two_hop
+                || settings.disable_background_cover_traffic,
+            enable_credentials_mode: false,
+            min_mixnode_performance: parse_threshold(settings.min_mixnode_performance)?,
+            min_gateway_mixnet_performance: parse_threshold(
+                settings.min_gateway_mixnet_performance,
+            )?,
+            min_gateway_vpn_performance: parse_threshold(settings.min_gateway_vpn_performance)?,
+            gateway_ranking: settings.gateway_ranking().into(),
+            sticky_gateways_secs: settings.sticky_gateways_secs,
+            retry: settings
+                .retry_policy
+                .map(parse_retry_policy)
+                .unwrap_or_default(),
+            connect_timeouts: settings
+                .connect_timeouts
+                .map(parse_connect_timeouts)
+                .unwrap_or_default(),
+            user_agent: None,
+            allow_lan: settings.allow_lan,
+        },
+        gateway_preferences,
+        dns_blocking,
+        dns_domain_rules,
+    };
+    config.validate()?;
+
+    Ok(config)
+}
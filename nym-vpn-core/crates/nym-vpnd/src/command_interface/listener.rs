@@ -13,35 +13,61 @@ use tokio::sync::{broadcast, mpsc::UnboundedSender};
 use nym_vpn_api_client::types::GatewayMinPerformance;
 use nym_vpn_lib::tunnel_state_machine::MixnetEvent;
 use nym_vpn_proto::{
-    conversions::ConversionError, nym_vpnd_server::NymVpnd, AccountError,
-    ConfirmZkNymDownloadedRequest, ConfirmZkNymDownloadedResponse, ConnectRequest, ConnectResponse,
-    ConnectionStateChange, ConnectionStatusUpdate, DisconnectRequest, DisconnectResponse, Empty,
+    conversions::ConversionError, nym_vpnd_server::NymVpnd, AccountError, AddExcludedAppRequest,
+    AddExcludedAppResponse, ConfirmZkNymDownloadedRequest, ConfirmZkNymDownloadedResponse,
+    ConnectRequest, ConnectResponse, ConnectionStateChange, ConnectionStatusUpdate,
+    DiagnoseDnsRequest, DiagnoseDnsResponse, DisconnectRequest, DisconnectResponse, Empty,
     ForgetAccountRequest, ForgetAccountResponse, GetAccountIdentityRequest,
     GetAccountIdentityResponse, GetAccountLinksRequest, GetAccountLinksResponse,
     GetAccountStateRequest, GetAccountStateResponse, GetAccountUsageRequest,
     GetAccountUsageResponse, GetActiveDevicesRequest, GetActiveDevicesResponse,
-    GetAvailableTicketsRequest, GetAvailableTicketsResponse, GetDeviceIdentityRequest,
-    GetDeviceIdentityResponse, GetDeviceZkNymsRequest, GetDeviceZkNymsResponse, GetDevicesRequest,
-    GetDevicesResponse, GetFeatureFlagsRequest, GetFeatureFlagsResponse, GetSystemMessagesRequest,
-    GetSystemMessagesResponse, GetZkNymByIdRequest, GetZkNymByIdResponse,
-    GetZkNymsAvailableForDownloadRequest, GetZkNymsAvailableForDownloadResponse, InfoRequest,
-    InfoResponse, IsAccountStoredRequest, IsAccountStoredResponse, IsReadyToConnectRequest,
-    IsReadyToConnectResponse, ListCountriesRequest, ListCountriesResponse, ListGatewaysRequest,
-    ListGatewaysResponse, RefreshAccountStateRequest, RefreshAccountStateResponse,
-    RegisterDeviceRequest, RegisterDeviceResponse, RequestZkNymRequest, RequestZkNymResponse,
-    ResetDeviceIdentityRequest, ResetDeviceIdentityResponse, SetNetworkRequest, SetNetworkResponse,
-    StatusRequest, StatusResponse, StoreAccountRequest, StoreAccountResponse,
+    GetAutoConnectRequest, GetAutoConnectResponse, GetAvailableTicketsRequest,
+    GetAvailableTicketsResponse, GetConnectionHistoryRequest, GetConnectionHistoryResponse,
+    GetDeviceIdentityRequest, GetDeviceIdentityResponse, GetDeviceZkNymsRequest,
+    GetDeviceZkNymsResponse, GetDevicesRequest, GetDevicesResponse, GetDnsBlockingRequest,
+    GetDnsBlockingResponse, GetDnsDomainRulesRequest, GetDnsDomainRulesResponse,
+    GetFeatureFlagsRequest, GetFeatureFlagsResponse, GetGatewayPreferencesRequest,
+    GetGatewayPreferencesResponse, GetSettingsRequest, GetSettingsResponse, GetSplitRoutesRequest,
+    GetSplitRoutesResponse, GetSystemMessagesRequest, GetSystemMessagesResponse,
+    GetZkNymByIdRequest, GetZkNymByIdResponse, GetZkNymsAvailableForDownloadRequest,
+    GetZkNymsAvailableForDownloadResponse, InfoRequest, InfoResponse, IsAccountStoredRequest,
+    IsAccountStoredResponse, IsReadyToConnectRequest, IsReadyToConnectResponse, ListCitiesRequest,
+    ListCitiesResponse, ListCountriesRequest, ListCountriesResponse, ListExcludedAppsRequest,
+    ListExcludedAppsResponse, ListGatewaysRequest, ListGatewaysResponse,
+    ListQuarantinedGatewaysRequest, ListQuarantinedGatewaysResponse, ListRegionsRequest,
+    ListRegionsResponse, RefreshAccountStateRequest, RefreshAccountStateResponse,
+    RegisterDeviceRequest, RegisterDeviceResponse, RemoveExcludedAppRequest,
+    RemoveExcludedAppResponse, RequestZkNymRequest, RequestZkNymResponse,
+    ResetDeviceIdentityRequest, ResetDeviceIdentityResponse, ResetSettingsRequest,
+    ResetSettingsResponse, SetAutoConnectRequest, SetAutoConnectResponse, SetDnsBlockingRequest,
+    SetDnsBlockingResponse, SetDnsDomainRulesRequest, SetDnsDomainRulesResponse,
+    SetGatewayPreferencesRequest, SetGatewayPreferencesResponse, SetLockdownModeRequest,
+    SetLockdownModeResponse, SetNetworkRequest, SetNetworkResponse, SetSettingsRequest,
+    SetSettingsResponse, SetSplitRoutesRequest, SetSplitRoutesResponse, Settings, StatusRequest,
+    StatusResponse, StoreAccountRequest, StoreAccountResponse,
 };
 use zeroize::Zeroizing;
 
 use super::{
     connection_handler::CommandInterfaceConnectionHandler,
     error::CommandInterfaceError,
-    helpers::{parse_entry_point, parse_exit_point, threshold_into_percent},
+    helpers::{
+        parse_connect_timeouts, parse_dns_blocking, parse_dns_domain_rules, parse_encrypted_dns,
+        parse_entry_point, parse_excluded_app, parse_exit_point, parse_gateway_preferences,
+        parse_retry_policy, parse_settings, parse_split_routes, threshold_into_percent,
+    },
 };
 use crate::{
-    command_interface::protobuf::info_response::into_proto_available_tickets,
-    service::{ConnectOptions, VpnServiceCommand, VpnServiceStateChange},
+    command_interface::protobuf::{
+        connection_history::into_proto_connection_history_record,
+        dns_diagnostics::into_proto_dns_diagnostics,
+        gateway_quarantine::into_proto_quarantined_gateway,
+        info_response::into_proto_available_tickets,
+        settings::{
+            into_proto_dns_blocking, into_proto_dns_domain_rule, into_proto_gateway_preferences,
+        },
+    },
+    service::{ConnectOptions, NymVpnServiceConfig, VpnServiceCommand, VpnServiceStateChange},
 };
 
 enum ListenerType {
@@ -59,6 +85,9 @@ pub(super) struct CommandInterface {
     // Broadcast connection status updates to our API endpoint listeners
     status_rx: broadcast::Receiver<MixnetEvent>,
 
+    // Listen to changes of the persisted settings
+    settings_changes_rx: broadcast::Receiver<NymVpnServiceConfig>,
+
     listener: ListenerType,
 }
 
@@ -67,12 +96,14 @@ impl CommandInterface {
         vpn_state_changes_rx: broadcast::Receiver<VpnServiceStateChange>,
         vpn_command_tx: UnboundedSender<VpnServiceCommand>,
         status_rx: broadcast::Receiver<MixnetEvent>,
+        settings_changes_rx: broadcast::Receiver<NymVpnServiceConfig>,
         socket_path: &Path,
     ) -> Self {
         Self {
             vpn_state_changes_rx,
             vpn_command_tx,
             status_rx,
+            settings_changes_rx,
             listener: ListenerType::Path(socket_path.to_path_buf()),
         }
     }
@@ -81,12 +112,14 @@ impl CommandInterface {
         vpn_state_changes_rx: broadcast::Receiver<VpnServiceStateChange>,
         vpn_command_tx: UnboundedSender<VpnServiceCommand>,
         status_rx: broadcast::Receiver<MixnetEvent>,
+        settings_changes_rx: broadcast::Receiver<NymVpnServiceConfig>,
         uri: SocketAddr,
     ) -> Self {
         Self {
             vpn_state_changes_rx,
             vpn_command_tx,
             status_rx,
+            settings_changes_rx,
             listener: ListenerType::Uri(uri),
         }
     }
@@ -257,6 +290,413 @@ impl NymVpnd for CommandInterface {
         Ok(tonic::Response::new(response))
     }
 
+    async fn get_lockdown_mode(
+        &self,
+        _request: tonic::Request<GetLockdownModeRequest>,
+    ) -> Result<tonic::Response<GetLockdownModeResponse>, tonic::Status> {
+        let enabled = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_get_lockdown_mode()
+            .await?;
+
+        let response = GetLockdownModeResponse { enabled };
+        tracing::debug!("Returning get lockdown mode response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn set_lockdown_mode(
+        &self,
+        request: tonic::Request<SetLockdownModeRequest>,
+    ) -> Result<tonic::Response<SetLockdownModeResponse>, tonic::Status> {
+        let enabled = request.into_inner().enabled;
+
+        let status = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_set_lockdown_mode(enabled)
+            .await?;
+
+        let response = SetLockdownModeResponse {
+            error: status
+                .err()
+                .map(nym_vpn_proto::SetLockdownModeRequestError::from),
+        };
+        tracing::debug!("Returning set lockdown mode response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn get_auto_connect(
+        &self,
+        _request: tonic::Request<GetAutoConnectRequest>,
+    ) -> Result<tonic::Response<GetAutoConnectResponse>, tonic::Status> {
+        let enabled = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_get_auto_connect()
+            .await?;
+
+        let response = GetAutoConnectResponse { enabled };
+        tracing::debug!("Returning get auto-connect response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn set_auto_connect(
+        &self,
+        request: tonic::Request<SetAutoConnectRequest>,
+    ) -> Result<tonic::Response<SetAutoConnectResponse>, tonic::Status> {
+        let enabled = request.into_inner().enabled;
+
+        let status = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_set_auto_connect(enabled)
+            .await?;
+
+        let response = SetAutoConnectResponse {
+            error: status
+                .err()
+                .map(nym_vpn_proto::SetAutoConnectRequestError::from),
+        };
+        tracing::debug!("Returning set auto-connect response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn get_settings(
+        &self,
+        _request: tonic::Request<GetSettingsRequest>,
+    ) -> Result<tonic::Response<GetSettingsResponse>, tonic::Status> {
+        let status = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_get_settings()
+            .await?;
+
+        let response = match status {
+            Ok(settings) => GetSettingsResponse {
+                settings: Some(Settings::from(settings)),
+                error: None,
+            },
+            Err(err) => GetSettingsResponse {
+                settings: None,
+                error: Some(nym_vpn_proto::SettingsError::from(err)),
+            },
+        };
+        tracing::debug!("Returning get settings response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn set_settings(
+        &self,
+        request: tonic::Request<SetSettingsRequest>,
+    ) -> Result<tonic::Response<SetSettingsResponse>, tonic::Status> {
+        let status = match parse_settings(request.into_inner().settings.unwrap_or_default()) {
+            Ok(settings) => {
+                CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+                    .handle_set_settings(settings)
+                    .await?
+            }
+            Err(err) => Err(err),
+        };
+
+        let response = SetSettingsResponse {
+            error: status.err().map(nym_vpn_proto::SettingsError::from),
+        };
+        tracing::debug!("Returning set settings response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn reset_settings(
+        &self,
+        _request: tonic::Request<ResetSettingsRequest>,
+    ) -> Result<tonic::Response<ResetSettingsResponse>, tonic::Status> {
+        let status = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_reset_settings()
+            .await?;
+
+        let response = ResetSettingsResponse {
+            error: status.err().map(nym_vpn_proto::SettingsError::from),
+        };
+        tracing::debug!("Returning reset settings response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn get_gateway_preferences(
+        &self,
+        _request: tonic::Request<GetGatewayPreferencesRequest>,
+    ) -> Result<tonic::Response<GetGatewayPreferencesResponse>, tonic::Status> {
+        let preferences = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_get_gateway_preferences()
+            .await?;
+
+        let response = GetGatewayPreferencesResponse {
+            preferences: Some(into_proto_gateway_preferences(preferences)),
+        };
+        tracing::debug!("Returning get gateway preferences response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn set_gateway_preferences(
+        &self,
+        request: tonic::Request<SetGatewayPreferencesRequest>,
+    ) -> Result<tonic::Response<SetGatewayPreferencesResponse>, tonic::Status> {
+        let preferences = request.into_inner().preferences.unwrap_or_default();
+        let status = match parse_gateway_preferences(preferences) {
+            Ok(preferences) => {
+                CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+                    .handle_set_gateway_preferences(preferences)
+                    .await?
+            }
+            Err(err) => Err(err),
+        };
+
+        let response = SetGatewayPreferencesResponse {
+            error: status
+                .err()
+                .map(nym_vpn_proto::SetGatewayPreferencesRequestError::from),
+        };
+        tracing::debug!("Returning set gateway preferences response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn list_quarantined_gateways(
+        &self,
+        _request: tonic::Request<ListQuarantinedGatewaysRequest>,
+    ) -> Result<tonic::Response<ListQuarantinedGatewaysResponse>, tonic::Status> {
+        let gateways = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_list_quarantined_gateways()
+            .await?;
+
+        let response = ListQuarantinedGatewaysResponse {
+            gateways: gateways
+                .into_iter()
+                .map(into_proto_quarantined_gateway)
+                .collect(),
+        };
+        tracing::debug!(
+            "Returning list quarantined gateways response: {:?}",
+            response
+        );
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn get_connection_history(
+        &self,
+        _request: tonic::Request<GetConnectionHistoryRequest>,
+    ) -> Result<tonic::Response<GetConnectionHistoryResponse>, tonic::Status> {
+        let records = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_get_connection_history()
+            .await?;
+
+        let response = GetConnectionHistoryResponse {
+            records: records
+                .into_iter()
+                .map(into_proto_connection_history_record)
+                .collect(),
+        };
+        tracing::debug!("Returning connection history response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn add_excluded_app(
+        &self,
+        request: tonic::Request<AddExcludedAppRequest>,
+    ) -> Result<tonic::Response<AddExcludedAppResponse>, tonic::Status> {
+        let app = parse_excluded_app(request.into_inner().app)?;
+
+        let status = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_add_excluded_app(app)
+            .await?;
+
+        let response = AddExcludedAppResponse {
+            error: status.err().map(nym_vpn_proto::SplitTunnelError::from),
+        };
+        tracing::debug!("Returning add excluded app response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn remove_excluded_app(
+        &self,
+        request: tonic::Request<RemoveExcludedAppRequest>,
+    ) -> Result<tonic::Response<RemoveExcludedAppResponse>, tonic::Status> {
+        let app = parse_excluded_app(request.into_inner().app)?;
+
+        let status = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_remove_excluded_app(app)
+            .await?;
+
+        let response = RemoveExcludedAppResponse {
+            error: status.err().map(nym_vpn_proto::SplitTunnelError::from),
+        };
+        tracing::debug!("Returning remove excluded app response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn list_excluded_apps(
+        &self,
+        _request: tonic::Request<ListExcludedAppsRequest>,
+    ) -> Result<tonic::Response<ListExcludedAppsResponse>, tonic::Status> {
+        let result = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_list_excluded_apps()
+            .await?;
+
+        let response = match result {
+            Ok(excluded_apps) => ListExcludedAppsResponse {
+                pids: excluded_apps.pids,
+                executables: excluded_apps
+                    .executables
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect(),
+                error: None,
+            },
+            Err(err) => ListExcludedAppsResponse {
+                pids: Vec::new(),
+                executables: Vec::new(),
+                error: Some(nym_vpn_proto::SplitTunnelError::from(err)),
+            },
+        };
+        tracing::debug!("Returning list excluded apps response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn get_split_routes(
+        &self,
+        _request: tonic::Request<GetSplitRoutesRequest>,
+    ) -> Result<tonic::Response<GetSplitRoutesResponse>, tonic::Status> {
+        let split_routes = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_get_split_routes()
+            .await?;
+
+        let response = GetSplitRoutesResponse {
+            excluded_routes: split_routes
+                .excluded
+                .iter()
+                .map(|n| n.to_string())
+                .collect(),
+            included_routes: split_routes
+                .included
+                .iter()
+                .map(|n| n.to_string())
+                .collect(),
+        };
+        tracing::debug!("Returning get split routes response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn set_split_routes(
+        &self,
+        request: tonic::Request<SetSplitRoutesRequest>,
+    ) -> Result<tonic::Response<SetSplitRoutesResponse>, tonic::Status> {
+        let status = match parse_split_routes(request.into_inner()) {
+            Ok(split_routes) => {
+                CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+                    .handle_set_split_routes(split_routes)
+                    .await?
+            }
+            Err(err) => Err(err),
+        };
+
+        let response = SetSplitRoutesResponse {
+            error: status
+                .err()
+                .map(nym_vpn_proto::SetSplitRoutesRequestError::from),
+        };
+        tracing::debug!("Returning set split routes response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn get_dns_blocking(
+        &self,
+        _request: tonic::Request<GetDnsBlockingRequest>,
+    ) -> Result<tonic::Response<GetDnsBlockingResponse>, tonic::Status> {
+        let dns_blocking = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_get_dns_blocking()
+            .await?;
+
+        let response = GetDnsBlockingResponse {
+            dns_blocking: Some(into_proto_dns_blocking(dns_blocking)),
+        };
+        tracing::debug!("Returning get dns blocking response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn set_dns_blocking(
+        &self,
+        request: tonic::Request<SetDnsBlockingRequest>,
+    ) -> Result<tonic::Response<SetDnsBlockingResponse>, tonic::Status> {
+        let dns_blocking = request.into_inner().dns_blocking.unwrap_or_default();
+        let status = match parse_dns_blocking(dns_blocking) {
+            Ok(dns_blocking) => {
+                CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+                    .handle_set_dns_blocking(dns_blocking)
+                    .await?
+            }
+            Err(err) => Err(err),
+        };
+
+        let response = SetDnsBlockingResponse {
+            error: status
+                .err()
+                .map(nym_vpn_proto::SetDnsBlockingRequestError::from),
+        };
+        tracing::debug!("Returning set dns blocking response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn get_dns_domain_rules(
+        &self,
+        _request: tonic::Request<GetDnsDomainRulesRequest>,
+    ) -> Result<tonic::Response<GetDnsDomainRulesResponse>, tonic::Status> {
+        let dns_domain_rules = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_get_dns_domain_rules()
+            .await?;
+
+        let response = GetDnsDomainRulesResponse {
+            dns_domain_rules: dns_domain_rules
+                .into_iter()
+                .map(into_proto_dns_domain_rule)
+                .collect(),
+        };
+        tracing::debug!("Returning get dns domain rules response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn set_dns_domain_rules(
+        &self,
+        request: tonic::Request<SetDnsDomainRulesRequest>,
+    ) -> Result<tonic::Response<SetDnsDomainRulesResponse>, tonic::Status> {
+        let dns_domain_rules = request.into_inner().dns_domain_rules;
+        let status = match parse_dns_domain_rules(dns_domain_rules) {
+            Ok(dns_domain_rules) => {
+                CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+                    .handle_set_dns_domain_rules(dns_domain_rules)
+                    .await?
+            }
+            Err(err) => Err(err),
+        };
+
+        let response = SetDnsDomainRulesResponse {
+            error: status
+                .err()
+                .map(nym_vpn_proto::SetDnsDomainRulesRequestError::from),
+        };
+        tracing::debug!("Returning set dns domain rules response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn diagnose_dns(
+        &self,
+        _request: tonic::Request<DiagnoseDnsRequest>,
+    ) -> Result<tonic::Response<DiagnoseDnsResponse>, tonic::Status> {
+        let result = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_diagnose_dns()
+            .await?;
+
+        let response = match result {
+            Ok(diagnostics) => DiagnoseDnsResponse {
+                diagnostics: Some(into_proto_dns_diagnostics(diagnostics)),
+                error: None,
+            },
+            Err(err) => DiagnoseDnsResponse {
+                diagnostics: None,
+                error: Some(nym_vpn_proto::DiagnoseDnsError::from(err)),
+            },
+        };
+        tracing::debug!("Returning diagnose dns response: {:?}", response);
+        Ok(tonic::Response::new(response))
+    }
+
     type ListenToConnectionStatusStream =
         BoxStream<'static, Result<ConnectionStatusUpdate, tonic::Status>>;
 
@@ -299,6 +739,25 @@ impl NymVpnd for CommandInterface {
         ))
     }
 
+    type ListenToSettingsChangesStream = BoxStream<'static, Result<Settings, tonic::Status>>;
+
+    async fn listen_to_settings_changes(
+        &self,
+        request: tonic::Request<Empty>,
+    ) -> Result<tonic::Response<Self::ListenToSettingsChangesStream>, tonic::Status> {
+        tracing::debug!("Got settings changes stream request: {request:?}");
+        let rx = self.settings_changes_rx.resubscribe();
+        let stream = tokio_stream::wrappers::BroadcastStream::new(rx).map(|settings| {
+            settings.map(Settings::from).map_err(|err| {
+                tracing::error!("Failed to receive settings change: {:?}", err);
+                tonic::Status::internal("Failed to receive settings change")
+            })
+        });
+        Ok(tonic::Response::new(
+            Box::pin(stream) as Self::ListenToSettingsChangesStream
+        ))
+    }
+
     async fn list_gateways(
         &self,
         request: tonic::Request<ListGatewaysRequest>,
@@ -407,6 +866,109 @@ impl NymVpnd for CommandInterface {
         Ok(tonic::Response::new(response))
     }
 
+    async fn list_cities(
+        &self,
+        request: tonic::Request<ListCitiesRequest>,
+    ) -> Result<tonic::Response<ListCitiesResponse>, tonic::Status> {
+        tracing::debug!("Got list cities request: {request:?}");
+
+        let request = request.into_inner();
+
+        let gw_type = nym_vpn_proto::GatewayType::try_from(request.kind)
+            .map_err(|err| ConversionError::Generic(err.to_string()))
+            .and_then(nym_vpn_lib::gateway_directory::GatewayType::try_from)
+            .map_err(|_err| {
+                let msg = format!("Failed to parse list cities kind: {}", request.kind);
+                tracing::error!(msg);
+                tonic::Status::invalid_argument(msg)
+            })?;
+
+        let user_agent = request
+            .user_agent
+            .map(nym_vpn_lib::UserAgent::from)
+            .unwrap_or_else(crate::util::construct_user_agent);
+
+        let min_mixnet_performance = request.min_mixnet_performance.map(threshold_into_percent);
+        let min_vpn_performance = request.min_vpn_performance.map(threshold_into_percent);
+
+        let min_gateway_performance = GatewayMinPerformance {
+            mixnet_min_performance: min_mixnet_performance,
+            vpn_min_performance: min_vpn_performance,
+        };
+
+        let cities = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_list_cities(gw_type, user_agent, min_gateway_performance)
+            .await
+            .map_err(|err| {
+                let msg = format!("Failed to list cities: {:?}", err);
+                tracing::error!(msg);
+                tonic::Status::internal(msg)
+            })?;
+
+        let response = nym_vpn_proto::ListCitiesResponse {
+            cities: cities.into_iter().map(nym_vpn_proto::City::from).collect(),
+        };
+
+        tracing::debug!(
+            "Returning list cities response: {} cities",
+            response.cities.len()
+        );
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn list_regions(
+        &self,
+        request: tonic::Request<ListRegionsRequest>,
+    ) -> Result<tonic::Response<ListRegionsResponse>, tonic::Status> {
+        tracing::debug!("Got list regions request: {request:?}");
+
+        let request = request.into_inner();
+
+        let gw_type = nym_vpn_proto::GatewayType::try_from(request.kind)
+            .map_err(|err| ConversionError::Generic(err.to_string()))
+            .and_then(nym_vpn_lib::gateway_directory::GatewayType::try_from)
+            .map_err(|_err| {
+                let msg = format!("Failed to parse list regions kind: {}", request.kind);
+                tracing::error!(msg);
+                tonic::Status::invalid_argument(msg)
+            })?;
+
+        let user_agent = request
+            .user_agent
+            .map(nym_vpn_lib::UserAgent::from)
+            .unwrap_or_else(crate::util::construct_user_agent);
+
+        let min_mixnet_performance = request.min_mixnet_performance.map(threshold_into_percent);
+        let min_vpn_performance = request.min_vpn_performance.map(threshold_into_percent);
+
+        let min_gateway_performance = GatewayMinPerformance {
+            mixnet_min_performance: min_mixnet_performance,
+            vpn_min_performance: min_vpn_performance,
+        };
+
+        let regions = CommandInterfaceConnectionHandler::new(self.vpn_command_tx.clone())
+            .handle_list_regions(gw_type, user_agent, min_gateway_performance)
+            .await
+            .map_err(|err| {
+                let msg = format!("Failed to list regions: {:?}", err);
+                tracing::error!(msg);
+                tonic::Status::internal(msg)
+            })?;
+
+        let response = nym_vpn_proto::ListRegionsResponse {
+            regions: regions
+                .into_iter()
+                .map(nym_vpn_proto::Region::from)
+                .collect(),
+        };
+
+        tracing::debug!(
+            "Returning list regions response: {} regions",
+            response.regions.len()
+        );
+        Ok(tonic::Response::new(response))
+    }
+
     async fn store_account(
         &self,
         request: tonic::Request<StoreAccountRequest>,
@@ -907,6 +1469,7 @@ impl TryFrom<ConnectRequest> for ConnectOptions {
                     })
             })
             .transpose()?;
+        let encrypted_dns = request.encrypted_dns.map(parse_encrypted_dns).transpose()?;
 
         let min_mixnode_performance = request.min_mixnode_performance.map(threshold_into_percent);
         let min_gateway_mixnet_performance = request
@@ -928,8 +1491,28 @@ impl TryFrom<ConnectRequest> for ConnectOptions {
             .map(nym_vpn_lib::UserAgent::from)
             .or(Some(crate::util::construct_user_agent()));
 
+        let retry = request
+            .retry_policy
+            .map(parse_retry_policy)
+            .unwrap_or_default();
+        if retry.max_attempts == Some(0) {
+            return Err(CommandInterfaceError::InvalidConnectOptions(
+                "max connect attempts must not be zero".to_owned(),
+            ));
+        }
+        let connect_timeouts = request
+            .connect_timeouts
+            .map(parse_connect_timeouts)
+            .unwrap_or_default();
+        if connect_timeouts.has_zero_timeout() {
+            return Err(CommandInterfaceError::InvalidConnectOptions(
+                "connect timeouts must not be zero".to_owned(),
+            ));
+        }
+
         Ok(ConnectOptions {
             dns,
+            encrypted_dns,
             disable_routing: request.disable_routing,
             enable_two_hop: request.enable_two_hop,
             netstack: request.netstack,
@@ -939,7 +1522,12 @@ impl TryFrom<ConnectRequest> for ConnectOptions {
             min_mixnode_performance,
             min_gateway_mixnet_performance,
             min_gateway_vpn_performance,
+            gateway_ranking: request.gateway_ranking().into(),
+            sticky_gateways_secs: request.sticky_gateways_secs,
+            retry,
+            connect_timeouts,
             user_agent,
+            allow_lan: request.allow_lan,
         })
     }
 }
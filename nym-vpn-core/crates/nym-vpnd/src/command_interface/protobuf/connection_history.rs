@@ -0,0 +1,74 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::time::Duration;
+
+use nym_vpn_lib::tunnel_state_machine::{ConnectionAttempt, ConnectionTimings, TunnelType};
+use nym_vpn_proto::connection_history_record::{
+    Outcome as ProtoOutcome, TunnelType as ProtoTunnelType,
+};
+use time::OffsetDateTime;
+
+use crate::service::{ConnectionHistoryRecord, ConnectionOutcome};
+
+pub(crate) fn into_proto_connection_history_record(
+    record: ConnectionHistoryRecord,
+) -> nym_vpn_proto::ConnectionHistoryRecord {
+    let tunnel_type = match record.tunnel_type {
+        TunnelType::Mixnet => ProtoTunnelType::Mixnet,
+        TunnelType::Wireguard => ProtoTunnelType::Wireguard,
+    };
+    let outcome = match record.outcome {
+        ConnectionOutcome::Connected => ProtoOutcome::Connected,
+        ConnectionOutcome::Failed => ProtoOutcome::Failed,
+        ConnectionOutcome::Cancelled => ProtoOutcome::Cancelled,
+        ConnectionOutcome::Offline => ProtoOutcome::Offline,
+    };
+
+    nym_vpn_proto::ConnectionHistoryRecord {
+        started_at: Some(into_proto_timestamp(record.started_at)),
+        finished_at: Some(into_proto_timestamp(record.finished_at)),
+        tunnel_type: tunnel_type as i32,
+        attempts: record
+            .connection_attempts
+            .into_iter()
+            .map(into_proto_connection_attempt)
+            .collect(),
+        outcome: outcome as i32,
+        error_state_reason: record
+            .error_state_reason
+            .map(|reason| format!("{:?}", reason)),
+    }
+}
+
+fn into_proto_connection_attempt(
+    attempt: ConnectionAttempt,
+) -> nym_vpn_proto::GatewayConnectionAttempt {
+    nym_vpn_proto::GatewayConnectionAttempt {
+        entry_gateway: Some(nym_vpn_proto::Gateway {
+            id: attempt.entry_gateway.to_base58_string(),
+        }),
+        exit_gateway: Some(nym_vpn_proto::Gateway {
+            id: attempt.exit_gateway.to_base58_string(),
+        }),
+        error: attempt.error,
+        timings: Some(into_proto_timings(attempt.timings)),
+    }
+}
+
+fn into_proto_timings(timings: ConnectionTimings) -> nym_vpn_proto::ConnectionPhaseTimings {
+    let as_millis = |duration: Option<Duration>| duration.map(|d| d.as_millis() as u64);
+    nym_vpn_proto::ConnectionPhaseTimings {
+        gateway_lookup_ms: as_millis(timings.gateway_lookup),
+        registration_ms: as_millis(timings.registration),
+        handshake_ms: as_millis(timings.handshake),
+        route_dns_setup_ms: as_millis(timings.route_dns_setup),
+    }
+}
+
+fn into_proto_timestamp(time: OffsetDateTime) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: time.unix_timestamp(),
+        nanos: time.nanosecond() as i32,
+    }
+}
@@ -17,6 +17,7 @@ impl From<VpnServiceStateChange> for nym_vpn_proto::ConnectionStateChange {
                 error = Some(nym_vpn_proto::Error::from(reason));
                 nym_vpn_proto::ConnectionStatus::ConnectionFailed
             }
+            VpnServiceStateChange::Offline => ConnectionStatus::Offline,
         } as i32;
 
         Self { status, error }
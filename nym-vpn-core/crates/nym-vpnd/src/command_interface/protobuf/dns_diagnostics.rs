@@ -0,0 +1,44 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use nym_vpn_proto::dns_resolver_status::Kind as ProtoKind;
+
+use crate::service::{DnsDiagnostics, DnsResolverKind, DnsResolverStatus};
+
+pub(crate) fn into_proto_dns_diagnostics(
+    diagnostics: DnsDiagnostics,
+) -> nym_vpn_proto::DnsDiagnostics {
+    nym_vpn_proto::DnsDiagnostics {
+        dns_manager: diagnostics.dns_manager,
+        tunnel_interface: diagnostics.tunnel_interface,
+        resolvers: diagnostics
+            .resolvers
+            .into_iter()
+            .map(into_proto_dns_resolver_status)
+            .collect(),
+        missing_servers: diagnostics
+            .missing_servers
+            .iter()
+            .map(|addr| addr.to_string())
+            .collect(),
+        foreign_interfaces: diagnostics.foreign_interfaces,
+        tunnel_dns_only: diagnostics.tunnel_dns_only,
+    }
+}
+
+fn into_proto_dns_resolver_status(resolver: DnsResolverStatus) -> nym_vpn_proto::DnsResolverStatus {
+    let kind = match resolver.kind {
+        DnsResolverKind::Tunnel => ProtoKind::Tunnel,
+        DnsResolverKind::DomainRule => ProtoKind::DomainRule,
+        DnsResolverKind::Foreign => ProtoKind::Foreign,
+    };
+
+    nym_vpn_proto::DnsResolverStatus {
+        address: resolver.address.to_string(),
+        interface: resolver.interface,
+        kind: kind as i32,
+        route_interface: resolver.route_interface,
+        response_time_ms: resolver.response_time.map(|d| d.as_millis() as u64),
+        error: resolver.error,
+    }
+}
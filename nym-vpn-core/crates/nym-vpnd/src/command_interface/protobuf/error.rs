@@ -5,7 +5,9 @@ use maplit::hashmap;
 use nym_vpn_proto::{error::ErrorType, Error as ProtoError};
 
 use crate::service::{
-    AccountNotReady, ConnectionFailedError, SetNetworkError, VpnServiceConnectError,
+    AccountNotReady, ConnectionFailedError, DiagnoseDnsError, SetAutoConnectError,
+    SetDnsBlockingError, SetDnsDomainRulesError, SetGatewayPreferencesError, SetLockdownModeError,
+    SetNetworkError, SetSplitRoutesError, SettingsError, SplitTunnelError, VpnServiceConnectError,
 };
 
 impl From<VpnServiceConnectError> for nym_vpn_proto::ConnectRequestError {
@@ -550,3 +552,183 @@ impl From<SetNetworkError> for nym_vpn_proto::SetNetworkRequestError {
         }
     }
 }
+
+impl From<SetLockdownModeError> for nym_vpn_proto::SetLockdownModeRequestError {
+    fn from(err: SetLockdownModeError) -> Self {
+        match err {
+            SetLockdownModeError::ReadConfig { .. }
+            | SetLockdownModeError::WriteConfig { .. }
+            | SetLockdownModeError::SendCommand => nym_vpn_proto::SetLockdownModeRequestError {
+                kind: nym_vpn_proto::set_lockdown_mode_request_error::SetLockdownModeRequestErrorType::Internal
+                    as i32,
+                message: err.to_string(),
+            },
+        }
+    }
+}
+
+impl From<SetAutoConnectError> for nym_vpn_proto::SetAutoConnectRequestError {
+    fn from(err: SetAutoConnectError) -> Self {
+        match err {
+            SetAutoConnectError::ReadConfig { .. }
+            | SetAutoConnectError::WriteConfig { .. }
+            | SetAutoConnectError::SendCommand => {
+                nym_vpn_proto::SetAutoConnectRequestError {
+                    kind: nym_vpn_proto::set_auto_connect_request_error::SetAutoConnectRequestErrorType::Internal
+                        as i32,
+                    message: err.to_string(),
+                }
+            }
+        }
+    }
+}
+
+impl From<SetGatewayPreferencesError> for nym_vpn_proto::SetGatewayPreferencesRequestError {
+    fn from(err: SetGatewayPreferencesError) -> Self {
+        let kind = match err {
+            SetGatewayPreferencesError::InvalidGateway(_) => {
+                nym_vpn_proto::set_gateway_preferences_request_error::SetGatewayPreferencesRequestErrorType::InvalidGateway
+            }
+            SetGatewayPreferencesError::InvalidCountry(_) => {
+                nym_vpn_proto::set_gateway_preferences_request_error::SetGatewayPreferencesRequestErrorType::InvalidCountry
+            }
+            SetGatewayPreferencesError::ReadConfig { .. }
+            | SetGatewayPreferencesError::WriteConfig { .. }
+            | SetGatewayPreferencesError::SendCommand => {
+                nym_vpn_proto::set_gateway_preferences_request_error::SetGatewayPreferencesRequestErrorType::Internal
+            }
+        };
+        nym_vpn_proto::SetGatewayPreferencesRequestError {
+            kind: kind as i32,
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<SettingsError> for nym_vpn_proto::SettingsError {
+    fn from(err: SettingsError) -> Self {
+        let kind = match err {
+            SettingsError::InvalidSettings(_) => {
+                nym_vpn_proto::settings_error::SettingsErrorType::InvalidSettings
+            }
+            SettingsError::ReadConfig { .. }
+            | SettingsError::WriteConfig { .. }
+            | SettingsError::SendCommand => {
+                nym_vpn_proto::settings_error::SettingsErrorType::Internal
+            }
+        };
+        nym_vpn_proto::SettingsError {
+            kind: kind as i32,
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<SetSplitRoutesError> for nym_vpn_proto::SetSplitRoutesRequestError {
+    fn from(err: SetSplitRoutesError) -> Self {
+        let kind = match err {
+            SetSplitRoutesError::InvalidNetwork(_) => {
+                nym_vpn_proto::set_split_routes_request_error::SetSplitRoutesRequestErrorType::InvalidNetwork
+            }
+            SetSplitRoutesError::ReadConfig { .. }
+            | SetSplitRoutesError::WriteConfig { .. }
+            | SetSplitRoutesError::SendCommand => {
+                nym_vpn_proto::set_split_routes_request_error::SetSplitRoutesRequestErrorType::Internal
+            }
+        };
+        nym_vpn_proto::SetSplitRoutesRequestError {
+            kind: kind as i32,
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<SetDnsBlockingError> for nym_vpn_proto::SetDnsBlockingRequestError {
+    fn from(err: SetDnsBlockingError) -> Self {
+        let kind = match err {
+            SetDnsBlockingError::InvalidBlocklist(_) => {
+                nym_vpn_proto::set_dns_blocking_request_error::SetDnsBlockingRequestErrorType::InvalidBlocklist
+            }
+            SetDnsBlockingError::ReadConfig { .. }
+            | SetDnsBlockingError::WriteConfig { .. }
+            | SetDnsBlockingError::SendCommand => {
+                nym_vpn_proto::set_dns_blocking_request_error::SetDnsBlockingRequestErrorType::Internal
+            }
+        };
+        nym_vpn_proto::SetDnsBlockingRequestError {
+            kind: kind as i32,
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<SetDnsDomainRulesError> for nym_vpn_proto::SetDnsDomainRulesRequestError {
+    fn from(err: SetDnsDomainRulesError) -> Self {
+        let kind = match err {
+            SetDnsDomainRulesError::InvalidRule(_) => {
+                nym_vpn_proto::set_dns_domain_rules_request_error::SetDnsDomainRulesRequestErrorType::InvalidRule
+            }
+            SetDnsDomainRulesError::ReadConfig { .. }
+            | SetDnsDomainRulesError::WriteConfig { .. }
+            | SetDnsDomainRulesError::SendCommand => {
+                nym_vpn_proto::set_dns_domain_rules_request_error::SetDnsDomainRulesRequestErrorType::Internal
+            }
+        };
+        nym_vpn_proto::SetDnsDomainRulesRequestError {
+            kind: kind as i32,
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<DiagnoseDnsError> for nym_vpn_proto::DiagnoseDnsError {
+    fn from(err: DiagnoseDnsError) -> Self {
+        let kind = match err {
+            DiagnoseDnsError::NotConnected => {
+                nym_vpn_proto::diagnose_dns_error::DiagnoseDnsErrorType::NotConnected
+            }
+            #[cfg(not(target_os = "linux"))]
+            DiagnoseDnsError::NotSupported => {
+                nym_vpn_proto::diagnose_dns_error::DiagnoseDnsErrorType::NotSupported
+            }
+            #[cfg(target_os = "linux")]
+            DiagnoseDnsError::Inspect(_) => {
+                nym_vpn_proto::diagnose_dns_error::DiagnoseDnsErrorType::Internal
+            }
+            DiagnoseDnsError::Internal(_) => {
+                nym_vpn_proto::diagnose_dns_error::DiagnoseDnsErrorType::Internal
+            }
+        };
+        nym_vpn_proto::DiagnoseDnsError {
+            kind: kind as i32,
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<SplitTunnelError> for nym_vpn_proto::SplitTunnelError {
+    fn from(err: SplitTunnelError) -> Self {
+        let kind = match err {
+            #[cfg(not(target_os = "linux"))]
+            SplitTunnelError::NotSupported => {
+                nym_vpn_proto::split_tunnel_error::SplitTunnelErrorType::NotSupported
+            }
+            SplitTunnelError::Unavailable { .. } => {
+                nym_vpn_proto::split_tunnel_error::SplitTunnelErrorType::Unavailable
+            }
+            SplitTunnelError::ReadConfig { .. }
+            | SplitTunnelError::WriteConfig { .. }
+            | SplitTunnelError::SendCommand => {
+                nym_vpn_proto::split_tunnel_error::SplitTunnelErrorType::Internal
+            }
+            #[cfg(target_os = "linux")]
+            SplitTunnelError::SplitTunnel(_) => {
+                nym_vpn_proto::split_tunnel_error::SplitTunnelErrorType::Internal
+            }
+        };
+        nym_vpn_proto::SplitTunnelError {
+            kind: kind as i32,
+            message: err.to_string(),
+        }
+    }
+}
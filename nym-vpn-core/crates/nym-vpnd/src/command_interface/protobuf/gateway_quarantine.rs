@@ -0,0 +1,17 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use nym_vpn_lib::tunnel_state_machine::QuarantinedGateway;
+
+pub(crate) fn into_proto_quarantined_gateway(
+    gateway: QuarantinedGateway,
+) -> nym_vpn_proto::QuarantinedGateway {
+    nym_vpn_proto::QuarantinedGateway {
+        id: gateway.identity.to_base58_string(),
+        reason: gateway.reason,
+        quarantined_until: Some(prost_types::Timestamp {
+            seconds: gateway.quarantined_until.unix_timestamp(),
+            nanos: gateway.quarantined_until.nanosecond() as i32,
+        }),
+    }
+}
@@ -4,8 +4,12 @@
 // This module primarily handles conversions to protobuf types
 
 pub(crate) mod account;
+pub(crate) mod connection_history;
 pub(crate) mod connection_state;
+pub(crate) mod dns_diagnostics;
 pub(crate) mod error;
+pub(crate) mod gateway_quarantine;
 pub(crate) mod info_response;
+pub(crate) mod settings;
 pub(crate) mod state_response;
 pub(crate) mod status_update;
@@ -0,0 +1,215 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use nym_vpn_lib::{
+    gateway_directory::{Area, Coordinates, EntryPoint, ExitPoint, GatewayPreferences},
+    tunnel_state_machine::{
+        BlockedResponse, DnsDomainRule, EncryptedDnsProtocol, EncryptedDnsUpstream,
+    },
+};
+use nym_vpn_proto::{entry_node::EntryNodeEnum, exit_node::ExitNodeEnum};
+
+use crate::service::{
+    ConnectTimeoutOptions, DnsBlockingConfig, NymVpnServiceConfig, RetryBackoff, RetryOptions,
+};
+
+fn into_proto_entry_node(entry_point: EntryPoint) -> nym_vpn_proto::EntryNode {
+    let entry_node_enum = match entry_point {
+        EntryPoint::Gateway { identity } => return nym_vpn_proto::EntryNode::from(&identity),
+        EntryPoint::Location { location } => EntryNodeEnum::Location(into_proto_location(location)),
+        EntryPoint::RandomLowLatency => EntryNodeEnum::RandomLowLatency(nym_vpn_proto::Empty {}),
+        EntryPoint::Random => EntryNodeEnum::Random(nym_vpn_proto::Empty {}),
+        EntryPoint::Region { region } => EntryNodeEnum::Region(region),
+        EntryPoint::City { city } => EntryNodeEnum::City(city),
+        EntryPoint::Nearest { position } => {
+            EntryNodeEnum::Nearest(into_proto_coordinates(position))
+        }
+        EntryPoint::Area { area } => EntryNodeEnum::Area(into_proto_area(area)),
+    };
+    nym_vpn_proto::EntryNode {
+        entry_node_enum: Some(entry_node_enum),
+    }
+}
+
+fn into_proto_exit_node(exit_point: ExitPoint) -> nym_vpn_proto::ExitNode {
+    let exit_node_enum = match exit_point {
+        ExitPoint::Address { address } => return nym_vpn_proto::ExitNode::from(&address),
+        ExitPoint::Gateway { identity } => return nym_vpn_proto::ExitNode::from(&identity),
+        ExitPoint::Location { location } => ExitNodeEnum::Location(into_proto_location(location)),
+        ExitPoint::Random => ExitNodeEnum::Random(nym_vpn_proto::Empty {}),
+        ExitPoint::RandomLowLatency => ExitNodeEnum::RandomLowLatency(nym_vpn_proto::Empty {}),
+        ExitPoint::LowLatencyPair { location } => {
+            ExitNodeEnum::LowLatencyPair(into_proto_location(location))
+        }
+        ExitPoint::Region { region } => ExitNodeEnum::Region(region),
+        ExitPoint::City { city } => ExitNodeEnum::City(city),
+        ExitPoint::Nearest { position } => ExitNodeEnum::Nearest(into_proto_coordinates(position)),
+        ExitPoint::Area { area } => ExitNodeEnum::Area(into_proto_area(area)),
+    };
+    nym_vpn_proto::ExitNode {
+        exit_node_enum: Some(exit_node_enum),
+    }
+}
+
+fn into_proto_location(two_letter_iso_country_code: String) -> nym_vpn_proto::Location {
+    nym_vpn_proto::Location {
+        two_letter_iso_country_code,
+        latitude: None,
+        longitude: None,
+        city: None,
+    }
+}
+
+fn into_proto_coordinates(coordinates: Coordinates) -> nym_vpn_proto::Coordinates {
+    nym_vpn_proto::Coordinates {
+        latitude: coordinates.latitude(),
+        longitude: coordinates.longitude(),
+    }
+}
+
+fn into_proto_area(area: Area) -> nym_vpn_proto::Area {
+    nym_vpn_proto::Area {
+        center: Some(into_proto_coordinates(area.center)),
+        radius_km: area.radius_km,
+    }
+}
+
+pub(crate) fn into_proto_gateway_preferences(
+    preferences: GatewayPreferences,
+) -> nym_vpn_proto::GatewayPreferences {
+    let into_strings = |identities: Vec<nym_vpn_lib::NodeIdentity>| {
+        identities
+            .iter()
+            .map(|identity| identity.to_base58_string())
+            .collect()
+    };
+
+    nym_vpn_proto::GatewayPreferences {
+        excluded_gateways: into_strings(preferences.excluded_gateways),
+        excluded_countries: preferences.excluded_countries,
+        preferred_gateways: into_strings(preferences.preferred_gateways),
+        preferred_countries: preferences.preferred_countries,
+    }
+}
+
+pub(crate) fn into_proto_encrypted_dns(
+    upstream: EncryptedDnsUpstream,
+) -> nym_vpn_proto::EncryptedDns {
+    let protocol = match upstream.protocol {
+        EncryptedDnsProtocol::Https => nym_vpn_proto::encrypted_dns::Protocol::Https,
+        EncryptedDnsProtocol::Tls => nym_vpn_proto::encrypted_dns::Protocol::Tls,
+    };
+
+    nym_vpn_proto::EncryptedDns {
+        protocol: protocol.into(),
+        ips: upstream.addresses.iter().map(|ip| ip.to_string()).collect(),
+        tls_name: upstream.tls_name,
+    }
+}
+
+pub(crate) fn into_proto_dns_blocking(config: DnsBlockingConfig) -> nym_vpn_proto::DnsBlocking {
+    let blocked_response = match config.blocked_response {
+        BlockedResponse::NxDomain => nym_vpn_proto::dns_blocking::BlockedResponse::Nxdomain,
+        BlockedResponse::UnspecifiedAddress => {
+            nym_vpn_proto::dns_blocking::BlockedResponse::UnspecifiedAddress
+        }
+    };
+
+    nym_vpn_proto::DnsBlocking {
+        blocklists: config
+            .blocklists
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect(),
+        blocked_response: blocked_response.into(),
+        refresh_interval_secs: config.refresh_interval_secs,
+    }
+}
+
+pub(crate) fn into_proto_dns_domain_rule(rule: DnsDomainRule) -> nym_vpn_proto::DnsDomainRule {
+    nym_vpn_proto::DnsDomainRule {
+        domain: rule.domain,
+        servers: rule.servers.iter().map(|ip| ip.to_string()).collect(),
+    }
+}
+
+pub(crate) fn into_proto_retry_policy(retry: RetryOptions) -> nym_vpn_proto::RetryPolicy {
+    let backoff = match retry.backoff {
+        RetryBackoff::Constant => nym_vpn_proto::retry_policy::Backoff::Constant,
+        RetryBackoff::Linear => nym_vpn_proto::retry_policy::Backoff::Linear,
+        RetryBackoff::Exponential => nym_vpn_proto::retry_policy::Backoff::Exponential,
+    };
+
+    nym_vpn_proto::RetryPolicy {
+        max_attempts: retry.max_attempts,
+        backoff: backoff.into(),
+        delay_secs: retry.delay_secs,
+        max_delay_secs: retry.max_delay_secs,
+        jitter_secs: retry.jitter_secs,
+    }
+}
+
+pub(crate) fn into_proto_connect_timeouts(
+    connect_timeouts: ConnectTimeoutOptions,
+) -> nym_vpn_proto::ConnectTimeouts {
+    nym_vpn_proto::ConnectTimeouts {
+        authenticator_registration_secs: connect_timeouts.authenticator_registration_secs,
+        ipr_connect_secs: connect_timeouts.ipr_connect_secs,
+        wireguard_handshake_secs: connect_timeouts.wireguard_handshake_secs,
+    }
+}
+
+impl From<NymVpnServiceConfig> for nym_vpn_proto::Settings {
+    fn from(config: NymVpnServiceConfig) -> Self {
+        let options = config.connect_options;
+        let into_threshold = |percent: nym_vpn_api_client::types::Percent| {
+            nym_vpn_proto::Threshold::from(percent.round_to_integer())
+        };
+
+        nym_vpn_proto::Settings {
+            entry: Some(into_proto_entry_node(config.entry_point)),
+            exit: Some(into_proto_exit_node(config.exit_point)),
+            dns: options.dns.map(nym_vpn_proto::Dns::from),
+            encrypted_dns: options.encrypted_dns.map(into_proto_encrypted_dns),
+            disable_routing: options.disable_routing,
+            enable_two_hop: options.enable_two_hop,
+            netstack: options.netstack,
+            disable_poisson_rate: options.disable_poisson_rate,
+            disable_background_cover_traffic: options.disable_background_cover_traffic,
+            min_mixnode_performance: options.min_mixnode_performance.map(into_threshold),
+            min_gateway_mixnet_performance: options
+                .min_gateway_mixnet_performance
+                .map(into_threshold),
+            min_gateway_vpn_performance: options.min_gateway_vpn_performance.map(into_threshold),
+            allow_lan: options.allow_lan,
+            lockdown_mode: config.lockdown_mode,
+            auto_connect: config.auto_connect,
+            excluded_routes: config
+                .excluded_routes
+                .iter()
+                .map(|n| n.to_string())
+                .collect(),
+            included_routes: config
+                .included_routes
+                .iter()
+                .map(|n| n.to_string())
+                .collect(),
+            excluded_executables: config
+                .excluded_executables
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect(),
+            gateway_preferences: Some(into_proto_gateway_preferences(config.gateway_preferences)),
+            gateway_ranking: nym_vpn_proto::GatewayRanking::from(options.gateway_ranking).into(),
+            sticky_gateways_secs: options.sticky_gateways_secs,
+            retry_policy: Some(into_proto_retry_policy(options.retry)),
+            connect_timeouts: Some(into_proto_connect_timeouts(options.connect_timeouts)),
+            dns_blocking: Some(into_proto_dns_blocking(config.dns_blocking)),
+            dns_domain_rules: config
+                .dns_domain_rules
+                .into_iter()
+                .map(into_proto_dns_domain_rule)
+                .collect(),
+        }
+    }
+}
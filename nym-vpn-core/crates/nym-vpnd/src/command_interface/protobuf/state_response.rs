@@ -60,6 +60,12 @@ impl From<VpnServiceStatus> for StatusResponse {
                         ),
                     }),
                     since: Some(timestamp),
+                    dns_blocking: conn_details.dns_blocking.map(|stats| {
+                        nym_vpn_proto::DnsBlockingCounters {
+                            queries: stats.queries,
+                            blocked: stats.blocked,
+                        }
+                    }),
                 });
                 ConnectionStatus::Connected
             }
@@ -68,6 +74,7 @@ impl From<VpnServiceStatus> for StatusResponse {
                 error = Some(ProtoError::from(reason));
                 ConnectionStatus::ConnectionFailed
             }
+            VpnServiceStatus::Offline => ConnectionStatus::Offline,
         } as i32;
 
         StatusResponse {
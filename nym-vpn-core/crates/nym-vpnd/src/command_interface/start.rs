@@ -23,7 +23,7 @@ use super::{
     listener::CommandInterface,
     socket_stream::setup_socket_stream,
 };
-use crate::service::{VpnServiceCommand, VpnServiceStateChange};
+use crate::service::{NymVpnServiceConfig, VpnServiceCommand, VpnServiceStateChange};
 
 // If the shutdown signal is received, we give the listeners a little extra time to finish
 const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
@@ -50,6 +50,7 @@ async fn run_uri_listener<T>(
     vpn_state_changes_rx: broadcast::Receiver<VpnServiceStateChange>,
     vpn_command_tx: UnboundedSender<VpnServiceCommand>,
     status_rx: broadcast::Receiver<MixnetEvent>,
+    settings_changes_rx: broadcast::Receiver<NymVpnServiceConfig>,
     addr: SocketAddr,
     shutdown_token: CancellationToken,
     health_service: HealthServer<T>,
@@ -62,8 +63,13 @@ where
         .register_encoded_file_descriptor_set(VPN_FD_SET)
         .build()
         .unwrap();
-    let command_interface =
-        CommandInterface::new_with_uri(vpn_state_changes_rx, vpn_command_tx, status_rx, addr);
+    let command_interface = CommandInterface::new_with_uri(
+        vpn_state_changes_rx,
+        vpn_command_tx,
+        status_rx,
+        settings_changes_rx,
+        addr,
+    );
 
     Server::builder()
         .trace_fn(grpc_span)
@@ -78,6 +84,7 @@ async fn run_socket_listener<T>(
     vpn_state_changes_rx: broadcast::Receiver<VpnServiceStateChange>,
     vpn_command_tx: UnboundedSender<VpnServiceCommand>,
     status_rx: broadcast::Receiver<MixnetEvent>,
+    settings_changes_rx: broadcast::Receiver<NymVpnServiceConfig>,
     socket_path: PathBuf,
     shutdown_token: CancellationToken,
     health_service: HealthServer<T>,
@@ -94,6 +101,7 @@ where
         vpn_state_changes_rx,
         vpn_command_tx,
         status_rx,
+        settings_changes_rx,
         &socket_path,
     );
     command_interface.remove_previous_socket_file();
@@ -138,6 +146,7 @@ async fn setup_health_service(
 pub(crate) fn start_command_interface(
     vpn_state_changes_rx: broadcast::Receiver<VpnServiceStateChange>,
     status_rx: broadcast::Receiver<MixnetEvent>,
+    settings_changes_rx: broadcast::Receiver<NymVpnServiceConfig>,
     command_interface_options: Option<CommandInterfaceOptions>,
     shutdown_token: CancellationToken,
 ) -> (JoinHandle<()>, UnboundedReceiver<VpnServiceCommand>) {
@@ -159,6 +168,7 @@ pub(crate) fn start_command_interface(
                 vpn_state_changes_rx.resubscribe(),
                 vpn_command_tx.clone(),
                 status_rx.resubscribe(),
+                settings_changes_rx.resubscribe(),
                 socket_path.to_path_buf(),
                 shutdown_token.child_token(),
                 health_service.clone(),
@@ -170,6 +180,7 @@ pub(crate) fn start_command_interface(
                 vpn_state_changes_rx,
                 vpn_command_tx.clone(),
                 status_rx.resubscribe(),
+                settings_changes_rx,
                 uri_addr,
                 shutdown_token.child_token(),
                 health_service,
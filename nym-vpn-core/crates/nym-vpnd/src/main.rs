@@ -71,11 +71,13 @@ async fn run_inner_async(args: CliArgs, network_env: Network) -> anyhow::Result<
 
     let (state_changes_tx, state_changes_rx) = broadcast::channel(10);
     let (status_tx, status_rx) = broadcast::channel(10);
+    let (settings_changes_tx, settings_changes_rx) = broadcast::channel(10);
     let shutdown_token = CancellationToken::new();
 
     let (command_handle, vpn_command_rx) = command_interface::start_command_interface(
         state_changes_rx,
         status_rx,
+        settings_changes_rx,
         Some(CommandInterfaceOptions {
             disable_socket_listener: args.disable_socket_listener,
             enable_http_listener: args.enable_http_listener,
@@ -92,6 +94,7 @@ async fn run_inner_async(args: CliArgs, network_env: Network) -> anyhow::Result<
         state_changes_tx,
         vpn_command_rx,
         status_tx,
+        settings_changes_tx,
         shutdown_token.child_token(),
         network_env,
         user_agent,
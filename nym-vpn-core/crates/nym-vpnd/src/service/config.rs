@@ -3,10 +3,20 @@
 
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
-use std::{fmt, fs, path::PathBuf};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use nym_vpn_lib::gateway_directory;
-use serde::{de::DeserializeOwned, Serialize};
+use ipnetwork::IpNetwork;
+use nym_vpn_lib::{
+    gateway_directory::{self, GatewayPreferences},
+    tunnel_state_machine::{BlockedResponse, DnsBlockingOptions, DnsDomainRule},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{error::SettingsError, ConnectOptions};
 
 #[cfg(not(windows))]
 const DEFAULT_DATA_DIR: &str = "/var/lib/nym-vpnd";
@@ -133,27 +143,164 @@ pub enum ConfigSetupError {
     MissingApiUrl,
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub(crate) struct NymVpnServiceConfig {
-    pub(super) entry_point: gateway_directory::EntryPoint,
-    pub(super) exit_point: gateway_directory::ExitPoint,
+    pub(crate) entry_point: gateway_directory::EntryPoint,
+    pub(crate) exit_point: gateway_directory::ExitPoint,
+    #[serde(default)]
+    pub(crate) lockdown_mode: bool,
+    #[serde(default)]
+    pub(crate) excluded_executables: Vec<PathBuf>,
+    #[serde(default)]
+    pub(crate) excluded_routes: Vec<IpNetwork>,
+    #[serde(default)]
+    pub(crate) included_routes: Vec<IpNetwork>,
+    #[serde(default)]
+    pub(crate) auto_connect: bool,
+    #[serde(default)]
+    pub(crate) connect_options: ConnectOptions,
+    #[serde(default)]
+    pub(crate) gateway_preferences: GatewayPreferences,
+    #[serde(default)]
+    pub(crate) dns_blocking: DnsBlockingConfig,
+    #[serde(default)]
+    pub(crate) dns_domain_rules: Vec<DnsDomainRule>,
 }
 
 impl fmt::Display for NymVpnServiceConfig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "entry point: {}, exit point: {}",
-            self.entry_point, self.exit_point
+            "entry point: {}, exit point: {}, lockdown mode: {}, auto connect: {}",
+            self.entry_point, self.exit_point, self.lockdown_mode, self.auto_connect
         )
     }
 }
 
+impl NymVpnServiceConfig {
+    // Checks the rules the types can't express, so that settings read from the config file are
+    // held to the same rules as the settings set over gRPC
+    pub(crate) fn validate(&self) -> Result<(), SettingsError> {
+        let invalid = |message: String| Err(SettingsError::InvalidSettings(message));
+
+        if let Some(path) = self.excluded_executables.iter().find(|p| !p.is_absolute()) {
+            return invalid(format!(
+                "executable path is not absolute: {}",
+                path.display()
+            ));
+        }
+
+        let preferences = &self.gateway_preferences;
+        if let Some(country) = preferences
+            .excluded_countries
+            .iter()
+            .chain(&preferences.preferred_countries)
+            .find(|country| !is_valid_country_code(country))
+        {
+            return invalid(format!("invalid country: {country}"));
+        }
+
+        if let Some(encrypted_dns) = &self.connect_options.encrypted_dns {
+            if !encrypted_dns.addresses.is_empty() && encrypted_dns.tls_name.is_empty() {
+                return invalid("the tls name of the resolver is required".to_owned());
+            }
+        }
+
+        let connect_options = &self.connect_options;
+        if connect_options.retry.max_attempts == Some(0) {
+            return invalid("max connect attempts must not be zero".to_owned());
+        }
+        if connect_options.connect_timeouts.has_zero_timeout() {
+            return invalid("connect timeouts must not be zero".to_owned());
+        }
+
+        if let Some(path) = self
+            .dns_blocking
+            .blocklists
+            .iter()
+            .find(|p| !p.is_absolute())
+        {
+            return invalid(format!(
+                "blocklist path is not absolute: {}",
+                path.display()
+            ));
+        }
+        if self.dns_blocking.refresh_interval_secs == Some(0) {
+            return invalid("refresh interval must not be zero".to_owned());
+        }
+
+        for rule in &self.dns_domain_rules {
+            if !is_valid_domain(&rule.domain) {
+                return invalid(format!("invalid domain: {:?}", rule.domain));
+            }
+            if rule.servers.is_empty() {
+                return invalid(format!("no resolver address for {}", rule.domain));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn is_valid_country_code(country: &str) -> bool {
+    country.len() == 2 && country.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+// Accepts names such as corp.example, corp.example. or *.corp.example
+pub(crate) fn is_valid_domain(domain: &str) -> bool {
+    let name = domain.trim_start_matches("*.").trim_end_matches('.');
+    !name.is_empty()
+        && name.len() <= 253
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
 impl Default for NymVpnServiceConfig {
     fn default() -> Self {
         Self {
             entry_point: gateway_directory::EntryPoint::Random,
             exit_point: gateway_directory::ExitPoint::Random,
+            lockdown_mode: false,
+            excluded_executables: Vec::new(),
+            excluded_routes: Vec::new(),
+            included_routes: Vec::new(),
+            auto_connect: false,
+            connect_options: ConnectOptions::default(),
+            gateway_preferences: GatewayPreferences::default(),
+            dns_blocking: DnsBlockingConfig::default(),
+            dns_domain_rules: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct DnsBlockingConfig {
+    // Absolute paths of the blocklists, blocking is disabled when empty
+    pub(crate) blocklists: Vec<PathBuf>,
+    pub(crate) blocked_response: BlockedResponse,
+    // Uses the default interval when not set
+    pub(crate) refresh_interval_secs: Option<u32>,
+}
+
+impl From<&DnsBlockingConfig> for DnsBlockingOptions {
+    fn from(config: &DnsBlockingConfig) -> Self {
+        let default_options = DnsBlockingOptions::default();
+
+        Self {
+            blocklists: config.blocklists.clone(),
+            blocked_response: config.blocked_response,
+            refresh_interval: config
+                .refresh_interval_secs
+                .map(|secs| Duration::from_secs(secs.into()))
+                .unwrap_or(default_options.refresh_interval),
         }
     }
 }
@@ -178,10 +325,7 @@ where
     })?;
 
     if !file_path.exists() {
-        fs::write(file_path, config_str).map_err(|error| ConfigSetupError::WriteFile {
-            file: file_path.clone(),
-            error,
-        })?;
+        write_file_atomically(file_path, config_str)?;
         tracing::info!("Config file created at {:?}", file_path.display());
     }
     Ok(config)
@@ -207,14 +351,21 @@ where
     C: Serialize,
 {
     let config_str = toml::to_string(&config).unwrap();
-    fs::write(file_path, config_str).map_err(|error| ConfigSetupError::WriteFile {
-        file: file_path.clone(),
-        error,
-    })?;
+    write_file_atomically(file_path, config_str)?;
     tracing::info!("Config file updated at {:?}", file_path);
     Ok(config)
 }
 
+// Readers never observe a partially written file, even if the daemon is interrupted mid-write.
+fn write_file_atomically(file_path: &Path, contents: String) -> Result<(), ConfigSetupError> {
+    nym_common::fs::write_atomically(file_path, contents).map_err(|error| {
+        ConfigSetupError::WriteFile {
+            file: file_path.to_path_buf(),
+            error,
+        }
+    })
+}
+
 pub(super) fn create_data_dir(data_dir: &PathBuf) -> Result<(), ConfigSetupError> {
     fs::create_dir_all(data_dir).map_err(|error| ConfigSetupError::CreateDirectory {
         dir: data_dir.clone(),
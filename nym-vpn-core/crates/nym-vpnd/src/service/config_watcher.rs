@@ -0,0 +1,113 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+// How often the config files are checked for modifications
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, strum::Display)]
+pub(super) enum ConfigReloadTrigger {
+    #[strum(to_string = "SIGHUP")]
+    Signal,
+    #[strum(to_string = "config file change")]
+    FileChanged,
+}
+
+// Notifies the service when the config should be reloaded, either because one of the config
+// files was modified or because the daemon received SIGHUP.
+pub(super) struct ConfigWatcher {
+    config_files: Vec<PathBuf>,
+    modified: Vec<Option<SystemTime>>,
+    #[cfg(unix)]
+    sighup: Option<tokio::signal::unix::Signal>,
+}
+
+impl ConfigWatcher {
+    pub(super) fn spawn(
+        config_files: Vec<PathBuf>,
+        shutdown_token: CancellationToken,
+    ) -> mpsc::UnboundedReceiver<ConfigReloadTrigger> {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        #[cfg(unix)]
+        let sighup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+            .inspect_err(|err| tracing::error!("Failed to set the SIGHUP handler: {}", err))
+            .ok();
+
+        let watcher = Self {
+            modified: Self::modified_times(&config_files),
+            config_files,
+            #[cfg(unix)]
+            sighup,
+        };
+        tokio::spawn(watcher.run(tx, shutdown_token));
+
+        rx
+    }
+
+    async fn run(
+        mut self,
+        tx: mpsc::UnboundedSender<ConfigReloadTrigger>,
+        shutdown_token: CancellationToken,
+    ) {
+        let mut interval = tokio::time::interval(CONFIG_POLL_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            let trigger = tokio::select! {
+                _ = shutdown_token.cancelled() => break,
+                _ = self.wait_for_hangup() => ConfigReloadTrigger::Signal,
+                _ = interval.tick() => ConfigReloadTrigger::FileChanged,
+            };
+
+            if matches!(trigger, ConfigReloadTrigger::FileChanged) {
+                let modified = Self::modified_times(&self.config_files);
+                if modified == self.modified {
+                    continue;
+                }
+                self.modified = modified;
+            }
+
+            if tx.send(trigger).is_err() {
+                break;
+            }
+        }
+
+        tracing::debug!("Config watcher exiting");
+    }
+
+    #[cfg(unix)]
+    async fn wait_for_hangup(&mut self) {
+        match self.sighup.as_mut() {
+            Some(sighup) => {
+                if sighup.recv().await.is_some() {
+                    return;
+                }
+                tracing::error!("SIGHUP handler has been dropped");
+                self.sighup = None;
+                std::future::pending().await
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    #[cfg(not(unix))]
+    async fn wait_for_hangup(&mut self) {
+        std::future::pending().await
+    }
+
+    fn modified_times(config_files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+        config_files
+            .iter()
+            .map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+}
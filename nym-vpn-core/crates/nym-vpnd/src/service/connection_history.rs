@@ -0,0 +1,359 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::collections::VecDeque;
+
+use nym_vpn_lib::tunnel_state_machine::{
+    ActionAfterDisconnect, ConnectionAttempt, ErrorStateReason, TunnelState, TunnelType,
+};
+use time::OffsetDateTime;
+
+// Number of connections kept in the history. The oldest connections are dropped first.
+const MAX_CONNECTION_HISTORY_LEN: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConnectionOutcome {
+    // The tunnel was established
+    Connected,
+
+    // Connecting failed, either retrying or entering the error state
+    Failed,
+
+    // The user disconnected before the tunnel was established
+    Cancelled,
+
+    // Connecting was suspended because the network went offline
+    Offline,
+}
+
+// A connection, from the moment the daemon started connecting until the tunnel was established
+// or connecting stopped.
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectionHistoryRecord {
+    pub(crate) started_at: OffsetDateTime,
+    pub(crate) finished_at: OffsetDateTime,
+    pub(crate) tunnel_type: TunnelType,
+
+    // Gateways tried, in order, with the time spent in each phase, including the ones that
+    // failed to connect.
+    pub(crate) connection_attempts: Vec<ConnectionAttempt>,
+
+    pub(crate) outcome: ConnectionOutcome,
+    pub(crate) error_state_reason: Option<ErrorStateReason>,
+}
+
+#[derive(Debug)]
+struct PendingConnection {
+    started_at: OffsetDateTime,
+    tunnel_type: TunnelType,
+    connection_attempts: Vec<ConnectionAttempt>,
+}
+
+// Bounded history of the recent connections, built from the tunnel state changes.
+#[derive(Debug, Default)]
+pub(crate) struct ConnectionHistory {
+    records: VecDeque<ConnectionHistoryRecord>,
+    pending: Option<PendingConnection>,
+}
+
+impl ConnectionHistory {
+    pub(crate) fn handle_tunnel_state(
+        &mut self,
+        tunnel_state: &TunnelState,
+        tunnel_type: TunnelType,
+    ) {
+        match tunnel_state {
+            TunnelState::Connecting {
+                connection_attempts,
+                ..
+            } => {
+                let pending = self.pending.get_or_insert_with(|| PendingConnection {
+                    started_at: OffsetDateTime::now_utc(),
+                    tunnel_type,
+                    connection_attempts: Vec::new(),
+                });
+                // Attempts of a previous retry are kept until the next one makes an attempt.
+                if !connection_attempts.is_empty() {
+                    pending.connection_attempts = connection_attempts.clone();
+                }
+            }
+            TunnelState::Connected { connection_data } => {
+                if let Some(pending) = self.pending.as_mut() {
+                    pending.connection_attempts = connection_data.connection_attempts.clone();
+                }
+                self.finish(ConnectionOutcome::Connected, None);
+            }
+            TunnelState::Disconnecting { after_disconnect } => match after_disconnect {
+                ActionAfterDisconnect::Nothing => self.finish(ConnectionOutcome::Cancelled, None),
+                ActionAfterDisconnect::Reconnect => self.finish(ConnectionOutcome::Failed, None),
+                // The reason is only known once the error state is entered.
+                ActionAfterDisconnect::Error => {}
+            },
+            TunnelState::Error(reason) => {
+                self.finish(ConnectionOutcome::Failed, Some(reason.clone()))
+            }
+            TunnelState::Offline => self.finish(ConnectionOutcome::Offline, None),
+            TunnelState::Disconnected => self.finish(ConnectionOutcome::Cancelled, None),
+        }
+    }
+
+    // Returns the recorded connections, most recent first.
+    pub(crate) fn records(&self) -> Vec<ConnectionHistoryRecord> {
+        self.records.iter().rev().cloned().collect()
+    }
+
+    fn finish(&mut self, outcome: ConnectionOutcome, error_state_reason: Option<ErrorStateReason>) {
+        let Some(pending) = self.pending.take() else {
+            return;
+        };
+
+        if self.records.len() == MAX_CONNECTION_HISTORY_LEN {
+            self.records.pop_front();
+        }
+        self.records.push_back(ConnectionHistoryRecord {
+            started_at: pending.started_at,
+            finished_at: OffsetDateTime::now_utc(),
+            tunnel_type: pending.tunnel_type,
+            connection_attempts: pending.connection_attempts,
+            outcome,
+            error_state_reason,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{Ipv4Addr, Ipv6Addr},
+        time::Duration,
+    };
+
+    use nym_vpn_lib::{
+        tunnel_state_machine::{
+            test_utils::{self, ENTRY_IDENTITY, EXIT_IDENTITY},
+            ConnectionData, ConnectionTimings, TunnelConnectionData, WireguardConnectionData,
+            WireguardNode,
+        },
+        NodeIdentity,
+    };
+
+    use super::*;
+
+    fn identity(identity: &str) -> Box<NodeIdentity> {
+        Box::new(test_utils::identity(identity))
+    }
+
+    fn timings() -> ConnectionTimings {
+        ConnectionTimings {
+            gateway_lookup: Some(Duration::from_millis(100)),
+            registration: Some(Duration::from_millis(200)),
+            handshake: Some(Duration::from_millis(300)),
+            route_dns_setup: Some(Duration::from_millis(400)),
+        }
+    }
+
+    fn attempt(error: Option<&str>) -> ConnectionAttempt {
+        ConnectionAttempt {
+            entry_gateway: identity(ENTRY_IDENTITY),
+            exit_gateway: identity(EXIT_IDENTITY),
+            error: error.map(ToOwned::to_owned),
+            timings: timings(),
+        }
+    }
+
+    fn wireguard_node() -> WireguardNode {
+        WireguardNode {
+            endpoint: "1.2.3.4:51820".parse().unwrap(),
+            public_key: Box::new([0; 32].into()),
+            private_ipv4: Ipv4Addr::new(10, 1, 0, 2),
+            private_ipv6: Ipv6Addr::LOCALHOST,
+        }
+    }
+
+    fn connection_data(connection_attempts: Vec<ConnectionAttempt>) -> ConnectionData {
+        ConnectionData {
+            entry_gateway: identity(ENTRY_IDENTITY),
+            exit_gateway: identity(EXIT_IDENTITY),
+            connected_at: None,
+            tunnel: TunnelConnectionData::Wireguard(WireguardConnectionData {
+                entry: wireguard_node(),
+                exit: wireguard_node(),
+            }),
+            connection_attempts,
+            system_dns_servers: vec![Ipv4Addr::LOCALHOST.into()],
+        }
+    }
+
+    fn connecting(connection_attempts: Option<Vec<ConnectionAttempt>>) -> TunnelState {
+        TunnelState::Connecting {
+            connection_data: connection_attempts.clone().map(connection_data),
+            connection_attempts: connection_attempts.unwrap_or_default(),
+        }
+    }
+
+    fn handle_tunnel_states(history: &mut ConnectionHistory, tunnel_states: &[TunnelState]) {
+        for tunnel_state in tunnel_states {
+            history.handle_tunnel_state(tunnel_state, TunnelType::Wireguard);
+        }
+    }
+
+    #[test]
+    fn test_records_connected_connection() {
+        let mut history = ConnectionHistory::default();
+        handle_tunnel_states(
+            &mut history,
+            &[
+                connecting(None),
+                connecting(Some(vec![attempt(Some("handshake timed out"))])),
+                TunnelState::Connected {
+                    connection_data: connection_data(vec![
+                        attempt(Some("handshake timed out")),
+                        attempt(None),
+                    ]),
+                },
+            ],
+        );
+
+        let records = history.records();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record.outcome, ConnectionOutcome::Connected);
+        assert_eq!(record.tunnel_type, TunnelType::Wireguard);
+        assert_eq!(record.error_state_reason, None);
+        assert!(record.started_at <= record.finished_at);
+        assert_eq!(
+            record.connection_attempts,
+            vec![attempt(Some("handshake timed out")), attempt(None)]
+        );
+        assert_eq!(record.connection_attempts[1].timings, timings());
+    }
+
+    #[test]
+    fn test_records_failed_attempts_before_error() {
+        let mut history = ConnectionHistory::default();
+        handle_tunnel_states(
+            &mut history,
+            &[
+                connecting(None),
+                TunnelState::Connecting {
+                    connection_data: None,
+                    connection_attempts: vec![attempt(Some("registration timed out"))],
+                },
+                TunnelState::Disconnecting {
+                    after_disconnect: ActionAfterDisconnect::Error,
+                },
+                TunnelState::Error(ErrorStateReason::ConnectionAttemptsExhausted),
+            ],
+        );
+
+        let records = history.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].outcome, ConnectionOutcome::Failed);
+        assert_eq!(
+            records[0].connection_attempts,
+            vec![attempt(Some("registration timed out"))]
+        );
+    }
+
+    #[test]
+    fn test_records_outcome_of_each_transition() {
+        let cases = [
+            (
+                TunnelState::Disconnecting {
+                    after_disconnect: ActionAfterDisconnect::Nothing,
+                },
+                ConnectionOutcome::Cancelled,
+            ),
+            (
+                TunnelState::Disconnecting {
+                    after_disconnect: ActionAfterDisconnect::Reconnect,
+                },
+                ConnectionOutcome::Failed,
+            ),
+            (TunnelState::Offline, ConnectionOutcome::Offline),
+            (TunnelState::Disconnected, ConnectionOutcome::Cancelled),
+        ];
+
+        for (tunnel_state, outcome) in cases {
+            let mut history = ConnectionHistory::default();
+            handle_tunnel_states(&mut history, &[connecting(None), tunnel_state]);
+
+            let records = history.records();
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].outcome, outcome);
+            assert_eq!(records[0].error_state_reason, None);
+        }
+    }
+
+    #[test]
+    fn test_records_error_state_reason() {
+        let mut history = ConnectionHistory::default();
+        handle_tunnel_states(
+            &mut history,
+            &[
+                connecting(None),
+                TunnelState::Disconnecting {
+                    after_disconnect: ActionAfterDisconnect::Error,
+                },
+            ],
+        );
+        assert!(history.records().is_empty());
+
+        handle_tunnel_states(&mut history, &[TunnelState::Error(ErrorStateReason::Dns)]);
+
+        let records = history.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].outcome, ConnectionOutcome::Failed);
+        assert_eq!(records[0].error_state_reason, Some(ErrorStateReason::Dns));
+    }
+
+    #[test]
+    fn test_ignores_transitions_outside_of_connections() {
+        let mut history = ConnectionHistory::default();
+        handle_tunnel_states(
+            &mut history,
+            &[
+                TunnelState::Disconnected,
+                TunnelState::Offline,
+                connecting(None),
+                TunnelState::Connected {
+                    connection_data: connection_data(vec![attempt(None)]),
+                },
+                TunnelState::Disconnecting {
+                    after_disconnect: ActionAfterDisconnect::Nothing,
+                },
+                TunnelState::Disconnected,
+            ],
+        );
+
+        let records = history.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].outcome, ConnectionOutcome::Connected);
+    }
+
+    #[test]
+    fn test_evicts_oldest_connections() {
+        let mut history = ConnectionHistory::default();
+        for i in 0..MAX_CONNECTION_HISTORY_LEN + 5 {
+            let error = i.to_string();
+            handle_tunnel_states(
+                &mut history,
+                &[
+                    connecting(Some(vec![attempt(Some(&error))])),
+                    TunnelState::Disconnected,
+                ],
+            );
+        }
+
+        let errors = history
+            .records()
+            .into_iter()
+            .map(|record| record.connection_attempts[0].error.clone().unwrap())
+            .collect::<Vec<_>>();
+        let expected = (5..MAX_CONNECTION_HISTORY_LEN + 5)
+            .rev()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(errors, expected);
+    }
+}
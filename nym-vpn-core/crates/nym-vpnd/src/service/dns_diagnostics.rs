@@ -0,0 +1,123 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{net::IpAddr, time::Duration};
+
+#[cfg(target_os = "linux")]
+use nym_vpn_lib::tunnel_state_machine::TunnelConnectionData;
+use nym_vpn_lib::tunnel_state_machine::{TunnelSettings, TunnelState};
+
+use super::error::DiagnoseDnsError;
+
+// Port the DNS servers are queried on
+#[cfg(target_os = "linux")]
+const DNS_PORT: u16 = 53;
+
+// How long to wait for the answers of the DNS servers, which are queried concurrently
+#[cfg(target_os = "linux")]
+const QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DnsResolverKind {
+    Tunnel,
+    DomainRule,
+    Foreign,
+}
+
+#[derive(Debug, Clone)]
+pub struct DnsResolverStatus {
+    pub address: IpAddr,
+    pub interface: Option<String>,
+    pub kind: DnsResolverKind,
+    pub route_interface: Option<String>,
+    pub response_time: Option<Duration>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DnsDiagnostics {
+    pub dns_manager: String,
+    pub tunnel_interface: Option<String>,
+    pub resolvers: Vec<DnsResolverStatus>,
+    pub missing_servers: Vec<IpAddr>,
+    pub foreign_interfaces: Vec<String>,
+    pub tunnel_dns_only: bool,
+}
+
+#[cfg(target_os = "linux")]
+impl From<nym_dns::diagnostics::DnsDiagnostics> for DnsDiagnostics {
+    fn from(value: nym_dns::diagnostics::DnsDiagnostics) -> Self {
+        use nym_dns::diagnostics::ResolverKind;
+
+        Self {
+            dns_manager: value.manager.to_string(),
+            tunnel_interface: value.tunnel_interface.clone(),
+            tunnel_dns_only: value.is_tunnel_dns_only(),
+            resolvers: value
+                .resolvers
+                .into_iter()
+                .map(|resolver| DnsResolverStatus {
+                    address: resolver.address,
+                    interface: resolver.interface,
+                    kind: match resolver.kind {
+                        ResolverKind::Tunnel => DnsResolverKind::Tunnel,
+                        ResolverKind::DomainRule => DnsResolverKind::DomainRule,
+                        ResolverKind::Foreign => DnsResolverKind::Foreign,
+                    },
+                    route_interface: resolver.route_interface,
+                    response_time: resolver.response_time,
+                    error: resolver.error,
+                })
+                .collect(),
+            missing_servers: value.missing_servers,
+            foreign_interfaces: value.foreign_interfaces,
+        }
+    }
+}
+
+// Checks the system resolver configuration against the DNS servers the tunnel is expected to
+// have set up.
+#[cfg(target_os = "linux")]
+pub(super) async fn diagnose_dns(
+    tunnel_state: &TunnelState,
+    tunnel_settings: &TunnelSettings,
+) -> Result<DnsDiagnostics, DiagnoseDnsError> {
+    let TunnelState::Connected { connection_data } = tunnel_state else {
+        return Err(DiagnoseDnsError::NotConnected);
+    };
+
+    // The DNS servers are set on the interface carrying the traffic to the exit gateway
+    let tunnel_addresses = match &connection_data.tunnel {
+        TunnelConnectionData::Mixnet(data) => vec![data.ipv4.into(), data.ipv6.into()],
+        TunnelConnectionData::Wireguard(data) => {
+            vec![data.exit.private_ipv4.into(), data.exit.private_ipv6.into()]
+        }
+    };
+    let options = nym_dns::diagnostics::DiagnoseOptions {
+        tunnel_addresses,
+        tunnel_servers: connection_data.system_dns_servers.clone(),
+        domain_rule_servers: tunnel_settings
+            .dns_domain_rules
+            .iter()
+            .flat_map(|rule| rule.servers.iter().copied())
+            .collect(),
+        port: DNS_PORT,
+        timeout: QUERY_TIMEOUT,
+    };
+
+    // Queries the DNS servers, which blocks until they answer or time out
+    tokio::task::spawn_blocking(move || {
+        let config = nym_dns::diagnostics::inspect_system_dns()?;
+        Ok(nym_dns::diagnostics::diagnose(&config, &options).into())
+    })
+    .await
+    .map_err(|e| DiagnoseDnsError::Internal(e.to_string()))?
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(super) async fn diagnose_dns(
+    _tunnel_state: &TunnelState,
+    _tunnel_settings: &TunnelSettings,
+) -> Result<DnsDiagnostics, DiagnoseDnsError> {
+    Err(DiagnoseDnsError::NotSupported)
+}
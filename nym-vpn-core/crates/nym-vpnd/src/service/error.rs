@@ -491,6 +491,225 @@ pub enum SetNetworkError {
     NetworkNotFound(String),
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum SetLockdownModeError {
+    #[error("failed to read config")]
+    ReadConfig {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("failed to write config")]
+    WriteConfig {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("failed to send command to set tunnel settings")]
+    SendCommand,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SetSplitRoutesError {
+    #[error("invalid network: {0}")]
+    InvalidNetwork(String),
+
+    #[error("failed to read config")]
+    ReadConfig {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("failed to write config")]
+    WriteConfig {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("failed to send command to set tunnel settings")]
+    SendCommand,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SetDnsBlockingError {
+    #[error("invalid blocklist: {0}")]
+    InvalidBlocklist(String),
+
+    #[error("failed to read config")]
+    ReadConfig {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("failed to write config")]
+    WriteConfig {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("failed to send command to set tunnel settings")]
+    SendCommand,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SetDnsDomainRulesError {
+    #[error("invalid dns domain rule: {0}")]
+    InvalidRule(String),
+
+    #[error("failed to read config")]
+    ReadConfig {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("failed to write config")]
+    WriteConfig {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("failed to send command to set tunnel settings")]
+    SendCommand,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DiagnoseDnsError {
+    #[error("the tunnel is not connected")]
+    NotConnected,
+
+    #[cfg(not(target_os = "linux"))]
+    #[error("dns diagnostics are not supported on this platform")]
+    NotSupported,
+
+    #[cfg(target_os = "linux")]
+    #[error("failed to inspect the system dns configuration: {0}")]
+    Inspect(#[from] nym_dns::diagnostics::Error),
+
+    #[error("internal error: {0}")]
+    Internal(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SplitTunnelError {
+    #[cfg(not(target_os = "linux"))]
+    #[error("split tunneling is not supported on this platform")]
+    NotSupported,
+
+    #[error("split tunneling failed to initialize: {reason}")]
+    Unavailable { reason: String },
+
+    #[error("failed to read config")]
+    ReadConfig {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("failed to write config")]
+    WriteConfig {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("failed to send command to set tunnel settings")]
+    SendCommand,
+
+    #[cfg(target_os = "linux")]
+    #[error("split tunnel error: {0}")]
+    SplitTunnel(#[from] nym_split_tunnel::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SetAutoConnectError {
+    #[error("failed to read config")]
+    ReadConfig {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("failed to write config")]
+    WriteConfig {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("failed to send command to set tunnel settings")]
+    SendCommand,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SetGatewayPreferencesError {
+    #[error("invalid gateway identity: {0}")]
+    InvalidGateway(String),
+
+    #[error("invalid country code: {0}")]
+    InvalidCountry(String),
+
+    #[error("failed to read config")]
+    ReadConfig {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("failed to write config")]
+    WriteConfig {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("failed to send command to set tunnel settings")]
+    SendCommand,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SettingsError {
+    #[error("invalid settings: {0}")]
+    InvalidSettings(String),
+
+    #[error("failed to read config")]
+    ReadConfig {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("failed to write config")]
+    WriteConfig {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("failed to send command to set tunnel settings")]
+    SendCommand,
+}
+
+// Failure to update the config file and apply the updated settings, converted into the error of
+// the request that triggered the update
+#[derive(Debug, thiserror::Error)]
+pub enum UpdateConfigError {
+    #[error("failed to read config")]
+    ReadConfig(#[source] ConfigSetupError),
+
+    #[error("failed to write config")]
+    WriteConfig(#[source] ConfigSetupError),
+
+    #[error("failed to send command to set tunnel settings")]
+    SendCommand,
+}
+
+macro_rules! impl_from_update_config_error {
+    ($($error:ident),+ $(,)?) => {
+        $(
+            impl From<UpdateConfigError> for $error {
+                fn from(err: UpdateConfigError) -> Self {
+                    match err {
+                        UpdateConfigError::ReadConfig(source) => Self::ReadConfig {
+                            source: source.into(),
+                        },
+                        UpdateConfigError::WriteConfig(source) => Self::WriteConfig {
+                            source: source.into(),
+                        },
+                        UpdateConfigError::SendCommand => Self::SendCommand,
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_from_update_config_error!(
+    SetLockdownModeError,
+    SetAutoConnectError,
+    SetSplitRoutesError,
+    SetDnsBlockingError,
+    SetDnsDomainRulesError,
+    SetGatewayPreferencesError,
+    SplitTunnelError,
+    SettingsError,
+);
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("account error: {0}")]
@@ -2,18 +2,29 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 mod config;
+mod config_watcher;
+mod connection_history;
+mod dns_diagnostics;
 mod error;
+mod split_tunnel;
 mod vpn_service;
 
 pub(crate) use config::{
-    config_dir, create_config_file, log_dir, read_config_file, write_config_file,
+    config_dir, create_config_file, is_valid_country_code, is_valid_domain, log_dir,
+    read_config_file, write_config_file, DnsBlockingConfig, NymVpnServiceConfig,
     DEFAULT_GLOBAL_CONFIG_FILE, DEFAULT_LOG_FILE,
 };
+pub(crate) use connection_history::{ConnectionHistoryRecord, ConnectionOutcome};
+pub(crate) use dns_diagnostics::{DnsDiagnostics, DnsResolverKind, DnsResolverStatus};
 pub(crate) use error::{
-    AccountError, AccountNotReady, ConnectionFailedError, SetNetworkError, VpnServiceConnectError,
+    AccountError, AccountNotReady, ConnectionFailedError, DiagnoseDnsError, SetAutoConnectError,
+    SetDnsBlockingError, SetDnsDomainRulesError, SetGatewayPreferencesError, SetLockdownModeError,
+    SetNetworkError, SetSplitRoutesError, SettingsError, SplitTunnelError, VpnServiceConnectError,
     VpnServiceDisconnectError,
 };
+pub(crate) use split_tunnel::{ExcludedApp, ExcludedApps};
 pub(crate) use vpn_service::{
-    ConnectArgs, ConnectOptions, ConnectedStateDetails, NymVpnService, VpnServiceCommand,
-    VpnServiceInfo, VpnServiceStateChange, VpnServiceStatus,
+    ConnectArgs, ConnectOptions, ConnectTimeoutOptions, ConnectedStateDetails, NymVpnService,
+    RetryBackoff, RetryOptions, VpnServiceCommand, VpnServiceInfo, VpnServiceStateChange,
+    VpnServiceStatus,
 };
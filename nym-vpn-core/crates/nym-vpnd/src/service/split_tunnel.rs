@@ -0,0 +1,185 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::path::PathBuf;
+#[cfg(target_os = "linux")]
+use std::{sync::Arc, time::Duration};
+
+use tokio_util::sync::CancellationToken;
+
+use super::error::SplitTunnelError;
+
+// How often running processes are scanned for excluded executables when new processes cannot
+// be picked up as they execute
+#[cfg(target_os = "linux")]
+const SYNC_EXECUTABLES_INTERVAL: Duration = Duration::from_secs(2);
+
+// How often running processes are scanned for excluded executables otherwise, to pick up the
+// processes whose events were dropped by the kernel
+#[cfg(target_os = "linux")]
+const RESYNC_EXECUTABLES_INTERVAL: Duration = Duration::from_secs(30);
+
+// How long to block waiting for process events before checking whether to stop listening
+#[cfg(target_os = "linux")]
+const EXEC_LISTENER_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExcludedApp {
+    Pid(u32),
+    Executable(PathBuf),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExcludedApps {
+    pub pids: Vec<u32>,
+    pub executables: Vec<PathBuf>,
+}
+
+// Processes excluded from the tunnel. Processes running excluded executables are picked up by a
+// background task as they execute, and by scanning the running processes periodically.
+#[derive(Clone)]
+pub(super) struct SplitTunnelHandle {
+    #[cfg(target_os = "linux")]
+    inner: Arc<tokio::sync::Mutex<nym_split_tunnel::SplitTunnel>>,
+}
+
+impl SplitTunnelHandle {
+    #[cfg(target_os = "linux")]
+    pub(super) fn spawn(
+        executables: Vec<PathBuf>,
+        shutdown_token: CancellationToken,
+    ) -> Result<Self, SplitTunnelError> {
+        let mut split_tunnel = nym_split_tunnel::SplitTunnel::new()?;
+        for executable in executables {
+            split_tunnel.add_executable(executable)?;
+        }
+
+        let inner = Arc::new(tokio::sync::Mutex::new(split_tunnel));
+        let inner_clone = Arc::clone(&inner);
+        tokio::spawn(async move {
+            let mut exec_rx = Self::spawn_exec_listener();
+            let mut interval = tokio::time::interval(if exec_rx.is_some() {
+                RESYNC_EXECUTABLES_INTERVAL
+            } else {
+                SYNC_EXECUTABLES_INTERVAL
+            });
+            loop {
+                tokio::select! {
+                    pid = async { exec_rx.as_mut()?.recv().await }, if exec_rx.is_some() => {
+                        match pid {
+                            Some(pid) => {
+                                if let Err(e) = inner_clone.lock().await.sync_process(pid) {
+                                    tracing::debug!("Failed to exclude process {}: {}", pid, e);
+                                }
+                            }
+                            None => {
+                                tracing::warn!("Process events stopped, scanning processes");
+                                exec_rx = None;
+                                interval = tokio::time::interval(SYNC_EXECUTABLES_INTERVAL);
+                            }
+                        }
+                    }
+                    _ = interval.tick() => {
+                        if let Err(e) = inner_clone.lock().await.sync_executables() {
+                            tracing::error!("Failed to exclude processes from the tunnel: {}", e);
+                        }
+                    }
+                    _ = shutdown_token.cancelled() => {
+                        tracing::debug!("Stopping split tunnel sync task");
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(Self { inner })
+    }
+
+    // Forward the PIDs of processes executing a new program from a blocking thread. The thread
+    // stops once the receiver is dropped or the process events fail.
+    #[cfg(target_os = "linux")]
+    fn spawn_exec_listener() -> Option<tokio::sync::mpsc::UnboundedReceiver<u32>> {
+        use nym_split_tunnel::proc_events::ExecListener;
+
+        let mut listener = ExecListener::new(EXEC_LISTENER_TIMEOUT)
+            .inspect_err(|e| {
+                tracing::warn!(
+                    "Failed to listen for process events, scanning processes: {}",
+                    e
+                )
+            })
+            .ok()?;
+        let (exec_tx, exec_rx) = tokio::sync::mpsc::unbounded_channel();
+
+        std::thread::spawn(move || {
+            while !exec_tx.is_closed() {
+                match listener.next_exec() {
+                    Ok(Some(pid)) => {
+                        if exec_tx.send(pid).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        tracing::error!("Failed to receive process events: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+
+        Some(exec_rx)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(super) fn spawn(
+        _executables: Vec<PathBuf>,
+        _shutdown_token: CancellationToken,
+    ) -> Result<Self, SplitTunnelError> {
+        Ok(Self {})
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) async fn add(&self, app: ExcludedApp) -> Result<(), SplitTunnelError> {
+        let mut split_tunnel = self.inner.lock().await;
+        match app {
+            ExcludedApp::Pid(pid) => split_tunnel.add_pid(pid)?,
+            ExcludedApp::Executable(path) => split_tunnel.add_executable(path)?,
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(super) async fn add(&self, _app: ExcludedApp) -> Result<(), SplitTunnelError> {
+        Err(SplitTunnelError::NotSupported)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) async fn remove(&self, app: ExcludedApp) -> Result<(), SplitTunnelError> {
+        let mut split_tunnel = self.inner.lock().await;
+        match app {
+            ExcludedApp::Pid(pid) => split_tunnel.remove_pid(pid)?,
+            ExcludedApp::Executable(path) => split_tunnel.remove_executable(&path)?,
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(super) async fn remove(&self, _app: ExcludedApp) -> Result<(), SplitTunnelError> {
+        Err(SplitTunnelError::NotSupported)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) async fn list(&self) -> Result<ExcludedApps, SplitTunnelError> {
+        let split_tunnel = self.inner.lock().await;
+        Ok(ExcludedApps {
+            pids: split_tunnel.list_pids()?,
+            executables: split_tunnel.list_executables(),
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(super) async fn list(&self) -> Result<ExcludedApps, SplitTunnelError> {
+        Err(SplitTunnelError::NotSupported)
+    }
+}
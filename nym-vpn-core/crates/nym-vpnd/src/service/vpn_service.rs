@@ -2,14 +2,16 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use std::{
-    fmt,
+    cmp, fmt,
+    future::Future,
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
     path::PathBuf,
     sync::Arc,
+    time::Duration,
 };
 
 use bip39::Mnemonic;
-use futures::FutureExt;
+use futures::{future::Fuse, FutureExt};
 use nym_vpn_network_config::{
     FeatureFlags, Network, NymNetwork, NymVpnNetwork, ParsedAccountLinks, SystemMessages,
 };
@@ -30,11 +32,15 @@ use nym_vpn_api_client::{
     types::Percent,
 };
 use nym_vpn_lib::{
-    gateway_directory::{self, EntryPoint, ExitPoint},
+    gateway_directory::{self, EntryPoint, ExitPoint, GatewayPreferences, GatewayRanking},
     tunnel_state_machine::{
-        ConnectionData, DnsOptions, GatewayPerformanceOptions, MixnetEvent, MixnetTunnelOptions,
-        NymConfig, TunnelCommand, TunnelConnectionData, TunnelEvent, TunnelSettings, TunnelState,
-        TunnelStateMachine, TunnelType, WireguardMultihopMode, WireguardTunnelOptions,
+        Backoff, ConnectTimeouts, ConnectionData, DnsBlockingCounters, DnsBlockingOptions,
+        DnsBlockingStats, DnsDomainRule, DnsOptions, EncryptedDnsUpstream,
+        GatewayPerformanceOptions, GatewayQuarantine, MixnetEvent, MixnetTunnelOptions, NymConfig,
+        QuarantinedGateway, RetryPolicy, SplitRoutes, TunnelCommand, TunnelConnectionData,
+        TunnelEvent, TunnelSettings, TunnelState, TunnelStateMachine, TunnelType,
+        WireguardMultihopMode, WireguardTunnelOptions, DEFAULT_MAX_RETRY_DELAY,
+        DEFAULT_RETRY_DELAY,
     },
     MixnetClientConfig, NodeIdentity, Recipient, UserAgent,
 };
@@ -43,8 +49,20 @@ use zeroize::Zeroizing;
 use crate::{config::GlobalConfigFile, service::AccountNotReady};
 
 use super::{
-    config::{ConfigSetupError, NetworkEnvironments, NymVpnServiceConfig, DEFAULT_CONFIG_FILE},
-    error::{AccountError, ConnectionFailedError, Error, Result, SetNetworkError},
+    config::{
+        ConfigSetupError, DnsBlockingConfig, NetworkEnvironments, NymVpnServiceConfig,
+        DEFAULT_CONFIG_FILE, DEFAULT_GLOBAL_CONFIG_FILE,
+    },
+    config_watcher::{ConfigReloadTrigger, ConfigWatcher},
+    connection_history::{ConnectionHistory, ConnectionHistoryRecord},
+    dns_diagnostics::DnsDiagnostics,
+    error::{
+        AccountError, ConnectionFailedError, DiagnoseDnsError, Error, Result, SetAutoConnectError,
+        SetDnsBlockingError, SetDnsDomainRulesError, SetGatewayPreferencesError,
+        SetLockdownModeError, SetNetworkError, SetSplitRoutesError, SettingsError,
+        SplitTunnelError, UpdateConfigError,
+    },
+    split_tunnel::{ExcludedApp, ExcludedApps, SplitTunnelHandle},
     VpnServiceConnectError, VpnServiceDisconnectError,
 };
 
@@ -89,6 +107,12 @@ impl fmt::Display for ConnectedStateDetails {
     }
 }
 
+// Interval between checks whether the account is ready when auto-connecting
+const AUTO_CONNECT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+// Directory in the data dir where the gateways fetched from the directory are cached
+const GATEWAY_CACHE_DIR: &str = "gateway_cache";
+
 // Seed used to generate device identity keys
 type Seed = [u8; 32];
 
@@ -107,6 +131,48 @@ pub enum VpnServiceCommand {
     ),
     Disconnect(oneshot::Sender<Result<(), VpnServiceDisconnectError>>, ()),
     Status(oneshot::Sender<VpnServiceStatus>, ()),
+    GetLockdownMode(oneshot::Sender<bool>, ()),
+    SetLockdownMode(oneshot::Sender<Result<(), SetLockdownModeError>>, bool),
+    GetAutoConnect(oneshot::Sender<bool>, ()),
+    SetAutoConnect(oneshot::Sender<Result<(), SetAutoConnectError>>, bool),
+    GetSettings(
+        oneshot::Sender<Result<NymVpnServiceConfig, SettingsError>>,
+        (),
+    ),
+    SetSettings(
+        oneshot::Sender<Result<(), SettingsError>>,
+        Box<NymVpnServiceConfig>,
+    ),
+    ResetSettings(oneshot::Sender<Result<(), SettingsError>>, ()),
+    GetGatewayPreferences(oneshot::Sender<GatewayPreferences>, ()),
+    SetGatewayPreferences(
+        oneshot::Sender<Result<(), SetGatewayPreferencesError>>,
+        GatewayPreferences,
+    ),
+    ListQuarantinedGateways(oneshot::Sender<Vec<QuarantinedGateway>>, ()),
+    GetConnectionHistory(oneshot::Sender<Vec<ConnectionHistoryRecord>>, ()),
+    AddExcludedApp(oneshot::Sender<Result<(), SplitTunnelError>>, ExcludedApp),
+    RemoveExcludedApp(oneshot::Sender<Result<(), SplitTunnelError>>, ExcludedApp),
+    ListExcludedApps(oneshot::Sender<Result<ExcludedApps, SplitTunnelError>>, ()),
+    GetSplitRoutes(oneshot::Sender<SplitRoutes>, ()),
+    SetSplitRoutes(
+        oneshot::Sender<Result<(), SetSplitRoutesError>>,
+        SplitRoutes,
+    ),
+    GetDnsBlocking(oneshot::Sender<DnsBlockingConfig>, ()),
+    SetDnsBlocking(
+        oneshot::Sender<Result<(), SetDnsBlockingError>>,
+        DnsBlockingConfig,
+    ),
+    GetDnsDomainRules(oneshot::Sender<Vec<DnsDomainRule>>, ()),
+    SetDnsDomainRules(
+        oneshot::Sender<Result<(), SetDnsDomainRulesError>>,
+        Vec<DnsDomainRule>,
+    ),
+    DiagnoseDns(
+        oneshot::Sender<Result<DnsDiagnostics, DiagnoseDnsError>>,
+        (),
+    ),
     StoreAccount(oneshot::Sender<Result<(), AccountError>>, Zeroizing<String>),
     IsAccountStored(oneshot::Sender<Result<bool, AccountError>>, ()),
     ForgetAccount(oneshot::Sender<Result<(), AccountError>>, ()),
@@ -145,19 +211,125 @@ pub struct ConnectArgs {
     pub options: ConnectOptions,
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+// Persisted as the last used connect options, except for the ones that are determined by the
+// client or the network at connect time.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct ConnectOptions {
     pub(crate) dns: Option<IpAddr>,
+    // Takes precedence over the plain DNS server when set
+    pub(crate) encrypted_dns: Option<EncryptedDnsUpstream>,
     pub(crate) disable_routing: bool,
     pub(crate) enable_two_hop: bool,
     pub(crate) netstack: bool,
     pub(crate) disable_poisson_rate: bool,
     pub(crate) disable_background_cover_traffic: bool,
+    #[serde(skip)]
     pub(crate) enable_credentials_mode: bool,
     pub(crate) min_mixnode_performance: Option<Percent>,
     pub(crate) min_gateway_mixnet_performance: Option<Percent>,
     pub(crate) min_gateway_vpn_performance: Option<Percent>,
+    pub(crate) gateway_ranking: GatewayRanking,
+    pub(crate) sticky_gateways_secs: Option<u32>,
+    pub(crate) retry: RetryOptions,
+    pub(crate) connect_timeouts: ConnectTimeoutOptions,
+    #[serde(skip)]
     pub(crate) user_agent: Option<UserAgent>,
+    pub(crate) allow_lan: bool,
+}
+
+// How to retry after failing to connect. Unset values use the defaults of the tunnel.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct RetryOptions {
+    // Consecutive failed attempts before entering the error state, retrying until connected
+    // when not set
+    pub(crate) max_attempts: Option<u32>,
+    pub(crate) backoff: RetryBackoff,
+    // Delay before the first retry
+    pub(crate) delay_secs: Option<u32>,
+    // Longest delay between retries, ignored by the constant backoff
+    pub(crate) max_delay_secs: Option<u32>,
+    // Upper bound of a random delay added before each retry
+    pub(crate) jitter_secs: u32,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RetryBackoff {
+    // Wait the same delay before every retry
+    Constant,
+    // Wait one delay longer before each retry
+    #[default]
+    Linear,
+    // Double the delay before each retry
+    Exponential,
+}
+
+impl RetryOptions {
+    fn retry_policy(&self) -> RetryPolicy {
+        let delay = self
+            .delay_secs
+            .map_or(DEFAULT_RETRY_DELAY, |secs| Duration::from_secs(secs.into()));
+        let max = self
+            .max_delay_secs
+            .map_or(cmp::max(delay, DEFAULT_MAX_RETRY_DELAY), |secs| {
+                Duration::from_secs(secs.into())
+            });
+        let backoff = match self.backoff {
+            RetryBackoff::Constant => Backoff::Constant(delay),
+            RetryBackoff::Linear => Backoff::Linear { step: delay, max },
+            RetryBackoff::Exponential => Backoff::Exponential {
+                initial: delay,
+                max,
+            },
+        };
+
+        RetryPolicy {
+            max_attempts: self.max_attempts,
+            backoff,
+            jitter: Duration::from_secs(self.jitter_secs.into()),
+        }
+    }
+}
+
+// Time allowed for each phase of connecting. Unset values use the defaults of the tunnel.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ConnectTimeoutOptions {
+    pub(crate) authenticator_registration_secs: Option<u32>,
+    pub(crate) ipr_connect_secs: Option<u32>,
+    pub(crate) wireguard_handshake_secs: Option<u32>,
+}
+
+impl ConnectTimeoutOptions {
+    fn connect_timeouts(&self) -> ConnectTimeouts {
+        let defaults = ConnectTimeouts::default();
+        let timeout = |secs: Option<u32>, default: Duration| {
+            secs.map_or(default, |secs| Duration::from_secs(secs.into()))
+        };
+
+        ConnectTimeouts {
+            authenticator_registration: timeout(
+                self.authenticator_registration_secs,
+                defaults.authenticator_registration,
+            ),
+            ipr_connect: timeout(self.ipr_connect_secs, defaults.ipr_connect),
+            wireguard_handshake: timeout(
+                self.wireguard_handshake_secs,
+                defaults.wireguard_handshake,
+            ),
+        }
+    }
+
+    pub(crate) fn has_zero_timeout(&self) -> bool {
+        [
+            self.authenticator_registration_secs,
+            self.ipr_connect_secs,
+            self.wireguard_handshake_secs,
+        ]
+        .contains(&Some(0))
+    }
 }
 
 // Respond with the current state of the VPN service. This is currently almost the same as VpnState,
@@ -169,6 +341,7 @@ pub enum VpnServiceStatus {
     Connected(Box<ConnectedResultDetails>),
     Disconnecting,
     ConnectionFailed(ConnectionFailedError),
+    Offline,
 }
 
 impl From<ConnectionData> for ConnectedResultDetails {
@@ -179,6 +352,7 @@ impl From<ConnectionData> for ConnectedResultDetails {
             specific_details: ConnectedStateDetails::from(value.tunnel),
             // FIXME: this cannot be mapped correctly
             since: value.connected_at.unwrap_or(OffsetDateTime::now_utc()),
+            dns_blocking: None,
         }
     }
 }
@@ -223,6 +397,7 @@ impl From<TunnelState> for VpnServiceStatus {
                     since: connection_data
                         .connected_at
                         .unwrap_or(OffsetDateTime::now_utc()),
+                    dns_blocking: None,
                 }))
             }
             TunnelState::Connecting { .. } => Self::Connecting,
@@ -231,6 +406,7 @@ impl From<TunnelState> for VpnServiceStatus {
             TunnelState::Error(e) => Self::ConnectionFailed(ConnectionFailedError::InternalError(
                 format!("Error state: {:?}", e),
             )),
+            TunnelState::Offline => Self::Offline,
         }
     }
 }
@@ -256,6 +432,7 @@ impl fmt::Display for VpnServiceStatus {
             VpnServiceStatus::ConnectionFailed(reason) => {
                 write!(f, "ConnectionFailed({})", reason)
             }
+            VpnServiceStatus::Offline => write!(f, "Offline"),
         }
     }
 }
@@ -266,6 +443,8 @@ pub struct ConnectedResultDetails {
     pub exit_gateway: NodeIdentity,
     pub specific_details: ConnectedStateDetails,
     pub since: time::OffsetDateTime,
+    // Set when DNS blocking is enabled
+    pub dns_blocking: Option<DnsBlockingStats>,
 }
 
 impl fmt::Display for ConnectedResultDetails {
@@ -294,6 +473,7 @@ pub enum VpnServiceStateChange {
     Connected,
     Disconnecting,
     ConnectionFailed(ConnectionFailedError),
+    Offline,
 }
 
 impl From<TunnelState> for VpnServiceStateChange {
@@ -306,6 +486,7 @@ impl From<TunnelState> for VpnServiceStateChange {
             TunnelState::Error(reason) => Self::ConnectionFailed(
                 ConnectionFailedError::InternalError(format!("Error state: {:?}", reason)),
             ),
+            TunnelState::Offline => Self::Offline,
         }
     }
 }
@@ -366,6 +547,33 @@ where
     // Event channel for receiving events from state machine
     event_receiver: mpsc::UnboundedReceiver<TunnelEvent>,
 
+    // Tunnel settings last sent to the state machine
+    tunnel_settings: TunnelSettings,
+
+    // Gateways that recently failed to connect, shared with the state machine
+    gateway_quarantine: GatewayQuarantine,
+
+    // DNS queries handled by the local DNS forwarder, shared with the state machine
+    dns_blocking_counters: DnsBlockingCounters,
+
+    // Recent connections, recorded from the tunnel state changes
+    connection_history: ConnectionHistory,
+
+    // Processes excluded from the tunnel, or why split tunneling could not be set up
+    split_tunnel: Result<SplitTunnelHandle, String>,
+
+    // Connect with the last used settings once the account is ready after startup
+    auto_connect: bool,
+
+    // Settings last persisted or loaded from the config file
+    settings: NymVpnServiceConfig,
+
+    // Broadcast persisted settings whenever they change
+    settings_changes_tx: broadcast::Sender<NymVpnServiceConfig>,
+
+    // Receive requests to reload the config files
+    config_reload_rx: mpsc::UnboundedReceiver<ConfigReloadTrigger>,
+
     // Service shutdown token.
     shutdown_token: CancellationToken,
 
@@ -378,6 +586,7 @@ impl NymVpnService<nym_vpn_lib::storage::VpnClientOnDiskStorage> {
         vpn_state_changes_tx: broadcast::Sender<VpnServiceStateChange>,
         vpn_command_rx: mpsc::UnboundedReceiver<VpnServiceCommand>,
         status_tx: broadcast::Sender<MixnetEvent>,
+        settings_changes_tx: broadcast::Sender<NymVpnServiceConfig>,
         shutdown_token: CancellationToken,
         network_env: Network,
         user_agent: UserAgent,
@@ -388,6 +597,7 @@ impl NymVpnService<nym_vpn_lib::storage::VpnClientOnDiskStorage> {
                 vpn_state_changes_tx,
                 vpn_command_rx,
                 status_tx,
+                settings_changes_tx,
                 shutdown_token,
                 network_env,
                 user_agent,
@@ -417,6 +627,7 @@ impl NymVpnService<nym_vpn_lib::storage::VpnClientOnDiskStorage> {
         vpn_state_changes_tx: broadcast::Sender<VpnServiceStateChange>,
         vpn_command_rx: mpsc::UnboundedReceiver<VpnServiceCommand>,
         status_tx: broadcast::Sender<MixnetEvent>,
+        settings_changes_tx: broadcast::Sender<NymVpnServiceConfig>,
         shutdown_token: CancellationToken,
         network_env: Network,
         user_agent: UserAgent,
@@ -456,7 +667,47 @@ impl NymVpnService<nym_vpn_lib::storage::VpnClientOnDiskStorage> {
         let (command_sender, command_receiver) = mpsc::unbounded_channel();
         let (event_sender, event_receiver) = mpsc::unbounded_channel();
 
-        let tunnel_settings = TunnelSettings::default();
+        let persisted_config = if config_file.exists() {
+            super::config::read_config_file::<NymVpnServiceConfig>(&config_file)
+                .inspect_err(|err| tracing::error!("Failed to read config file: {:?}", err))
+                .ok()
+        } else {
+            None
+        };
+
+        // Apply the persisted settings right away so that the lockdown policy blocks traffic
+        // from the start, with the persisted LAN access and DNS settings
+        let tunnel_settings = persisted_config
+            .as_ref()
+            .map(|config| {
+                Self::tunnel_settings_from_config(config, statistics_recipient, false, None)
+            })
+            .unwrap_or_default();
+
+        let auto_connect = persisted_config
+            .as_ref()
+            .is_some_and(|config| config.auto_connect);
+        let settings = persisted_config.clone().unwrap_or_default();
+
+        // Failing to set up split tunneling should not prevent the service from running
+        let excluded_executables = persisted_config
+            .map(|config| config.excluded_executables)
+            .unwrap_or_default();
+        let split_tunnel =
+            SplitTunnelHandle::spawn(excluded_executables, shutdown_token.child_token())
+                .inspect_err(|err| tracing::error!("Failed to set up split tunneling: {}", err))
+                .map_err(|err| err.to_string());
+
+        // Reload the config files when they change so that running daemons can be managed by
+        // editing them in place
+        let config_reload_rx = ConfigWatcher::spawn(
+            vec![
+                config_file.clone(),
+                super::config::config_dir().join(DEFAULT_GLOBAL_CONFIG_FILE),
+            ],
+            shutdown_token.child_token(),
+        );
+
         let api_url = network_env
             .api_url()
             .ok_or(Error::ConfigSetup(ConfigSetupError::MissingApiUrl))?;
@@ -464,17 +715,22 @@ impl NymVpnService<nym_vpn_lib::storage::VpnClientOnDiskStorage> {
             api_url,
             nym_vpn_api_url: Some(network_env.vpn_api_url()),
             min_gateway_performance: None,
+            cache_dir: Some(data_dir.join(GATEWAY_CACHE_DIR)),
         };
         let nym_config = NymConfig {
             data_path: Some(data_dir.clone()),
             gateway_config,
         };
 
+        let gateway_quarantine = GatewayQuarantine::default();
+        let dns_blocking_counters = DnsBlockingCounters::default();
         let state_machine_handle = TunnelStateMachine::spawn(
             command_receiver,
             event_sender,
             nym_config,
-            tunnel_settings,
+            tunnel_settings.clone(),
+            gateway_quarantine.clone(),
+            dns_blocking_counters.clone(),
             shutdown_token.child_token(),
         )
         .await
@@ -495,6 +751,15 @@ impl NymVpnService<nym_vpn_lib::storage::VpnClientOnDiskStorage> {
             state_machine_handle,
             command_sender,
             event_receiver,
+            tunnel_settings,
+            gateway_quarantine,
+            dns_blocking_counters,
+            connection_history: ConnectionHistory::default(),
+            split_tunnel,
+            auto_connect,
+            settings,
+            settings_changes_tx,
+            config_reload_rx,
             shutdown_token,
             statistics_recipient,
         })
@@ -506,16 +771,36 @@ where
     S: nym_vpn_store::VpnStorage,
 {
     pub(crate) async fn run(mut self) -> anyhow::Result<()> {
+        let mut auto_connect = if self.auto_connect {
+            let credentials_mode = self
+                .network_env
+                .get_feature_flag_credential_mode()
+                .unwrap_or(false);
+            Self::wait_until_ready_to_connect(self.shared_account_state.clone(), credentials_mode)
+                .boxed()
+                .fuse()
+        } else {
+            Fuse::terminated()
+        };
+
         loop {
             tokio::select! {
                 Some(command) = self.vpn_command_rx.recv() => {
                     tracing::debug!("VPN: Received command: {command}");
                     self.handle_service_command(command).await;
                 }
+                _ = &mut auto_connect => {
+                    self.handle_auto_connect().await;
+                }
+                Some(trigger) = self.config_reload_rx.recv() => {
+                    self.handle_reload_config(trigger).await;
+                }
                 Some(event) = self.event_receiver.recv() => {
                     tracing::info!("Tunnel event: {}", event);
                     match event {
                         TunnelEvent::NewState(new_state) => {
+                            self.connection_history
+                                .handle_tunnel_state(&new_state, self.tunnel_settings.tunnel_type);
                             self.tunnel_state = new_state.clone();
                             let vpn_state_change = VpnServiceStateChange::from(new_state);
                             if let Err(e) = self.vpn_state_changes_tx.send(vpn_state_change) {
@@ -579,6 +864,93 @@ where
                 let result = self.handle_status().await;
                 let _ = tx.send(result);
             }
+            VpnServiceCommand::GetLockdownMode(tx, ()) => {
+                let result = self.handle_get_lockdown_mode().await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::SetLockdownMode(tx, enabled) => {
+                let result = self.handle_set_lockdown_mode(enabled).await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::GetAutoConnect(tx, ()) => {
+                let result = self.handle_get_auto_connect().await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::SetAutoConnect(tx, enabled) => {
+                let result = self.handle_set_auto_connect(enabled).await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::GetSettings(tx, ()) => {
+                let result = self.handle_get_settings().await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::SetSettings(tx, settings) => {
+                let result = self.handle_set_settings(*settings).await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::ResetSettings(tx, ()) => {
+                let result = self.handle_reset_settings().await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::GetGatewayPreferences(tx, ()) => {
+                let result = self.handle_get_gateway_preferences().await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::SetGatewayPreferences(tx, preferences) => {
+                let result = self.handle_set_gateway_preferences(preferences).await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::ListQuarantinedGateways(tx, ()) => {
+                let result = self.handle_list_quarantined_gateways().await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::GetConnectionHistory(tx, ()) => {
+                let result = self.handle_get_connection_history().await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::AddExcludedApp(tx, app) => {
+                let result = self.handle_add_excluded_app(app).await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::RemoveExcludedApp(tx, app) => {
+                let result = self.handle_remove_excluded_app(app).await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::ListExcludedApps(tx, ()) => {
+                let result = self.handle_list_excluded_apps().await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::GetSplitRoutes(tx, ()) => {
+                let result = self.handle_get_split_routes().await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::SetSplitRoutes(tx, split_routes) => {
+                let result = self.handle_set_split_routes(split_routes).await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::GetDnsBlocking(tx, ()) => {
+                let result = self.handle_get_dns_blocking().await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::SetDnsBlocking(tx, dns_blocking) => {
+                let result = self.handle_set_dns_blocking(dns_blocking).await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::GetDnsDomainRules(tx, ()) => {
+                let result = self.handle_get_dns_domain_rules().await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::SetDnsDomainRules(tx, dns_domain_rules) => {
+                let result = self.handle_set_dns_domain_rules(dns_domain_rules).await;
+                let _ = tx.send(result);
+            }
+            VpnServiceCommand::DiagnoseDns(tx, ()) => {
+                // Waiting for the DNS servers to answer must not hold up the other commands
+                let diagnose_dns = self.handle_diagnose_dns();
+                tokio::spawn(async move {
+                    let _ = tx.send(diagnose_dns.await);
+                });
+            }
             VpnServiceCommand::StoreAccount(tx, account) => {
                 let result = self.handle_store_account(account).await;
                 let _ = tx.send(result);
@@ -666,27 +1038,46 @@ where
         &self,
         entry: Option<gateway_directory::EntryPoint>,
         exit: Option<gateway_directory::ExitPoint>,
+        options: &ConnectOptions,
     ) -> Result<NymVpnServiceConfig> {
+        // Keep the in-memory settings in line with what is read back from the config file
+        let options = ConnectOptions {
+            enable_credentials_mode: false,
+            user_agent: None,
+            ..options.clone()
+        };
+
         // If the config file does not exit, create it
         let config = if self.config_file.exists() {
-            let mut read_config: NymVpnServiceConfig =
-                super::config::read_config_file(&self.config_file)
-                    .map_err(|err| {
-                        tracing::error!(
-                            "Failed to read config file, resetting to defaults: {:?}",
-                            err
-                        );
-                    })
-                    .unwrap_or_default();
-            read_config.entry_point = entry.unwrap_or(read_config.entry_point);
-            read_config.exit_point = exit.unwrap_or(read_config.exit_point);
-            super::config::write_config_file(&self.config_file, &read_config)
-                .map_err(Error::ConfigSetup)?;
-            read_config
+            match super::config::read_config_file::<NymVpnServiceConfig>(&self.config_file) {
+                Ok(mut read_config) => {
+                    read_config.entry_point = entry.unwrap_or(read_config.entry_point);
+                    read_config.exit_point = exit.unwrap_or(read_config.exit_point);
+                    read_config.connect_options = options;
+                    super::config::write_config_file(&self.config_file, read_config)
+                        .map_err(Error::ConfigSetup)?
+                }
+                // Leave the broken file alone for the user to fix, and connect with the last
+                // good settings instead
+                Err(err) => {
+                    tracing::error!(
+                        "Failed to read config file, keeping the last good settings: {}",
+                        err
+                    );
+                    NymVpnServiceConfig {
+                        entry_point: entry.unwrap_or(self.settings.entry_point.clone()),
+                        exit_point: exit.unwrap_or(self.settings.exit_point.clone()),
+                        connect_options: options,
+                        ..self.settings.clone()
+                    }
+                }
+            }
         } else {
             let config = NymVpnServiceConfig {
                 entry_point: entry.unwrap_or(EntryPoint::Random),
                 exit_point: exit.unwrap_or(ExitPoint::Random),
+                connect_options: options,
+                ..Default::default()
             };
             super::config::create_config_file(&self.config_file, config)
                 .map_err(Error::ConfigSetup)?
@@ -768,10 +1159,54 @@ where
         tracing::info!("Using options: {:?}", options);
 
         let config = self
-            .try_setup_config(entry, exit)
+            .try_setup_config(entry, exit, &options)
             .map_err(|err| VpnServiceConnectError::Internal(err.to_string()))?;
         tracing::info!("Using config: {}", config);
 
+        self.notify_settings_changed(&config);
+
+        tracing::info!(
+            "Using statistics recipient: {:?}",
+            self.statistics_recipient
+        );
+        let tunnel_settings = Self::tunnel_settings_from_config(
+            &config,
+            self.statistics_recipient,
+            options.enable_credentials_mode,
+            options.user_agent,
+        );
+        self.tunnel_settings = tunnel_settings.clone();
+
+        match self
+            .command_sender
+            .send(TunnelCommand::SetTunnelSettings(tunnel_settings))
+        {
+            Ok(()) => self
+                .command_sender
+                .send(TunnelCommand::Connect)
+                .map_err(|e| {
+                    tracing::error!("Failed to send command to connect: {}", e);
+                    VpnServiceConnectError::Internal("failed to send command to connect".to_owned())
+                }),
+            Err(e) => {
+                tracing::error!("Failed to send command to set tunnel options: {}", e);
+                Err(VpnServiceConnectError::Internal(
+                    "failed to send command to set tunnel options".to_owned(),
+                ))
+            }
+        }
+    }
+
+    // Build the tunnel settings from the persisted config. Settings that are not persisted are
+    // provided by the caller.
+    fn tunnel_settings_from_config(
+        config: &NymVpnServiceConfig,
+        statistics_recipient: Option<Recipient>,
+        enable_credentials_mode: bool,
+        user_agent: Option<UserAgent>,
+    ) -> TunnelSettings {
+        let options = &config.connect_options;
+
         let gateway_options = GatewayPerformanceOptions {
             mixnet_min_performance: options
                 .min_gateway_mixnet_performance
@@ -779,13 +1214,9 @@ where
             vpn_min_performance: options
                 .min_gateway_vpn_performance
                 .map(|x| x.round_to_integer()),
+            ranking: options.gateway_ranking,
         };
 
-        tracing::info!(
-            "Using statistics recipient: {:?}",
-            self.statistics_recipient
-        );
-
         let mixnet_client_config = MixnetClientConfig {
             disable_poisson_rate: options.disable_poisson_rate,
             disable_background_cover_traffic: options.disable_background_cover_traffic,
@@ -803,15 +1234,16 @@ where
             TunnelType::Mixnet
         };
 
-        let dns = options
-            .dns
-            .map(|addr| DnsOptions::Custom(vec![addr]))
-            .unwrap_or(DnsOptions::default());
+        let dns = match (&options.encrypted_dns, options.dns) {
+            (Some(upstream), _) => DnsOptions::Encrypted(upstream.clone()),
+            (None, Some(addr)) => DnsOptions::Custom(vec![addr]),
+            (None, None) => DnsOptions::default(),
+        };
 
-        let tunnel_settings = TunnelSettings {
+        TunnelSettings {
             tunnel_type,
-            enable_credentials_mode: options.enable_credentials_mode,
-            statistics_recipient: self.statistics_recipient.map(Box::new),
+            enable_credentials_mode,
+            statistics_recipient: statistics_recipient.map(Box::new),
             mixnet_tunnel_options: MixnetTunnelOptions::default(),
             wireguard_tunnel_options: WireguardTunnelOptions {
                 multihop_mode: if options.netstack {
@@ -822,30 +1254,79 @@ where
             },
             gateway_performance_options: gateway_options,
             mixnet_client_config: Some(mixnet_client_config),
-            entry_point: Box::new(config.entry_point),
-            exit_point: Box::new(config.exit_point),
+            entry_point: Box::new(config.entry_point.clone()),
+            exit_point: Box::new(config.exit_point.clone()),
+            gateway_preferences: config.gateway_preferences.clone(),
+            sticky_gateways: options
+                .sticky_gateways_secs
+                .map(|secs| Duration::from_secs(secs.into())),
+            retry_policy: options.retry.retry_policy(),
+            connect_timeouts: options.connect_timeouts.connect_timeouts(),
             dns,
-            user_agent: options.user_agent,
-        };
+            dns_blocking: DnsBlockingOptions::from(&config.dns_blocking),
+            dns_domain_rules: config.dns_domain_rules.clone(),
+            user_agent,
+            lockdown_mode: config.lockdown_mode,
+            allow_lan: options.allow_lan,
+            split_routes: SplitRoutes {
+                excluded: config.excluded_routes.clone(),
+                included: config.included_routes.clone(),
+            },
+        }
+    }
 
-        match self
-            .command_sender
-            .send(TunnelCommand::SetTunnelSettings(tunnel_settings))
-        {
-            Ok(()) => self
-                .command_sender
-                .send(TunnelCommand::Connect)
-                .map_err(|e| {
-                    tracing::error!("Failed to send command to connect: {}", e);
-                    VpnServiceConnectError::Internal("failed to send command to connect".to_owned())
-                }),
-            Err(e) => {
-                tracing::error!("Failed to send command to set tunnel options: {}", e);
-                Err(VpnServiceConnectError::Internal(
-                    "failed to send command to set tunnel options".to_owned(),
-                ))
-            }
+    fn notify_settings_changed(&mut self, config: &NymVpnServiceConfig) {
+        self.settings = config.clone();
+        if let Err(e) = self.settings_changes_tx.send(config.clone()) {
+            tracing::error!("Failed to send settings change: {}", e);
+        }
+    }
+
+    // Update the config file, creating it first if needed, and apply the updated settings.
+    fn update_config<E>(
+        &mut self,
+        update: impl FnOnce(&mut NymVpnServiceConfig),
+    ) -> Result<NymVpnServiceConfig, E>
+    where
+        E: From<UpdateConfigError>,
+    {
+        let mut config =
+            super::config::create_config_file(&self.config_file, NymVpnServiceConfig::default())
+                .and_then(|_| {
+                    super::config::read_config_file::<NymVpnServiceConfig>(&self.config_file)
+                })
+                .map_err(UpdateConfigError::ReadConfig)?;
+        update(&mut config);
+        super::config::write_config_file(&self.config_file, &config)
+            .map_err(UpdateConfigError::WriteConfig)?;
+
+        self.apply_config(&config)?;
+        Ok(config)
+    }
+
+    // Notify the listeners of the new settings. Reconnects with the new tunnel settings when
+    // they have changed and the tunnel is connected.
+    fn apply_config(&mut self, config: &NymVpnServiceConfig) -> Result<(), UpdateConfigError> {
+        self.notify_settings_changed(config);
+
+        let tunnel_settings = Self::tunnel_settings_from_config(
+            config,
+            self.statistics_recipient,
+            self.tunnel_settings.enable_credentials_mode,
+            self.tunnel_settings.user_agent.clone(),
+        );
+        if tunnel_settings == self.tunnel_settings {
+            return Ok(());
         }
+        self.tunnel_settings = tunnel_settings;
+        self.command_sender
+            .send(TunnelCommand::SetTunnelSettings(
+                self.tunnel_settings.clone(),
+            ))
+            .map_err(|e| {
+                tracing::error!("Failed to send command to set tunnel settings: {}", e);
+                UpdateConfigError::SendCommand
+            })
     }
 
     async fn handle_disconnect(&mut self) -> Result<(), VpnServiceDisconnectError> {
@@ -858,7 +1339,330 @@ where
     }
 
     async fn handle_status(&self) -> VpnServiceStatus {
-        VpnServiceStatus::from(self.tunnel_state.clone())
+        let mut status = VpnServiceStatus::from(self.tunnel_state.clone());
+        if let VpnServiceStatus::Connected(details) = &mut status {
+            if self.tunnel_settings.dns_blocking.is_enabled() {
+                details.dns_blocking = Some(self.dns_blocking_counters.stats());
+            }
+        }
+        status
+    }
+
+    async fn handle_get_lockdown_mode(&self) -> bool {
+        self.tunnel_settings.lockdown_mode
+    }
+
+    async fn handle_set_lockdown_mode(
+        &mut self,
+        enabled: bool,
+    ) -> Result<(), SetLockdownModeError> {
+        self.update_config(|config| config.lockdown_mode = enabled)?;
+
+        tracing::info!(
+            "Lockdown mode {}",
+            if enabled { "enabled" } else { "disabled" }
+        );
+        Ok(())
+    }
+
+    async fn handle_get_auto_connect(&self) -> bool {
+        self.auto_connect
+    }
+
+    async fn handle_set_auto_connect(&mut self, enabled: bool) -> Result<(), SetAutoConnectError> {
+        self.update_config(|config| config.auto_connect = enabled)?;
+
+        tracing::info!(
+            "Auto-connect {}",
+            if enabled { "enabled" } else { "disabled" }
+        );
+
+        // Takes effect on the next startup
+        self.auto_connect = enabled;
+        Ok(())
+    }
+
+    async fn wait_until_ready_to_connect(
+        shared_account_state: SharedAccountState,
+        credentials_mode: bool,
+    ) {
+        loop {
+            match shared_account_state
+                .is_ready_to_connect(credentials_mode)
+                .await
+            {
+                ReadyToConnect::Ready => return,
+                not_ready => tracing::debug!("Auto-connect is waiting: {}", not_ready),
+            }
+            tokio::time::sleep(AUTO_CONNECT_POLL_INTERVAL).await;
+        }
+    }
+
+    async fn handle_auto_connect(&mut self) {
+        if self.tunnel_state != TunnelState::Disconnected {
+            tracing::debug!("Skipping auto-connect since the tunnel is not disconnected");
+            return;
+        }
+
+        let options =
+            match super::config::read_config_file::<NymVpnServiceConfig>(&self.config_file) {
+                Ok(config) => config.connect_options,
+                Err(err) => {
+                    tracing::error!("Failed to read config file for auto-connect: {:?}", err);
+                    return;
+                }
+            };
+
+        tracing::info!("Auto-connecting with the last used settings");
+        let connect_args = ConnectArgs {
+            entry: None,
+            exit: None,
+            options: ConnectOptions {
+                user_agent: Some(self.user_agent.clone()),
+                ..options
+            },
+        };
+        if let Err(err) = self.handle_connect(connect_args).await {
+            tracing::error!("Failed to auto-connect: {}", err);
+        }
+    }
+
+    async fn handle_get_settings(&self) -> Result<NymVpnServiceConfig, SettingsError> {
+        if !self.config_file.exists() {
+            return Ok(NymVpnServiceConfig::default());
+        }
+        super::config::read_config_file(&self.config_file).map_err(|source| {
+            SettingsError::ReadConfig {
+                source: source.into(),
+            }
+        })
+    }
+
+    async fn handle_set_settings(
+        &mut self,
+        settings: NymVpnServiceConfig,
+    ) -> Result<(), SettingsError> {
+        // Make sure the config directory exists before replacing the config file
+        super::config::create_config_file(&self.config_file, NymVpnServiceConfig::default())
+            .and_then(|_| super::config::write_config_file(&self.config_file, &settings))
+            .map_err(|source| SettingsError::WriteConfig {
+                source: source.into(),
+            })?;
+
+        self.apply_settings(settings).await
+    }
+
+    async fn handle_reset_settings(&mut self) -> Result<(), SettingsError> {
+        self.handle_set_settings(NymVpnServiceConfig::default())
+            .await
+    }
+
+    async fn apply_settings(&mut self, settings: NymVpnServiceConfig) -> Result<(), SettingsError> {
+        tracing::info!("Using settings: {}", settings);
+
+        self.auto_connect = settings.auto_connect;
+        self.sync_excluded_executables(&settings.excluded_executables)
+            .await;
+        Ok(self.apply_config(&settings)?)
+    }
+
+    // Re-apply the settings when the config files were changed by something other than the
+    // daemon itself. Settings are applied by reconnecting, while changing the network requires
+    // a restart.
+    async fn handle_reload_config(&mut self, trigger: ConfigReloadTrigger) {
+        tracing::debug!("Reloading config files on {}", trigger);
+
+        if self.config_file.exists() {
+            match super::config::read_config_file::<NymVpnServiceConfig>(&self.config_file) {
+                Ok(settings) if settings == self.settings => {
+                    tracing::debug!("Settings are unchanged")
+                }
+                Ok(settings) => match settings.validate() {
+                    Ok(()) => {
+                        tracing::info!("Config file has changed, applying settings");
+                        if let Err(err) = self.apply_settings(settings).await {
+                            tracing::error!("Failed to apply reloaded settings: {}", err);
+                        }
+                    }
+                    Err(err) => {
+                        tracing::error!("Invalid config file, keeping settings: {}", err)
+                    }
+                },
+                Err(err) => {
+                    tracing::error!("Failed to reload config file, keeping settings: {}", err)
+                }
+            }
+        }
+
+        match GlobalConfigFile::read_from_file() {
+            Ok(global_config) => {
+                let current_network = &self.network_env.nym_network_details().network_name;
+                if &global_config.network_name != current_network {
+                    tracing::warn!(
+                        "Network changed from {} to {}, restart nym-vpnd to use it",
+                        current_network,
+                        global_config.network_name
+                    );
+                }
+            }
+            Err(err) => tracing::error!("Failed to reload global config file: {}", err),
+        }
+    }
+
+    // Bring the excluded executables in line with the persisted settings. Excluded PIDs are left
+    // untouched.
+    async fn sync_excluded_executables(&self, executables: &[PathBuf]) {
+        let Ok(split_tunnel) = self.split_tunnel.as_ref() else {
+            if !executables.is_empty() {
+                tracing::warn!("Split tunneling is unavailable, not excluding executables");
+            }
+            return;
+        };
+
+        let current = match split_tunnel.list().await {
+            Ok(excluded_apps) => excluded_apps.executables,
+            Err(err) => {
+                tracing::error!("Failed to list excluded apps: {}", err);
+                return;
+            }
+        };
+
+        for path in current.iter().filter(|path| !executables.contains(path)) {
+            if let Err(err) = split_tunnel
+                .remove(ExcludedApp::Executable(path.clone()))
+                .await
+            {
+                tracing::error!("Failed to include {} again: {}", path.display(), err);
+            }
+        }
+        for path in executables.iter().filter(|path| !current.contains(path)) {
+            if let Err(err) = split_tunnel
+                .add(ExcludedApp::Executable(path.clone()))
+                .await
+            {
+                tracing::error!("Failed to exclude {}: {}", path.display(), err);
+            }
+        }
+    }
+
+    async fn handle_get_split_routes(&self) -> SplitRoutes {
+        self.tunnel_settings.split_routes.clone()
+    }
+
+    async fn handle_set_split_routes(
+        &mut self,
+        split_routes: SplitRoutes,
+    ) -> Result<(), SetSplitRoutesError> {
+        self.update_config(|config| {
+            config.excluded_routes = split_routes.excluded.clone();
+            config.included_routes = split_routes.included.clone();
+        })?;
+
+        tracing::info!("Using split routes: {:?}", split_routes);
+        Ok(())
+    }
+
+    async fn handle_get_dns_blocking(&self) -> DnsBlockingConfig {
+        self.settings.dns_blocking.clone()
+    }
+
+    async fn handle_set_dns_blocking(
+        &mut self,
+        dns_blocking: DnsBlockingConfig,
+    ) -> Result<(), SetDnsBlockingError> {
+        let config = self.update_config(|config| config.dns_blocking = dns_blocking)?;
+
+        tracing::info!("Using dns blocking: {:?}", config.dns_blocking);
+        Ok(())
+    }
+
+    async fn handle_get_dns_domain_rules(&self) -> Vec<DnsDomainRule> {
+        self.settings.dns_domain_rules.clone()
+    }
+
+    async fn handle_set_dns_domain_rules(
+        &mut self,
+        dns_domain_rules: Vec<DnsDomainRule>,
+    ) -> Result<(), SetDnsDomainRulesError> {
+        let config = self.update_config(|config| config.dns_domain_rules = dns_domain_rules)?;
+
+        tracing::info!("Using dns domain rules: {:?}", config.dns_domain_rules);
+        Ok(())
+    }
+
+    fn handle_diagnose_dns(
+        &self,
+    ) -> impl Future<Output = Result<DnsDiagnostics, DiagnoseDnsError>> + Send + 'static {
+        let tunnel_state = self.tunnel_state.clone();
+        let tunnel_settings = self.tunnel_settings.clone();
+
+        async move { super::dns_diagnostics::diagnose_dns(&tunnel_state, &tunnel_settings).await }
+    }
+
+    async fn handle_get_gateway_preferences(&self) -> GatewayPreferences {
+        self.settings.gateway_preferences.clone()
+    }
+
+    async fn handle_set_gateway_preferences(
+        &mut self,
+        preferences: GatewayPreferences,
+    ) -> Result<(), SetGatewayPreferencesError> {
+        let config = self.update_config(|config| config.gateway_preferences = preferences)?;
+
+        tracing::info!(
+            "Using gateway preferences: {:?}",
+            config.gateway_preferences
+        );
+        Ok(())
+    }
+
+    async fn handle_list_quarantined_gateways(&self) -> Vec<QuarantinedGateway> {
+        self.gateway_quarantine.quarantined_gateways()
+    }
+
+    async fn handle_get_connection_history(&self) -> Vec<ConnectionHistoryRecord> {
+        self.connection_history.records()
+    }
+
+    fn split_tunnel(&self) -> Result<&SplitTunnelHandle, SplitTunnelError> {
+        self.split_tunnel
+            .as_ref()
+            .map_err(|reason| SplitTunnelError::Unavailable {
+                reason: reason.clone(),
+            })
+    }
+
+    async fn handle_add_excluded_app(&mut self, app: ExcludedApp) -> Result<(), SplitTunnelError> {
+        let is_executable = matches!(app, ExcludedApp::Executable(_));
+        self.split_tunnel()?.add(app).await?;
+        if is_executable {
+            self.persist_excluded_executables().await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_remove_excluded_app(
+        &mut self,
+        app: ExcludedApp,
+    ) -> Result<(), SplitTunnelError> {
+        let is_executable = matches!(app, ExcludedApp::Executable(_));
+        self.split_tunnel()?.remove(app).await?;
+        if is_executable {
+            self.persist_excluded_executables().await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_list_excluded_apps(&self) -> Result<ExcludedApps, SplitTunnelError> {
+        self.split_tunnel()?.list().await
+    }
+
+    // Excluded executables are persisted so that they are excluded again after a restart.
+    // Excluded PIDs only make sense for the lifetime of the process and are not persisted.
+    async fn persist_excluded_executables(&mut self) -> Result<(), SplitTunnelError> {
+        let excluded_apps = self.split_tunnel()?.list().await?;
+        self.update_config(|config| config.excluded_executables = excluded_apps.executables)?;
+        Ok(())
     }
 
     async fn handle_info(&self) -> VpnServiceInfo {
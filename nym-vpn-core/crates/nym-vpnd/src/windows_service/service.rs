@@ -92,6 +92,7 @@ async fn run_service_inner(
 
     let (state_changes_tx, state_changes_rx) = broadcast::channel(10);
     let (status_tx, status_rx) = broadcast::channel(10);
+    let (settings_changes_tx, settings_changes_rx) = broadcast::channel(10);
 
     // The idea here for explicly starting two separate runtimes is to make sure they are properly
     // separated. Looking ahead a little ideally it would be nice to be able for the command
@@ -101,6 +102,7 @@ async fn run_service_inner(
     let (command_handle, vpn_command_rx) = command_interface::start_command_interface(
         state_changes_rx,
         status_rx,
+        settings_changes_rx,
         None,
         shutdown_token.child_token(),
     );
@@ -112,6 +114,7 @@ async fn run_service_inner(
         state_changes_tx,
         vpn_command_rx,
         status_tx,
+        settings_changes_tx,
         shutdown_token.child_token(),
         network_env,
         user_agent,
@@ -44,6 +44,15 @@ impl<'a> From<&'a [u8]> for Value<'a> {
     }
 }
 
+/// Returns true if any peer of the UAPI config returned by `get` completed a handshake.
+pub(crate) fn has_handshake(config: &str) -> bool {
+    config.lines().any(|line| {
+        line.strip_prefix("last_handshake_time_sec=")
+            .and_then(|secs| secs.parse::<u64>().ok())
+            .is_some_and(|secs| secs > 0)
+    })
+}
+
 impl<'a> Value<'a> {
     fn to_bytes(&self) -> Cow<'a, [u8]> {
         match self {
@@ -55,7 +64,7 @@ impl<'a> Value<'a> {
 
 #[cfg(test)]
 pub mod tests {
-    use super::UapiConfigBuilder;
+    use super::{has_handshake, UapiConfigBuilder};
 
     #[test]
     fn test_encode_string() {
@@ -70,4 +79,14 @@ pub mod tests {
         config_builder.add("key", "bytes".as_bytes());
         assert_eq!(config_builder.into_bytes(), b"key=6279746573\n\n");
     }
+
+    #[test]
+    fn test_has_handshake() {
+        let pending = "public_key=abcd\nlast_handshake_time_sec=0\nlast_handshake_time_nsec=0\n";
+        let completed =
+            "public_key=abcd\nlast_handshake_time_sec=1732105200\nlast_handshake_time_nsec=12\n";
+        assert!(!has_handshake(pending));
+        assert!(has_handshake(completed));
+        assert!(!has_handshake(""));
+    }
 }
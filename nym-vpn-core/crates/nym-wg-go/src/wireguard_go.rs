@@ -14,8 +14,8 @@ use nym_windows::net::AddressFamily;
 use windows_sys::Win32::NetworkManagement::Ndis::NET_LUID_LH;
 
 use super::{
-    uapi::UapiConfigBuilder, Error, LoggingCallback, PeerConfig, PeerEndpointUpdate, PrivateKey,
-    Result,
+    uapi::{self, UapiConfigBuilder},
+    Error, LoggingCallback, PeerConfig, PeerEndpointUpdate, PrivateKey, Result,
 };
 #[cfg(feature = "amnezia")]
 use crate::amnezia::AmneziaConfig;
@@ -244,6 +244,24 @@ impl Tunnel {
         }
     }
 
+    /// Returns true once the tunnel has completed a handshake with its peer.
+    pub fn has_handshake(&self) -> bool {
+        let config_ptr = unsafe { wgGetConfig(self.handle) };
+        if config_ptr.is_null() {
+            return false;
+        }
+
+        // SAFETY: libwg returns a nul-terminated string when the pointer is not null.
+        let has_handshake = unsafe { CStr::from_ptr(config_ptr) }
+            .to_str()
+            .is_ok_and(uapi::has_handshake);
+
+        // SAFETY: free C string allocated in Go using the correct deallocator.
+        unsafe { wgFreePtr(config_ptr as *mut _) };
+
+        has_handshake
+    }
+
     fn stop_inner(&mut self) {
         if self.handle >= 0 {
             unsafe { wgTurnOff(self.handle) };
@@ -287,14 +305,12 @@ extern "C" {
     fn wgTurnOff(handle: i32);
 
     /// Returns the config of the WireGuard interface.
-    #[allow(unused)]
     fn wgGetConfig(handle: i32) -> *mut c_char;
 
     /// Sets the config of the WireGuard interface.
     fn wgSetConfig(handle: i32, settings: *const c_char) -> i32;
 
     /// Frees a pointer allocated by the go runtime - useful to free return value of wgGetConfig
-    #[allow(unused)]
     fn wgFreePtr(ptr: *mut c_void);
 
     /// Re-attach wireguard-go to the tunnel interface.